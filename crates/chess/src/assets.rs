@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 
+use crate::theme::PieceSet;
 use crate::types::{Piece, PieceType, PlayerColor};
 use vitae::prelude::*;
 
@@ -10,7 +11,7 @@ pub struct PieceSvgs {
 }
 
 impl PieceSvgs {
-    pub fn load() -> Self {
+    pub fn load(set: &PieceSet) -> Self {
         let pieces = [
             (PieceType::King, PlayerColor::White),
             (PieceType::Queen, PlayerColor::White),
@@ -31,7 +32,11 @@ impl PieceSvgs {
             let piece = Piece { piece_type, color };
             let path = format!("crates/chess/assets/pieces/{}", piece.svg_filename());
             if let Ok(svg) = load_svg(&path) {
-                svgs.insert((piece_type, color), svg);
+                let data = set.paint(svg.data(), color);
+                svgs.insert(
+                    (piece_type, color),
+                    Svg::new(data, svg.width(), svg.height()),
+                );
             }
         }
 
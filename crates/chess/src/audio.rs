@@ -0,0 +1,59 @@
+use std::cell::RefCell;
+use std::time::Duration;
+
+use rodio::source::{SineWave, Source};
+use rodio::{OutputStream, OutputStreamHandle};
+
+/// Audio feedback for something that happened on the board.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Move,
+    Capture,
+    Check,
+    GameEnd,
+}
+
+thread_local! {
+    // Playback stops when the stream is dropped, so it is opened once and
+    // kept. `Some(None)` means there is no usable output device.
+    static OUTPUT: RefCell<Option<Option<(OutputStream, OutputStreamHandle)>>> =
+        const { RefCell::new(None) };
+}
+
+/// A short sine tone, faded in so it doesn't click.
+fn tone(frequency: f32, millis: u64) -> impl Source<Item = f32> + Send {
+    SineWave::new(frequency)
+        .take_duration(Duration::from_millis(millis))
+        .fade_in(Duration::from_millis(5))
+        .amplify(0.2)
+}
+
+fn after(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// Play `sound` without waiting for it to finish. Does nothing if no audio
+/// device is available.
+pub fn play(sound: Sound) {
+    OUTPUT.with(|output| {
+        let mut output = output.borrow_mut();
+        let Some((_, handle)) = output.get_or_insert_with(|| OutputStream::try_default().ok())
+        else {
+            return;
+        };
+
+        // Sound is only feedback, so playback errors are ignored
+        let _ = match sound {
+            Sound::Move => handle.play_raw(tone(660.0, 60)),
+            Sound::Capture => {
+                handle.play_raw(tone(330.0, 60).mix(tone(220.0, 90).delay(after(30))))
+            }
+            Sound::Check => handle.play_raw(tone(880.0, 70).mix(tone(880.0, 70).delay(after(110)))),
+            Sound::GameEnd => handle.play_raw(
+                tone(523.0, 140)
+                    .mix(tone(659.0, 140).delay(after(150)))
+                    .mix(tone(784.0, 300).delay(after(300))),
+            ),
+        };
+    });
+}
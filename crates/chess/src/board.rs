@@ -89,3 +89,64 @@ pub fn setup_initial_board() -> [[Option<Piece>; 8]; 8] {
 
     board
 }
+
+/// Number of Chess960 starting positions.
+pub const CHESS960_POSITIONS: u32 = 960;
+
+/// The FEN of Chess960 starting position `index` (0 to 959), in the
+/// standard numbering where 518 is the usual setup. The castling field names
+/// the rooks' files (Shredder-FEN).
+pub fn chess960_fen(index: u32) -> String {
+    let mut rank = [None; 8];
+    let mut index = index % CHESS960_POSITIONS;
+
+    // Bishops on opposite colors, then the queen and knights on the free
+    // squares left to right
+    rank[(index % 4) as usize * 2 + 1] = Some('b');
+    index /= 4;
+    rank[(index % 4) as usize * 2] = Some('b');
+    index /= 4;
+    place_on_free_square(&mut rank, (index % 6) as usize, 'q');
+    index /= 6;
+    let (first, second) = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 2),
+        (1, 3),
+        (1, 4),
+        (2, 3),
+        (2, 4),
+        (3, 4),
+    ][index as usize];
+    // The second knight's square is counted before the first is taken
+    place_on_free_square(&mut rank, second, 'n');
+    place_on_free_square(&mut rank, first, 'n');
+
+    // The king goes between the rooks on the last three squares
+    for piece in ['r', 'k', 'r'] {
+        place_on_free_square(&mut rank, 0, piece);
+    }
+
+    let black: String = rank.iter().map(|p| p.unwrap_or('r')).collect();
+    let rook_files: Vec<usize> = (0..8).filter(|&col| rank[col] == Some('r')).collect();
+    let castling: String = [rook_files[1], rook_files[0]]
+        .iter()
+        .map(|&col| (b'A' + col as u8) as char)
+        .collect();
+    format!(
+        "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w {}{} - 0 1",
+        black,
+        black.to_ascii_uppercase(),
+        castling,
+        castling.to_ascii_lowercase()
+    )
+}
+
+/// Put `piece` on the `n`th empty square of `rank`.
+fn place_on_free_square(rank: &mut [Option<char>; 8], n: usize, piece: char) {
+    if let Some(square) = rank.iter_mut().filter(|s| s.is_none()).nth(n) {
+        *square = Some(piece);
+    }
+}
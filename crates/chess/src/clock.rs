@@ -0,0 +1,144 @@
+use crate::types::PlayerColor;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Time each player starts with, plus the time added after each of their
+/// moves.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeControl {
+    pub initial: Duration,
+    pub increment: Duration,
+}
+
+impl TimeControl {
+    /// 3 minutes plus 2 seconds per move.
+    pub const BLITZ: TimeControl = TimeControl::new(3, 2);
+    /// 10 minutes plus 5 seconds per move.
+    pub const RAPID: TimeControl = TimeControl::new(10, 5);
+
+    pub const fn new(minutes: u64, increment_secs: u64) -> Self {
+        Self {
+            initial: Duration::from_secs(minutes * 60),
+            increment: Duration::from_secs(increment_secs),
+        }
+    }
+
+    pub fn minutes(&self) -> u64 {
+        self.initial.as_secs() / 60
+    }
+
+    pub fn increment_secs(&self) -> u64 {
+        self.increment.as_secs()
+    }
+
+    /// Short name such as `"3+2"`.
+    pub fn label(&self) -> String {
+        format!("{}+{}", self.minutes(), self.increment_secs())
+    }
+}
+
+/// A pair of chess clocks. Only the clock of the side to move runs.
+///
+/// Saved clocks keep the time banked by each side and come back stopped.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ChessClock {
+    pub control: TimeControl,
+    white: Duration,
+    black: Duration,
+    /// The side whose clock is running, and when it was last started
+    #[serde(skip)]
+    running: Option<(PlayerColor, Instant)>,
+}
+
+impl ChessClock {
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            white: control.initial,
+            black: control.initial,
+            running: None,
+        }
+    }
+
+    fn banked(&mut self, color: PlayerColor) -> &mut Duration {
+        match color {
+            PlayerColor::White => &mut self.white,
+            PlayerColor::Black => &mut self.black,
+        }
+    }
+
+    /// Subtract the running side's elapsed time from its clock and stop it.
+    fn bank_running(&mut self, now: Instant) -> Option<PlayerColor> {
+        let (side, started) = self.running.take()?;
+        let banked = self.banked(side);
+        *banked = banked.saturating_sub(now - started);
+        Some(side)
+    }
+
+    pub fn remaining(&self, color: PlayerColor) -> Duration {
+        let banked = match color {
+            PlayerColor::White => self.white,
+            PlayerColor::Black => self.black,
+        };
+        match self.running {
+            Some((side, started)) if side == color => banked.saturating_sub(started.elapsed()),
+            _ => banked,
+        }
+    }
+
+    pub fn running_side(&self) -> Option<PlayerColor> {
+        self.running.map(|(side, _)| side)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// The side that has run out of time, if any.
+    pub fn flagged(&self) -> Option<PlayerColor> {
+        self.running_side()
+            .filter(|&side| self.remaining(side).is_zero())
+    }
+
+    /// `mover` has just moved: add the increment to their clock and start
+    /// their opponent's. The first press starts the clocks.
+    pub fn press(&mut self, mover: PlayerColor) {
+        let now = Instant::now();
+        if self.bank_running(now) == Some(mover) {
+            let increment = self.control.increment;
+            *self.banked(mover) += increment;
+        }
+        self.running = Some((mover.opposite(), now));
+    }
+
+    /// Run `color`'s clock instead, without adding any increment. Does
+    /// nothing if the clocks are stopped.
+    pub fn switch_to(&mut self, color: PlayerColor) {
+        let now = Instant::now();
+        if self.bank_running(now).is_some() {
+            self.running = Some((color, now));
+        }
+    }
+
+    /// Run `color`'s clock, starting the clocks if they are stopped.
+    pub fn start(&mut self, color: PlayerColor) {
+        let now = Instant::now();
+        self.bank_running(now);
+        self.running = Some((color, now));
+    }
+
+    pub fn stop(&mut self) {
+        self.bank_running(Instant::now());
+    }
+}
+
+/// Format a clock reading as `m:ss`, with tenths of a second once under
+/// ten seconds.
+pub fn format_clock(time: Duration) -> String {
+    if time < Duration::from_secs(10) {
+        format!("0:{:02}.{}", time.as_secs(), time.subsec_millis() / 100)
+    } else {
+        let secs = time.as_secs();
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -0,0 +1,205 @@
+use crate::check::{is_in_check, Board};
+use crate::moves::{castling_side, generate_legal_moves, CastlingRights, CastlingSide, Move};
+use crate::types::{Piece, PieceType, PlayerColor};
+
+/// Default search depth in plies.
+pub const DEFAULT_DEPTH: u8 = 3;
+/// Deepest search the side panel offers.
+pub const MAX_DEPTH: u8 = 5;
+
+const MATE_SCORE: i32 = 100_000;
+
+/// How deep the computer looks before answering a draw offer.
+const DRAW_OFFER_DEPTH: u8 = 2;
+/// How far ahead, in centipawns, the computer can think it is and still
+/// take a draw.
+const DRAW_MARGIN: i32 = 50;
+
+/// Everything the engine needs to know about a position. Unlike `ChessGame`
+/// this is `Send`, so it can be searched on a background thread.
+#[derive(Clone, Copy)]
+pub struct Position {
+    pub board: Board,
+    pub turn: PlayerColor,
+    pub en_passant_target: Option<usize>,
+    pub castling: CastlingRights,
+}
+
+impl Position {
+    fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = generate_legal_moves(
+            &self.board,
+            self.turn,
+            self.en_passant_target,
+            &self.castling,
+        );
+        // Searching captures first prunes far more of the tree
+        moves.sort_by_key(|m| {
+            std::cmp::Reverse(
+                self.board[m.to.0][m.to.1]
+                    .map(|p| p.piece_type.points())
+                    .unwrap_or(0),
+            )
+        });
+        moves
+    }
+
+    /// The position after `mv`. Pawns reaching the last rank become queens.
+    fn play(&self, mv: Move) -> Position {
+        let mut next = *self;
+        let (from_row, from_col) = mv.from;
+        let (to_row, to_col) = mv.to;
+        let piece = self.board[from_row][from_col].unwrap();
+
+        // En passant captures the pawn beside the moving pawn
+        if piece.piece_type == PieceType::Pawn
+            && from_col != to_col
+            && self.board[to_row][to_col].is_none()
+        {
+            next.board[from_row][to_col] = None;
+        }
+
+        // Castling moves the king and rook to their files, which in Chess960
+        // can be where either of them started
+        let castling = castling_side(&self.board, mv, &self.castling);
+        if let Some(side) = castling {
+            let (king_to, rook_to) = side.destinations();
+            next.board[from_row][from_col] = None;
+            next.board[from_row][self.castling.rook_file(side)] = None;
+            next.board[from_row][rook_to] = Some(Piece {
+                piece_type: PieceType::Rook,
+                color: piece.color,
+            });
+            next.board[from_row][king_to] = Some(piece);
+        }
+
+        next.en_passant_target = None;
+        if piece.piece_type == PieceType::Pawn && from_row.abs_diff(to_row) == 2 {
+            next.en_passant_target = Some(to_col);
+        }
+
+        match (piece.piece_type, piece.color) {
+            (PieceType::King, PlayerColor::White) => next.castling.white_king_moved = true,
+            (PieceType::King, PlayerColor::Black) => next.castling.black_king_moved = true,
+            _ => {}
+        }
+        let queenside = self.castling.rook_file(CastlingSide::Queenside);
+        let kingside = self.castling.rook_file(CastlingSide::Kingside);
+        for (row, col) in [mv.from, mv.to] {
+            match (row, col) {
+                (7, col) if col == queenside => next.castling.white_rook_a_moved = true,
+                (7, col) if col == kingside => next.castling.white_rook_h_moved = true,
+                (0, col) if col == queenside => next.castling.black_rook_a_moved = true,
+                (0, col) if col == kingside => next.castling.black_rook_h_moved = true,
+                _ => {}
+            }
+        }
+
+        if castling.is_none() {
+            next.board[to_row][to_col] = next.board[from_row][from_col].take();
+        }
+        if piece.piece_type == PieceType::Pawn && (to_row == 0 || to_row == 7) {
+            next.board[to_row][to_col] = Some(Piece {
+                piece_type: PieceType::Queen,
+                color: piece.color,
+            });
+        }
+
+        next.turn = self.turn.opposite();
+        next
+    }
+}
+
+/// Find the best move for the side to move, searching `depth` plies ahead.
+/// Returns `None` if there are no legal moves.
+///
+/// The returned move never includes a promotion choice; pawns that reach
+/// the last rank should be promoted to a queen.
+pub fn best_move(position: &Position, depth: u8) -> Option<Move> {
+    let depth = depth.max(1);
+    let mut best = None;
+    let mut alpha = -MATE_SCORE * 2;
+    let beta = MATE_SCORE * 2;
+
+    for mv in position.legal_moves() {
+        let score = -negamax(&position.play(mv), depth - 1, -beta, -alpha);
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some(mv);
+        }
+    }
+    best
+}
+
+/// Whether `color` takes a draw in this position: only if it doesn't think
+/// it is clearly better.
+pub fn accepts_draw(position: &Position, color: PlayerColor) -> bool {
+    let score = negamax(position, DRAW_OFFER_DEPTH, -MATE_SCORE * 2, MATE_SCORE * 2);
+    let score = if position.turn == color {
+        score
+    } else {
+        -score
+    };
+    score <= DRAW_MARGIN
+}
+
+/// Alpha-beta search, scored from the point of view of the side to move.
+fn negamax(position: &Position, depth: u8, mut alpha: i32, beta: i32) -> i32 {
+    if depth == 0 {
+        return evaluate(position);
+    }
+
+    let moves = position.legal_moves();
+    if moves.is_empty() {
+        return if is_in_check(&position.board, position.turn) {
+            // Prefer the quickest mate (and the slowest loss)
+            -MATE_SCORE - depth as i32
+        } else {
+            0
+        };
+    }
+
+    for mv in moves {
+        let score = -negamax(&position.play(mv), depth - 1, -beta, -alpha);
+        if score >= beta {
+            return beta;
+        }
+        alpha = alpha.max(score);
+    }
+    alpha
+}
+
+/// Material plus a small bonus for central pieces and advanced pawns, from
+/// the point of view of the side to move.
+fn evaluate(position: &Position) -> i32 {
+    let mut score = 0;
+    for (row, rank) in position.board.iter().enumerate() {
+        for (col, square) in rank.iter().enumerate() {
+            let Some(piece) = square else {
+                continue;
+            };
+            let mut value = piece.piece_type.points() * 100;
+
+            // Distance from the center, 0 (center) to 6 (corner)
+            let center = (2 * row).abs_diff(7) / 2 + (2 * col).abs_diff(7) / 2;
+            match piece.piece_type {
+                PieceType::Knight | PieceType::Bishop => value += 12 - 4 * center as i32,
+                PieceType::Pawn => {
+                    let advanced = match piece.color {
+                        PlayerColor::White => 6usize.saturating_sub(row),
+                        PlayerColor::Black => row.saturating_sub(1),
+                    };
+                    value += 5 * advanced as i32;
+                }
+                _ => {}
+            }
+
+            if piece.color == position.turn {
+                score += value;
+            } else {
+                score -= value;
+            }
+        }
+    }
+    score
+}
@@ -1,12 +1,17 @@
 use crate::check::Board;
-use crate::moves::CastlingRights;
+use crate::moves::{CastlingRights, CastlingSide};
 use crate::types::{Piece, PieceType, PlayerColor};
 
+/// The standard starting position.
+pub const INITIAL_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
 pub struct FenState {
     pub board: Board,
     pub turn: PlayerColor,
     pub castling: CastlingRights,
     pub en_passant_target: Option<usize>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
 }
 
 pub fn parse_fen(fen: &str) -> Result<FenState, String> {
@@ -17,17 +22,106 @@ pub fn parse_fen(fen: &str) -> Result<FenState, String> {
 
     let board = parse_board(parts[0])?;
     let turn = parse_turn(parts[1])?;
-    let castling = parse_castling(parts[2]);
+    let castling = parse_castling(parts[2], &board);
     let en_passant_target = parse_en_passant(parts[3]);
 
+    // The move counters are often left out; default to the start of a game
+    let halfmove_clock = match parts.get(4) {
+        Some(n) => n
+            .parse()
+            .map_err(|_| format!("Invalid halfmove clock: {}", n))?,
+        None => 0,
+    };
+    let fullmove_number = match parts.get(5) {
+        Some(n) => n
+            .parse()
+            .map_err(|_| format!("Invalid fullmove number: {}", n))?,
+        None => 1,
+    };
+
     Ok(FenState {
         board,
         turn,
         castling,
         en_passant_target,
+        halfmove_clock,
+        fullmove_number,
     })
 }
 
+pub fn write_fen(state: &FenState) -> String {
+    let mut placement = String::new();
+    for (rank_idx, rank) in state.board.iter().enumerate() {
+        if rank_idx > 0 {
+            placement.push('/');
+        }
+        let mut empty = 0;
+        for square in rank {
+            match square {
+                Some(piece) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    placement.push(piece_to_char(*piece));
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        }
+    }
+
+    let turn = match state.turn {
+        PlayerColor::White => "w",
+        PlayerColor::Black => "b",
+    };
+
+    // Chess960 names the rooks' files instead (Shredder-FEN), e.g. `HAha`
+    let castling = &state.castling;
+    let (kingside, queenside) = match castling.chess960 {
+        Some(_) => (
+            (b'A' + castling.rook_file(CastlingSide::Kingside) as u8) as char,
+            (b'A' + castling.rook_file(CastlingSide::Queenside) as u8) as char,
+        ),
+        None => ('K', 'Q'),
+    };
+    let mut rights = String::new();
+    if !castling.white_king_moved && !castling.white_rook_h_moved {
+        rights.push(kingside);
+    }
+    if !castling.white_king_moved && !castling.white_rook_a_moved {
+        rights.push(queenside);
+    }
+    if !castling.black_king_moved && !castling.black_rook_h_moved {
+        rights.push(kingside.to_ascii_lowercase());
+    }
+    if !castling.black_king_moved && !castling.black_rook_a_moved {
+        rights.push(queenside.to_ascii_lowercase());
+    }
+    if rights.is_empty() {
+        rights.push('-');
+    }
+
+    // The target square is behind the pawn that just moved two squares
+    let en_passant = match state.en_passant_target {
+        Some(col) => {
+            let rank = match state.turn {
+                PlayerColor::White => 6,
+                PlayerColor::Black => 3,
+            };
+            format!("{}{}", (b'a' + col as u8) as char, rank)
+        }
+        None => "-".to_string(),
+    };
+
+    format!(
+        "{} {} {} {} {} {}",
+        placement, turn, rights, en_passant, state.halfmove_clock, state.fullmove_number
+    )
+}
+
 fn parse_board(placement: &str) -> Result<Board, String> {
     let mut board: Board = [[None; 8]; 8];
     let ranks: Vec<&str> = placement.split('/').collect();
@@ -84,6 +178,21 @@ fn char_to_piece(c: char) -> Result<Piece, String> {
     Ok(Piece { piece_type, color })
 }
 
+fn piece_to_char(piece: Piece) -> char {
+    let c = match piece.piece_type {
+        PieceType::King => 'k',
+        PieceType::Queen => 'q',
+        PieceType::Rook => 'r',
+        PieceType::Bishop => 'b',
+        PieceType::Knight => 'n',
+        PieceType::Pawn => 'p',
+    };
+    match piece.color {
+        PlayerColor::White => c.to_ascii_uppercase(),
+        PlayerColor::Black => c,
+    }
+}
+
 fn parse_turn(turn: &str) -> Result<PlayerColor, String> {
     match turn {
         "w" => Ok(PlayerColor::White),
@@ -92,15 +201,76 @@ fn parse_turn(turn: &str) -> Result<PlayerColor, String> {
     }
 }
 
-fn parse_castling(castling: &str) -> CastlingRights {
-    CastlingRights {
-        white_king_moved: !castling.contains('K') && !castling.contains('Q'),
-        black_king_moved: !castling.contains('k') && !castling.contains('q'),
-        white_rook_h_moved: !castling.contains('K'),
-        white_rook_a_moved: !castling.contains('Q'),
-        black_rook_h_moved: !castling.contains('k'),
-        black_rook_a_moved: !castling.contains('q'),
+/// Read the castling field: `KQkq`, or the files of the castling rooks
+/// (Shredder-FEN, e.g. `HAha`) for Chess960. `K` and `Q` stand for the
+/// outermost rooks, so they work for Chess960 positions too (X-FEN).
+fn parse_castling(castling: &str, board: &Board) -> CastlingRights {
+    let mut rights = CastlingRights {
+        white_king_moved: true,
+        black_king_moved: true,
+        white_rook_a_moved: true,
+        white_rook_h_moved: true,
+        black_rook_a_moved: true,
+        black_rook_h_moved: true,
+        chess960: None,
+    };
+    let mut queenside_file = 0;
+    let mut kingside_file = 7;
+    let mut chess960 = false;
+
+    for c in castling.chars().filter(|c| c.is_ascii_alphabetic()) {
+        let color = if c.is_ascii_uppercase() {
+            PlayerColor::White
+        } else {
+            PlayerColor::Black
+        };
+        let row = match color {
+            PlayerColor::White => 7,
+            PlayerColor::Black => 0,
+        };
+        let is_own =
+            |col: &usize, piece_type| board[row][*col] == Some(Piece { piece_type, color });
+        let king_col = (0..8).find(|col| is_own(col, PieceType::King)).unwrap_or(4);
+        let rook_col = match c.to_ascii_lowercase() {
+            'k' => (king_col..8)
+                .rev()
+                .find(|col| is_own(col, PieceType::Rook))
+                .unwrap_or(7),
+            'q' => (0..king_col)
+                .find(|col| is_own(col, PieceType::Rook))
+                .unwrap_or(0),
+            file @ 'a'..='h' => {
+                chess960 = true;
+                file as usize - 'a' as usize
+            }
+            _ => continue,
+        };
+        if king_col != 4 {
+            chess960 = true;
+        }
+
+        let kingside = rook_col > king_col;
+        if kingside {
+            kingside_file = rook_col;
+        } else {
+            queenside_file = rook_col;
+        }
+        match (color, kingside) {
+            (PlayerColor::White, true) => rights.white_rook_h_moved = false,
+            (PlayerColor::White, false) => rights.white_rook_a_moved = false,
+            (PlayerColor::Black, true) => rights.black_rook_h_moved = false,
+            (PlayerColor::Black, false) => rights.black_rook_a_moved = false,
+        }
+        match color {
+            PlayerColor::White => rights.white_king_moved = false,
+            PlayerColor::Black => rights.black_king_moved = false,
+        }
+    }
+
+    if chess960 || queenside_file != 0 || kingside_file != 7 {
+        rights.chess960 = Some((queenside_file, kingside_file));
     }
+    rights
 }
 
 fn parse_en_passant(ep: &str) -> Option<usize> {
@@ -120,3 +290,65 @@ fn parse_en_passant(ep: &str) -> Option<usize> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{chess960_fen, CHESS960_POSITIONS};
+
+    /// Read `fen`, write it back out and read that again.
+    fn round_trip(fen: &str) -> (FenState, String) {
+        let written = write_fen(&parse_fen(fen).unwrap());
+        (parse_fen(&written).unwrap(), written)
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 12 40",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            "rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 1",
+        ] {
+            assert_eq!(round_trip(fen).1, fen);
+        }
+    }
+
+    #[test]
+    fn en_passant_square_round_trips() {
+        let (state, written) = round_trip("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2");
+        assert_eq!(state.en_passant_target, Some(3));
+        assert!(written.contains(" d6 "));
+
+        // The square is behind the pawn, on the other side for Black to move
+        let (state, written) = round_trip("4k3/8/8/8/Pp6/8/8/4K3 b - a3 0 1");
+        assert_eq!(state.en_passant_target, Some(0));
+        assert!(written.contains(" a3 "));
+    }
+
+    #[test]
+    fn chess960_castling_round_trips() {
+        for index in 0..CHESS960_POSITIONS {
+            let fen = chess960_fen(index);
+            let (state, written) = round_trip(&fen);
+            assert_eq!(written, fen);
+            assert!(state.castling.chess960.is_some(), "{}", fen);
+        }
+
+        // Rights for one side only, named by the rooks' files
+        let (state, written) = round_trip("1r4kr/8/8/8/8/8/8/1R4KR w Hb - 0 1");
+        assert_eq!(state.castling.chess960, Some((1, 7)));
+        assert!(!state.castling.white_rook_h_moved && state.castling.white_rook_a_moved);
+        assert!(state.castling.black_rook_h_moved && !state.castling.black_rook_a_moved);
+        assert!(written.contains(" Hb "));
+    }
+
+    #[test]
+    fn x_fen_castling_is_written_as_shredder_fen() {
+        // `K` and `Q` stand for the outermost rooks
+        let (state, written) = round_trip("1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1");
+        assert_eq!(state.castling.chess960, Some((1, 7)));
+        assert!(written.contains(" HBhb "));
+    }
+}
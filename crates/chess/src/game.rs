@@ -1,25 +1,53 @@
 use crate::assets::PieceSvgs;
+use crate::audio::{self, Sound};
 use crate::board;
 use crate::check::{
     find_king, is_checkmate, is_in_check, is_insufficient_material, is_stalemate, Board,
 };
-use crate::fen::parse_fen;
-use crate::moves::{is_valid_move, CastlingRights, Move};
+use crate::clock::{ChessClock, TimeControl};
+use crate::engine::{self, Position};
+use crate::fen::{parse_fen, write_fen, FenState, INITIAL_FEN};
+use crate::moves::{
+    self, generate_legal_moves, is_premove_target, move_to_uci, parse_uci, CastlingRights,
+    CastlingSide, Move,
+};
+use crate::net::{self, Connection, Message, NetEvent};
+use crate::openings::{self, Opening};
+use crate::pgn::{self, normalize_san, parse_pgn, write_pgn};
+use crate::puzzles::{Puzzle, PUZZLES};
+use crate::save::{SavedGame, Slot};
+use crate::settings::Settings;
+use crate::theme::{self, BoardTheme, PieceSet};
 use crate::types::{Piece, PieceType, PlayerColor};
-
-#[derive(Clone, Copy, PartialEq, Eq)]
+use crate::uci::{self, EngineEvent, Score, UciEngine};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use vitae::toast::{self, Toast};
+use vitae::{clear_timer, set_interval, set_timeout, spawn_task, TimerId};
+
+/// How often the clocks are redrawn while running.
+const CLOCK_REFRESH: Duration = Duration::from_millis(100);
+/// Pause before a puzzle's reply, so the player sees their move land.
+const PUZZLE_REPLY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DrawReason {
     Stalemate,
     InsufficientMaterial,
     FiftyMoveRule,
     ThreefoldRepetition,
+    Agreement,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameResult {
     Ongoing,
     Checkmate(PlayerColor), // The color that won
     Draw(DrawReason),
+    Timeout(PlayerColor),     // The color that won
+    Resignation(PlayerColor), // The color that won
 }
 
 #[derive(Clone)]
@@ -36,6 +64,8 @@ pub struct MoveRecord {
     pub prev_castling_rights: CastlingRights,
     pub prev_halfmove_clock: u32,
     pub notation: String,
+    /// Standard algebraic notation, e.g. `Nbd7` or `exd8=Q+`.
+    pub san: String,
 }
 
 #[derive(Clone, Copy)]
@@ -45,16 +75,149 @@ pub struct PendingPromotion {
     pub captured: Option<Piece>,
 }
 
+/// A piece being dragged with the mouse.
+#[derive(Clone, Copy)]
+pub struct PieceDrag {
+    pub from: (usize, usize),
+    /// Cursor position in window coordinates, once it has moved
+    pub cursor: Option<(f32, f32)>,
+    /// Whether the piece was already selected when it was picked up, so
+    /// releasing it in place deselects it
+    was_selected: bool,
+}
+
+/// Cursor travel (in pixels) that turns a right click into an arrow drag.
+const ARROW_DRAG_DISTANCE: f32 = 8.0;
+
+/// Arrows and highlighted squares drawn with the right mouse button. They
+/// are cleared by the next move.
+#[derive(Clone, Default)]
+pub struct Annotations {
+    pub arrows: Vec<((usize, usize), (usize, usize))>,
+    pub squares: Vec<(usize, usize)>,
+}
+
+/// A move queued during the opponent's turn, played as soon as it is the
+/// player's turn if it is legal then.
 #[derive(Clone, Copy, PartialEq, Eq)]
-pub enum CastlingSide {
-    Kingside,
-    Queenside,
+pub struct Premove {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    /// Premoved pawns promote to a queen
+    promotion: Option<PieceType>,
+}
+
+/// A right-button press on the board that becomes an arrow or a square
+/// highlight when released.
+#[derive(Clone, Copy)]
+struct AnnotationDraw {
+    from: (usize, usize),
+    /// Where the cursor was first seen after the press
+    start: Option<(f32, f32)>,
+    dragged: bool,
+}
+
+/// A puzzle being solved.
+#[derive(Clone, Copy)]
+pub struct PuzzleState {
+    /// Index into `PUZZLES`
+    pub index: usize,
+    /// The side the player solves for
+    pub solver: PlayerColor,
+    /// Moves of the solution played so far
+    progress: usize,
+    /// Whether a wrong move was tried, which breaks the streak
+    pub missed: bool,
+    pub solved: bool,
+}
+
+impl PuzzleState {
+    pub fn puzzle(&self) -> &'static Puzzle {
+        &PUZZLES[self.index]
+    }
+}
+
+/// Which end of a network game this board is. The host plays White and
+/// owns the game: the guest follows its moves, resets and loads.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NetworkRole {
+    Host { port: u16 },
+    Guest { host: SocketAddr },
+}
+
+/// A game against another instance over the network.
+#[derive(Clone)]
+pub struct NetworkGame {
+    pub role: NetworkRole,
+    pub connected: bool,
+    connection: Connection,
+}
+
+impl NetworkGame {
+    pub fn local_color(&self) -> PlayerColor {
+        match self.role {
+            NetworkRole::Host { .. } => PlayerColor::White,
+            NetworkRole::Guest { .. } => PlayerColor::Black,
+        }
+    }
+
+    pub fn is_host(&self) -> bool {
+        matches!(self.role, NetworkRole::Host { .. })
+    }
+
+    /// The connection state, for the side panel.
+    pub fn status(&self) -> String {
+        match (self.role, self.connected) {
+            (_, true) => format!("Connected, you play {}", color_name(self.local_color())),
+            (NetworkRole::Host { port }, false) => {
+                format!("Hosting on port {}, waiting for opponent...", port)
+            }
+            (NetworkRole::Guest { host }, false) => format!("Connecting to {}...", host),
+        }
+    }
+}
+
+/// Live analysis of the current position by an external engine.
+#[derive(Clone)]
+pub struct Analysis {
+    engine: UciEngine,
+    /// Searches started so far
+    searches: u64,
+    /// The number of the search of the current position, matching the
+    /// engine's count of finished searches when it started
+    search: u64,
+    /// The side to move in the position being searched
+    turn: PlayerColor,
+    pub depth: u32,
+    /// From White's point of view
+    pub score: Option<Score>,
+    /// The engine's preferred move, in UCI notation
+    pub best_move: Option<String>,
+}
+
+fn color_name(color: PlayerColor) -> &'static str {
+    match color {
+        PlayerColor::White => "White",
+        PlayerColor::Black => "Black",
+    }
 }
 
 #[derive(Clone)]
 pub struct ChessGame {
     pub board: Board,
     pub selected: Option<(usize, usize)>,
+    pub drag: Option<PieceDrag>,
+    /// Whether the view should slide the pieces of the last move into
+    /// place. Moves dropped by dragging are already there.
+    pub animate_last_move: bool,
+    pub annotations: Annotations,
+    annotation_draw: Option<AnnotationDraw>,
+    pub premove: Option<Premove>,
+    /// The legal moves of the side to move, found once per position rather
+    /// than for every square the view highlights.
+    legal_moves: Vec<Move>,
+    /// The side offering a draw, until the other side answers or moves.
+    pub draw_offer: Option<PlayerColor>,
     pub last_move: Option<String>,
     pub turn: PlayerColor,
     pub pieces: PieceSvgs,
@@ -68,22 +231,69 @@ pub struct ChessGame {
     pub white_rook_h_moved: bool,
     pub black_rook_a_moved: bool,
     pub black_rook_h_moved: bool,
+    /// Whether new games use a random Chess960 starting position.
+    pub chess960: bool,
+    /// The starting files of the queenside and kingside rooks, if the
+    /// current game is Chess960.
+    chess960_rooks: Option<(usize, usize)>,
     pub result: GameResult,
     pub history: Vec<MoveRecord>,
     pub redo_stack: Vec<MoveRecord>,
     pub pending_promotion: Option<PendingPromotion>,
     pub halfmove_clock: u32,
+    /// Starts at 1 and increases after each of Black's moves.
+    pub fullmove_number: u32,
     pub position_history: Vec<u64>,
+    /// The FEN the game started from, if it didn't start from the initial
+    /// position.
+    pub start_fen: Option<String>,
+    pub vs_computer: bool,
+    pub computer_color: PlayerColor,
+    /// Search depth in plies.
+    pub computer_depth: u8,
+    pub computer_thinking: bool,
+    /// Identifies the running search, so results for a position that has
+    /// since changed (undo, reset, ...) are ignored.
+    search_id: u64,
+    pub time_control: Option<TimeControl>,
+    /// The time control offered by the "Custom" button.
+    pub custom_time_control: TimeControl,
+    /// `None` for untimed games.
+    pub clock: Option<ChessClock>,
+    /// Redraws the view while a clock is running.
+    clock_ticker: Option<TimerId>,
+    pub settings: Settings,
+    pub network: Option<NetworkGame>,
+    /// Identifies the current network game, so events from one that has
+    /// been left are ignored.
+    network_id: u64,
+    pub analysis: Option<Analysis>,
+    /// Identifies the running engine, so events from one that has been
+    /// turned off are ignored.
+    analysis_id: u64,
+    pub puzzle: Option<PuzzleState>,
+    /// Puzzles solved in a row without a wrong move.
+    pub puzzle_streak: u32,
+    /// Whether the game is due to be autosaved.
+    autosave_pending: bool,
 }
 
 impl ChessGame {
     pub fn new() -> Self {
-        Self {
+        let settings = Settings::load();
+        let mut game = Self {
             board: board::setup_initial_board(),
             selected: None,
+            drag: None,
+            animate_last_move: false,
+            annotations: Annotations::default(),
+            annotation_draw: None,
+            premove: None,
+            legal_moves: Vec::new(),
+            draw_offer: None,
             last_move: None,
             turn: PlayerColor::White,
-            pieces: PieceSvgs::load(),
+            pieces: PieceSvgs::load(theme::piece_set(&settings.piece_set)),
             flip_board: true,
             captured_by_white: Vec::new(),
             captured_by_black: Vec::new(),
@@ -94,11 +304,14 @@ impl ChessGame {
             white_rook_h_moved: false,
             black_rook_a_moved: false,
             black_rook_h_moved: false,
+            chess960: false,
+            chess960_rooks: None,
             result: GameResult::Ongoing,
             history: Vec::new(),
             redo_stack: Vec::new(),
             pending_promotion: None,
             halfmove_clock: 0,
+            fullmove_number: 1,
             position_history: vec![Self::hash_position(
                 &board::setup_initial_board(),
                 PlayerColor::White,
@@ -110,9 +323,34 @@ impl ChessGame {
                     white_rook_h_moved: false,
                     black_rook_a_moved: false,
                     black_rook_h_moved: false,
+                    chess960: None,
                 },
             )],
+            start_fen: None,
+            vs_computer: false,
+            computer_color: PlayerColor::Black,
+            computer_depth: engine::DEFAULT_DEPTH,
+            computer_thinking: false,
+            search_id: 0,
+            time_control: None,
+            custom_time_control: TimeControl::new(5, 0),
+            clock: None,
+            clock_ticker: None,
+            settings,
+            network: None,
+            network_id: 0,
+            analysis: None,
+            analysis_id: 0,
+            puzzle: None,
+            puzzle_streak: 0,
+            autosave_pending: false,
+        };
+        game.update_legal_moves();
+        // Pick up where the last run left off
+        if let Ok(saved) = SavedGame::load(Slot::Autosave) {
+            let _ = game.restore(saved);
         }
+        game
     }
 
     fn hash_position(
@@ -161,9 +399,12 @@ impl ChessGame {
             white_rook_h_moved: self.white_rook_h_moved,
             black_rook_a_moved: self.black_rook_a_moved,
             black_rook_h_moved: self.black_rook_h_moved,
+            chess960: self.chess960_rooks,
         }
     }
 
+    /// Whether moving from one square to another is legal for the side to
+    /// move.
     pub fn is_valid_move(
         &self,
         from_row: usize,
@@ -171,19 +412,96 @@ impl ChessGame {
         to_row: usize,
         to_col: usize,
     ) -> bool {
-        is_valid_move(
+        self.legal_moves
+            .contains(&Move::new(from_row, from_col, to_row, to_col))
+    }
+
+    fn update_legal_moves(&mut self) {
+        self.legal_moves = generate_legal_moves(
             &self.board,
-            from_row,
-            from_col,
-            to_row,
-            to_col,
+            self.turn,
             self.en_passant_target,
             &self.castling_rights(),
-        )
+        );
     }
 
-    pub fn select_square(&mut self, row: usize, col: usize) {
+    pub fn is_computer_turn(&self) -> bool {
+        self.vs_computer && self.turn == self.computer_color
+    }
+
+    /// Whether the player at this board may move now: not while the
+    /// computer or a network opponent is to move, nor before the opponent
+    /// has connected.
+    pub fn is_local_turn(&self) -> bool {
+        if let Some(puzzle) = &self.puzzle {
+            return !puzzle.solved && puzzle.solver == self.turn;
+        }
+        match &self.network {
+            Some(network) => network.connected && network.local_color() == self.turn,
+            None => !self.is_computer_turn(),
+        }
+    }
+
+    pub fn player_name(&self, color: PlayerColor) -> &'static str {
+        if let Some(network) = &self.network {
+            return match (color, network.local_color() == color) {
+                (PlayerColor::White, true) => "You (White)",
+                (PlayerColor::Black, true) => "You (Black)",
+                (PlayerColor::White, false) => "Opponent (White)",
+                (PlayerColor::Black, false) => "Opponent (Black)",
+            };
+        }
+        let is_computer = self.vs_computer && self.computer_color == color;
+        match (color, is_computer) {
+            (PlayerColor::White, true) => "Computer (White)",
+            (PlayerColor::Black, true) => "Computer (Black)",
+            (PlayerColor::White, false) => "Player 1 (White)",
+            (PlayerColor::Black, false) => "Player 2 (Black)",
+        }
+    }
+
+    /// The side the player at this board queues premoves for, while the
+    /// computer or a network opponent is to move.
+    fn premove_color(&self) -> Option<PlayerColor> {
+        if self.is_game_over() || self.puzzle.is_some() {
+            return None;
+        }
+        match &self.network {
+            Some(network) => Some(network.local_color()).filter(|&color| color != self.turn),
+            None if self.is_computer_turn() => Some(self.computer_color.opposite()),
+            None => None,
+        }
+    }
+
+    /// The side whose pieces the player can pick up: the side to move on
+    /// their turn, or their own side for a premove.
+    fn movable_color(&self) -> Option<PlayerColor> {
         if self.is_game_over() || self.is_awaiting_promotion() {
+            None
+        } else if self.is_local_turn() {
+            Some(self.turn)
+        } else {
+            self.premove_color()
+        }
+    }
+
+    /// Whether the selected piece on `from` can be sent to `to`: a legal
+    /// move on the player's turn, or a premove during the opponent's.
+    pub fn is_move_target(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        if self.is_local_turn() {
+            self.is_valid_move(from.0, from.1, to.0, to.1)
+        } else {
+            self.premove_color().is_some()
+                && is_premove_target(&self.board, from, to, &self.castling_rights())
+        }
+    }
+
+    pub fn select_square(&mut self, row: usize, col: usize) {
+        if let Some(color) = self.premove_color() {
+            self.select_premove(color, row, col);
+            return;
+        }
+        if self.is_game_over() || self.is_awaiting_promotion() || !self.is_local_turn() {
             return;
         }
 
@@ -215,6 +533,7 @@ impl ChessGame {
 
             self.make_move(Move::new(selected_row, selected_col, row, col), None);
             self.selected = None;
+            self.finish_local_move();
         } else {
             // Select piece (only if there's a piece and it's your turn)
             if let Some(piece) = self.board[row][col] {
@@ -225,6 +544,134 @@ impl ChessGame {
         }
     }
 
+    /// During the opponent's turn, pick a piece and then the square to
+    /// premove it to. Anything else cancels the premove.
+    fn select_premove(&mut self, color: PlayerColor, row: usize, col: usize) {
+        if let Some(from) = self.selected {
+            if self.is_move_target(from, (row, col)) {
+                let promotes = self.board[from.0][from.1]
+                    .is_some_and(|p| p.piece_type == PieceType::Pawn)
+                    && (row == 0 || row == 7);
+                self.premove = Some(Premove {
+                    from,
+                    to: (row, col),
+                    promotion: promotes.then_some(PieceType::Queen),
+                });
+                self.selected = None;
+                return;
+            }
+        }
+
+        self.premove = None;
+        self.selected = self.board[row][col]
+            .filter(|piece| piece.color == color && self.selected != Some((row, col)))
+            .map(|_| (row, col));
+    }
+
+    /// Play the queued premove now that it is the player's turn, or drop it
+    /// if the opponent's move made it illegal.
+    fn play_premove(&mut self) {
+        let Some(premove) = self.premove.take() else {
+            return;
+        };
+        let mv = Move::new(premove.from.0, premove.from.1, premove.to.0, premove.to.1);
+        if !self.is_local_turn() || self.is_game_over() || !self.is_legal(mv, premove.promotion) {
+            return;
+        }
+        self.selected = None;
+        self.make_move(mv, premove.promotion);
+        self.finish_local_move();
+    }
+
+    /// Mouse pressed on a square: move the selected piece there if it can
+    /// go there, otherwise pick up the piece on it.
+    pub fn press_square(&mut self, row: usize, col: usize) {
+        if let Some(from) = self.selected {
+            if self.is_move_target(from, (row, col)) {
+                self.select_square(row, col);
+                return;
+            }
+        }
+
+        let movable_color = self.movable_color();
+        let movable = self.board[row][col].is_some_and(|piece| Some(piece.color) == movable_color);
+        if !movable {
+            self.selected = None;
+            self.premove = None;
+            return;
+        }
+
+        self.drag = Some(PieceDrag {
+            from: (row, col),
+            cursor: None,
+            was_selected: self.selected == Some((row, col)),
+        });
+        self.selected = Some((row, col));
+    }
+
+    pub fn drag_to(&mut self, x: f32, y: f32) {
+        if let Some(drag) = &mut self.drag {
+            drag.cursor = Some((x, y));
+        }
+    }
+
+    /// Mouse released over a square, or anywhere for the square the drag
+    /// started from. Dropping on a legal target moves there; anything else
+    /// puts the piece back.
+    pub fn release_square(&mut self, row: usize, col: usize) {
+        let Some(drag) = self.drag.take() else {
+            return;
+        };
+        if (row, col) == drag.from {
+            // Pressing a selected piece and letting go deselects it
+            if drag.was_selected {
+                self.selected = None;
+            }
+            return;
+        }
+        self.select_square(row, col);
+        self.animate_last_move = false;
+    }
+
+    /// Right button pressed on a square: start drawing an annotation.
+    pub fn start_annotation(&mut self, row: usize, col: usize) {
+        self.annotation_draw = Some(AnnotationDraw {
+            from: (row, col),
+            start: None,
+            dragged: false,
+        });
+    }
+
+    pub fn annotation_drag(&mut self, x: f32, y: f32) {
+        let Some(draw) = &mut self.annotation_draw else {
+            return;
+        };
+        match draw.start {
+            None => draw.start = Some((x, y)),
+            Some((start_x, start_y)) => {
+                if (x - start_x).hypot(y - start_y) > ARROW_DRAG_DISTANCE {
+                    draw.dragged = true;
+                }
+            }
+        }
+    }
+
+    /// Right button released over a square, or anywhere for the square it
+    /// was pressed on. A click toggles a square highlight, a drag between
+    /// two squares toggles an arrow, and a drag that ends where it started
+    /// (or off the board) is ignored.
+    pub fn finish_annotation(&mut self, row: usize, col: usize) {
+        let Some(draw) = self.annotation_draw.take() else {
+            return;
+        };
+
+        if draw.from != (row, col) {
+            toggle(&mut self.annotations.arrows, (draw.from, (row, col)));
+        } else if !draw.dragged {
+            toggle(&mut self.annotations.squares, (row, col));
+        }
+    }
+
     pub fn promote_to(&mut self, piece_type: PieceType) {
         let pending = match self.pending_promotion.take() {
             Some(p) => p,
@@ -235,6 +682,615 @@ impl ChessGame {
             Move::new(pending.from.0, pending.from.1, pending.to.0, pending.to.1),
             Some(piece_type),
         );
+        self.finish_local_move();
+    }
+
+    /// Follow up on a move made at this board: play its sound, pass it on to
+    /// a network opponent, check it against the puzzle, and let the computer
+    /// reply.
+    fn finish_local_move(&mut self) {
+        self.play_move_sound();
+        self.send_last_move();
+        self.check_puzzle_move();
+        self.start_computer_move();
+    }
+
+    /// Start the next built-in puzzle. Skipping one unsolved breaks the
+    /// streak.
+    pub fn next_puzzle(&mut self) {
+        let index = match self.puzzle {
+            Some(puzzle) => {
+                if !puzzle.solved {
+                    self.puzzle_streak = 0;
+                }
+                (puzzle.index + 1) % PUZZLES.len()
+            }
+            None => 0,
+        };
+
+        self.vs_computer = false;
+        if let Err(err) = self.load_fen(PUZZLES[index].fen) {
+            toast::push(Toast::error(format!("Couldn't load puzzle: {}", err)));
+            return;
+        }
+        self.puzzle = Some(PuzzleState {
+            index,
+            solver: self.turn,
+            progress: 0,
+            missed: false,
+            solved: false,
+        });
+    }
+
+    /// Check the move just made against the puzzle's solution. A wrong move
+    /// is taken back; a right one is answered with the next reply.
+    fn check_puzzle_move(&mut self) {
+        let (Some(mut puzzle), Some(record)) = (self.puzzle, self.history.last()) else {
+            return;
+        };
+        let solution = puzzle.puzzle().solution();
+        let played = move_to_uci(
+            Move::new(record.from.0, record.from.1, record.to.0, record.to.1),
+            record.promotion,
+        );
+        // Any mate finishes it, in case there is more than one
+        let is_last = puzzle.progress + 1 == solution.len();
+        let correct = solution.get(puzzle.progress) == Some(&played.as_str())
+            || (is_last && matches!(self.result, GameResult::Checkmate(_)));
+
+        if !correct {
+            puzzle.missed = true;
+            self.puzzle = Some(puzzle);
+            self.puzzle_streak = 0;
+            self.undo_move();
+            self.redo_stack.clear();
+            toast::push(Toast::warning("Not the best move, try again"));
+            return;
+        }
+
+        puzzle.progress += 1;
+        if puzzle.progress == solution.len() {
+            puzzle.solved = true;
+            toast::push(Toast::success("Puzzle solved!"));
+            if !puzzle.missed {
+                self.puzzle_streak += 1;
+                if self.puzzle_streak > self.settings.best_puzzle_streak {
+                    self.settings.best_puzzle_streak = self.puzzle_streak;
+                    self.save_settings();
+                }
+            }
+        } else {
+            let (index, progress) = (puzzle.index, puzzle.progress);
+            set_timeout(PUZZLE_REPLY_DELAY, move |g: &mut ChessGame| {
+                g.play_puzzle_reply(index, progress)
+            });
+        }
+        self.puzzle = Some(puzzle);
+    }
+
+    /// Play the opponent's reply in a puzzle, unless the player has moved on
+    /// since it was scheduled.
+    fn play_puzzle_reply(&mut self, index: usize, progress: usize) {
+        let Some(puzzle) = &mut self.puzzle else {
+            return;
+        };
+        if puzzle.index != index || puzzle.progress != progress {
+            return;
+        }
+        let Some((mv, promotion)) = puzzle
+            .puzzle()
+            .solution()
+            .get(progress)
+            .and_then(|uci| parse_uci(uci))
+        else {
+            return;
+        };
+        puzzle.progress += 1;
+        self.make_move(mv, promotion);
+        self.play_move_sound();
+    }
+
+    /// Let the computer search for its move on a background thread, if it
+    /// is its turn.
+    fn start_computer_move(&mut self) {
+        if !self.is_computer_turn() || self.is_game_over() || self.computer_thinking {
+            return;
+        }
+
+        let position = Position {
+            board: self.board,
+            turn: self.turn,
+            en_passant_target: self.en_passant_target,
+            castling: self.castling_rights(),
+        };
+        let depth = self.computer_depth;
+        let search_id = self.search_id;
+        self.computer_thinking = true;
+        spawn_task(
+            move || engine::best_move(&position, depth),
+            move |g: &mut ChessGame, mv| g.finish_computer_move(search_id, mv),
+        );
+    }
+
+    fn finish_computer_move(&mut self, search_id: u64, mv: Option<Move>) {
+        if search_id != self.search_id {
+            return;
+        }
+        self.computer_thinking = false;
+
+        let Some(mv) = mv else {
+            return;
+        };
+        let promotion = self.board[mv.from.0][mv.from.1]
+            .filter(|p| p.piece_type == PieceType::Pawn && (mv.to.0 == 0 || mv.to.0 == 7))
+            .map(|_| PieceType::Queen);
+        self.selected = None;
+        self.make_move(mv, promotion);
+        self.play_move_sound();
+        self.play_premove();
+    }
+
+    fn play_sound(&self, sound: Sound) {
+        if !self.settings.muted {
+            audio::play(sound);
+        }
+    }
+
+    /// Play the sound for the move just made, or for the end of the game.
+    fn play_move_sound(&self) {
+        let sound = if self.is_game_over() {
+            Sound::GameEnd
+        } else if self.king_in_check().is_some() {
+            Sound::Check
+        } else if self.history.last().is_some_and(|r| r.captured.is_some()) {
+            Sound::Capture
+        } else {
+            Sound::Move
+        };
+        self.play_sound(sound);
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.settings.muted = !self.settings.muted;
+        self.save_settings();
+    }
+
+    pub fn set_board_theme(&mut self, theme: &BoardTheme) {
+        self.settings.board_theme = theme.name.to_string();
+        self.save_settings();
+    }
+
+    pub fn set_piece_set(&mut self, set: &PieceSet) {
+        self.settings.piece_set = set.name.to_string();
+        self.pieces = PieceSvgs::load(set);
+        self.save_settings();
+    }
+
+    pub fn toggle_coordinates(&mut self) {
+        self.settings.hide_coordinates = !self.settings.hide_coordinates;
+        self.save_settings();
+    }
+
+    pub fn set_board_ratio(&mut self, ratio: f32) {
+        self.settings.board_ratio = Some(ratio);
+        self.save_settings();
+    }
+
+    fn save_settings(&mut self) {
+        if let Err(err) = self.settings.save() {
+            toast::push(Toast::error(format!("Couldn't save settings: {}", err)));
+        }
+    }
+
+    /// Discard the result of any running search.
+    fn cancel_computer_move(&mut self) {
+        self.search_id += 1;
+        self.computer_thinking = false;
+    }
+
+    /// Host a network game on the default port, starting a new game.
+    pub fn host_network_game(&mut self) {
+        self.leave_network_game();
+        match Connection::host(net::DEFAULT_PORT) {
+            Ok((connection, events)) => {
+                let role = NetworkRole::Host {
+                    port: net::DEFAULT_PORT,
+                };
+                self.start_network_game(role, connection, events);
+            }
+            Err(err) => {
+                toast::push(Toast::error(format!(
+                    "Couldn't host on port {}: {}",
+                    net::DEFAULT_PORT,
+                    err
+                )));
+            }
+        }
+    }
+
+    /// Join the network game hosted at `host`. The board shows the host's
+    /// game once connected.
+    pub fn join_network_game(&mut self, host: SocketAddr) {
+        self.leave_network_game();
+        let (connection, events) = Connection::join(host);
+        self.start_network_game(NetworkRole::Guest { host }, connection, events);
+    }
+
+    fn start_network_game(
+        &mut self,
+        role: NetworkRole,
+        connection: Connection,
+        events: Receiver<NetEvent>,
+    ) {
+        self.vs_computer = false;
+        self.network = Some(NetworkGame {
+            role,
+            connected: false,
+            connection,
+        });
+        self.reset();
+        self.listen_network(events, self.network_id);
+    }
+
+    pub fn leave_network_game(&mut self) {
+        if let Some(network) = self.network.take() {
+            network.connection.close();
+            self.network_id += 1;
+            self.premove = None;
+        }
+    }
+
+    /// Wait on a background thread for the next network event, handle it,
+    /// and wait again, until the connection is closed.
+    fn listen_network(&self, events: Receiver<NetEvent>, network_id: u64) {
+        spawn_task(
+            move || {
+                let event = events.recv().ok();
+                (events, event)
+            },
+            move |g: &mut ChessGame, (events, event)| {
+                if let Some(event) = event {
+                    if network_id == g.network_id {
+                        g.handle_network_event(event);
+                        g.listen_network(events, network_id);
+                    }
+                }
+            },
+        );
+    }
+
+    fn handle_network_event(&mut self, event: NetEvent) {
+        let Some(network) = &mut self.network else {
+            return;
+        };
+        match event {
+            NetEvent::Connected => {
+                network.connected = true;
+                // Catch the guest up, whether it is new or reconnecting
+                if network.is_host() {
+                    self.sync_network();
+                }
+            }
+            NetEvent::Disconnected => {
+                network.connected = false;
+                self.drag = None;
+                self.selected = None;
+            }
+            NetEvent::Message(message) => self.receive_network_message(message),
+        }
+    }
+
+    fn receive_network_message(&mut self, message: Message) {
+        let Some(is_host) = self.network.as_ref().map(NetworkGame::is_host) else {
+            return;
+        };
+        match message {
+            Message::Sync { fen, moves } if !is_host => {
+                if let Err(err) = self.replay_network_game(&fen, &moves) {
+                    toast::push(Toast::error(format!(
+                        "Couldn't follow the host's game: {}",
+                        err
+                    )));
+                }
+            }
+            Message::Move { ply, uci } => {
+                let played = self.play_remote_move(ply, &uci);
+                // A move that doesn't fit means the two games have drifted
+                // apart, so the host's game is sent over again
+                if !played {
+                    self.sync_network();
+                }
+            }
+            Message::Resync if is_host => self.sync_network(),
+            Message::Resign if !self.is_game_over() => {
+                let local = self.acting_color();
+                self.end_game(GameResult::Resignation(local));
+            }
+            Message::OfferDraw if !self.is_game_over() => {
+                self.draw_offer = Some(self.acting_color().opposite());
+            }
+            Message::AnswerDraw { accepted } if self.draw_offer == Some(self.acting_color()) => {
+                self.draw_offer = None;
+                if accepted {
+                    self.end_game(GameResult::Draw(DrawReason::Agreement));
+                } else {
+                    toast::push(Toast::info("Your draw offer was declined"));
+                }
+            }
+            Message::Rematch if is_host && self.is_game_over() => self.reset(),
+            _ => {}
+        }
+    }
+
+    /// Play the opponent's move, if it is legal and the one expected next.
+    fn play_remote_move(&mut self, ply: usize, uci: &str) -> bool {
+        let Some((mv, promotion)) = parse_uci(uci) else {
+            return false;
+        };
+        let remote_turn = self
+            .network
+            .as_ref()
+            .is_some_and(|network| network.local_color() != self.turn);
+        if ply != self.history.len()
+            || !remote_turn
+            || self.is_game_over()
+            || !self.is_legal(mv, promotion)
+        {
+            return false;
+        }
+
+        self.selected = None;
+        self.drag = None;
+        self.make_move(mv, promotion);
+        self.play_move_sound();
+        self.play_premove();
+        true
+    }
+
+    /// Set the board to the host's game.
+    fn replay_network_game(&mut self, fen: &str, moves: &[String]) -> Result<(), String> {
+        // Starting over here mustn't ask the host for its game again
+        let network = self.network.take();
+        let mut replay = || {
+            if fen == INITIAL_FEN {
+                self.reset_to_initial_position();
+            } else {
+                self.load_fen(fen)?;
+            }
+            for uci in moves {
+                self.play_uci(uci)?;
+            }
+            Ok(())
+        };
+        let replayed = replay();
+        self.network = network;
+        replayed
+    }
+
+    /// Play the move written as `uci`, if it is legal.
+    fn play_uci(&mut self, uci: &str) -> Result<(), String> {
+        let (mv, promotion) = parse_uci(uci)
+            .filter(|&(mv, promotion)| self.is_legal(mv, promotion))
+            .ok_or_else(|| format!("Illegal move {}", uci))?;
+        self.make_move(mv, promotion);
+        Ok(())
+    }
+
+    /// The moves played so far, in UCI notation.
+    fn uci_moves(&self) -> Vec<String> {
+        self.history
+            .iter()
+            .map(|r| move_to_uci(Move::new(r.from.0, r.from.1, r.to.0, r.to.1), r.promotion))
+            .collect()
+    }
+
+    /// Whether `mv` can be played now, with a promotion piece exactly when
+    /// a pawn reaches the last rank.
+    fn is_legal(&self, mv: Move, promotion: Option<PieceType>) -> bool {
+        let Some(piece) = self.board[mv.from.0][mv.from.1] else {
+            return false;
+        };
+        let promotes = piece.piece_type == PieceType::Pawn && (mv.to.0 == 0 || mv.to.0 == 7);
+        piece.color == self.turn
+            && promotes == promotion.is_some()
+            && self.is_valid_move(mv.from.0, mv.from.1, mv.to.0, mv.to.1)
+    }
+
+    /// Bring the two ends of a network game back in step: the host sends
+    /// its game, and the guest asks for it.
+    fn sync_network(&self) {
+        let Some(network) = &self.network else {
+            return;
+        };
+        let message = if network.is_host() {
+            Message::Sync {
+                fen: self
+                    .start_fen
+                    .clone()
+                    .unwrap_or_else(|| INITIAL_FEN.to_string()),
+                moves: self.uci_moves(),
+            }
+        } else {
+            Message::Resync
+        };
+        network.connection.send(&message);
+    }
+
+    fn send_network(&self, message: Message) {
+        if let Some(network) = &self.network {
+            network.connection.send(&message);
+        }
+    }
+
+    /// Tell the network opponent about the move just made here.
+    fn send_last_move(&self) {
+        let (Some(network), Some(record)) = (&self.network, self.history.last()) else {
+            return;
+        };
+        network.connection.send(&Message::Move {
+            ply: self.history.len() - 1,
+            uci: move_to_uci(
+                Move::new(record.from.0, record.from.1, record.to.0, record.to.1),
+                record.promotion,
+            ),
+        });
+    }
+
+    /// Start or stop analysing with the external engine.
+    pub fn toggle_analysis(&mut self) {
+        if let Some(analysis) = self.analysis.take() {
+            analysis.engine.quit();
+            self.analysis_id += 1;
+            return;
+        }
+
+        let command = uci::engine_command();
+        match UciEngine::start(&command) {
+            Ok((engine, events)) => {
+                self.analysis = Some(Analysis {
+                    engine,
+                    searches: 0,
+                    search: 0,
+                    turn: self.turn,
+                    depth: 0,
+                    score: None,
+                    best_move: None,
+                });
+                self.update_analysis();
+                self.listen_engine(events, self.analysis_id);
+            }
+            Err(err) => {
+                toast::push(Toast::error(format!(
+                    "Couldn't start engine {} (set {}): {}",
+                    command,
+                    uci::ENGINE_VAR,
+                    err
+                )));
+            }
+        }
+    }
+
+    /// Keep what follows the game up to date after it changes: the legal
+    /// moves, the analysis and the autosave.
+    fn game_changed(&mut self) {
+        self.update_legal_moves();
+        self.update_analysis();
+        self.schedule_autosave();
+    }
+
+    /// Point the engine at the current position.
+    fn update_analysis(&mut self) {
+        if self.analysis.is_none() {
+            return;
+        }
+        let game_over = self.is_game_over();
+        let fen = self
+            .start_fen
+            .clone()
+            .unwrap_or_else(|| INITIAL_FEN.to_string());
+        let moves = self.uci_moves();
+        let Some(analysis) = &mut self.analysis else {
+            return;
+        };
+        analysis.depth = 0;
+        analysis.score = None;
+        analysis.best_move = None;
+        if game_over {
+            analysis.engine.stop();
+            return;
+        }
+
+        analysis.search = analysis.searches;
+        analysis.searches += 1;
+        analysis.turn = self.turn;
+        analysis
+            .engine
+            .analyse(&fen, &moves, self.chess960_rooks.is_some());
+    }
+
+    /// Wait on a background thread for the next engine event, handle it,
+    /// and wait again, until the engine exits or analysis is turned off.
+    fn listen_engine(&self, events: Receiver<EngineEvent>, analysis_id: u64) {
+        spawn_task(
+            move || {
+                let event = events.recv().ok();
+                (events, event)
+            },
+            move |g: &mut ChessGame, (events, event)| {
+                if let Some(event) = event {
+                    if analysis_id == g.analysis_id {
+                        g.handle_engine_event(event);
+                        g.listen_engine(events, analysis_id);
+                    }
+                }
+            },
+        );
+    }
+
+    fn handle_engine_event(&mut self, event: EngineEvent) {
+        let Some(analysis) = &mut self.analysis else {
+            return;
+        };
+        match event {
+            EngineEvent::Info {
+                search,
+                depth,
+                score,
+                best_move,
+            } if search == analysis.search => {
+                analysis.depth = depth;
+                analysis.score = Some(match analysis.turn {
+                    PlayerColor::White => score,
+                    PlayerColor::Black => score.negate(),
+                });
+                if best_move.is_some() {
+                    analysis.best_move = best_move;
+                }
+            }
+            EngineEvent::Info { .. } => {}
+            EngineEvent::Exited => {
+                self.analysis = None;
+                self.analysis_id += 1;
+                toast::push(Toast::warning("The analysis engine exited"));
+            }
+        }
+    }
+
+    /// The engine's preferred move, if it is legal here.
+    pub fn best_move(&self) -> Option<(Move, Option<PieceType>)> {
+        let uci = self.analysis.as_ref()?.best_move.as_deref()?;
+        parse_uci(uci).filter(|&(mv, promotion)| self.is_legal(mv, promotion))
+    }
+
+    /// The engine's preferred move in standard algebraic notation.
+    pub fn best_move_san(&self) -> Option<String> {
+        let (mv, promotion) = self.best_move()?;
+        Some(pgn::san(
+            &self.board,
+            mv,
+            promotion,
+            self.en_passant_target,
+            &self.castling_rights(),
+        ))
+    }
+
+    pub fn toggle_vs_computer(&mut self) {
+        self.vs_computer = !self.vs_computer;
+        self.premove = None;
+        self.cancel_computer_move();
+        self.start_computer_move();
+    }
+
+    pub fn toggle_computer_color(&mut self) {
+        self.computer_color = self.computer_color.opposite();
+        self.premove = None;
+        self.cancel_computer_move();
+        self.start_computer_move();
+    }
+
+    /// Step through search depths 1 to `engine::MAX_DEPTH`.
+    pub fn cycle_computer_depth(&mut self) {
+        self.computer_depth = self.computer_depth % engine::MAX_DEPTH + 1;
     }
 
     fn make_move(&mut self, mv: Move, promotion: Option<PieceType>) {
@@ -251,21 +1307,13 @@ impl ChessGame {
             && from_col != to_col
             && self.board[to_row][to_col].is_none();
 
-        let castling_side = if piece.piece_type == PieceType::King {
-            let col_diff = to_col as isize - from_col as isize;
-            if col_diff == 2 {
-                Some(CastlingSide::Kingside)
-            } else if col_diff == -2 {
-                Some(CastlingSide::Queenside)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let castling_side = moves::castling_side(&self.board, mv, &prev_castling_rights);
 
-        // Determine captured piece
-        let captured = if is_en_passant {
+        // Determine captured piece. Chess960 castling lands on your own
+        // rook without capturing it.
+        let captured = if castling_side.is_some() {
+            None
+        } else if is_en_passant {
             self.board[from_row][to_col]
         } else {
             self.board[to_row][to_col]
@@ -290,6 +1338,14 @@ impl ChessGame {
             promotion_char
         );
 
+        let san = pgn::san(
+            &self.board,
+            mv,
+            promotion,
+            self.en_passant_target,
+            &prev_castling_rights,
+        );
+
         // Save halfmove clock for undo
         let prev_halfmove_clock = self.halfmove_clock;
 
@@ -306,9 +1362,12 @@ impl ChessGame {
             prev_castling_rights,
             prev_halfmove_clock,
             notation: notation.clone(),
+            san,
         };
         self.history.push(record);
         self.redo_stack.clear();
+        self.animate_last_move = true;
+        self.annotations = Annotations::default();
 
         // Track capture
         if let Some(captured_piece) = captured {
@@ -334,40 +1393,43 @@ impl ChessGame {
             }
         }
 
-        // Handle castling - move the rook
+        // Handle castling - put the king and rook on their files. Either
+        // may start where the other ends up in Chess960, so both are lifted
+        // first.
         if let Some(side) = castling_side {
-            match side {
-                CastlingSide::Kingside => {
-                    self.board[to_row][5] = self.board[to_row][7].take();
-                }
-                CastlingSide::Queenside => {
-                    self.board[to_row][3] = self.board[to_row][0].take();
-                }
-            }
+            let (king_to, rook_to) = side.destinations();
+            let rook = self.board[from_row][prev_castling_rights.rook_file(side)].take();
+            let king = self.board[from_row][from_col].take();
+            self.board[from_row][rook_to] = rook;
+            self.board[from_row][king_to] = king;
         }
 
         // Update castling flags
+        let queenside_file = prev_castling_rights.rook_file(CastlingSide::Queenside);
+        let kingside_file = prev_castling_rights.rook_file(CastlingSide::Kingside);
         match piece.piece_type {
             PieceType::King => match piece.color {
                 PlayerColor::White => self.white_king_moved = true,
                 PlayerColor::Black => self.black_king_moved = true,
             },
             PieceType::Rook => {
-                if from_row == 7 && from_col == 0 {
+                if from_row == 7 && from_col == queenside_file {
                     self.white_rook_a_moved = true;
-                } else if from_row == 7 && from_col == 7 {
+                } else if from_row == 7 && from_col == kingside_file {
                     self.white_rook_h_moved = true;
-                } else if from_row == 0 && from_col == 0 {
+                } else if from_row == 0 && from_col == queenside_file {
                     self.black_rook_a_moved = true;
-                } else if from_row == 0 && from_col == 7 {
+                } else if from_row == 0 && from_col == kingside_file {
                     self.black_rook_h_moved = true;
                 }
             }
             _ => {}
         }
 
-        // Move piece
-        self.board[to_row][to_col] = self.board[from_row][from_col].take();
+        // Move piece (castling has done so already)
+        if castling_side.is_none() {
+            self.board[to_row][to_col] = self.board[from_row][from_col].take();
+        }
 
         // Handle promotion
         if let Some(promote_to) = promotion {
@@ -384,7 +1446,18 @@ impl ChessGame {
             self.halfmove_clock += 1;
         }
 
+        if piece.color == PlayerColor::Black {
+            self.fullmove_number += 1;
+        }
+
         self.last_move = Some(notation);
+        // Moving instead of answering declines a draw offer
+        if self
+            .draw_offer
+            .is_some_and(|offerer| offerer != piece.color)
+        {
+            self.draw_offer = None;
+        }
         self.turn = self.turn.opposite();
 
         // Add current position to history for threefold repetition
@@ -398,6 +1471,194 @@ impl ChessGame {
 
         // Check for checkmate or draw
         self.update_game_result();
+
+        let check_suffix = if matches!(self.result, GameResult::Checkmate(_)) {
+            "#"
+        } else if is_in_check(&self.board, self.turn) {
+            "+"
+        } else {
+            ""
+        };
+        if let Some(record) = self.history.last_mut() {
+            record.san.push_str(check_suffix);
+        }
+
+        if let Some(clock) = &mut self.clock {
+            clock.press(piece.color);
+        }
+        self.sync_clock();
+        self.game_changed();
+    }
+
+    /// Stop the clocks once the game is over and keep the ticker running
+    /// only while a clock is.
+    fn sync_clock(&mut self) {
+        let game_over = self.is_game_over();
+        let running = match &mut self.clock {
+            Some(clock) => {
+                if game_over {
+                    clock.stop();
+                }
+                clock.is_running()
+            }
+            None => false,
+        };
+
+        match (running, self.clock_ticker) {
+            (true, None) => {
+                self.clock_ticker = Some(set_interval(CLOCK_REFRESH, |g: &mut ChessGame| {
+                    g.check_flag()
+                }));
+            }
+            (false, Some(ticker)) => {
+                clear_timer(ticker);
+                self.clock_ticker = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// End the game if the side to move has run out of time.
+    fn check_flag(&mut self) {
+        let Some(flagged) = self.clock.and_then(|clock| clock.flagged()) else {
+            return;
+        };
+        if self.is_game_over() {
+            return;
+        }
+        self.end_game(GameResult::Timeout(flagged.opposite()));
+    }
+
+    /// End the game other than by a move: on time, by resignation or by
+    /// agreement.
+    fn end_game(&mut self, result: GameResult) {
+        self.result = result;
+        self.pending_promotion = None;
+        self.selected = None;
+        self.drag = None;
+        self.premove = None;
+        self.draw_offer = None;
+        self.cancel_computer_move();
+        self.sync_clock();
+        self.play_sound(Sound::GameEnd);
+        self.game_changed();
+    }
+
+    /// The side the resign and draw buttons act for: the player's own side
+    /// against the computer or over the network, otherwise the side to
+    /// move.
+    pub fn acting_color(&self) -> PlayerColor {
+        match &self.network {
+            Some(network) => network.local_color(),
+            None if self.vs_computer => self.computer_color.opposite(),
+            None => self.turn,
+        }
+    }
+
+    /// Whether resigning or offering a draw makes sense now.
+    pub fn can_concede(&self) -> bool {
+        !self.is_game_over() && self.puzzle.is_none()
+    }
+
+    pub fn resign(&mut self) {
+        if !self.can_concede() {
+            return;
+        }
+        let color = self.acting_color();
+        self.end_game(GameResult::Resignation(color.opposite()));
+        self.send_network(Message::Resign);
+    }
+
+    /// Offer a draw. The computer answers right away; another player
+    /// answers with `answer_draw`.
+    pub fn offer_draw(&mut self) {
+        if !self.can_concede() || self.draw_offer.is_some() {
+            return;
+        }
+        let color = self.acting_color();
+        if self.vs_computer && self.network.is_none() {
+            let position = Position {
+                board: self.board,
+                turn: self.turn,
+                en_passant_target: self.en_passant_target,
+                castling: self.castling_rights(),
+            };
+            if engine::accepts_draw(&position, self.computer_color) {
+                self.end_game(GameResult::Draw(DrawReason::Agreement));
+                toast::push(Toast::info("The computer accepts the draw"));
+            } else {
+                toast::push(Toast::info("The computer declines the draw"));
+            }
+            return;
+        }
+        self.draw_offer = Some(color);
+        self.send_network(Message::OfferDraw);
+    }
+
+    /// Whether the player at this board can answer the pending draw offer:
+    /// over the network only the side it was made to can.
+    pub fn can_answer_draw(&self) -> bool {
+        self.draw_offer.is_some_and(|offerer| {
+            self.network
+                .as_ref()
+                .is_none_or(|network| network.local_color() != offerer)
+        })
+    }
+
+    pub fn answer_draw(&mut self, accepted: bool) {
+        if !self.can_answer_draw() {
+            return;
+        }
+        self.draw_offer = None;
+        if accepted {
+            self.end_game(GameResult::Draw(DrawReason::Agreement));
+        }
+        self.send_network(Message::AnswerDraw { accepted });
+    }
+
+    /// Start another game once this one is over. The computer switches
+    /// sides; over the network, the host starts it.
+    pub fn rematch(&mut self) {
+        if !self.is_game_over() {
+            return;
+        }
+        match &self.network {
+            Some(network) if !network.is_host() => {
+                self.send_network(Message::Rematch);
+                toast::push(Toast::info("Asked the host for a rematch"));
+            }
+            _ => {
+                if self.vs_computer {
+                    self.computer_color = self.computer_color.opposite();
+                }
+                self.reset();
+            }
+        }
+    }
+
+    /// Put fresh clocks for the current time control on the board. They
+    /// start with the first move.
+    fn reset_clock(&mut self) {
+        self.clock = self.time_control.map(ChessClock::new);
+        self.sync_clock();
+    }
+
+    /// Switch time control, starting a new game.
+    pub fn set_time_control(&mut self, control: Option<TimeControl>) {
+        self.time_control = control;
+        self.reset();
+    }
+
+    /// Change the custom time control by the given number of minutes and
+    /// increment seconds. If it is in use, a new game starts with it.
+    pub fn adjust_custom_time_control(&mut self, minutes: i64, increment_secs: i64) {
+        let old = self.custom_time_control;
+        let minutes = (old.minutes() as i64 + minutes).clamp(1, 180) as u64;
+        let increment = (old.increment_secs() as i64 + increment_secs).clamp(0, 60) as u64;
+        self.custom_time_control = TimeControl::new(minutes, increment);
+        if self.time_control == Some(old) {
+            self.set_time_control(Some(self.custom_time_control));
+        }
     }
 
     fn update_game_result(&mut self) {
@@ -443,18 +1704,42 @@ impl ChessGame {
         self.result = GameResult::Ongoing;
     }
 
+    /// Take back the last move. Against the computer, its reply is taken
+    /// back too, so it is your turn again.
     pub fn undo(&mut self) {
+        if !self.can_undo() {
+            return;
+        }
+        self.cancel_computer_move();
+        self.undo_move();
+        if self.is_computer_turn() && self.can_undo() {
+            self.undo_move();
+        }
+        self.start_computer_move();
+    }
+
+    fn undo_move(&mut self) {
         let record = match self.history.pop() {
             Some(r) => r,
             None => return,
         };
+        self.premove = None;
+        self.draw_offer = None;
 
         let (from_row, from_col) = record.from;
         let (to_row, to_col) = record.to;
 
         // Move piece back
+        if let Some(side) = record.was_castling {
+            // Lift both pieces before putting them back, as in make_move
+            let (king_to, rook_to) = side.destinations();
+            let rook = self.board[from_row][rook_to].take();
+            self.board[from_row][king_to] = None;
+            self.board[from_row][record.prev_castling_rights.rook_file(side)] = rook;
+        } else {
+            self.board[to_row][to_col] = None;
+        }
         self.board[from_row][from_col] = Some(record.piece);
-        self.board[to_row][to_col] = None;
 
         // Restore captured piece
         if let Some(captured) = record.captured {
@@ -472,18 +1757,6 @@ impl ChessGame {
             };
         }
 
-        // Undo castling - move rook back
-        if let Some(side) = record.was_castling {
-            match side {
-                CastlingSide::Kingside => {
-                    self.board[to_row][7] = self.board[to_row][5].take();
-                }
-                CastlingSide::Queenside => {
-                    self.board[to_row][0] = self.board[to_row][3].take();
-                }
-            }
-        }
-
         // Restore previous state
         self.en_passant_target = record.prev_en_passant_target;
         self.white_king_moved = record.prev_castling_rights.white_king_moved;
@@ -493,6 +1766,9 @@ impl ChessGame {
         self.black_rook_a_moved = record.prev_castling_rights.black_rook_a_moved;
         self.black_rook_h_moved = record.prev_castling_rights.black_rook_h_moved;
         self.halfmove_clock = record.prev_halfmove_clock;
+        if record.piece.color == PlayerColor::Black {
+            self.fullmove_number -= 1;
+        }
 
         // Remove the position from history
         self.position_history.pop();
@@ -508,10 +1784,31 @@ impl ChessGame {
 
         // Clear selection and update result
         self.selected = None;
+        self.drag = None;
         self.update_game_result();
+
+        // The clock follows the side to move, without refunding time
+        if let Some(clock) = &mut self.clock {
+            clock.switch_to(self.turn);
+        }
+        self.sync_clock();
+        self.game_changed();
     }
 
     pub fn redo(&mut self) {
+        if !self.can_redo() {
+            return;
+        }
+        self.cancel_computer_move();
+        self.redo_move();
+        if self.is_computer_turn() && self.can_redo() {
+            self.redo_move();
+        }
+        self.play_move_sound();
+        self.start_computer_move();
+    }
+
+    fn redo_move(&mut self) {
         let record = match self.redo_stack.pop() {
             Some(r) => r,
             None => return,
@@ -530,12 +1827,13 @@ impl ChessGame {
         self.redo_stack = remaining_redo;
     }
 
+    /// Moves can't be taken back in network games or puzzles.
     pub fn can_undo(&self) -> bool {
-        !self.history.is_empty()
+        !self.history.is_empty() && self.network.is_none() && self.puzzle.is_none()
     }
 
     pub fn can_redo(&self) -> bool {
-        !self.redo_stack.is_empty()
+        !self.redo_stack.is_empty() && self.network.is_none() && self.puzzle.is_none()
     }
 
     pub fn points_for(&self, color: PlayerColor) -> i32 {
@@ -546,9 +1844,22 @@ impl ChessGame {
         captured.iter().map(|p| p.piece_type.points()).sum()
     }
 
+    /// Start a new game, from a random position in Chess960 mode.
     pub fn reset(&mut self) {
+        if self.chess960 {
+            self.reset_chess960();
+        } else {
+            self.reset_to_initial_position();
+        }
+    }
+
+    fn reset_to_initial_position(&mut self) {
         self.board = board::setup_initial_board();
         self.selected = None;
+        self.drag = None;
+        self.annotations = Annotations::default();
+        self.premove = None;
+        self.draw_offer = None;
         self.last_move = None;
         self.turn = PlayerColor::White;
         self.captured_by_white.clear();
@@ -565,23 +1876,48 @@ impl ChessGame {
         self.redo_stack.clear();
         self.pending_promotion = None;
         self.halfmove_clock = 0;
+        self.fullmove_number = 1;
         self.position_history = vec![Self::hash_position(
             &self.board,
             self.turn,
             None,
             &self.castling_rights(),
         )];
+        self.chess960_rooks = None;
+        self.start_fen = None;
+        self.puzzle = None;
+        self.reset_clock();
+        self.cancel_computer_move();
+        self.start_computer_move();
+        self.sync_network();
+        self.game_changed();
+    }
+
+    /// Start a new game from a random Chess960 position.
+    fn reset_chess960(&mut self) {
+        use std::collections::hash_map::RandomState;
+        use std::hash::{BuildHasher, Hasher};
+
+        // Hash keys are random, which is all the randomness needed here
+        let random = RandomState::new().build_hasher().finish();
+        let index = (random % board::CHESS960_POSITIONS as u64) as u32;
+        if let Err(err) = self.load_fen(&board::chess960_fen(index)) {
+            toast::push(Toast::error(format!("Couldn't set up Chess960: {}", err)));
+        }
+    }
+
+    /// Switch between standard chess and Chess960, starting a new game.
+    pub fn toggle_chess960(&mut self) {
+        self.chess960 = !self.chess960;
+        self.reset();
     }
 
     pub fn toggle_auto_flip(&mut self) {
         self.flip_board = !self.flip_board;
     }
 
-    pub fn load_fen(&mut self, fen: &str) {
-        let state = match parse_fen(fen) {
-            Ok(s) => s,
-            Err(_) => return,
-        };
+    pub fn load_fen(&mut self, fen: &str) -> Result<(), String> {
+        let state = parse_fen(fen)?;
 
         self.board = state.board;
         self.turn = state.turn;
@@ -592,16 +1928,22 @@ impl ChessGame {
         self.white_rook_h_moved = state.castling.white_rook_h_moved;
         self.black_rook_a_moved = state.castling.black_rook_a_moved;
         self.black_rook_h_moved = state.castling.black_rook_h_moved;
+        self.chess960_rooks = state.castling.chess960;
 
         // Clear game state
         self.selected = None;
+        self.drag = None;
+        self.annotations = Annotations::default();
+        self.premove = None;
+        self.draw_offer = None;
         self.last_move = None;
         self.captured_by_white.clear();
         self.captured_by_black.clear();
         self.history.clear();
         self.redo_stack.clear();
         self.pending_promotion = None;
-        self.halfmove_clock = 0;
+        self.halfmove_clock = state.halfmove_clock;
+        self.fullmove_number = state.fullmove_number;
         self.position_history = vec![Self::hash_position(
             &self.board,
             self.turn,
@@ -609,6 +1951,308 @@ impl ChessGame {
             &self.castling_rights(),
         )];
 
+        self.start_fen = Some(fen.trim().to_string());
+        self.puzzle = None;
+
         self.update_game_result();
+        self.reset_clock();
+        self.cancel_computer_move();
+        self.start_computer_move();
+        self.sync_network();
+        self.game_changed();
+        Ok(())
+    }
+
+    /// Whether the current game is Chess960, whether started as one or
+    /// loaded from a Chess960 FEN.
+    pub fn is_chess960(&self) -> bool {
+        self.chess960_rooks.is_some()
+    }
+
+    /// The opening played so far, if the book knows it. Games set up from a
+    /// FEN have none.
+    pub fn opening(&self) -> Option<&'static Opening> {
+        if self.start_fen.is_some() {
+            return None;
+        }
+        openings::identify(self.history.iter().map(|r| r.san.as_str()))
+    }
+
+    /// The current position in Forsyth-Edwards Notation.
+    pub fn to_fen(&self) -> String {
+        write_fen(&FenState {
+            board: self.board,
+            turn: self.turn,
+            castling: self.castling_rights(),
+            en_passant_target: self.en_passant_target,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+        })
+    }
+
+    /// The game so far as PGN, with the moves in standard algebraic
+    /// notation.
+    pub fn to_pgn(&self) -> String {
+        let result = match self.result {
+            GameResult::Ongoing => "*",
+            GameResult::Checkmate(PlayerColor::White)
+            | GameResult::Timeout(PlayerColor::White)
+            | GameResult::Resignation(PlayerColor::White) => "1-0",
+            GameResult::Checkmate(PlayerColor::Black)
+            | GameResult::Timeout(PlayerColor::Black)
+            | GameResult::Resignation(PlayerColor::Black) => "0-1",
+            GameResult::Draw(_) => "1/2-1/2",
+        };
+
+        let mut headers = vec![
+            ("Event".to_string(), "Casual Game".to_string()),
+            ("Site".to_string(), "vitae".to_string()),
+            ("Date".to_string(), "????.??.??".to_string()),
+            ("Round".to_string(), "-".to_string()),
+            (
+                "White".to_string(),
+                self.player_name(PlayerColor::White).to_string(),
+            ),
+            (
+                "Black".to_string(),
+                self.player_name(PlayerColor::Black).to_string(),
+            ),
+            ("Result".to_string(), result.to_string()),
+        ];
+        if self.is_chess960() {
+            headers.push(("Variant".to_string(), "Chess960".to_string()));
+        }
+        if let Some(control) = self.time_control {
+            headers.push((
+                "TimeControl".to_string(),
+                format!(
+                    "{}+{}",
+                    control.initial.as_secs(),
+                    control.increment.as_secs()
+                ),
+            ));
+        }
+        if let Some(opening) = self.opening() {
+            headers.push(("ECO".to_string(), opening.eco.to_string()));
+            headers.push(("Opening".to_string(), opening.name.to_string()));
+        }
+        if matches!(self.result, GameResult::Timeout(_)) {
+            headers.push(("Termination".to_string(), "time forfeit".to_string()));
+        }
+
+        // Games set up from a FEN record it, along with where the numbering
+        // starts
+        let mut first_move = 1;
+        let mut black_first = false;
+        if let Some(fen) = &self.start_fen {
+            headers.push(("SetUp".to_string(), "1".to_string()));
+            headers.push(("FEN".to_string(), fen.clone()));
+            let fields: Vec<&str> = fen.split_whitespace().collect();
+            black_first = fields.get(1) == Some(&"b");
+            first_move = fields.get(5).and_then(|n| n.parse().ok()).unwrap_or(1);
+        }
+
+        let moves: Vec<String> = self.history.iter().map(|r| r.san.clone()).collect();
+        write_pgn(&headers, &moves, first_move, black_first, result)
+    }
+
+    /// Replace the game with one read from PGN. The moves are played out, so
+    /// they can be stepped through with undo and redo.
+    pub fn load_pgn(&mut self, pgn: &str) -> Result<(), String> {
+        let parsed = parse_pgn(pgn)?;
+
+        // Replay on a copy so a bad move leaves the current game untouched.
+        // The computer stays out of it until the whole game is loaded.
+        // The clocks stay out of it too, and start afresh afterwards, and
+        // a network opponent and the analysis engine only hear about the
+        // finished game.
+        let mut game = self.clone();
+        game.vs_computer = false;
+        game.time_control = None;
+        game.clock = None;
+        game.clock_ticker = None;
+        game.network = None;
+        game.analysis = None;
+        match parsed.header("FEN") {
+            Some(fen) => game.load_fen(fen)?,
+            None => game.reset_to_initial_position(),
+        }
+
+        for (i, san) in parsed.moves.iter().enumerate() {
+            let (mv, promotion) = game
+                .find_san_move(san)
+                .ok_or_else(|| format!("Illegal move {} at ply {}", san, i + 1))?;
+            game.make_move(mv, promotion);
+        }
+
+        game.vs_computer = self.vs_computer;
+        game.time_control = self.time_control;
+        game.clock = self.clock;
+        game.clock_ticker = self.clock_ticker;
+        game.network = self.network.take();
+        game.analysis = self.analysis.take();
+        *self = game;
+        self.reset_clock();
+        self.cancel_computer_move();
+        self.start_computer_move();
+        self.sync_network();
+        self.game_changed();
+        Ok(())
+    }
+
+    /// Save the game for the next run once the current update is done, so
+    /// that a burst of moves, such as a replay, is written once.
+    fn schedule_autosave(&mut self) {
+        if !self.autosave_pending {
+            self.autosave_pending = true;
+            set_timeout(Duration::ZERO, |g: &mut ChessGame| g.autosave());
+        }
+    }
+
+    fn autosave(&mut self) {
+        self.autosave_pending = false;
+        // Failing to autosave only costs resuming the game next time
+        let _ = self.to_saved().save(Slot::Autosave);
+    }
+
+    /// Save the game for "Load Game" to bring back.
+    pub fn save_game(&mut self) {
+        toast::push(match self.to_saved().save(Slot::Saved) {
+            Ok(()) => Toast::success("Game saved"),
+            Err(err) => Toast::error(format!("Couldn't save game: {}", err)),
+        });
+    }
+
+    /// Go back to the game saved with `save_game`.
+    pub fn load_saved_game(&mut self) {
+        let loaded = SavedGame::load(Slot::Saved)
+            .map_err(|err| err.to_string())
+            .and_then(|saved| self.restore(saved));
+        toast::push(match loaded {
+            Ok(()) => Toast::success("Game loaded"),
+            Err(err) => Toast::error(format!("Couldn't load game: {}", err)),
+        });
+    }
+
+    fn to_saved(&self) -> SavedGame {
+        SavedGame {
+            start_fen: self.start_fen.clone(),
+            moves: self.uci_moves(),
+            redo: self
+                .redo_stack
+                .iter()
+                .rev()
+                .map(|r| move_to_uci(Move::new(r.from.0, r.from.1, r.to.0, r.to.1), r.promotion))
+                .collect(),
+            result: self.result,
+            // Stopping a copy banks the time the side to move has used
+            clock: self.clock.map(|mut clock| {
+                clock.stop();
+                clock
+            }),
+            time_control: self.time_control,
+            custom_time_control: self.custom_time_control,
+            chess960: self.chess960,
+            flip_board: self.flip_board,
+            vs_computer: self.vs_computer,
+            computer_color: self.computer_color,
+            computer_depth: self.computer_depth,
+        }
+    }
+
+    /// Replace the game with `saved`, replaying its moves.
+    fn restore(&mut self, saved: SavedGame) -> Result<(), String> {
+        // Replay on a copy, as in load_pgn
+        let mut game = self.clone();
+        game.vs_computer = false;
+        game.time_control = None;
+        game.clock = None;
+        game.clock_ticker = None;
+        game.network = None;
+        game.analysis = None;
+        match &saved.start_fen {
+            Some(fen) => game.load_fen(fen)?,
+            None => game.reset_to_initial_position(),
+        }
+        // The redo stack is rebuilt by playing its moves and taking them
+        // back again
+        for uci in saved.moves.iter().chain(&saved.redo) {
+            game.play_uci(uci)?;
+        }
+        for _ in &saved.redo {
+            game.undo_move();
+        }
+        if game.result == GameResult::Ongoing {
+            game.result = saved.result;
+        }
+
+        game.chess960 = saved.chess960;
+        game.flip_board = saved.flip_board;
+        game.vs_computer = saved.vs_computer;
+        game.computer_color = saved.computer_color;
+        game.computer_depth = saved.computer_depth;
+        game.time_control = saved.time_control;
+        game.custom_time_control = saved.custom_time_control;
+        game.clock = saved.clock;
+        game.clock_ticker = self.clock_ticker;
+        game.network = self.network.take();
+        game.analysis = self.analysis.take();
+        *self = game;
+
+        // Clocks only run once the game is under way
+        let running = !self.history.is_empty() && !self.is_game_over();
+        if let (Some(clock), true) = (&mut self.clock, running) {
+            clock.start(self.turn);
+        }
+        self.sync_clock();
+        self.cancel_computer_move();
+        self.start_computer_move();
+        self.sync_network();
+        self.game_changed();
+        Ok(())
+    }
+
+    /// Find the legal move written as `san` in the current position.
+    fn find_san_move(&self, san: &str) -> Option<(Move, Option<PieceType>)> {
+        let target = normalize_san(san);
+        let castling = self.castling_rights();
+        self.legal_moves
+            .iter()
+            .copied()
+            .flat_map(|mv| {
+                let piece = self.board[mv.from.0][mv.from.1];
+                let promotes = piece.is_some_and(|p| p.piece_type == PieceType::Pawn)
+                    && (mv.to.0 == 0 || mv.to.0 == 7);
+                let promotions = if promotes {
+                    vec![
+                        Some(PieceType::Queen),
+                        Some(PieceType::Rook),
+                        Some(PieceType::Bishop),
+                        Some(PieceType::Knight),
+                    ]
+                } else {
+                    vec![None]
+                };
+                promotions.into_iter().map(move |promotion| (mv, promotion))
+            })
+            .find(|&(mv, promotion)| {
+                let candidate = pgn::san(
+                    &self.board,
+                    mv,
+                    promotion,
+                    self.en_passant_target,
+                    &castling,
+                );
+                normalize_san(&candidate) == target
+            })
+    }
+}
+
+/// Remove `item` from `items` if it is there, otherwise add it.
+fn toggle<T: PartialEq>(items: &mut Vec<T>, item: T) {
+    if let Some(index) = items.iter().position(|i| *i == item) {
+        items.remove(index);
+    } else {
+        items.push(item);
     }
 }
@@ -1,16 +1,27 @@
 mod assets;
+mod audio;
 mod board;
 mod check;
+mod clock;
+mod engine;
 mod fen;
 mod game;
 mod moves;
+mod net;
+mod openings;
+mod pgn;
+mod puzzles;
+mod save;
+mod settings;
+mod theme;
 mod types;
+mod uci;
 mod view;
 
 use game::ChessGame;
 use vitae::prelude::*;
 
-fn main() {
-    let app = App::new(ChessGame::new(), view::view);
-    app.run();
+fn main() -> Result<(), vitae::Error> {
+    let app = App::new(ChessGame::new(), view::view)?;
+    app.run()
 }
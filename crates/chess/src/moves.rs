@@ -1,4 +1,4 @@
-use crate::check::{is_path_clear, is_square_attacked, would_be_in_check, Board};
+use crate::check::{is_square_attacked, would_be_in_check, Board};
 use crate::types::{Piece, PieceType, PlayerColor};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -16,6 +16,8 @@ impl Move {
     }
 }
 
+/// Castling rights. The a- and h-file rooks are the queenside and kingside
+/// rooks, wherever they start in Chess960.
 #[derive(Clone, Copy)]
 pub struct CastlingRights {
     pub white_king_moved: bool,
@@ -24,173 +26,81 @@ pub struct CastlingRights {
     pub white_rook_h_moved: bool,
     pub black_rook_a_moved: bool,
     pub black_rook_h_moved: bool,
+    /// In Chess960, the files the queenside and kingside rooks start on.
+    /// Castling is then written as the king moving onto its own rook.
+    pub chess960: Option<(usize, usize)>,
 }
 
-pub fn is_valid_move(
-    board: &Board,
-    from_row: usize,
-    from_col: usize,
-    to_row: usize,
-    to_col: usize,
-    en_passant_target: Option<usize>,
-    castling: &CastlingRights,
-) -> bool {
-    if from_row == to_row && from_col == to_col {
-        return false;
-    }
-
-    let piece = match board[from_row][from_col] {
-        Some(p) => p,
-        None => return false,
-    };
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CastlingSide {
+    Kingside,
+    Queenside,
+}
 
-    // Can't capture your own piece
-    if let Some(target) = board[to_row][to_col] {
-        if target.color == piece.color {
-            return false;
+impl CastlingSide {
+    /// Files the king and rook end up on, the same in every variant.
+    pub fn destinations(self) -> (usize, usize) {
+        match self {
+            CastlingSide::Kingside => (6, 5),
+            CastlingSide::Queenside => (2, 3),
         }
     }
+}
 
-    let row_diff = (to_row as isize - from_row as isize).abs();
-    let col_diff = (to_col as isize - from_col as isize).abs();
-    let is_capture = board[to_row][to_col].is_some();
-
-    let is_valid_pattern = match piece.piece_type {
-        PieceType::Pawn => {
-            let direction: isize = match piece.color {
-                PlayerColor::White => -1,
-                PlayerColor::Black => 1,
-            };
-            let start_row = match piece.color {
-                PlayerColor::White => 6,
-                PlayerColor::Black => 1,
-            };
-            let en_passant_row = match piece.color {
-                PlayerColor::White => 3,
-                PlayerColor::Black => 4,
-            };
-
-            let forward = to_row as isize - from_row as isize;
-
-            // Standard one-square move
-            if forward == direction && from_col == to_col && !is_capture {
-                return !would_be_in_check(
-                    board,
-                    from_row,
-                    from_col,
-                    to_row,
-                    to_col,
-                    en_passant_target,
-                );
-            }
-
-            // Two-square initial move
-            if forward == 2 * direction
-                && from_col == to_col
-                && from_row == start_row
-                && !is_capture
-                && is_path_clear(board, from_row, from_col, to_row, to_col)
-            {
-                return !would_be_in_check(
-                    board,
-                    from_row,
-                    from_col,
-                    to_row,
-                    to_col,
-                    en_passant_target,
-                );
-            }
-
-            // Diagonal capture
-            if forward == direction && col_diff == 1 && is_capture {
-                return !would_be_in_check(
-                    board,
-                    from_row,
-                    from_col,
-                    to_row,
-                    to_col,
-                    en_passant_target,
-                );
-            }
-
-            // En passant capture
-            if forward == direction
-                && col_diff == 1
-                && from_row == en_passant_row
-                && en_passant_target == Some(to_col)
-            {
-                return !would_be_in_check(
-                    board,
-                    from_row,
-                    from_col,
-                    to_row,
-                    to_col,
-                    en_passant_target,
-                );
-            }
-
-            false
+impl CastlingRights {
+    /// The file the castling rook for `side` starts on.
+    pub fn rook_file(&self, side: CastlingSide) -> usize {
+        match (side, self.chess960) {
+            (CastlingSide::Kingside, Some((_, kingside))) => kingside,
+            (CastlingSide::Queenside, Some((queenside, _))) => queenside,
+            (CastlingSide::Kingside, None) => 7,
+            (CastlingSide::Queenside, None) => 0,
         }
-        PieceType::Rook => {
-            if from_row != to_row && from_col != to_col {
-                return false;
-            }
-            is_path_clear(board, from_row, from_col, to_row, to_col)
-        }
-        PieceType::Bishop => {
-            if row_diff != col_diff {
-                return false;
-            }
-            is_path_clear(board, from_row, from_col, to_row, to_col)
-        }
-        PieceType::Queen => {
-            let is_straight = from_row == to_row || from_col == to_col;
-            let is_diagonal = row_diff == col_diff;
-            if !is_straight && !is_diagonal {
-                return false;
-            }
-            is_path_clear(board, from_row, from_col, to_row, to_col)
-        }
-        PieceType::Knight => (row_diff == 2 && col_diff == 1) || (row_diff == 1 && col_diff == 2),
-        PieceType::King => {
-            // Normal king move
-            if row_diff <= 1 && col_diff <= 1 {
-                return !would_be_in_check(
-                    board,
-                    from_row,
-                    from_col,
-                    to_row,
-                    to_col,
-                    en_passant_target,
-                );
-            }
+    }
 
-            // Castling
-            if row_diff == 0 && col_diff == 2 {
-                return is_valid_castling(board, from_row, from_col, to_col, piece.color, castling);
-            }
+    /// The move that castles to `side` for a king on `king_col`.
+    pub fn castling_move(&self, row: usize, king_col: usize, side: CastlingSide) -> Move {
+        let to_col = match self.chess960 {
+            Some(_) => self.rook_file(side),
+            None => side.destinations().0,
+        };
+        Move::new(row, king_col, row, to_col)
+    }
+}
 
-            false
-        }
-    };
+/// Which way `mv` castles, if it is a castling move at all: the king moving
+/// two squares, or in Chess960 onto its own castling rook.
+pub fn castling_side(board: &Board, mv: Move, castling: &CastlingRights) -> Option<CastlingSide> {
+    let king = board[mv.from.0][mv.from.1].filter(|p| p.piece_type == PieceType::King)?;
+    if mv.from.0 != mv.to.0 {
+        return None;
+    }
 
-    if !is_valid_pattern {
-        return false;
+    if castling.chess960.is_none() {
+        return match mv.to.1 as isize - mv.from.1 as isize {
+            2 => Some(CastlingSide::Kingside),
+            -2 => Some(CastlingSide::Queenside),
+            _ => None,
+        };
     }
 
-    // Check that the move doesn't leave our king in check
-    if piece.piece_type == PieceType::King && col_diff == 2 {
-        true // Castling already validated
-    } else {
-        !would_be_in_check(board, from_row, from_col, to_row, to_col, en_passant_target)
+    let own_rook = Piece {
+        piece_type: PieceType::Rook,
+        color: king.color,
+    };
+    if board[mv.to.0][mv.to.1] != Some(own_rook) {
+        return None;
     }
+    [CastlingSide::Kingside, CastlingSide::Queenside]
+        .into_iter()
+        .find(|&side| castling.rook_file(side) == mv.to.1)
 }
 
 fn is_valid_castling(
     board: &Board,
     from_row: usize,
     from_col: usize,
-    to_col: usize,
+    side: CastlingSide,
     color: PlayerColor,
     castling: &CastlingRights,
 ) -> bool {
@@ -199,81 +109,100 @@ fn is_valid_castling(
         PlayerColor::Black => 0,
     };
 
-    if from_row != king_row || from_col != 4 {
+    // In Chess960 the king starts anywhere between its rooks
+    if from_row != king_row || (castling.chess960.is_none() && from_col != 4) {
         return false;
     }
 
-    let king_moved = match color {
-        PlayerColor::White => castling.white_king_moved,
-        PlayerColor::Black => castling.black_king_moved,
+    let (king_moved, rook_moved) = match (color, side) {
+        (PlayerColor::White, CastlingSide::Kingside) => {
+            (castling.white_king_moved, castling.white_rook_h_moved)
+        }
+        (PlayerColor::White, CastlingSide::Queenside) => {
+            (castling.white_king_moved, castling.white_rook_a_moved)
+        }
+        (PlayerColor::Black, CastlingSide::Kingside) => {
+            (castling.black_king_moved, castling.black_rook_h_moved)
+        }
+        (PlayerColor::Black, CastlingSide::Queenside) => {
+            (castling.black_king_moved, castling.black_rook_a_moved)
+        }
     };
-    if king_moved {
+    if king_moved || rook_moved {
         return false;
     }
 
-    let enemy_color = color.opposite();
-    if is_square_attacked(board, king_row, 4, enemy_color) {
+    let rook_col = castling.rook_file(side);
+    let rook_on_side = match side {
+        CastlingSide::Kingside => rook_col > from_col,
+        CastlingSide::Queenside => rook_col < from_col,
+    };
+    let own_rook = Piece {
+        piece_type: PieceType::Rook,
+        color,
+    };
+    if !rook_on_side || board[king_row][rook_col] != Some(own_rook) {
         return false;
     }
 
-    // Kingside castling
-    if to_col == 6 {
-        let rook_moved = match color {
-            PlayerColor::White => castling.white_rook_h_moved,
-            PlayerColor::Black => castling.black_rook_h_moved,
-        };
-        if rook_moved {
-            return false;
-        }
-        if let Some(rook) = board[king_row][7] {
-            if rook.piece_type != PieceType::Rook || rook.color != color {
-                return false;
-            }
-        } else {
-            return false;
-        }
-        if board[king_row][5].is_some() || board[king_row][6].is_some() {
-            return false;
-        }
-        if is_square_attacked(board, king_row, 5, enemy_color)
-            || is_square_attacked(board, king_row, 6, enemy_color)
-        {
-            return false;
-        }
-        return true;
+    // Every square the king or rook crosses must be empty, apart from the
+    // two of them
+    let (king_to, rook_to) = side.destinations();
+    let first = from_col.min(rook_col).min(king_to).min(rook_to);
+    let last = from_col.max(rook_col).max(king_to).max(rook_to);
+    if (first..=last)
+        .any(|col| col != from_col && col != rook_col && board[king_row][col].is_some())
+    {
+        return false;
     }
 
-    // Queenside castling
-    if to_col == 2 {
-        let rook_moved = match color {
-            PlayerColor::White => castling.white_rook_a_moved,
-            PlayerColor::Black => castling.black_rook_a_moved,
-        };
-        if rook_moved {
-            return false;
-        }
-        if let Some(rook) = board[king_row][0] {
-            if rook.piece_type != PieceType::Rook || rook.color != color {
-                return false;
-            }
-        } else {
-            return false;
-        }
-        if board[king_row][1].is_some()
-            || board[king_row][2].is_some()
-            || board[king_row][3].is_some()
-        {
-            return false;
-        }
-        if is_square_attacked(board, king_row, 2, enemy_color)
-            || is_square_attacked(board, king_row, 3, enemy_color)
-        {
-            return false;
-        }
+    // The king can't castle out of, through or into check. The castling
+    // rook is lifted first, in case it was shielding the king's destination.
+    let mut lifted = *board;
+    lifted[king_row][rook_col] = None;
+    let enemy_color = color.opposite();
+    (from_col.min(king_to)..=from_col.max(king_to))
+        .all(|col| !is_square_attacked(&lifted, king_row, col, enemy_color))
+}
+
+/// Whether the piece on `from` could be premoved to `to`: a square it
+/// moves to when nothing is in the way, since the opponent's move may yet
+/// clear the path or give a pawn something to capture. The move is checked
+/// for real when it is played.
+pub fn is_premove_target(
+    board: &Board,
+    from: (usize, usize),
+    to: (usize, usize),
+    castling: &CastlingRights,
+) -> bool {
+    let Some(piece) = board[from.0][from.1] else {
+        return false;
+    };
+    if from == to {
+        return false;
+    }
+    if castling_side(board, Move::new(from.0, from.1, to.0, to.1), castling).is_some() {
         return true;
     }
 
-    false
+    let forward = to.0 as isize - from.0 as isize;
+    let row_diff = forward.abs();
+    let col_diff = (to.1 as isize - from.1 as isize).abs();
+    match piece.piece_type {
+        PieceType::Pawn => {
+            let (direction, start_row) = match piece.color {
+                PlayerColor::White => (-1, 6),
+                PlayerColor::Black => (1, 1),
+            };
+            (forward == direction && col_diff <= 1)
+                || (forward == 2 * direction && col_diff == 0 && from.0 == start_row)
+        }
+        PieceType::Knight => (row_diff == 1 && col_diff == 2) || (row_diff == 2 && col_diff == 1),
+        PieceType::Bishop => row_diff == col_diff,
+        PieceType::Rook => row_diff == 0 || col_diff == 0,
+        PieceType::Queen => row_diff == col_diff || row_diff == 0 || col_diff == 0,
+        PieceType::King => row_diff <= 1 && col_diff <= 1,
+    }
 }
 
 pub fn generate_legal_moves(
@@ -303,9 +232,11 @@ pub fn generate_legal_moves(
         }
     }
 
-    // Filter out moves that leave king in check
+    // Filter out moves that leave king in check. Castling moves were
+    // checked as they were generated.
     moves.retain(|m| {
-        !would_be_in_check(board, m.from.0, m.from.1, m.to.0, m.to.1, en_passant_target)
+        castling_side(board, *m, castling).is_some()
+            || !would_be_in_check(board, m.from.0, m.from.1, m.to.0, m.to.1, en_passant_target)
     });
 
     moves
@@ -518,10 +449,57 @@ fn generate_king_moves(
     }
 
     // Castling
-    if is_valid_castling(board, from_row, from_col, 6, color, castling) {
-        moves.push(Move::new(from_row, from_col, from_row, 6));
+    for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+        if is_valid_castling(board, from_row, from_col, side, color, castling) {
+            moves.push(castling.castling_move(from_row, from_col, side));
+        }
     }
-    if is_valid_castling(board, from_row, from_col, 2, color, castling) {
-        moves.push(Move::new(from_row, from_col, from_row, 2));
+}
+
+fn square_name((row, col): (usize, usize)) -> String {
+    format!("{}{}", (b'a' + col as u8) as char, 8 - row)
+}
+
+fn parse_square(name: &[u8]) -> Option<(usize, usize)> {
+    match name {
+        [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+            Some(((b'8' - rank) as usize, (file - b'a') as usize))
+        }
+        _ => None,
     }
 }
+
+/// A move in UCI notation, e.g. `e2e4` or `e7e8q`.
+pub fn move_to_uci(mv: Move, promotion: Option<PieceType>) -> String {
+    let promotion = match promotion {
+        Some(PieceType::Queen) => "q",
+        Some(PieceType::Rook) => "r",
+        Some(PieceType::Bishop) => "b",
+        Some(PieceType::Knight) => "n",
+        _ => "",
+    };
+    format!(
+        "{}{}{}",
+        square_name(mv.from),
+        square_name(mv.to),
+        promotion
+    )
+}
+
+pub fn parse_uci(uci: &str) -> Option<(Move, Option<PieceType>)> {
+    let bytes = uci.as_bytes();
+    if bytes.len() != 4 && bytes.len() != 5 {
+        return None;
+    }
+    let from = parse_square(&bytes[0..2])?;
+    let to = parse_square(&bytes[2..4])?;
+    let promotion = match bytes.get(4) {
+        None => None,
+        Some(b'q') => Some(PieceType::Queen),
+        Some(b'r') => Some(PieceType::Rook),
+        Some(b'b') => Some(PieceType::Bishop),
+        Some(b'n') => Some(PieceType::Knight),
+        Some(_) => return None,
+    };
+    Some((Move { from, to }, promotion))
+}
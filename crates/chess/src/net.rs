@@ -0,0 +1,225 @@
+//! Playing against another instance over TCP.
+//!
+//! The two sides exchange newline-terminated text messages:
+//!
+//! - `SYNC <fen>|<moves>`: the whole game, as the position it started from
+//!   and the moves since then in UCI notation (`e2e4`, `e7e8q`). The host
+//!   sends it whenever a guest connects, so a reconnecting guest catches up.
+//! - `MOVE <ply> <move>`: a move, numbered from 0 so both sides can tell
+//!   whether they are still in step.
+//! - `RESYNC`: the guest lost track of the game and asks for a `SYNC`.
+//! - `RESIGN`: the sender gives up.
+//! - `DRAW OFFER`, `DRAW ACCEPT`, `DRAW DECLINE`: a draw offer and the
+//!   answer to it.
+//! - `REMATCH`: the guest asks the host to start a new game once this one is
+//!   over.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// How long a guest waits before trying to reach the host again.
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+/// How often the host checks whether it should stop listening.
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    Sync { fen: String, moves: Vec<String> },
+    Move { ply: usize, uci: String },
+    Resync,
+    Resign,
+    OfferDraw,
+    AnswerDraw { accepted: bool },
+    Rematch,
+}
+
+impl Message {
+    pub fn parse(line: &str) -> Option<Message> {
+        let (kind, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match kind {
+            "SYNC" => {
+                let (fen, moves) = rest.split_once('|')?;
+                Some(Message::Sync {
+                    fen: fen.trim().to_string(),
+                    moves: moves.split_whitespace().map(str::to_string).collect(),
+                })
+            }
+            "MOVE" => {
+                let (ply, uci) = rest.split_once(' ')?;
+                Some(Message::Move {
+                    ply: ply.parse().ok()?,
+                    uci: uci.trim().to_string(),
+                })
+            }
+            "RESYNC" => Some(Message::Resync),
+            "RESIGN" => Some(Message::Resign),
+            "DRAW" => match rest.trim() {
+                "OFFER" => Some(Message::OfferDraw),
+                "ACCEPT" => Some(Message::AnswerDraw { accepted: true }),
+                "DECLINE" => Some(Message::AnswerDraw { accepted: false }),
+                _ => None,
+            },
+            "REMATCH" => Some(Message::Rematch),
+            _ => None,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        match self {
+            Message::Sync { fen, moves } => format!("SYNC {}|{}\n", fen, moves.join(" ")),
+            Message::Move { ply, uci } => format!("MOVE {} {}\n", ply, uci),
+            Message::Resync => "RESYNC\n".to_string(),
+            Message::Resign => "RESIGN\n".to_string(),
+            Message::OfferDraw => "DRAW OFFER\n".to_string(),
+            Message::AnswerDraw { accepted: true } => "DRAW ACCEPT\n".to_string(),
+            Message::AnswerDraw { accepted: false } => "DRAW DECLINE\n".to_string(),
+            Message::Rematch => "REMATCH\n".to_string(),
+        }
+    }
+}
+
+/// Something that happened on the connection, delivered to the game.
+pub enum NetEvent {
+    Connected,
+    Disconnected,
+    Message(Message),
+}
+
+/// One end of a network game. Background threads keep the connection up
+/// and report on it through the receiver returned when it is opened.
+#[derive(Clone)]
+pub struct Connection {
+    /// The stream to the other side, while there is one
+    writer: Arc<Mutex<Option<TcpStream>>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Connection {
+    fn new() -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(None)),
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Listen on `port` for a guest. Only one guest is connected at a time;
+    /// once it drops, another can take its place.
+    pub fn host(port: u16) -> io::Result<(Connection, Receiver<NetEvent>)> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        // Accepting is polled, so the thread notices when it's closed
+        listener.set_nonblocking(true)?;
+
+        let connection = Connection::new();
+        let (events, receiver) = mpsc::channel();
+        let this = connection.clone();
+        thread::spawn(move || {
+            while !this.is_closed() {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if stream.set_nonblocking(false).is_err() || this.is_connected() {
+                            continue;
+                        }
+                        let this = this.clone();
+                        let events = events.clone();
+                        thread::spawn(move || this.serve(stream, &events));
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_POLL)
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok((connection, receiver))
+    }
+
+    /// Connect to a host at `address`, reconnecting whenever the connection
+    /// drops until the connection is closed.
+    pub fn join(address: SocketAddr) -> (Connection, Receiver<NetEvent>) {
+        let connection = Connection::new();
+        let (events, receiver) = mpsc::channel();
+        let this = connection.clone();
+        thread::spawn(move || {
+            while !this.is_closed() {
+                if let Ok(stream) = TcpStream::connect_timeout(&address, RECONNECT_DELAY) {
+                    this.serve(stream, &events);
+                }
+                if !this.is_closed() {
+                    thread::sleep(RECONNECT_DELAY);
+                }
+            }
+        });
+        (connection, receiver)
+    }
+
+    /// Read messages from `stream` until it closes.
+    fn serve(&self, stream: TcpStream, events: &Sender<NetEvent>) {
+        let Ok(writer) = stream.try_clone() else {
+            return;
+        };
+        *self.writer.lock().unwrap() = Some(writer);
+        if self.is_closed() {
+            // Closed while connecting, after `close` looked for a stream
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        let _ = events.send(NetEvent::Connected);
+
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            // Lines that don't parse are ignored; a lost move is caught by
+            // the ply numbering
+            if let Some(message) = Message::parse(&line) {
+                let _ = events.send(NetEvent::Message(message));
+            }
+        }
+
+        *self.writer.lock().unwrap() = None;
+        let _ = events.send(NetEvent::Disconnected);
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.writer.lock().unwrap().is_some()
+    }
+
+    /// Send `message` to the other side. Messages sent while disconnected
+    /// are dropped; the host resyncs the game on reconnect.
+    pub fn send(&self, message: &Message) {
+        if let Some(stream) = self.writer.lock().unwrap().as_mut() {
+            // A failed write means the connection dropped, which the
+            // reading side reports
+            let _ = stream.write_all(message.to_line().as_bytes());
+        }
+    }
+
+    /// Disconnect and stop listening or reconnecting.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        if let Some(stream) = self.writer.lock().unwrap().as_ref() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Read a host address typed as `ip:port`, or just `ip` for the default
+/// port.
+pub fn parse_address(text: &str) -> Option<SocketAddr> {
+    let text = text.trim();
+    text.parse().ok().or_else(|| {
+        text.parse::<IpAddr>()
+            .ok()
+            .map(|ip| SocketAddr::new(ip, DEFAULT_PORT))
+    })
+}
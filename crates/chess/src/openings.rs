@@ -0,0 +1,264 @@
+use crate::pgn::normalize_san;
+
+/// A named opening from the ECO (Encyclopaedia of Chess Openings)
+/// classification.
+pub struct Opening {
+    pub eco: &'static str,
+    pub name: &'static str,
+    /// The moves that reach it from the initial position, in SAN
+    moves: &'static str,
+}
+
+const fn opening(eco: &'static str, name: &'static str, moves: &'static str) -> Opening {
+    Opening { eco, name, moves }
+}
+
+/// A small opening book covering the common main lines.
+const OPENINGS: &[Opening] = &[
+    // Flank openings
+    opening("A00", "Polish Opening", "b4"),
+    opening("A00", "Grob Opening", "g4"),
+    opening("A01", "Nimzo-Larsen Attack", "b3"),
+    opening("A02", "Bird's Opening", "f4"),
+    opening("A04", "Zukertort Opening", "Nf3"),
+    opening("A07", "King's Indian Attack", "Nf3 d5 g3"),
+    opening("A09", "Réti Opening", "Nf3 d5 c4"),
+    opening("A10", "English Opening", "c4"),
+    opening("A20", "English Opening: King's English Variation", "c4 e5"),
+    opening("A30", "English Opening: Symmetrical Variation", "c4 c5"),
+    // Queen's pawn
+    opening("A40", "Queen's Pawn Game", "d4"),
+    opening("A43", "Old Benoni Defense", "d4 c5"),
+    opening("A45", "Indian Defense", "d4 Nf6"),
+    opening("A56", "Benoni Defense", "d4 Nf6 c4 c5"),
+    opening("A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"),
+    opening("A60", "Modern Benoni", "d4 Nf6 c4 c5 d5 e6"),
+    opening("A80", "Dutch Defense", "d4 f5"),
+    opening("D00", "Queen's Pawn Game", "d4 d5"),
+    opening("D00", "London System", "d4 d5 Bf4"),
+    opening("D06", "Queen's Gambit", "d4 d5 c4"),
+    opening(
+        "D07",
+        "Queen's Gambit Declined: Chigorin Defense",
+        "d4 d5 c4 Nc6",
+    ),
+    opening(
+        "D08",
+        "Queen's Gambit Declined: Albin Countergambit",
+        "d4 d5 c4 e5",
+    ),
+    opening("D10", "Slav Defense", "d4 d5 c4 c6"),
+    opening("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    opening("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    opening(
+        "D35",
+        "Queen's Gambit Declined: Exchange Variation",
+        "d4 d5 c4 e6 Nc3 Nf6 cxd5",
+    ),
+    opening("D43", "Semi-Slav Defense", "d4 d5 c4 e6 Nc3 Nf6 Nf3 c6"),
+    opening("D80", "Grünfeld Defense", "d4 Nf6 c4 g6 Nc3 d5"),
+    opening("E00", "Catalan Opening", "d4 Nf6 c4 e6 g3"),
+    opening("E11", "Bogo-Indian Defense", "d4 Nf6 c4 e6 Nf3 Bb4"),
+    opening("E12", "Queen's Indian Defense", "d4 Nf6 c4 e6 Nf3 b6"),
+    opening("E20", "Nimzo-Indian Defense", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    opening("E60", "King's Indian Defense", "d4 Nf6 c4 g6"),
+    // King's pawn, semi-open
+    opening("B00", "King's Pawn Game", "e4"),
+    opening("B00", "Nimzowitsch Defense", "e4 Nc6"),
+    opening("B01", "Scandinavian Defense", "e4 d5"),
+    opening(
+        "B01",
+        "Scandinavian Defense: Mieses-Kotroc Variation",
+        "e4 d5 exd5 Qxd5",
+    ),
+    opening("B02", "Alekhine's Defense", "e4 Nf6"),
+    opening("B06", "Modern Defense", "e4 g6"),
+    opening("B07", "Pirc Defense", "e4 d6 d4 Nf6"),
+    opening("B10", "Caro-Kann Defense", "e4 c6"),
+    opening(
+        "B12",
+        "Caro-Kann Defense: Advance Variation",
+        "e4 c6 d4 d5 e5",
+    ),
+    opening(
+        "B13",
+        "Caro-Kann Defense: Exchange Variation",
+        "e4 c6 d4 d5 exd5 cxd5",
+    ),
+    opening("B15", "Caro-Kann Defense", "e4 c6 d4 d5 Nc3"),
+    opening(
+        "B18",
+        "Caro-Kann Defense: Classical Variation",
+        "e4 c6 d4 d5 Nc3 dxe4 Nxe4 Bf5",
+    ),
+    opening("B20", "Sicilian Defense", "e4 c5"),
+    opening(
+        "B21",
+        "Sicilian Defense: Smith-Morra Gambit",
+        "e4 c5 d4 cxd4 c3",
+    ),
+    opening("B22", "Sicilian Defense: Alapin Variation", "e4 c5 c3"),
+    opening("B23", "Sicilian Defense: Closed", "e4 c5 Nc3"),
+    opening("B27", "Sicilian Defense", "e4 c5 Nf3"),
+    opening("B30", "Sicilian Defense: Old Sicilian", "e4 c5 Nf3 Nc6"),
+    opening(
+        "B33",
+        "Sicilian Defense: Sveshnikov Variation",
+        "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4 Nf6 Nc3 e5",
+    ),
+    opening("B40", "Sicilian Defense: French Variation", "e4 c5 Nf3 e6"),
+    opening(
+        "B41",
+        "Sicilian Defense: Kan Variation",
+        "e4 c5 Nf3 e6 d4 cxd4 Nxd4 a6",
+    ),
+    opening(
+        "B44",
+        "Sicilian Defense: Taimanov Variation",
+        "e4 c5 Nf3 e6 d4 cxd4 Nxd4 Nc6",
+    ),
+    opening("B50", "Sicilian Defense", "e4 c5 Nf3 d6"),
+    opening("B54", "Sicilian Defense: Open", "e4 c5 Nf3 d6 d4 cxd4 Nxd4"),
+    opening(
+        "B56",
+        "Sicilian Defense: Classical Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 Nc6",
+    ),
+    opening(
+        "B70",
+        "Sicilian Defense: Dragon Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6",
+    ),
+    opening(
+        "B80",
+        "Sicilian Defense: Scheveningen Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 e6",
+    ),
+    opening(
+        "B90",
+        "Sicilian Defense: Najdorf Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6",
+    ),
+    opening("C00", "French Defense", "e4 e6"),
+    opening(
+        "C01",
+        "French Defense: Exchange Variation",
+        "e4 e6 d4 d5 exd5",
+    ),
+    opening("C02", "French Defense: Advance Variation", "e4 e6 d4 d5 e5"),
+    opening(
+        "C03",
+        "French Defense: Tarrasch Variation",
+        "e4 e6 d4 d5 Nd2",
+    ),
+    opening(
+        "C10",
+        "French Defense: Paulsen Variation",
+        "e4 e6 d4 d5 Nc3",
+    ),
+    opening(
+        "C11",
+        "French Defense: Classical Variation",
+        "e4 e6 d4 d5 Nc3 Nf6",
+    ),
+    opening(
+        "C15",
+        "French Defense: Winawer Variation",
+        "e4 e6 d4 d5 Nc3 Bb4",
+    ),
+    // King's pawn, open
+    opening("C20", "King's Pawn Game", "e4 e5"),
+    opening("C21", "Center Game", "e4 e5 d4 exd4"),
+    opening("C21", "Danish Gambit", "e4 e5 d4 exd4 c3"),
+    opening("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    opening("C24", "Bishop's Opening: Berlin Defense", "e4 e5 Bc4 Nf6"),
+    opening("C25", "Vienna Game", "e4 e5 Nc3"),
+    opening("C30", "King's Gambit", "e4 e5 f4"),
+    opening("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    opening("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    opening("C40", "Latvian Gambit", "e4 e5 Nf3 f5"),
+    opening("C41", "Philidor Defense", "e4 e5 Nf3 d6"),
+    opening("C42", "Petrov's Defense", "e4 e5 Nf3 Nf6"),
+    opening(
+        "C44",
+        "King's Knight Opening: Normal Variation",
+        "e4 e5 Nf3 Nc6",
+    ),
+    opening("C44", "Ponziani Opening", "e4 e5 Nf3 Nc6 c3"),
+    opening("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4"),
+    opening("C45", "Scotch Game", "e4 e5 Nf3 Nc6 d4 exd4 Nxd4"),
+    opening("C46", "Three Knights Opening", "e4 e5 Nf3 Nc6 Nc3"),
+    opening("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    opening("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    opening(
+        "C50",
+        "Italian Game: Hungarian Defense",
+        "e4 e5 Nf3 Nc6 Bc4 Be7",
+    ),
+    opening("C50", "Italian Game: Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"),
+    opening(
+        "C51",
+        "Italian Game: Evans Gambit",
+        "e4 e5 Nf3 Nc6 Bc4 Bc5 b4",
+    ),
+    opening(
+        "C53",
+        "Italian Game: Classical Variation",
+        "e4 e5 Nf3 Nc6 Bc4 Bc5 c3",
+    ),
+    opening(
+        "C55",
+        "Italian Game: Two Knights Defense",
+        "e4 e5 Nf3 Nc6 Bc4 Nf6",
+    ),
+    opening(
+        "C57",
+        "Italian Game: Two Knights Defense, Knight Attack",
+        "e4 e5 Nf3 Nc6 Bc4 Nf6 Ng5",
+    ),
+    opening("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    opening("C65", "Ruy Lopez: Berlin Defense", "e4 e5 Nf3 Nc6 Bb5 Nf6"),
+    opening(
+        "C68",
+        "Ruy Lopez: Exchange Variation",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6",
+    ),
+    opening("C70", "Ruy Lopez: Morphy Defense", "e4 e5 Nf3 Nc6 Bb5 a6"),
+    opening(
+        "C78",
+        "Ruy Lopez: Morphy Defense",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O",
+    ),
+    opening(
+        "C80",
+        "Ruy Lopez: Open Variation",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Nxe4",
+    ),
+    opening(
+        "C84",
+        "Ruy Lopez: Closed Variation",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7",
+    ),
+    opening(
+        "C89",
+        "Ruy Lopez: Marshall Attack",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7 Re1 b5 Bb3 O-O c3 d5",
+    ),
+];
+
+/// The most specific opening the game has followed, given its moves in SAN
+/// from the initial position.
+///
+/// Openings are matched on move order, so transpositions into a known line
+/// are not recognized.
+pub fn identify<'a>(moves: impl IntoIterator<Item = &'a str>) -> Option<&'static Opening> {
+    let played: Vec<String> = moves.into_iter().map(normalize_san).collect();
+
+    OPENINGS
+        .iter()
+        .filter(|opening| {
+            let line: Vec<&str> = opening.moves.split(' ').collect();
+            line.len() <= played.len() && line.iter().zip(&played).all(|(a, b)| a == b)
+        })
+        .max_by_key(|opening| opening.moves.split(' ').count())
+}
@@ -0,0 +1,451 @@
+use crate::check::Board;
+use crate::moves::{castling_side, generate_legal_moves, CastlingRights, CastlingSide, Move};
+use crate::types::PieceType;
+
+/// Maximum length of a movetext line when writing PGN.
+const LINE_WIDTH: usize = 80;
+
+/// A game read from PGN: its tag pairs and its moves in SAN.
+pub struct PgnGame {
+    pub headers: Vec<(String, String)>,
+    pub moves: Vec<String>,
+}
+
+impl PgnGame {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Algebraic name of a square, e.g. `(7, 4)` is `"e1"`.
+pub fn square_name((row, col): (usize, usize)) -> String {
+    format!("{}{}", (b'a' + col as u8) as char, 8 - row)
+}
+
+fn piece_letter(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::King => "K",
+        PieceType::Queen => "Q",
+        PieceType::Rook => "R",
+        PieceType::Bishop => "B",
+        PieceType::Knight => "N",
+        PieceType::Pawn => "",
+    }
+}
+
+/// Standard algebraic notation for `mv`, played in the given position.
+///
+/// The check (`+`) and checkmate (`#`) suffixes are not included, since
+/// they depend on the position after the move.
+pub fn san(
+    board: &Board,
+    mv: Move,
+    promotion: Option<PieceType>,
+    en_passant_target: Option<usize>,
+    castling: &CastlingRights,
+) -> String {
+    let (from_row, from_col) = mv.from;
+    let (to_row, to_col) = mv.to;
+    let Some(piece) = board[from_row][from_col] else {
+        return String::new();
+    };
+
+    if let Some(side) = castling_side(board, mv, castling) {
+        return match side {
+            CastlingSide::Kingside => "O-O",
+            CastlingSide::Queenside => "O-O-O",
+        }
+        .to_string();
+    }
+
+    let is_capture = board[to_row][to_col].is_some()
+        || (piece.piece_type == PieceType::Pawn && from_col != to_col);
+
+    let mut san = String::new();
+    if piece.piece_type == PieceType::Pawn {
+        if is_capture {
+            san.push((b'a' + from_col as u8) as char);
+        }
+    } else {
+        san.push_str(piece_letter(piece.piece_type));
+
+        // Disambiguate between identical pieces that can reach the same square
+        let rivals: Vec<Move> =
+            generate_legal_moves(board, piece.color, en_passant_target, castling)
+                .into_iter()
+                .filter(|other| {
+                    other.to == mv.to
+                        && other.from != mv.from
+                        && board[other.from.0][other.from.1] == Some(piece)
+                })
+                .collect();
+        if !rivals.is_empty() {
+            let file = (b'a' + from_col as u8) as char;
+            let rank = (b'0' + (8 - from_row) as u8) as char;
+            if rivals.iter().all(|other| other.from.1 != from_col) {
+                san.push(file);
+            } else if rivals.iter().all(|other| other.from.0 != from_row) {
+                san.push(rank);
+            } else {
+                san.push(file);
+                san.push(rank);
+            }
+        }
+    }
+
+    if is_capture {
+        san.push('x');
+    }
+    san.push_str(&square_name(mv.to));
+    if let Some(promotion) = promotion {
+        san.push('=');
+        san.push_str(piece_letter(promotion));
+    }
+    san
+}
+
+/// Reduce a SAN move to a canonical form for comparison, dropping
+/// annotations, check marks and the `=` of promotions.
+pub fn normalize_san(san: &str) -> String {
+    san.replace("0-0-0", "O-O-O")
+        .replace("0-0", "O-O")
+        .chars()
+        .filter(|c| !matches!(c, '+' | '#' | '!' | '?' | '='))
+        .collect()
+}
+
+/// Write a game as PGN.
+///
+/// `first_move` is the move number of the first move, and `black_first`
+/// whether that move is Black's (for games starting from a FEN position).
+pub fn write_pgn(
+    headers: &[(String, String)],
+    moves: &[String],
+    first_move: u32,
+    black_first: bool,
+    result: &str,
+) -> String {
+    let mut pgn = String::new();
+    for (name, value) in headers {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+    }
+    pgn.push('\n');
+
+    let mut tokens = Vec::new();
+    for (i, san) in moves.iter().enumerate() {
+        let ply = i + black_first as usize;
+        let number = first_move as usize + ply / 2;
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}.", number));
+        } else if i == 0 {
+            tokens.push(format!("{}...", number));
+        }
+        tokens.push(san.clone());
+    }
+    tokens.push(result.to_string());
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+            pgn.push_str(&line);
+            pgn.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    pgn.push_str(&line);
+    pgn.push('\n');
+    pgn
+}
+
+/// Read the first game in a PGN string.
+///
+/// Comments, variations, numeric annotations and move numbers are skipped;
+/// moves are returned as written and resolved against the board by the
+/// caller.
+pub fn parse_pgn(pgn: &str) -> Result<PgnGame, String> {
+    let mut headers = Vec::new();
+    let mut movetext = String::new();
+
+    for line in pgn.lines() {
+        let line = line.trim();
+        if line.starts_with('%') {
+            // Escaped line
+            continue;
+        }
+        if let Some(tag) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if !movetext.trim().is_empty() {
+                // Start of the next game
+                break;
+            }
+            headers.push(parse_tag(tag)?);
+        } else {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    let mut moves = Vec::new();
+    let mut chars = movetext.chars();
+    let mut variation_depth = 0;
+    let mut token = String::new();
+
+    while let Some(c) = chars.next() {
+        // Every delimiter ends the current token
+        if c.is_whitespace() || matches!(c, '.' | '{' | ';' | '(' | ')') {
+            if variation_depth == 0 {
+                push_move(&mut moves, &token)?;
+            }
+            token.clear();
+        }
+        match c {
+            '{' => {
+                // Comment until the closing brace
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                // Comment until the end of the line
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth -= 1,
+            c if c.is_whitespace() || c == '.' => {}
+            c => token.push(c),
+        }
+        if variation_depth < 0 {
+            return Err("Unbalanced ')' in movetext".to_string());
+        }
+    }
+    if variation_depth == 0 {
+        push_move(&mut moves, &token)?;
+    }
+
+    Ok(PgnGame { headers, moves })
+}
+
+fn parse_tag(tag: &str) -> Result<(String, String), String> {
+    let (name, value) = tag
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("Malformed tag: [{}]", tag))?;
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(|| format!("Tag value must be quoted: [{}]", tag))?;
+    Ok((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+/// Add a movetext token to `moves` if it is a move.
+fn push_move(moves: &mut Vec<String>, token: &str) -> Result<(), String> {
+    let is_number = !token.is_empty() && token.chars().all(|c| c.is_ascii_digit());
+    let is_result = matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*");
+    if token.is_empty() || is_number || is_result || token.starts_with('$') {
+        return Ok(());
+    }
+    if !token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '0') {
+        return Err(format!("Unexpected token in movetext: {}", token));
+    }
+    moves.push(token.to_string());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::parse_fen;
+    use crate::types::PlayerColor;
+
+    /// A move written in UCI notation, e.g. `e7e8q`, with its promotion.
+    fn parse_move(uci: &str) -> (Move, Option<PieceType>) {
+        let bytes = uci.as_bytes();
+        let square = |name: &[u8]| ((b'8' - name[1]) as usize, (name[0] - b'a') as usize);
+        let promotion = match bytes.get(4) {
+            None => None,
+            Some(b'q') => Some(PieceType::Queen),
+            Some(b'r') => Some(PieceType::Rook),
+            Some(b'b') => Some(PieceType::Bishop),
+            Some(b'n') => Some(PieceType::Knight),
+            Some(_) => panic!("bad promotion in {uci}"),
+        };
+        let (from, to) = (square(&bytes[0..2]), square(&bytes[2..4]));
+        (Move { from, to }, promotion)
+    }
+
+    /// Find the legal move written as `written` in the position `fen`, the
+    /// way loading a game does.
+    fn resolve(fen: &str, written: &str) -> Option<(Move, Option<PieceType>)> {
+        let state = parse_fen(fen).unwrap();
+        let target = normalize_san(written);
+        let moves = generate_legal_moves(
+            &state.board,
+            state.turn,
+            state.en_passant_target,
+            &state.castling,
+        );
+        let promotions = [
+            None,
+            Some(PieceType::Queen),
+            Some(PieceType::Rook),
+            Some(PieceType::Bishop),
+            Some(PieceType::Knight),
+        ];
+        moves
+            .into_iter()
+            .flat_map(|mv| promotions.map(|promotion| (mv, promotion)))
+            .find(|&(mv, promotion)| {
+                let candidate = san(
+                    &state.board,
+                    mv,
+                    promotion,
+                    state.en_passant_target,
+                    &state.castling,
+                );
+                normalize_san(&candidate) == target
+            })
+    }
+
+    /// Write the move `uci` in the position `fen` as PGN, read it back,
+    /// check it resolves to the same move, and return its SAN.
+    fn round_trip(fen: &str, uci: &str) -> String {
+        let state = parse_fen(fen).unwrap();
+        let (mv, promotion) = parse_move(uci);
+        let written = san(
+            &state.board,
+            mv,
+            promotion,
+            state.en_passant_target,
+            &state.castling,
+        );
+
+        let headers = vec![
+            ("SetUp".to_string(), "1".to_string()),
+            ("FEN".to_string(), fen.to_string()),
+        ];
+        let black_first = state.turn == PlayerColor::Black;
+        let pgn = write_pgn(
+            &headers,
+            std::slice::from_ref(&written),
+            1,
+            black_first,
+            "*",
+        );
+        let game = parse_pgn(&pgn).unwrap();
+        assert_eq!(game.header("FEN"), Some(fen));
+        assert_eq!(game.moves, vec![written.clone()]);
+        assert_eq!(resolve(fen, &game.moves[0]), Some((mv, promotion)));
+        written
+    }
+
+    #[test]
+    fn castling_round_trips() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(round_trip(fen, "e1g1"), "O-O");
+        assert_eq!(round_trip(fen, "e1c1"), "O-O-O");
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1";
+        assert_eq!(round_trip(fen, "e8g8"), "O-O");
+        assert_eq!(round_trip(fen, "e8c8"), "O-O-O");
+    }
+
+    #[test]
+    fn promotion_round_trips() {
+        let fen = "1r5k/P7/8/8/8/8/8/K7 w - - 0 1";
+        assert_eq!(round_trip(fen, "a7a8q"), "a8=Q");
+        assert_eq!(round_trip(fen, "a7a8n"), "a8=N");
+        assert_eq!(round_trip(fen, "a7b8r"), "axb8=R");
+    }
+
+    #[test]
+    fn en_passant_round_trips() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2";
+        assert_eq!(round_trip(fen, "e5d6"), "exd6");
+    }
+
+    #[test]
+    fn ambiguous_moves_round_trip() {
+        // Told apart by file, by rank, and by both
+        let fen = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+        assert_eq!(round_trip(fen, "b1d2"), "Nbd2");
+        let fen = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(round_trip(fen, "a1a3"), "R1a3");
+        let fen = "k7/8/8/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(round_trip(fen, "h4e1"), "Qh4e1");
+        assert_eq!(round_trip(fen, "e4e1"), "Qee1");
+        assert_eq!(round_trip(fen, "h1e1"), "Q1e1");
+    }
+
+    #[test]
+    fn chess960_castling_round_trips() {
+        // The king castles by moving onto its own rook
+        let fen = "1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1";
+        assert_eq!(round_trip(fen, "g1h1"), "O-O");
+        assert_eq!(round_trip(fen, "g1b1"), "O-O-O");
+    }
+
+    #[test]
+    fn games_round_trip() {
+        let headers = vec![
+            ("Event".to_string(), "A \"quoted\" \\ event".to_string()),
+            ("Result".to_string(), "1/2-1/2".to_string()),
+        ];
+        let moves: Vec<String> = ["Nf3", "Nf6", "Ng1", "Ng8"]
+            .iter()
+            .cycle()
+            .take(60)
+            .map(|san| san.to_string())
+            .collect();
+        let pgn = write_pgn(&headers, &moves, 1, false, "1/2-1/2");
+        assert!(pgn.lines().all(|line| line.len() <= LINE_WIDTH));
+
+        let game = parse_pgn(&pgn).unwrap();
+        assert_eq!(game.headers, headers);
+        assert_eq!(game.moves, moves);
+
+        let pgn = write_pgn(&[], &moves[..3], 12, true, "*");
+        assert!(pgn.contains("12... Nf3 13. Nf6 Ng1 *"));
+        assert_eq!(parse_pgn(&pgn).unwrap().moves, &moves[..3]);
+    }
+
+    #[test]
+    fn malformed_movetext_is_rejected() {
+        assert!(parse_pgn("1. e4 e5 ) 2. Nf3").is_err());
+        assert!(parse_pgn("1. e4 @ 2. Nf3").is_err());
+        assert!(parse_pgn("1. e4 e5 2. -Nf3").is_err());
+        assert!(parse_pgn("[Event]\n\n1. e4").is_err());
+        assert!(parse_pgn("[Event Casual]\n\n1. e4").is_err());
+    }
+
+    #[test]
+    fn comments_and_variations_are_skipped() {
+        let pgn = "[Event \"Test\"]\n\
+                   % An escaped line: 1. d4\n\
+                   \n\
+                   1. e4 {Best by test (1. d4)} e5 (1... c5 2. Nf3 (2. c3) d6)\n\
+                   2. Nf3 ; A comment to the end of the line 2... d6\n\
+                   Nc6 $1 3.Bb5!? {Spanish}a6 1-0\n\
+                   \n\
+                   [Event \"Next\"]\n\
+                   \n\
+                   1. d4 *\n";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(game.header("Event"), Some("Test"));
+        assert_eq!(game.moves, ["e4", "e5", "Nf3", "Nc6", "Bb5!?", "a6"]);
+    }
+}
@@ -0,0 +1,69 @@
+/// A tactics puzzle: a position and the line that wins from it.
+pub struct Puzzle {
+    pub name: &'static str,
+    pub fen: &'static str,
+    /// The solution in UCI notation, alternating the solver's moves and the
+    /// opponent's replies
+    solution: &'static str,
+}
+
+impl Puzzle {
+    pub fn solution(&self) -> Vec<&'static str> {
+        self.solution.split(' ').collect()
+    }
+}
+
+const fn puzzle(name: &'static str, fen: &'static str, solution: &'static str) -> Puzzle {
+    Puzzle {
+        name,
+        fen,
+        solution,
+    }
+}
+
+/// Built-in puzzles, easiest first. Each is a forced mate with only one
+/// winning move at every step.
+pub const PUZZLES: &[Puzzle] = &[
+    puzzle(
+        "Back-rank mate",
+        "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+        "a1a8",
+    ),
+    puzzle(
+        "Scholar's mate",
+        "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        "f3f7",
+    ),
+    puzzle(
+        "Smothered mate",
+        "6rk/6pp/8/6N1/8/8/8/6K1 w - - 0 1",
+        "g5f7",
+    ),
+    puzzle("Arabian mate", "7k/R7/5N2/8/8/8/8/6K1 w - - 0 1", "a7h7"),
+    puzzle(
+        "Queen and knight",
+        "6k1/8/8/8/6n1/7q/5P2/5RK1 b - - 0 1",
+        "h3h2",
+    ),
+    puzzle("Queen and king", "7k/8/5K2/8/8/8/8/6Q1 w - - 0 1", "g1g7"),
+    puzzle(
+        "Doubled rooks",
+        "r5k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1",
+        "e2e8 a8e8 e1e8",
+    ),
+    puzzle(
+        "Doubled rooks for Black",
+        "4r1k1/4rppp/8/8/8/8/5PPP/R5K1 b - - 0 1",
+        "e7e1 a1e1 e8e1",
+    ),
+    puzzle(
+        "Anastasia's mate",
+        "5r1k/4Nppp/8/4R3/8/3Q4/8/6K1 w - - 0 1",
+        "d3h7 h8h7 e5h5",
+    ),
+    puzzle(
+        "Philidor's legacy",
+        "r6k/6pp/8/6N1/2Q5/8/8/6K1 w - - 0 1",
+        "g5f7 h8g8 f7h6 g8h8 c4g8 a8g8 h6f7",
+    ),
+];
@@ -0,0 +1,70 @@
+//! Games written to disk, to be picked up again later.
+
+use crate::clock::{ChessClock, TimeControl};
+use crate::game::GameResult;
+use crate::settings::config_dir;
+use crate::types::PlayerColor;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// A game as stored on disk. The moves are kept rather than the position
+/// and replayed on loading, so everything that follows from them (captures,
+/// repetitions, the move list) comes back too.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    /// The FEN the game started from, if it didn't start from the initial
+    /// position
+    pub start_fen: Option<String>,
+    /// The moves played, in UCI notation
+    pub moves: Vec<String>,
+    /// Moves taken back that can be redone, the next one first
+    pub redo: Vec<String>,
+    /// Kept for results the moves don't show, such as a loss on time
+    pub result: GameResult,
+    pub clock: Option<ChessClock>,
+    pub time_control: Option<TimeControl>,
+    pub custom_time_control: TimeControl,
+    pub chess960: bool,
+    pub flip_board: bool,
+    pub vs_computer: bool,
+    pub computer_color: PlayerColor,
+    pub computer_depth: u8,
+}
+
+/// Where a game is saved.
+#[derive(Clone, Copy)]
+pub enum Slot {
+    /// The game in progress, saved as it is played and resumed on the next
+    /// run
+    Autosave,
+    /// The game saved with the "Save Game" button
+    Saved,
+}
+
+impl Slot {
+    fn path(self) -> io::Result<PathBuf> {
+        let dir = config_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        Ok(dir.join(match self {
+            Slot::Autosave => "autosave.json",
+            Slot::Saved => "saved_game.json",
+        }))
+    }
+}
+
+impl SavedGame {
+    pub fn load(slot: Slot) -> io::Result<SavedGame> {
+        let contents = fs::read_to_string(slot.path()?)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, slot: Slot) -> io::Result<()> {
+        let path = slot.path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Preferences kept between runs, stored as `key = value` lines.
+#[derive(Clone, Default)]
+pub struct Settings {
+    pub muted: bool,
+    /// Longest run of puzzles solved without a wrong move
+    pub best_puzzle_streak: u32,
+    /// Names of the chosen board theme and piece set
+    pub board_theme: String,
+    pub piece_set: String,
+    pub hide_coordinates: bool,
+    /// The share of the window the board gets beside the side panel, once
+    /// the divider between them has been moved
+    pub board_ratio: Option<f32>,
+}
+
+/// Where the app keeps its files, following each platform's convention for
+/// per-user configuration.
+pub fn config_dir() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| std::env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("vitae_chess"))
+}
+
+fn settings_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("settings.txt"))
+}
+
+impl Settings {
+    /// Read the saved settings, falling back to the defaults for anything
+    /// missing or unreadable.
+    pub fn load() -> Self {
+        let mut settings = Settings::default();
+        let Some(contents) = settings_path().and_then(|path| fs::read_to_string(path).ok()) else {
+            return settings;
+        };
+
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match key.trim() {
+                "muted" => settings.muted = value.trim() == "true",
+                "best_puzzle_streak" => {
+                    settings.best_puzzle_streak = value.trim().parse().unwrap_or(0)
+                }
+                "board_theme" => settings.board_theme = value.trim().to_string(),
+                "piece_set" => settings.piece_set = value.trim().to_string(),
+                "hide_coordinates" => settings.hide_coordinates = value.trim() == "true",
+                "board_ratio" => settings.board_ratio = value.trim().parse().ok(),
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = settings_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(
+            path,
+            format!(
+                "muted = {}\nbest_puzzle_streak = {}\nboard_theme = {}\npiece_set = {}\nhide_coordinates = {}\nboard_ratio = {}\n",
+                self.muted,
+                self.best_puzzle_streak,
+                self.board_theme,
+                self.piece_set,
+                self.hide_coordinates,
+                self.board_ratio.map_or(String::new(), |ratio| ratio.to_string())
+            ),
+        )
+    }
+}
@@ -0,0 +1,115 @@
+//! Looks for the board and pieces, chosen in the theme popover.
+
+use crate::types::PlayerColor;
+
+/// Colors of the board's squares. Coordinates are drawn in the color of
+/// the other kind of square.
+pub struct BoardTheme {
+    pub name: &'static str,
+    pub light: &'static str,
+    pub dark: &'static str,
+}
+
+pub const BOARD_THEMES: &[BoardTheme] = &[
+    BoardTheme {
+        name: "Rose",
+        light: "#f2e5e5",
+        dark: "#a3524c",
+    },
+    BoardTheme {
+        name: "Brown",
+        light: "#f0d9b5",
+        dark: "#b58863",
+    },
+    BoardTheme {
+        name: "Green",
+        light: "#eeeed2",
+        dark: "#769656",
+    },
+    BoardTheme {
+        name: "Blue",
+        light: "#dee3e6",
+        dark: "#8ca2ad",
+    },
+    BoardTheme {
+        name: "Slate",
+        light: "#c8c8c8",
+        dark: "#5a5a5a",
+    },
+];
+
+/// The board theme called `name`, or the first one if there is none.
+pub fn board_theme(name: &str) -> &'static BoardTheme {
+    BOARD_THEMES
+        .iter()
+        .find(|theme| theme.name == name)
+        .unwrap_or(&BOARD_THEMES[0])
+}
+
+/// A look for the pieces. All sets share the artwork in `assets/pieces`;
+/// sets other than the first repaint it.
+pub struct PieceSet {
+    pub name: &'static str,
+    palette: Option<PiecePalette>,
+}
+
+/// Colors replacing the black and white of the piece artwork.
+struct PiecePalette {
+    white_body: &'static str,
+    white_lines: &'static str,
+    black_body: &'static str,
+    black_lines: &'static str,
+}
+
+pub const PIECE_SETS: &[PieceSet] = &[
+    PieceSet {
+        name: "Classic",
+        palette: None,
+    },
+    PieceSet {
+        name: "Wood",
+        palette: Some(PiecePalette {
+            white_body: "#f4e4c1",
+            white_lines: "#4a3020",
+            black_body: "#5c3a21",
+            black_lines: "#f4e4c1",
+        }),
+    },
+    PieceSet {
+        name: "Marine",
+        palette: Some(PiecePalette {
+            white_body: "#e8f1ff",
+            white_lines: "#1c3d6e",
+            black_body: "#1c3d6e",
+            black_lines: "#e8f1ff",
+        }),
+    },
+];
+
+/// The piece set called `name`, or the first one if there is none.
+pub fn piece_set(name: &str) -> &'static PieceSet {
+    PIECE_SETS
+        .iter()
+        .find(|set| set.name == name)
+        .unwrap_or(&PIECE_SETS[0])
+}
+
+impl PieceSet {
+    /// Repaint the SVG source of one of `color`'s pieces in this set's
+    /// colors.
+    pub fn paint(&self, data: &str, color: PlayerColor) -> String {
+        let Some(palette) = &self.palette else {
+            return data.to_string();
+        };
+        // The artwork is drawn in pure black and white only. A white piece
+        // is a white body with black lines, and a black piece the reverse.
+        let (black, white) = match color {
+            PlayerColor::White => (palette.white_lines, palette.white_body),
+            PlayerColor::Black => (palette.black_body, palette.black_lines),
+        };
+        // Swap through a placeholder so the colors don't overwrite each other
+        data.replace("#000", "\u{0}")
+            .replace("#FFF", white)
+            .replace('\u{0}', black)
+    }
+}
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum PieceType {
     King,
@@ -8,7 +10,7 @@ pub enum PieceType {
     Pawn,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum PlayerColor {
     White,
     Black,
@@ -0,0 +1,200 @@
+//! Analysis by an external engine speaking the Universal Chess Interface.
+//!
+//! The engine runs as a child process. Commands go to its standard input,
+//! and a background thread reads its standard output and reports the
+//! evaluation of the position being searched.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Names the engine executable to run, overriding `DEFAULT_ENGINE`.
+pub const ENGINE_VAR: &str = "VITAE_CHESS_ENGINE";
+const DEFAULT_ENGINE: &str = "stockfish";
+
+/// The engine executable to run: `$VITAE_CHESS_ENGINE`, or Stockfish from
+/// the `PATH`.
+pub fn engine_command() -> String {
+    std::env::var(ENGINE_VAR).unwrap_or_else(|_| DEFAULT_ENGINE.to_string())
+}
+
+/// An evaluation, from the point of view of one side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+    /// In hundredths of a pawn
+    Centipawns(i32),
+    /// Mate in this many moves; negative when getting mated
+    Mate(i32),
+}
+
+impl Score {
+    /// The same evaluation from the other side's point of view.
+    pub fn negate(self) -> Score {
+        match self {
+            Score::Centipawns(cp) => Score::Centipawns(-cp),
+            Score::Mate(moves) => Score::Mate(-moves),
+        }
+    }
+
+    /// Short form for display, e.g. `+0.35` or `-M3`.
+    pub fn label(self) -> String {
+        match self {
+            Score::Centipawns(cp) => format!("{:+.2}", cp as f32 / 100.0),
+            Score::Mate(moves) if moves < 0 => format!("-M{}", -moves),
+            Score::Mate(moves) => format!("M{}", moves),
+        }
+    }
+
+    /// How much of an evaluation bar this side gets, from 0 to 1. A pawn
+    /// up is worth about 0.6; a forced mate fills the bar.
+    pub fn bar_share(self) -> f32 {
+        match self {
+            Score::Centipawns(cp) => 1.0 / (1.0 + (-cp as f32 / 250.0).exp()),
+            Score::Mate(moves) if moves < 0 => 0.0,
+            Score::Mate(_) => 1.0,
+        }
+    }
+}
+
+/// Something the engine reported, delivered to the game.
+pub enum EngineEvent {
+    /// Progress on a search. `search` counts the searches that had finished
+    /// before it, so reports from a search that was stopped can be told
+    /// apart from the one that replaced it.
+    Info {
+        search: u64,
+        depth: u32,
+        /// From the point of view of the side to move
+        score: Score,
+        /// The move the engine prefers, in UCI notation
+        best_move: Option<String>,
+    },
+    /// The engine exited or closed its output.
+    Exited,
+}
+
+/// A running engine. Clones share the same process.
+#[derive(Clone)]
+pub struct UciEngine {
+    stdin: Arc<Mutex<ChildStdin>>,
+    child: Arc<Mutex<Child>>,
+    /// Whether a search is running. Only those are stopped, so that every
+    /// `bestmove` ends a search this side started.
+    searching: Arc<AtomicBool>,
+}
+
+impl UciEngine {
+    /// Start the engine at `command` and put it in UCI mode.
+    pub fn start(command: &str) -> io::Result<(UciEngine, Receiver<EngineEvent>)> {
+        let mut child = Command::new(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(io::Error::other("engine has no standard input or output"));
+        };
+
+        let (events, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // Every search ends with exactly one `bestmove`, including ones
+            // cut short by `stop`
+            let mut finished = 0;
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if line.starts_with("bestmove") {
+                    finished += 1;
+                } else if let Some((depth, score, best_move)) = parse_info(&line) {
+                    let _ = events.send(EngineEvent::Info {
+                        search: finished,
+                        depth,
+                        score,
+                        best_move,
+                    });
+                }
+            }
+            let _ = events.send(EngineEvent::Exited);
+        });
+
+        let engine = UciEngine {
+            stdin: Arc::new(Mutex::new(stdin)),
+            child: Arc::new(Mutex::new(child)),
+            searching: Arc::new(AtomicBool::new(false)),
+        };
+        engine.send("uci");
+        engine.send("isready");
+        Ok((engine, receiver))
+    }
+
+    fn send(&self, command: &str) {
+        // A failed write means the engine exited, which the reading thread
+        // reports
+        let _ = writeln!(self.stdin.lock().unwrap(), "{}", command);
+    }
+
+    /// Stop the current search, if any, and search the position reached by
+    /// playing `moves` (in UCI notation) from `fen` until stopped.
+    pub fn analyse(&self, fen: &str, moves: &[String], chess960: bool) {
+        self.stop();
+        self.send(&format!("setoption name UCI_Chess960 value {}", chess960));
+        if moves.is_empty() {
+            self.send(&format!("position fen {}", fen));
+        } else {
+            self.send(&format!("position fen {} moves {}", fen, moves.join(" ")));
+        }
+        self.send("go infinite");
+        self.searching.store(true, Ordering::SeqCst);
+    }
+
+    pub fn stop(&self) {
+        if self.searching.swap(false, Ordering::SeqCst) {
+            self.send("stop");
+        }
+    }
+
+    /// Ask the engine to exit, and reap it in the background.
+    pub fn quit(&self) {
+        self.stop();
+        self.send("quit");
+        let child = self.child.clone();
+        thread::spawn(move || {
+            let _ = child.lock().unwrap().wait();
+        });
+    }
+}
+
+/// Read the depth, score and first move of the principal variation from an
+/// `info` line. Lines without an exact score, such as `currmove` updates
+/// and bounds from an aspiration window, give `None`.
+fn parse_info(line: &str) -> Option<(u32, Score, Option<String>)> {
+    let mut tokens = line.split_whitespace();
+    if tokens.next() != Some("info") {
+        return None;
+    }
+
+    let (mut depth, mut score, mut best_move) = (None, None, None);
+    while let Some(token) = tokens.next() {
+        match token {
+            "depth" => depth = tokens.next()?.parse().ok(),
+            "score" => {
+                score = match (tokens.next()?, tokens.next()?.parse().ok()?) {
+                    ("cp", cp) => Some(Score::Centipawns(cp)),
+                    ("mate", moves) => Some(Score::Mate(moves)),
+                    _ => None,
+                }
+            }
+            "lowerbound" | "upperbound" => return None,
+            "pv" => best_move = tokens.next().map(str::to_string),
+            // The rest of the line is free text
+            "string" => return None,
+            _ => {}
+        }
+    }
+    Some((depth?, score?, best_move))
+}
@@ -1,6 +1,23 @@
-use crate::game::{ChessGame, DrawReason, GameResult};
-use crate::types::{PieceType, PlayerColor};
+use crate::clock::{format_clock, TimeControl};
+use crate::game::{Analysis, ChessGame, DrawReason, GameResult};
+use crate::game::{Annotations, MoveRecord};
+use crate::moves::CastlingSide;
+use crate::net;
+use crate::puzzles::PUZZLES;
+use crate::theme::{self, BoardTheme, BOARD_THEMES, PIECE_SETS};
+use crate::types::{Piece, PieceType, PlayerColor};
+use crate::uci::Score;
+use std::borrow::Cow;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use vitae::clipboard;
 use vitae::prelude::*;
+use vitae::toast::{self, Toast};
+
+/// The share of the window the board gets until the divider is moved.
+const BOARD_RATIO: f32 = 0.65;
+/// Width of the bar showing the evaluation, beside the board.
+const EVAL_BAR_WIDTH: f32 = 20.0;
 
 fn move_list(game: &ChessGame) -> ElementBuilder {
     let moves: Vec<String> = game
@@ -23,7 +40,7 @@ fn move_list(game: &ChessGame) -> ElementBuilder {
 }
 
 fn promotion_ui() -> ElementBuilder {
-    let promotion_button = |label: &str, piece_type: PieceType| {
+    let promotion_button = |label: &'static str, piece_type: PieceType| {
         div()
             .bg(Color::from_hex("#5a5a5a"))
             .p(px(8.0))
@@ -49,12 +66,14 @@ fn promotion_ui() -> ElementBuilder {
 }
 
 fn debug_menu() -> ElementBuilder {
-    let fen_button = |label: &str, fen: &'static str| {
+    let fen_button = |label: &'static str, fen: &'static str| {
         div()
             .bg(Color::from_hex("#3a3a5a"))
             .p(px(4.0))
             .child(text(label).color(Color::from_hex("#c0c0e0")))
-            .on_left_click(move |g: &mut ChessGame| g.load_fen(fen))
+            .on_left_click(move |g: &mut ChessGame| {
+                let _ = g.load_fen(fen);
+            })
     };
 
     div()
@@ -88,9 +107,138 @@ fn debug_menu() -> ElementBuilder {
         .child(fen_button("King vs King", "4k3/8/8/8/8/8/8/4K3 w - - 0 1"))
 }
 
-fn checkerboard_colors(x: usize, y: usize) -> (Color, Color) {
-    let light_square = Color::rgb(242, 229, 229);
-    let dark_square = Color::rgb(163, 82, 76);
+/// How long a piece takes to slide to its new square.
+const MOVE_ANIMATION: Duration = Duration::from_millis(200);
+
+/// A piece drawn over the board while the last move animates.
+struct MovingPiece {
+    piece: Piece,
+    from: (usize, usize),
+    to: (usize, usize),
+    /// Captured pieces fade out where they stood
+    fades: bool,
+}
+
+/// The pieces the last move slides (or removes), captured pieces first so
+/// they are drawn underneath.
+fn moving_pieces(game: &ChessGame, record: &MoveRecord) -> Vec<MovingPiece> {
+    let mut pieces = Vec::new();
+    if let Some(captured) = record.captured {
+        let square = if record.was_en_passant {
+            (record.from.0, record.to.1)
+        } else {
+            record.to
+        };
+        pieces.push(MovingPiece {
+            piece: captured,
+            from: square,
+            to: square,
+            fades: true,
+        });
+    }
+
+    // A castling king lands on its own file, not on the move's target (the
+    // rook's square in Chess960)
+    let row = record.to.0;
+    let destinations = record.was_castling.map(CastlingSide::destinations);
+    let to = match destinations {
+        Some((king_to, _)) => (row, king_to),
+        None => record.to,
+    };
+
+    // The board already shows the result, so this is the promoted piece
+    if let Some(piece) = game.board[to.0][to.1] {
+        pieces.push(MovingPiece {
+            piece,
+            from: record.from,
+            to,
+            fades: false,
+        });
+    }
+
+    if let (Some(side), Some((_, rook_to))) = (record.was_castling, destinations) {
+        let rook_from = record.prev_castling_rights.rook_file(side);
+        if let Some(rook) = game.board[row][rook_to] {
+            pieces.push(MovingPiece {
+                piece: rook,
+                from: (row, rook_from),
+                to: (row, rook_to),
+                fades: false,
+            });
+        }
+    }
+    pieces
+}
+
+/// Size of a square in the annotation overlay's coordinate space.
+const OVERLAY_SQUARE: f32 = 100.0;
+
+/// Arrows and square highlights, plus the engine's suggested move as a
+/// `hint` arrow, as an SVG covering the whole board, in view coordinates
+/// (`to_view` maps board squares to view rows/columns).
+fn annotation_svg(
+    annotations: &Annotations,
+    hint: Option<((usize, usize), (usize, usize))>,
+    to_view: impl Fn((usize, usize)) -> (f32, f32),
+) -> Svg {
+    let size = OVERLAY_SQUARE * 8.0;
+    let center = |square| {
+        let (row, col) = to_view(square);
+        ((col + 0.5) * OVERLAY_SQUARE, (row + 0.5) * OVERLAY_SQUARE)
+    };
+
+    let mut shapes = String::new();
+    for &square in &annotations.squares {
+        let (row, col) = to_view(square);
+        shapes.push_str(&format!(
+            r##"<rect x="{}" y="{}" width="{s}" height="{s}" fill="#e0403a" fill-opacity="0.55"/>"##,
+            col * OVERLAY_SQUARE,
+            row * OVERLAY_SQUARE,
+            s = OVERLAY_SQUARE,
+        ));
+    }
+
+    let head_length = OVERLAY_SQUARE * 0.35;
+    let head_width = OVERLAY_SQUARE * 0.25;
+    let mut arrow = |from, to, color: &str| {
+        let (x1, y1) = center(from);
+        let (x2, y2) = center(to);
+        let length = (x2 - x1).hypot(y2 - y1);
+        let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+        // Stop the shaft where the head begins so the ends don't overlap
+        let (base_x, base_y) = (x2 - dx * head_length, y2 - dy * head_length);
+        let (px, py) = (-dy * head_width, dx * head_width);
+        shapes.push_str(&format!(
+            r##"<g fill="{color}" stroke="{color}" opacity="0.8"><line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}" stroke-linecap="round"/><polygon points="{},{} {},{} {},{}" stroke="none"/></g>"##,
+            x1,
+            y1,
+            base_x,
+            base_y,
+            OVERLAY_SQUARE * 0.18,
+            x2,
+            y2,
+            base_x + px,
+            base_y + py,
+            base_x - px,
+            base_y - py,
+        ));
+    };
+    for &(from, to) in &annotations.arrows {
+        arrow(from, to, "#15781b");
+    }
+    if let Some((from, to)) = hint {
+        arrow(from, to, "#2a6fd6");
+    }
+
+    let data = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {size} {size}" width="{size}" height="{size}">{shapes}</svg>"#,
+    );
+    Svg::new(data, size, size)
+}
+
+fn checkerboard_colors(x: usize, y: usize, theme: &BoardTheme) -> (Color, Color) {
+    let light_square = Color::from_hex(theme.light);
+    let dark_square = Color::from_hex(theme.dark);
 
     if ((x + y) & 1) == 0 {
         (light_square, dark_square) // (bg, text)
@@ -99,26 +247,103 @@ fn checkerboard_colors(x: usize, y: usize) -> (Color, Color) {
     }
 }
 
+/// Rank numbers by board row, top to bottom.
+const RANKS: [&str; 8] = ["8", "7", "6", "5", "4", "3", "2", "1"];
+/// File letters by board column, left to right.
+const FILES: [&str; 8] = ["a", "b", "c", "d", "e", "f", "g", "h"];
+
 pub fn view(game: &ChessGame) -> ElementBuilder {
     let hover = use_signal(|| None::<(usize, usize)>);
 
-    let flipped = game.flip_board && game.turn == PlayerColor::Black;
+    // Against the computer or over the network, keep your own pieces at
+    // the bottom
+    let flipped = if let Some(puzzle) = &game.puzzle {
+        puzzle.solver == PlayerColor::Black
+    } else if let Some(network) = &game.network {
+        network.local_color() == PlayerColor::Black
+    } else if game.vs_computer {
+        game.computer_color == PlayerColor::White
+    } else {
+        game.flip_board && game.turn == PlayerColor::Black
+    };
     let king_in_check = game.king_in_check();
 
-    let chessboard = div()
-        .h(FULL)
+    // Slide the pieces of each new move into place
+    let shown_plies = use_signal(|| game.history.len());
+    let slide = use_animated(|| 1.0_f32, MOVE_ANIMATION, Easing::EaseOut);
+    if shown_plies.get() != game.history.len() {
+        if game.history.len() == shown_plies.get() + 1 && game.animate_last_move {
+            slide.jump(0.0);
+            slide.set(1.0);
+        } else {
+            slide.jump(1.0);
+        }
+        shown_plies.set(game.history.len());
+    }
+    let progress = slide.get();
+    let moving = match game.history.last() {
+        Some(record) if slide.is_animating() => moving_pieces(game, record),
+        _ => Vec::new(),
+    };
+    // Squares whose pieces are drawn by the animation instead
+    let in_motion: Vec<(usize, usize)> = moving.iter().map(|m| m.to).collect();
+    let to_view = move |(row, col): (usize, usize)| {
+        if flipped {
+            ((7 - row) as f32, (7 - col) as f32)
+        } else {
+            (row as f32, col as f32)
+        }
+    };
+    let animation_layer = moving.iter().filter_map(|moving| {
+        let piece_svg = game.pieces.get(&moving.piece)?;
+        let (from_row, from_col) = to_view(moving.from);
+        let (to_row, to_col) = to_view(moving.to);
+        let row = from_row + (to_row - from_row) * progress;
+        let col = from_col + (to_col - from_col) * progress;
+        let opacity = if moving.fades { 1.0 - progress } else { 1.0 };
+        Some(
+            div()
+                .absolute()
+                .left(pc(col * 100. / 8.))
+                .top(pc(row * 100. / 8.))
+                .w(pc(100. / 8.))
+                .h(pc(100. / 8.))
+                .center()
+                .child(svg(piece_svg).size(pc(80.0)).opacity(opacity)),
+        )
+    });
+
+    let hint = game.best_move().map(|(mv, _)| (mv.from, mv.to));
+    let board_theme = theme::board_theme(&game.settings.board_theme);
+    let coordinates = !game.settings.hide_coordinates;
+
+    // Fit the board in its side of the split, next to the eval bar
+    let (pane_width, pane_height) = use_element_size("board-pane").get();
+    let room = pane_width
+        - if game.analysis.is_some() {
+            EVAL_BAR_WIDTH
+        } else {
+            0.0
+        };
+    let chessboard = if room < pane_height {
+        div().w(pc(room.max(0.0) / pane_width * 100.0))
+    } else {
+        div().h(FULL)
+    };
+    let chessboard = chessboard
+        .debug_name("chessboard")
         .square()
         .col()
-        .children((0..8).map(move |view_row| {
+        .children((0..8).map(|view_row| {
             div()
                 .row()
                 .h(pc(100. / 8.))
                 .w(FULL)
-                .children((0..8).map(move |view_col| {
+                .children((0..8).map(|view_col| {
                     let row = if flipped { 7 - view_row } else { view_row };
                     let col = if flipped { 7 - view_col } else { view_col };
 
-                    let (bg_color, label_color) = checkerboard_colors(row, col);
+                    let (bg_color, label_color) = checkerboard_colors(row, col, board_theme);
                     let mut square = div().bg(bg_color).w(pc(100. / 8.)).h(FULL);
 
                     // Highlight king in check
@@ -134,18 +359,35 @@ pub fn view(game: &ChessGame) -> ElementBuilder {
                         square = square.bg(Color::rgb(200, 200, 100));
                     }
 
-                    let is_valid_target = if let Some((sel_row, sel_col)) = game.selected {
-                        game.is_valid_move(sel_row, sel_col, row, col)
-                    } else {
-                        false
-                    };
+                    // A queued premove shows faintly until it is played
+                    let premove = game
+                        .premove
+                        .filter(|premove| premove.from == (row, col) || premove.to == (row, col));
+                    if premove.is_some() {
+                        square = square.bg(Color::rgb(150, 160, 190));
+                    }
+
+                    let is_valid_target = game
+                        .selected
+                        .is_some_and(|selected| game.is_move_target(selected, (row, col)));
 
-                    if let Some(piece) = game.board[row][col] {
+                    let piece = game.board[row][col].filter(|_| !in_motion.contains(&(row, col)));
+                    if let Some(piece) = piece {
                         if is_valid_target {
                             square = square.bg(Color::rgb(200, 80, 80));
                         }
                         if let Some(piece_svg) = game.pieces.get(&piece) {
-                            square = square.center().child(svg(piece_svg).size(pc(80.0)));
+                            // Leave a ghost behind while the piece is dragged
+                            let dragged = game
+                                .drag
+                                .is_some_and(|d| d.from == (row, col) && d.cursor.is_some());
+                            square = square.center().child(
+                                svg(piece_svg).size(pc(80.0)).opacity(if dragged {
+                                    0.3
+                                } else {
+                                    1.0
+                                }),
+                            );
                         }
                     } else if is_valid_target {
                         square = square.center().child(
@@ -153,37 +395,88 @@ pub fn view(game: &ChessGame) -> ElementBuilder {
                                 .rounded()
                                 .opacity(0.5)
                                 .bg(Color::rgb(50, 50, 50))
-                                .size(pc(30.0)),
+                                .size(pc(30.0))
+                                .pointer_events(false),
                         );
                     }
 
+                    let premoved_piece = premove
+                        .filter(|premove| premove.to == (row, col))
+                        .and_then(|premove| game.board[premove.from.0][premove.from.1])
+                        .and_then(|piece| game.pieces.get(&piece));
+                    if let Some(piece_svg) = premoved_piece {
+                        square = square.center().child(
+                            svg(piece_svg)
+                                .absolute()
+                                .left(pc(10.0))
+                                .top(pc(10.0))
+                                .size(pc(80.0))
+                                .opacity(0.4)
+                                .pointer_events(false),
+                        );
+                    }
+
+                    // Pieces can be picked up, and stay held until dropped
+                    if game.drag.is_some_and(|d| d.cursor.is_some()) {
+                        square = square.cursor(CursorIcon::Grabbing);
+                    } else if piece.is_some() {
+                        square = square.cursor(CursorIcon::Grab);
+                    }
+
                     // Rank number (1-8) in top-left of left edge squares
-                    if view_col == 0 {
-                        let rank = 8 - row;
+                    if coordinates && view_col == 0 {
                         square = square.child(
-                            text(rank.to_string())
+                            text(RANKS[row])
                                 .color(label_color)
                                 .absolute()
                                 .top(px(2.0))
-                                .left(px(2.0)),
+                                .left(px(2.0))
+                                .pointer_events(false),
                         );
                     }
 
                     // File letter (a-h) in bottom-right of bottom edge squares
-                    if view_row == 7 {
-                        let file = (b'a' + col as u8) as char;
+                    if coordinates && view_row == 7 {
                         square = square.child(
-                            text(file.to_string())
+                            text(FILES[col])
                                 .color(label_color)
                                 .absolute()
                                 .bottom(px(2.0))
-                                .right(px(2.0)),
+                                .right(px(2.0))
+                                .pointer_events(false),
                         );
                     }
 
-                    square.on_left_click(move |g: &mut ChessGame| g.select_square(row, col))
+                    square
+                        .on_mouse_down(move |g: &mut ChessGame, button| match button {
+                            MouseButton::Left => g.press_square(row, col),
+                            MouseButton::Right => g.start_annotation(row, col),
+                            MouseButton::Middle => {}
+                        })
+                        .on_mouse_move(|g: &mut ChessGame, x, y| {
+                            g.drag_to(x, y);
+                            g.annotation_drag(x, y);
+                        })
+                        .on_mouse_up(move |g: &mut ChessGame, button| match button {
+                            MouseButton::Left => g.release_square(row, col),
+                            MouseButton::Right => g.finish_annotation(row, col),
+                            MouseButton::Middle => {}
+                        })
                 }))
-        }));
+        }))
+        .children(animation_layer)
+        .child(
+            if game.annotations.arrows.is_empty()
+                && game.annotations.squares.is_empty()
+                && hint.is_none()
+            {
+                div()
+            } else {
+                svg(&annotation_svg(&game.annotations, hint, to_view))
+                    .absolute()
+                    .size(FULL)
+            },
+        );
 
     let player_panel = |name: &str, color: PlayerColor, is_turn: bool| {
         let my_points = game.points_for(color);
@@ -201,6 +494,19 @@ pub fn view(game: &ChessGame) -> ElementBuilder {
             String::new()
         };
 
+        let clock = match game.clock {
+            Some(clock) => {
+                let remaining = clock.remaining(color);
+                let clock_color = if remaining < Duration::from_secs(10) {
+                    Color::from_hex("#ff6060")
+                } else {
+                    Color::from_hex("#e0e0e0")
+                };
+                text(format_clock(remaining)).color(clock_color)
+            }
+            None => text(""),
+        };
+
         div()
             .w(FULL)
             .col()
@@ -211,6 +517,7 @@ pub fn view(game: &ChessGame) -> ElementBuilder {
                 Color::from_hex("#444444")
             })
             .child(text(format!("{}{}", name, score_str)).color(Color::from_hex("#e0e0e0")))
+            .child(clock)
             .child(
                 text(if captured_str.is_empty() {
                     "-".to_string()
@@ -221,12 +528,12 @@ pub fn view(game: &ChessGame) -> ElementBuilder {
             )
     };
 
-    let button = |label: &str| {
+    fn button(label: impl Into<Cow<'static, str>>) -> ElementBuilder {
         div()
             .bg(Color::from_hex("#444444"))
             .p(px(8.0))
             .child(text(label).color(Color::from_hex("#e0e0e0")))
-    };
+    }
 
     let auto_flip_label = if game.flip_board {
         "Auto-Flip: On"
@@ -234,23 +541,79 @@ pub fn view(game: &ChessGame) -> ElementBuilder {
         "Auto-Flip: Off"
     };
 
+    let chess960_label = if game.chess960 {
+        "Chess960: On"
+    } else {
+        "Chess960: Off"
+    };
+
+    let sound_label = if game.settings.muted {
+        "Sound: Off"
+    } else {
+        "Sound: On"
+    };
+
+    let analysis_label = if game.analysis.is_some() {
+        "Analysis: On"
+    } else {
+        "Analysis: Off"
+    };
+
+    let vs_computer_label = if game.vs_computer {
+        "Play vs Computer: On"
+    } else {
+        "Play vs Computer: Off"
+    };
+    let computer_color_label = match game.computer_color {
+        PlayerColor::White => "Computer plays: White",
+        PlayerColor::Black => "Computer plays: Black",
+    };
+
+    let time_control_button = |label: Cow<'static, str>, control: Option<TimeControl>| {
+        let selected = game.time_control == control;
+        div()
+            .bg(if selected {
+                Color::from_hex("#4a6a4a")
+            } else {
+                Color::from_hex("#444444")
+            })
+            .p(px(8.0))
+            .child(text(label).color(Color::from_hex("#e0e0e0")))
+            .on_left_click(move |g: &mut ChessGame| g.set_time_control(control))
+    };
+    let custom = game.custom_time_control;
+
+    let show_themes = use_signal(|| false);
+    let confirm = use_signal(|| None);
+
     let side_panel = div()
+        .debug_name("side_panel")
         .size(FULL)
-        .border_l(2.0, Color::from_hex("#4a4a4a"))
         .bg(Color::from_hex("#333333"))
         .p(px(12.0))
         .col()
         .child(text("Chess").color(Color::from_hex("#e0e0e0")))
+        .child(match game.opening() {
+            Some(opening) => {
+                text(format!("{} {}", opening.eco, opening.name)).color(Color::from_hex("#b0b0b0"))
+            }
+            None => text(""),
+        })
         .child(player_panel(
-            "Player 2 (Black)",
+            game.player_name(PlayerColor::Black),
             PlayerColor::Black,
             game.turn == PlayerColor::Black,
         ))
         .child(player_panel(
-            "Player 1 (White)",
+            game.player_name(PlayerColor::White),
             PlayerColor::White,
             game.turn == PlayerColor::White,
         ))
+        .child(if game.computer_thinking {
+            text("Computer is thinking...").color(Color::from_hex("#b0b0b0"))
+        } else {
+            text("")
+        })
         .child(if let Some(ref last_move) = game.last_move {
             text(format!("Last: {}", last_move)).color(Color::from_hex("#b0b0b0"))
         } else {
@@ -264,12 +627,29 @@ pub fn view(game: &ChessGame) -> ElementBuilder {
                 };
                 text(format!("Checkmate! {} wins", winner_name)).color(Color::from_hex("#ffcc00"))
             }
+            GameResult::Timeout(winner) => {
+                let winner_name = match winner {
+                    PlayerColor::White => "White",
+                    PlayerColor::Black => "Black",
+                };
+                text(format!("Time! {} wins on time", winner_name))
+                    .color(Color::from_hex("#ffcc00"))
+            }
+            GameResult::Resignation(winner) => {
+                let (loser_name, winner_name) = match winner {
+                    PlayerColor::White => ("Black", "White"),
+                    PlayerColor::Black => ("White", "Black"),
+                };
+                text(format!("{} resigns, {} wins", loser_name, winner_name))
+                    .color(Color::from_hex("#ffcc00"))
+            }
             GameResult::Draw(reason) => {
                 let reason_str = match reason {
                     DrawReason::Stalemate => "Stalemate",
                     DrawReason::InsufficientMaterial => "Insufficient material",
                     DrawReason::FiftyMoveRule => "Fifty-move rule",
                     DrawReason::ThreefoldRepetition => "Threefold repetition",
+                    DrawReason::Agreement => "Agreed",
                 };
                 text(format!("Draw - {}", reason_str)).color(Color::from_hex("#ffcc00"))
             }
@@ -284,18 +664,241 @@ pub fn view(game: &ChessGame) -> ElementBuilder {
             div()
                 .row()
                 .w(FULL)
-                .child(button("Undo").on_left_click(|g: &mut ChessGame| g.undo()))
-                .child(button("Redo").on_left_click(|g: &mut ChessGame| g.redo())),
+                .child(
+                    button("Undo")
+                        .disabled(!game.can_undo())
+                        .on_left_click(|g: &mut ChessGame| g.undo()),
+                )
+                .child(
+                    button("Redo")
+                        .disabled(!game.can_redo())
+                        .on_left_click(|g: &mut ChessGame| g.redo()),
+                ),
         )
+        .child(if game.is_game_over() {
+            div().row().w(FULL).child(
+                button("Rematch")
+                    .disabled(game.puzzle.is_some())
+                    .on_left_click(|g: &mut ChessGame| g.rematch()),
+            )
+        } else {
+            div()
+                .row()
+                .w(FULL)
+                .child(
+                    button("Resign")
+                        .disabled(!game.can_concede())
+                        .on_left_click(move |_: &mut ChessGame| confirm.set(Some(Confirm::Resign))),
+                )
+                .child(
+                    button("Offer Draw")
+                        .disabled(!game.can_concede() || game.draw_offer.is_some())
+                        .on_left_click(move |_: &mut ChessGame| {
+                            confirm.set(Some(Confirm::OfferDraw))
+                        }),
+                )
+        })
+        .child(match game.draw_offer {
+            Some(offerer) if game.can_answer_draw() => div()
+                .row()
+                .w(FULL)
+                .child(
+                    text(format!("{} offers a draw", game.player_name(offerer)))
+                        .color(Color::from_hex("#ffcc00")),
+                )
+                .child(button("Accept").on_left_click(|g: &mut ChessGame| g.answer_draw(true)))
+                .child(button("Decline").on_left_click(|g: &mut ChessGame| g.answer_draw(false))),
+            Some(_) => {
+                text("Draw offered, waiting for an answer").color(Color::from_hex("#b0b0b0"))
+            }
+            None => div(),
+        })
         .child(
             div()
                 .row()
                 .w(FULL)
                 .child(button("Reset").on_left_click(|g: &mut ChessGame| g.reset()))
+                .child(
+                    button(chess960_label).on_left_click(|g: &mut ChessGame| g.toggle_chess960()),
+                )
                 .child(
                     button(auto_flip_label).on_left_click(|g: &mut ChessGame| g.toggle_auto_flip()),
+                )
+                .child(button(sound_label).on_left_click(|g: &mut ChessGame| g.toggle_mute()))
+                .child(
+                    button("Theme")
+                        .key("theme-button")
+                        .on_left_click(move |_: &mut ChessGame| {
+                            show_themes.set(!show_themes.get())
+                        }),
+                ),
+        )
+        .child(
+            div()
+                .row()
+                .w(FULL)
+                .child(time_control_button("No clock".into(), None))
+                .child(time_control_button("3+2".into(), Some(TimeControl::BLITZ)))
+                .child(time_control_button("10+5".into(), Some(TimeControl::RAPID)))
+                .child(time_control_button(
+                    format!("Custom {}", custom.label()).into(),
+                    Some(custom),
+                )),
+        )
+        .child(
+            div()
+                .row()
+                .w(FULL)
+                .child(
+                    button("-1 min")
+                        .on_left_click(|g: &mut ChessGame| g.adjust_custom_time_control(-1, 0)),
+                )
+                .child(
+                    button("+1 min")
+                        .on_left_click(|g: &mut ChessGame| g.adjust_custom_time_control(1, 0)),
+                )
+                .child(
+                    button("-1 s")
+                        .on_left_click(|g: &mut ChessGame| g.adjust_custom_time_control(0, -1)),
+                )
+                .child(
+                    button("+1 s")
+                        .on_left_click(|g: &mut ChessGame| g.adjust_custom_time_control(0, 1)),
+                ),
+        )
+        .child(
+            div().row().w(FULL).child(
+                button(vs_computer_label)
+                    .disabled(game.network.is_some())
+                    .on_left_click(|g: &mut ChessGame| g.toggle_vs_computer()),
+            ),
+        )
+        .child(
+            div()
+                .row()
+                .w(FULL)
+                .child(
+                    button(computer_color_label)
+                        .disabled(!game.vs_computer)
+                        .on_left_click(|g: &mut ChessGame| g.toggle_computer_color()),
+                )
+                .child(
+                    button(format!("Depth: {}", game.computer_depth))
+                        .disabled(!game.vs_computer)
+                        .on_left_click(|g: &mut ChessGame| g.cycle_computer_depth()),
+                ),
+        )
+        .child(
+            div()
+                .row()
+                .w(FULL)
+                .child(
+                    button(analysis_label).on_left_click(|g: &mut ChessGame| g.toggle_analysis()),
+                )
+                .child(match &game.analysis {
+                    Some(analysis) => {
+                        text(analysis_summary(game, analysis)).color(Color::from_hex("#b0b0b0"))
+                    }
+                    None => text(""),
+                }),
+        )
+        .child(match &game.puzzle {
+            Some(puzzle) => {
+                let to_play = match puzzle.solver {
+                    PlayerColor::White => "White",
+                    PlayerColor::Black => "Black",
+                };
+                let status = if puzzle.solved {
+                    "solved".to_string()
+                } else {
+                    format!("{} to play and mate", to_play)
+                };
+                text(format!(
+                    "Puzzle {}/{}: {} - {}",
+                    puzzle.index + 1,
+                    PUZZLES.len(),
+                    puzzle.puzzle().name,
+                    status
+                ))
+                .color(Color::from_hex("#ffcc00"))
+            }
+            None => text(""),
+        })
+        .child(
+            div()
+                .row()
+                .w(FULL)
+                .child(
+                    button("Next Puzzle")
+                        .disabled(game.network.is_some())
+                        .on_left_click(|g: &mut ChessGame| g.next_puzzle()),
+                )
+                .child(
+                    text(format!(
+                        "Streak: {} (best {})",
+                        game.puzzle_streak, game.settings.best_puzzle_streak
+                    ))
+                    .color(Color::from_hex("#b0b0b0")),
                 ),
         )
+        .child(match &game.network {
+            Some(network) => text(network.status()).color(Color::from_hex("#b0b0b0")),
+            None => text(""),
+        })
+        .child(
+            div()
+                .row()
+                .w(FULL)
+                .child(button("Host Game").on_left_click(|g: &mut ChessGame| g.host_network_game()))
+                .child(button("Join Game").on_left_click(|g: &mut ChessGame| {
+                    // Join the address on the clipboard, or this machine
+                    let host = clipboard::get_text()
+                        .ok()
+                        .and_then(|text| net::parse_address(&text))
+                        .unwrap_or_else(|| {
+                            SocketAddr::from((Ipv4Addr::LOCALHOST, net::DEFAULT_PORT))
+                        });
+                    g.join_network_game(host);
+                }))
+                .child(
+                    button("Leave Game")
+                        .disabled(game.network.is_none())
+                        .on_left_click(|g: &mut ChessGame| g.leave_network_game()),
+                ),
+        )
+        .child(
+            div()
+                .row()
+                .w(FULL)
+                .child(button("Copy PGN").on_left_click(|g: &mut ChessGame| {
+                    toast::push(match clipboard::set_text(g.to_pgn()) {
+                        Ok(()) => Toast::success("PGN copied to clipboard"),
+                        Err(err) => Toast::error(format!("Couldn't copy PGN: {}", err)),
+                    });
+                }))
+                .child(button("Copy FEN").on_left_click(|g: &mut ChessGame| {
+                    toast::push(match clipboard::set_text(g.to_fen()) {
+                        Ok(()) => Toast::success("FEN copied to clipboard"),
+                        Err(err) => Toast::error(format!("Couldn't copy FEN: {}", err)),
+                    });
+                }))
+                .child(button("Paste PGN").on_left_click(|g: &mut ChessGame| {
+                    let loaded = clipboard::get_text()
+                        .map_err(|err| err.to_string())
+                        .and_then(|pgn| g.load_pgn(&pgn));
+                    toast::push(match loaded {
+                        Ok(()) => Toast::success("PGN loaded"),
+                        Err(err) => Toast::error(format!("Couldn't load PGN: {}", err)),
+                    });
+                })),
+        )
+        .child(
+            div()
+                .row()
+                .w(FULL)
+                .child(button("Save Game").on_left_click(|g: &mut ChessGame| g.save_game()))
+                .child(button("Load Game").on_left_click(|g: &mut ChessGame| g.load_saved_game())),
+        )
         .child(move_list(game))
         .child({
             let show_debug = use_signal(|| false);
@@ -321,5 +924,206 @@ pub fn view(game: &ChessGame) -> ElementBuilder {
                 })
         });
 
-    div().size(FULL).row().child(chessboard).child(side_panel)
+    let board_pane = div()
+        .key("board-pane")
+        .size(FULL)
+        .row()
+        .child(chessboard)
+        .child(match &game.analysis {
+            Some(analysis) => eval_bar(analysis, flipped),
+            None => div(),
+        });
+
+    div()
+        .size(FULL)
+        .row()
+        .child(
+            split(true, board_pane, side_panel)
+                .key("board-split")
+                .ratio(game.settings.board_ratio.unwrap_or(BOARD_RATIO))
+                .min_first(320.0)
+                .min_second(240.0)
+                .on_resize(ChessGame::set_board_ratio),
+        )
+        .child(if show_themes.get() {
+            theme_popover(game, show_themes)
+        } else {
+            div()
+        })
+        .child(match confirm.get() {
+            Some(action) => confirm_dialog(action, confirm),
+            None => div(),
+        })
+        .child(dragged_piece(game))
+        .child(toast::stack())
+}
+
+/// An action from the side panel that asks for confirmation first.
+#[derive(Clone, Copy)]
+enum Confirm {
+    Resign,
+    OfferDraw,
+}
+
+/// Asks to confirm `action` over a dimmed window, until either button is
+/// clicked or the dialog is dismissed.
+fn confirm_dialog(action: Confirm, open: Signal<Option<Confirm>>) -> ElementBuilder {
+    let button = |label: &'static str| {
+        div()
+            .bg(Color::from_hex("#444444"))
+            .p(px(8.0))
+            .child(text(label).color(Color::from_hex("#e0e0e0")))
+    };
+    let question = match action {
+        Confirm::Resign => "Resign this game?",
+        Confirm::OfferDraw => "Offer a draw?",
+    };
+
+    // Over the theme popover, if that is open too
+    portal()
+        .layer(1)
+        .modal()
+        .on_dismiss(move |_: &mut ChessGame| open.set(None))
+        .top(px(0.0))
+        .right(px(0.0))
+        .bottom(px(0.0))
+        .left(px(0.0))
+        .center()
+        .bg(Color::new(0.0, 0.0, 0.0, 0.5))
+        .child(
+            div()
+                .col()
+                .gap(px(8.0))
+                .p(px(16.0))
+                .bg(Color::from_hex("#2a2a2a"))
+                .border(2.0, Color::from_hex("#4a4a4a"))
+                .radius(8.0)
+                .child(text(question).color(Color::from_hex("#e0e0e0")))
+                .child(
+                    div()
+                        .row()
+                        .gap(px(8.0))
+                        .child(button("Confirm").on_left_click(move |g: &mut ChessGame| {
+                            open.set(None);
+                            match action {
+                                Confirm::Resign => g.resign(),
+                                Confirm::OfferDraw => g.offer_draw(),
+                            }
+                        }))
+                        .child(
+                            button("Cancel").on_left_click(move |_: &mut ChessGame| open.set(None)),
+                        ),
+                ),
+        )
+}
+
+/// Settings for the look of the board, dropping down from the Theme button.
+fn theme_popover(game: &ChessGame, open: Signal<bool>) -> ElementBuilder {
+    let option = |label: &'static str, selected: bool| {
+        div()
+            .bg(if selected {
+                Color::from_hex("#4a6a4a")
+            } else {
+                Color::from_hex("#444444")
+            })
+            .p(px(8.0))
+            .child(text(label).color(Color::from_hex("#e0e0e0")))
+    };
+    let board_theme = theme::board_theme(&game.settings.board_theme);
+    let piece_set = theme::piece_set(&game.settings.piece_set);
+
+    popover("theme-button", Placement::Bottom)
+        .col()
+        .gap(px(8.0))
+        .p(px(12.0))
+        .bg(Color::from_hex("#2a2a2a"))
+        .border(2.0, Color::from_hex("#4a4a4a"))
+        .radius(8.0)
+        .child(text("Board").color(Color::from_hex("#b0b0b0")))
+        .child(div().row().children(BOARD_THEMES.iter().map(|theme| {
+            option(theme.name, theme.name == board_theme.name)
+                .on_left_click(move |g: &mut ChessGame| g.set_board_theme(theme))
+        })))
+        .child(text("Pieces").color(Color::from_hex("#b0b0b0")))
+        .child(div().row().children(PIECE_SETS.iter().map(|set| {
+            option(set.name, set.name == piece_set.name)
+                .on_left_click(move |g: &mut ChessGame| g.set_piece_set(set))
+        })))
+        .child(
+            option("Coordinates", !game.settings.hide_coordinates)
+                .on_left_click(|g: &mut ChessGame| g.toggle_coordinates()),
+        )
+        .child(
+            div()
+                .bg(Color::from_hex("#444444"))
+                .p(px(8.0))
+                .child(text("Done").color(Color::from_hex("#e0e0e0")))
+                .on_left_click(move |_: &mut ChessGame| open.set(false)),
+        )
+}
+
+/// The engine's evaluation and suggested move, for the side panel.
+fn analysis_summary(game: &ChessGame, analysis: &Analysis) -> String {
+    match analysis.score {
+        Some(score) => format!(
+            "{} (depth {}), best: {}",
+            score.label(),
+            analysis.depth,
+            game.best_move_san().unwrap_or_else(|| "-".to_string())
+        ),
+        None if game.is_game_over() => "Game over".to_string(),
+        None => "Thinking...".to_string(),
+    }
+}
+
+/// A vertical bar between the board and the side panel, split between
+/// White and Black by the engine's evaluation. White's part is at White's
+/// end of the board.
+fn eval_bar(analysis: &Analysis, flipped: bool) -> ElementBuilder {
+    let white_share = analysis.score.map_or(0.5, Score::bar_share);
+    let white = div()
+        .w(FULL)
+        .h(pc(white_share * 100.0))
+        .bg(Color::from_hex("#f0f0f0"));
+    let black = div()
+        .w(FULL)
+        .h(pc((1.0 - white_share) * 100.0))
+        .bg(Color::from_hex("#202020"));
+
+    let bar = div().w(px(EVAL_BAR_WIDTH)).h(FULL).col();
+    if flipped {
+        bar.child(white).child(black)
+    } else {
+        bar.child(black).child(white)
+    }
+}
+
+/// The piece being dragged, drawn above everything at the cursor.
+fn dragged_piece(game: &ChessGame) -> ElementBuilder {
+    let Some(drag) = game.drag else {
+        return div();
+    };
+    let (Some((x, y)), Some(piece)) = (drag.cursor, game.board[drag.from.0][drag.from.1]) else {
+        return div();
+    };
+    let Some(piece_svg) = game.pieces.get(&piece) else {
+        return div();
+    };
+
+    // The board fills the window height, so a square is an eighth of it.
+    // The piece is centered on the cursor.
+    // Follows the cursor, so it must not hide the square underneath
+    portal()
+        .left(px(x))
+        .top(px(y))
+        .h(pc(100. / 8.))
+        .square()
+        .pointer_events(false)
+        .child(
+            svg(piece_svg)
+                .absolute()
+                .left(pc(-40.0))
+                .top(pc(-40.0))
+                .size(pc(80.0)),
+        )
 }
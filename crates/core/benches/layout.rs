@@ -0,0 +1,134 @@
+//! Building and laying out element trees of different shapes, with text
+//! measured by `FixedMeasurer` rather than shaped, and hit testing them.
+//! Run with `cargo bench -p vitae_core`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use vitae_core::{div, layout_tree, pc, px, text, Color, ElementBuilder, FixedMeasurer, HitIndex};
+
+const WIDTH: f32 = 1280.0;
+const HEIGHT: f32 = 800.0;
+
+/// A column of `count` rows, each with a few children.
+fn wide_tree(count: usize) -> ElementBuilder {
+    div().col().w(pc(100.0)).children((0..count).map(|i| {
+        div()
+            .row()
+            .gap(px(8.0))
+            .p(px(4.0))
+            .child(div().size(px(16.0)).bg(Color::BLACK))
+            .child(text(format!("Row {i}")))
+            .child(div().w(px(40.0)).h(px(16.0)).on_left_click(|_: &mut ()| {}))
+    }))
+}
+
+/// `depth` elements, each nested in the last.
+fn deep_tree(depth: usize) -> ElementBuilder {
+    (0..depth).fold(text("Leaf"), |inner, i| {
+        let element = if i % 2 == 0 { div().row() } else { div().col() };
+        element.p(px(1.0)).child(inner)
+    })
+}
+
+/// Paragraphs of wrapping text in a column.
+fn text_tree(paragraphs: usize) -> ElementBuilder {
+    let paragraph = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+    div()
+        .col()
+        .gap(px(12.0))
+        .p(px(16.0))
+        .w(px(600.0))
+        .children((0..paragraphs).map(|_| text(paragraph.clone())))
+}
+
+/// A chess board like the chess example's: 64 squares holding pieces and
+/// coordinates, next to a move list.
+fn chess_board() -> ElementBuilder {
+    let board = div().col().size(px(640.0)).children((0..8).map(|rank| {
+        div()
+            .row()
+            .w(pc(100.0))
+            .h(pc(12.5))
+            .children((0..8).map(move |file| {
+                let light = (rank + file) % 2 == 0;
+                let mut square = div()
+                    .w(pc(12.5))
+                    .h(pc(100.0))
+                    .center()
+                    .bg(Color::from_hex(if light { "#f0d9b5" } else { "#b58863" }));
+                if !(2..=5).contains(&rank) {
+                    square = square.child(div().size(pc(80.0)).radius(40.0).bg(Color::WHITE));
+                }
+                if file == 0 {
+                    square = square.child(text(format!("{}", 8 - rank)).absolute().top(px(2.0)));
+                }
+                square
+            }))
+    }));
+    let moves = div().col().w(px(240.0)).children((1..=40).map(|number| {
+        div()
+            .row()
+            .gap(px(8.0))
+            .child(text(format!("{number}.")))
+            .child(text("Nf3"))
+            .child(text("Nf6"))
+    }));
+    div()
+        .row()
+        .gap(px(16.0))
+        .p(px(16.0))
+        .child(board)
+        .child(moves)
+}
+
+/// Build `root` and lay it out in the window.
+fn build_and_layout(root: ElementBuilder) {
+    black_box(layout_tree(
+        root,
+        WIDTH,
+        HEIGHT,
+        &mut FixedMeasurer::default(),
+    ));
+}
+
+fn bench_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("layout");
+    for count in [100, 1000, 10000] {
+        group.bench_with_input(BenchmarkId::new("wide", count), &count, |b, &count| {
+            b.iter_batched(|| wide_tree(count), build_and_layout, BatchSize::SmallInput)
+        });
+    }
+    for depth in [50, 200] {
+        group.bench_with_input(BenchmarkId::new("deep", depth), &depth, |b, &depth| {
+            b.iter_batched(|| deep_tree(depth), build_and_layout, BatchSize::SmallInput)
+        });
+    }
+    group.bench_function("text", |b| {
+        b.iter_batched(|| text_tree(200), build_and_layout, BatchSize::SmallInput)
+    });
+    group.bench_function("chess_board", |b| {
+        b.iter_batched(chess_board, build_and_layout, BatchSize::SmallInput)
+    });
+    group.finish();
+}
+
+fn bench_hit_test(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hit_test");
+    let tree = layout_tree(
+        wide_tree(1000),
+        WIDTH,
+        HEIGHT,
+        &mut FixedMeasurer::default(),
+    );
+    // A point on the button of a row halfway down the window
+    let (x, y) = (140.0, HEIGHT / 2.0);
+    group.bench_function("tree", |b| b.iter(|| black_box(tree.hit_test(x, y))));
+    group.bench_function("index_build", |b| {
+        b.iter(|| black_box(HitIndex::new(&tree)))
+    });
+    let index = HitIndex::new(&tree);
+    group.bench_function("index", |b| b.iter(|| black_box(index.hit_test(x, y))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_layout, bench_hit_test);
+criterion_main!(benches);
@@ -0,0 +1,320 @@
+use std::time::Duration;
+
+use crate::color::Color;
+use crate::style::Length;
+
+/// Easing curve applied to the progress of an animation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map linear progress `t` in `0.0..=1.0` onto the curve.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// Values that can be smoothly interpolated between two states.
+pub trait Interpolate: Clone {
+    /// Interpolate from `self` (at `t = 0.0`) to `other` (at `t = 1.0`).
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let [r0, g0, b0, a0] = self.to_array();
+        let [r1, g1, b1, a1] = other.to_array();
+        // Clamp so overshooting springs don't produce out-of-gamut colors
+        let channel = |a: f32, b: f32| a.lerp(&b, t).clamp(0.0, 1.0);
+        Color::new(
+            channel(r0, r1),
+            channel(g0, g1),
+            channel(b0, b1),
+            channel(a0, a1),
+        )
+    }
+}
+
+impl Interpolate for Length {
+    /// Lengths of the same unit interpolate; mismatched units (or `Auto`)
+    /// jump to the target once the animation completes.
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        match (self, other) {
+            (Length::Px(a), Length::Px(b)) => Length::Px(a.lerp(b, t)),
+            (Length::Percent(a), Length::Percent(b)) => Length::Percent(a.lerp(b, t)),
+            _ if t < 1.0 => *self,
+            _ => *other,
+        }
+    }
+}
+
+impl<A: Interpolate, B: Interpolate> Interpolate for (A, B) {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        (self.0.lerp(&other.0, t), self.1.lerp(&other.1, t))
+    }
+}
+
+/// A damped spring used to drive animations.
+///
+/// Unlike a fixed-duration tween, a spring settles in its own time, and
+/// under-damped springs overshoot their target before coming to rest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spring {
+    pub stiffness: f32,
+    pub damping: f32,
+    pub mass: f32,
+}
+
+/// Displacement from the target below which a spring counts as settled.
+const SPRING_REST_THRESHOLD: f32 = 0.001;
+
+impl Spring {
+    /// Responsive, settling without visible overshoot.
+    pub const DEFAULT: Self = Self::new(170.0, 26.0);
+    /// Slow and smooth, without overshoot.
+    pub const GENTLE: Self = Self::new(120.0, 22.0);
+    /// Noticeably bouncy.
+    pub const WOBBLY: Self = Self::new(180.0, 12.0);
+    /// Faster than the default.
+    pub const STIFF: Self = Self::new(210.0, 28.0);
+
+    /// A spring with a mass of `1.0`.
+    ///
+    /// # Panics
+    /// Panics if `stiffness` is not positive or `damping` is negative.
+    pub const fn new(stiffness: f32, damping: f32) -> Self {
+        assert!(stiffness > 0.0, "spring stiffness must be positive");
+        assert!(damping >= 0.0, "spring damping must not be negative");
+        Self {
+            stiffness,
+            damping,
+            mass: 1.0,
+        }
+    }
+
+    /// Set the mass. Heavier springs move slower and bounce longer.
+    ///
+    /// # Panics
+    /// Panics if `mass` is not positive.
+    pub const fn mass(mut self, mass: f32) -> Self {
+        assert!(mass > 0.0, "spring mass must be positive");
+        self.mass = mass;
+        self
+    }
+
+    /// Progress of a spring released at rest from `0.0` towards `1.0`, `t`
+    /// seconds after release. Under-damped springs overshoot past `1.0`.
+    pub fn progress(&self, t: f32) -> f32 {
+        1.0 + self.displacement(t)
+    }
+
+    /// Whether the spring has come to rest `t` seconds after release.
+    pub fn is_settled(&self, t: f32) -> bool {
+        let omega = (self.stiffness / self.mass).sqrt();
+        let zeta = self.damping_ratio();
+        if zeta < 1.0 {
+            // Bound on the oscillation amplitude, so we don't stop at a zero
+            // crossing while the spring is still moving fast.
+            let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+            let amplitude = (1.0 + (zeta * omega / omega_d).powi(2)).sqrt();
+            amplitude * (-zeta * omega * t).exp() < SPRING_REST_THRESHOLD
+        } else {
+            self.displacement(t).abs() < SPRING_REST_THRESHOLD
+        }
+    }
+
+    fn damping_ratio(&self) -> f32 {
+        self.damping / (2.0 * (self.stiffness * self.mass).sqrt())
+    }
+
+    /// Closed-form displacement from the target, starting at `-1.0` at rest.
+    fn displacement(&self, t: f32) -> f32 {
+        let x0 = -1.0;
+        let omega = (self.stiffness / self.mass).sqrt();
+        let zeta = self.damping_ratio();
+
+        if zeta < 1.0 {
+            let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+            (-zeta * omega * t).exp()
+                * (x0 * (omega_d * t).cos() + (zeta * omega * x0 / omega_d) * (omega_d * t).sin())
+        } else if zeta == 1.0 {
+            (-omega * t).exp() * (x0 + omega * x0 * t)
+        } else {
+            let root = (zeta * zeta - 1.0).sqrt();
+            let r1 = -omega * (zeta - root);
+            let r2 = -omega * (zeta + root);
+            let c2 = -r1 * x0 / (r2 - r1);
+            let c1 = x0 - c2;
+            c1 * (r1 * t).exp() + c2 * (r2 * t).exp()
+        }
+    }
+}
+
+impl Default for Spring {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How many times a keyframe animation plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repeat {
+    Times(u32),
+    Forever,
+}
+
+/// Which way each iteration of a keyframe animation runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlaybackDirection {
+    #[default]
+    Normal,
+    Reverse,
+    /// Forwards, then backwards, then forwards again...
+    Alternate,
+    /// Backwards, then forwards, then backwards again...
+    AlternateReverse,
+}
+
+/// A sequence of values at offsets in `0.0..=1.0`, interpolated over a
+/// duration. Usually created with the `keyframes!` macro.
+#[derive(Clone, Debug)]
+pub struct Keyframes<T> {
+    frames: Vec<(f32, T)>,
+    pub duration: Duration,
+    /// Easing applied between each pair of frames.
+    pub easing: Easing,
+    pub repeat: Repeat,
+    pub direction: PlaybackDirection,
+}
+
+/// Default duration of one keyframe iteration.
+const DEFAULT_KEYFRAMES_DURATION: Duration = Duration::from_secs(1);
+
+impl<T: Interpolate> Keyframes<T> {
+    /// # Panics
+    /// Panics if `frames` is empty.
+    pub fn new(mut frames: Vec<(f32, T)>) -> Self {
+        assert!(!frames.is_empty(), "keyframes need at least one frame");
+        frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            frames,
+            duration: DEFAULT_KEYFRAMES_DURATION,
+            easing: Easing::Linear,
+            repeat: Repeat::Times(1),
+            direction: PlaybackDirection::Normal,
+        }
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Play forever.
+    pub fn looping(self) -> Self {
+        self.repeat(Repeat::Forever)
+    }
+
+    pub fn direction(mut self, direction: PlaybackDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Whether the animation has played all of its iterations after `elapsed`.
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        match self.repeat {
+            Repeat::Forever => false,
+            Repeat::Times(n) => elapsed >= self.duration * n,
+        }
+    }
+
+    /// The value `elapsed` after the animation started.
+    pub fn sample(&self, elapsed: Duration) -> T {
+        let (iteration, local) = if self.duration.is_zero() || self.is_finished(elapsed) {
+            // Hold the end of the last iteration
+            let last = match self.repeat {
+                Repeat::Times(n) => n.saturating_sub(1),
+                Repeat::Forever => 0,
+            };
+            (last, 1.0)
+        } else {
+            let cycles = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+            (cycles as u32, cycles.fract())
+        };
+
+        let forwards = match self.direction {
+            PlaybackDirection::Normal => true,
+            PlaybackDirection::Reverse => false,
+            PlaybackDirection::Alternate => iteration % 2 == 0,
+            PlaybackDirection::AlternateReverse => iteration % 2 == 1,
+        };
+        let offset = if forwards { local } else { 1.0 - local };
+
+        self.value_at(offset)
+    }
+
+    fn value_at(&self, offset: f32) -> T {
+        let (first_offset, first) = &self.frames[0];
+        if offset <= *first_offset {
+            return first.clone();
+        }
+        for pair in self.frames.windows(2) {
+            let ((from_offset, from), (to_offset, to)) = (&pair[0], &pair[1]);
+            if offset < *to_offset {
+                let t = (offset - from_offset) / (to_offset - from_offset);
+                return from.lerp(to, self.easing.apply(t));
+            }
+        }
+        self.frames[self.frames.len() - 1].1.clone()
+    }
+}
+
+/// Build `Keyframes` from `(offset, value)` pairs, with offsets in `0.0..=1.0`.
+///
+/// # Example
+/// ```
+/// let pulse = keyframes![(0.0, 1.0), (0.5, 0.4), (1.0, 1.0)]
+///     .duration(Duration::from_millis(800))
+///     .looping();
+/// ```
+#[macro_export]
+macro_rules! keyframes {
+    ($(($offset:expr, $value:expr)),+ $(,)?) => {
+        $crate::animation::Keyframes::new(vec![$(($offset, $value)),+])
+    };
+}
@@ -1,17 +1,25 @@
 use std::any::Any;
+use std::borrow::Cow;
+use std::ops::Range;
 use std::rc::Rc;
 
 use crate::color::Color;
 use crate::element::{ElementTree, NodeKind};
-use crate::events::{Event, EventHandler, EventResult, MouseButton};
+use crate::events::{
+    ClickInfo, Event, EventHandler, EventResult, Key, LayoutHandler, MouseButton, NamedKey,
+    VisibilityHandler,
+};
+use crate::layout::Layout;
 use crate::style::{
-    Align, Border, BorderEdge, BorderRadius, Direction, Distribute, EdgeSizes, Length, Position,
-    Style,
+    Align, Border, BorderEdge, BorderRadius, CursorIcon, Direction, Distribute, EdgeSizes, Length,
+    ObjectFit, Overflow, Position, Style, TextAlign,
 };
 use crate::svg_data::Svg;
 use crate::texture::Texture;
+use crate::transition::Transition;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum ElementKind {
     Element,
     Text,
@@ -19,15 +27,32 @@ enum ElementKind {
     Svg,
 }
 
+/// Builder for a tree of elements.
+///
+/// With the `serde` feature enabled, builders can be serialized and
+/// deserialized. Event handlers are not part of the serialized form: they
+/// are skipped when dumping, and a loaded tree has none attached.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ElementBuilder {
     node_type: ElementKind,
-    style: Style,
-    text: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) style: Style,
+    text: Option<Cow<'static, str>>,
     texture: Option<Texture>,
     svg: Option<Svg>,
-    children: Vec<ElementBuilder>,
-    on_event: Option<EventHandler>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) children: Vec<ElementBuilder>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    handlers: Vec<EventHandler>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    layout_handlers: Vec<LayoutHandler>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    visibility_handlers: Vec<VisibilityHandler>,
+    debug_name: Option<String>,
+    pub(crate) key: Option<String>,
+    pub(crate) enter: Option<Transition>,
+    pub(crate) exit: Option<Transition>,
 }
 
 // Manual Debug implementation since EventHandler doesn't implement Debug
@@ -40,7 +65,13 @@ impl std::fmt::Debug for ElementBuilder {
             .field("texture", &self.texture)
             .field("svg", &self.svg)
             .field("children", &self.children)
-            .field("on_event", &self.on_event.as_ref().map(|_| "EventHandler"))
+            .field("handlers", &self.handlers.len())
+            .field("layout_handlers", &self.layout_handlers.len())
+            .field("visibility_handlers", &self.visibility_handlers.len())
+            .field("debug_name", &self.debug_name)
+            .field("key", &self.key)
+            .field("enter", &self.enter)
+            .field("exit", &self.exit)
             .finish()
     }
 }
@@ -54,11 +85,17 @@ impl ElementBuilder {
             texture: None,
             svg: None,
             children: Vec::new(),
-            on_event: None,
+            handlers: Vec::new(),
+            layout_handlers: Vec::new(),
+            visibility_handlers: Vec::new(),
+            debug_name: None,
+            key: None,
+            enter: None,
+            exit: None,
         }
     }
 
-    pub fn new_text(text: String) -> Self {
+    pub fn new_text(text: Cow<'static, str>) -> Self {
         Self {
             node_type: ElementKind::Text,
             style: Style::default(),
@@ -66,7 +103,13 @@ impl ElementBuilder {
             texture: None,
             svg: None,
             children: Vec::new(),
-            on_event: None,
+            handlers: Vec::new(),
+            layout_handlers: Vec::new(),
+            visibility_handlers: Vec::new(),
+            debug_name: None,
+            key: None,
+            enter: None,
+            exit: None,
         }
     }
 
@@ -78,7 +121,13 @@ impl ElementBuilder {
             texture: Some(texture),
             svg: None,
             children: Vec::new(),
-            on_event: None,
+            handlers: Vec::new(),
+            layout_handlers: Vec::new(),
+            visibility_handlers: Vec::new(),
+            debug_name: None,
+            key: None,
+            enter: None,
+            exit: None,
         }
     }
 
@@ -90,10 +139,60 @@ impl ElementBuilder {
             texture: None,
             svg: Some(svg),
             children: Vec::new(),
-            on_event: None,
+            handlers: Vec::new(),
+            layout_handlers: Vec::new(),
+            visibility_handlers: Vec::new(),
+            debug_name: None,
+            key: None,
+            enter: None,
+            exit: None,
         }
     }
 
+    /// Attach a human-readable name to the element.
+    ///
+    /// The name is stored on the built node and shows up in `Debug` output and
+    /// in panic messages from layout and rendering, making it possible to tell
+    /// which part of the view a node came from.
+    pub fn debug_name(mut self, name: impl Into<String>) -> Self {
+        self.debug_name = Some(name.into());
+        self
+    }
+
+    /// Give the element a key that identifies it across view rebuilds.
+    ///
+    /// Keys must be unique within the view. They are required for enter and
+    /// exit transitions, which need to tell when an element was added or
+    /// removed, and they name the anchors of popovers.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Play a transition when the element appears. Requires a `key`.
+    ///
+    /// # Example
+    /// ```
+    /// div().key("toast").enter(Transition::slide(0.0, 20.0))
+    /// ```
+    pub fn enter(mut self, transition: Transition) -> Self {
+        self.enter = Some(transition);
+        self
+    }
+
+    /// Play a transition when the element disappears, keeping it on screen
+    /// (but non-interactive) until the transition finishes. Requires a `key`.
+    pub fn exit(mut self, transition: Transition) -> Self {
+        self.exit = Some(transition);
+        self
+    }
+
+    /// Play the same transition when the element appears and disappears.
+    /// Requires a `key`.
+    pub fn transition(self, transition: Transition) -> Self {
+        self.enter(transition).exit(transition)
+    }
+
     /// Make the element render children in a row.
     pub fn row(mut self) -> Self {
         self.style.direction = Direction::Row;
@@ -106,6 +205,25 @@ impl ElementBuilder {
         self
     }
 
+    /// Start a new row (or column) of children where the next one wouldn't
+    /// fit, instead of letting them run past the end. Each line is
+    /// distributed and aligned on its own, and lines are spaced by the gap
+    /// across them (`gap_y` between rows, `gap_x` between columns).
+    ///
+    /// # Example
+    /// ```
+    /// div()
+    ///     .row()
+    ///     .wrap()
+    ///     .w(FULL)
+    ///     .gap(SM)
+    ///     .children(photos.iter().map(thumbnail))
+    /// ```
+    pub fn wrap(mut self) -> Self {
+        self.style.wrap = true;
+        self
+    }
+
     /// Make the element render children in a direction.
     pub fn direction(mut self, dir: Direction) -> Self {
         self.style.direction = dir;
@@ -203,6 +321,28 @@ impl ElementBuilder {
         self
     }
 
+    /// Set whether children are drawn where they go outside the element.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.style.overflow = overflow;
+        self
+    }
+
+    /// Cut children off at the inside of the element's border and rounded
+    /// corners, the same as `overflow(Overflow::Hidden)`. Parts of children
+    /// that are cut off can't be clicked either.
+    ///
+    /// # Example
+    /// ```
+    /// div()
+    ///     .radius(12.0)
+    ///     .clip()
+    ///     .child(img(&cover).w(FULL))
+    ///     .child(text(title))
+    /// ```
+    pub fn clip(self) -> Self {
+        self.overflow(Overflow::Hidden)
+    }
+
     /// Set the width of the element.
     pub fn w(mut self, length: Length) -> Self {
         self.style.width = length;
@@ -234,16 +374,126 @@ impl ElementBuilder {
         self
     }
 
+    /// Set how an image is sized to the element: stretched to fill it (the
+    /// default), scaled to fit inside or cover it, or left at its own size.
+    /// An image left at its own size is also laid out at that size, unless
+    /// given a width or height.
+    ///
+    /// # Example
+    /// ```
+    /// img(photo).size(px(160.0)).object_fit(ObjectFit::Cover)
+    /// ```
+    pub fn object_fit(mut self, fit: ObjectFit) -> Self {
+        self.style.object_fit = fit;
+        self
+    }
+
+    /// Take a share of the space left over along the parent's row or
+    /// column, in proportion to `factor` among the siblings that grow too.
+    /// The element's own size is where it starts from.
+    ///
+    /// # Example
+    /// ```
+    /// div()
+    ///     .row()
+    ///     .w(FULL)
+    ///     .child(button("Back"))
+    ///     .child(text(title).grow(1.0))
+    ///     .child(button("Menu"))
+    /// ```
+    pub fn grow(mut self, factor: f32) -> Self {
+        self.style.grow = factor.max(0.0);
+        self
+    }
+
+    /// Get smaller when the parent's row or column overflows, giving up
+    /// room in proportion to `factor` times the element's size among the
+    /// siblings that shrink too. It never gets smaller than the fixed-size
+    /// parts inside it (see `min_size`).
+    pub fn shrink(mut self, factor: f32) -> Self {
+        self.style.shrink = factor.max(0.0);
+        self
+    }
+
     pub fn p(mut self, size: Length) -> Self {
         self.style.padding = EdgeSizes::splat(size);
         self
     }
 
+    /// Set the padding on the left and right. The trailing underscore
+    /// keeps it apart from the `px` length function.
+    pub fn px_(mut self, size: Length) -> Self {
+        self.style.padding.left = size;
+        self.style.padding.right = size;
+        self
+    }
+
+    /// Set the padding on the top and bottom.
+    pub fn py_(mut self, size: Length) -> Self {
+        self.style.padding.top = size;
+        self.style.padding.bottom = size;
+        self
+    }
+
+    pub fn pt(mut self, size: Length) -> Self {
+        self.style.padding.top = size;
+        self
+    }
+
+    pub fn pr(mut self, size: Length) -> Self {
+        self.style.padding.right = size;
+        self
+    }
+
+    pub fn pb(mut self, size: Length) -> Self {
+        self.style.padding.bottom = size;
+        self
+    }
+
+    pub fn pl(mut self, size: Length) -> Self {
+        self.style.padding.left = size;
+        self
+    }
+
     pub fn m(mut self, size: Length) -> Self {
         self.style.margin = EdgeSizes::splat(size);
         self
     }
 
+    /// Set the margin on the left and right.
+    pub fn mx(mut self, size: Length) -> Self {
+        self.style.margin.left = size;
+        self.style.margin.right = size;
+        self
+    }
+
+    /// Set the margin on the top and bottom.
+    pub fn my(mut self, size: Length) -> Self {
+        self.style.margin.top = size;
+        self.style.margin.bottom = size;
+        self
+    }
+
+    pub fn mt(mut self, size: Length) -> Self {
+        self.style.margin.top = size;
+        self
+    }
+
+    pub fn mr(mut self, size: Length) -> Self {
+        self.style.margin.right = size;
+        self
+    }
+
+    pub fn mb(mut self, size: Length) -> Self {
+        self.style.margin.bottom = size;
+        self
+    }
+
+    pub fn ml(mut self, size: Length) -> Self {
+        self.style.margin.left = size;
+        self
+    }
+
     /// Set the position mode.
     pub fn position(mut self, position: Position) -> Self {
         self.style.position = position;
@@ -280,12 +530,138 @@ impl ElementBuilder {
         self
     }
 
+    /// Set the stacking layer of a portal. Higher layers are drawn over
+    /// lower ones and get events first; portals in the same layer stack in
+    /// tree order. A portal inside another one is never stacked below it.
+    ///
+    /// # Example
+    /// ```
+    /// portal().layer(10).child(tooltip())
+    /// ```
+    pub fn layer(mut self, layer: i32) -> Self {
+        self.style.layer = layer;
+        self
+    }
+
+    /// Make a portal modal. While it is the topmost modal portal, the rest of
+    /// the view gets no mouse events: clicks beside the portal's children go
+    /// to the portal itself, as its scrim. Key events and Tab focus stay
+    /// inside it too.
+    ///
+    /// # Example
+    /// ```
+    /// portal()
+    ///     .modal()
+    ///     .on_dismiss(|m: &mut MyModel| m.dialog = None)
+    ///     .size(FULL)
+    ///     .bg(Color::new(0.0, 0.0, 0.0, 0.5))
+    ///     .child(dialog())
+    /// ```
+    pub fn modal(mut self) -> Self {
+        self.style.modal = true;
+        self
+    }
+
     /// Set the font size for text elements.
     pub fn font_size(mut self, size: f32) -> Self {
         self.style.font_size = Some(size);
         self
     }
 
+    /// Draw text in the font family named `family`, such as `"Inter"` or
+    /// `"serif"`. Characters it has no glyphs for, and text whose family
+    /// isn't installed or registered, use the configured fonts.
+    pub fn font_family(mut self, family: &str) -> Self {
+        self.style.font_family = Some(family.to_string());
+        self
+    }
+
+    /// Set the weight of text, from 1 to 1000: 400 is regular and 700 is
+    /// bold. The font's nearest weight is used.
+    pub fn font_weight(mut self, weight: u16) -> Self {
+        self.style.font_weight = Some(weight.clamp(1, 1000));
+        self
+    }
+
+    /// Draw text in bold, the same as `font_weight(700)`.
+    pub fn bold(self) -> Self {
+        self.font_weight(700)
+    }
+
+    /// Draw text in italics, slanting it if the font has no italic style.
+    pub fn italic(mut self) -> Self {
+        self.style.italic = true;
+        self
+    }
+
+    /// Line text up on the left, in the center or on the right of a text
+    /// element, rather than on the side its direction starts on. Text sized
+    /// to fit has no room to move unless it wraps onto several lines.
+    pub fn text_align(mut self, align: TextAlign) -> Self {
+        self.style.text_align = align;
+        self
+    }
+
+    /// Set the height of each line of text, as a multiple of the font size
+    /// (1.2 unless set).
+    pub fn line_height(mut self, height: f32) -> Self {
+        self.style.line_height = Some(height);
+        self
+    }
+
+    /// Show at most `lines` lines of text, ending the last one with an
+    /// ellipsis when there is more than fits.
+    ///
+    /// # Example
+    /// ```
+    /// text(&file.name).w(px(160.0)).max_lines(1)
+    /// ```
+    pub fn max_lines(mut self, lines: usize) -> Self {
+        self.style.max_lines = Some(lines.max(1));
+        self
+    }
+
+    /// Keep text on one line, however wide, instead of wrapping it at the
+    /// width of the element or its parent. Line breaks in the text itself
+    /// still start new lines.
+    pub fn no_wrap(mut self) -> Self {
+        self.style.no_wrap = true;
+        self
+    }
+
+    /// Underline text.
+    pub fn underline(mut self) -> Self {
+        self.style.underline = true;
+        self
+    }
+
+    /// Draw text in the monospace font, where every character is as wide as
+    /// the next, such as for code.
+    pub fn monospace(mut self) -> Self {
+        self.style.monospace = true;
+        self
+    }
+
+    /// Show a blinking text caret before the byte at `offset` of a text
+    /// element's content. An offset at the end of the text places the caret
+    /// after the last character.
+    pub fn caret(mut self, offset: usize) -> Self {
+        self.style.caret = Some(offset);
+        self
+    }
+
+    /// Highlight the bytes `range` of a text element's content as selected.
+    pub fn selection(mut self, range: Range<usize>) -> Self {
+        self.style.selection = Some((range.start, range.end));
+        self
+    }
+
+    /// Set the mouse cursor shown while hovering the element.
+    pub fn cursor(mut self, cursor: CursorIcon) -> Self {
+        self.style.cursor = cursor;
+        self
+    }
+
     /// Set the text color.
     pub fn color(mut self, color: Color) -> Self {
         self.style.text_color = color;
@@ -298,6 +674,62 @@ impl ElementBuilder {
         self
     }
 
+    /// Offset the element (and its children) after layout, without moving
+    /// its siblings.
+    pub fn translate(mut self, x: f32, y: f32) -> Self {
+        self.style.translate_x = x;
+        self.style.translate_y = y;
+        self
+    }
+
+    /// Scale the element (and its children) around its center when painting.
+    /// Layout is unaffected.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.style.scale = scale;
+        self
+    }
+
+    /// Disable the element: it is greyed out and it and its children no
+    /// longer receive pointer or keyboard events.
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.style.disabled = disabled;
+        self
+    }
+
+    /// Let the mouse pass through the element and its children (`false`) to
+    /// whatever is underneath, without changing how it looks. For
+    /// decorations drawn over interactive elements, such as labels, badges
+    /// and markers.
+    ///
+    /// # Example
+    /// ```
+    /// div()
+    ///     .on_left_click(|m: &mut MyModel| m.open_inbox())
+    ///     .child(text("Inbox"))
+    ///     .child(unread_badge().absolute().top(px(-4.0)).right(px(-4.0)).pointer_events(false))
+    /// ```
+    pub fn pointer_events(mut self, enabled: bool) -> Self {
+        self.style.pointer_events = enabled;
+        self
+    }
+
+    /// Set whether Tab and Shift+Tab stop at the element. Elements with
+    /// event handlers are stopped at unless set otherwise; use `false` for
+    /// ones only the mouse needs, such as a scrollbar, and `true` for an
+    /// element whose key handlers sit on an ancestor.
+    ///
+    /// # Example
+    /// ```
+    /// div()
+    ///     .on_key(|m: &mut Editor, key| m.handle_key(key))
+    ///     .child(editor_text(model).focusable(true))
+    ///     .child(scrollbar(model).focusable(false))
+    /// ```
+    pub fn focusable(mut self, focusable: bool) -> Self {
+        self.style.focusable = Some(focusable);
+        self
+    }
+
     /// Set the gap between children on both axes.
     pub fn gap(mut self, length: Length) -> Self {
         self.style.gap_x = length;
@@ -318,8 +750,8 @@ impl ElementBuilder {
     }
 
     /// Add a child to the element.
-    pub fn child(mut self, child: ElementBuilder) -> Self {
-        self.children.push(child);
+    pub fn child(mut self, child: impl Into<ElementBuilder>) -> Self {
+        self.children.push(child.into());
         self
     }
 
@@ -343,6 +775,10 @@ impl ElementBuilder {
     /// This is the foundation for all event handling. Typed helpers like
     /// `on_click` are convenience wrappers around this method.
     ///
+    /// Handlers accumulate: attaching several keeps all of them, and they are
+    /// called in the order they were attached until one returns
+    /// `EventResult::Stop`.
+    ///
     /// # Example
     /// ```
     /// div().on_event(|model: &mut MyModel, event: &Event| {
@@ -357,13 +793,27 @@ impl ElementBuilder {
         M: 'static,
         F: Fn(&mut M, &Event) -> EventResult + 'static,
     {
-        self.on_event = Some(Rc::new(move |model: &mut dyn Any, event: &Event| {
-            if let Some(m) = model.downcast_mut::<M>() {
-                handler(m, event)
-            } else {
-                EventResult::Continue
-            }
-        }));
+        self.handlers
+            .push(Rc::new(move |model: &mut dyn Any, event: &Event| {
+                if let Some(m) = model.downcast_mut::<M>() {
+                    handler(m, event)
+                } else {
+                    EventResult::Continue
+                }
+            }));
+        self
+    }
+
+    /// Attach an event handler that works with any model type.
+    ///
+    /// The handler receives the model as `&mut dyn Any`. This is meant for
+    /// reusable elements with side effects that don't touch the model, like
+    /// opening a URL.
+    pub fn on_event_any<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&mut dyn Any, &Event) -> EventResult + 'static,
+    {
+        self.handlers.push(Rc::new(handler));
         self
     }
 
@@ -377,51 +827,441 @@ impl ElementBuilder {
     where
         M: 'static,
         F: Fn(&mut M) + 'static,
+    {
+        self.on_left_click_with(move |model: &mut M, _: &ClickInfo| handler(model))
+    }
+
+    /// Attach a left click event handler that receives click details
+    /// (position, modifiers, click count).
+    ///
+    /// # Example
+    /// ```
+    /// div().on_left_click_with(|m: &mut MyModel, click: &ClickInfo| {
+    ///     if click.modifiers.shift {
+    ///         m.extend_selection();
+    ///     }
+    /// })
+    /// ```
+    pub fn on_left_click_with<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, &ClickInfo) + 'static,
+    {
+        self.on_click_with_button(MouseButton::Left, handler)
+    }
+
+    /// Attach a right click event handler.
+    ///
+    /// # Example
+    /// ```
+    /// button("Options").on_right_click(MyModel::show_context_menu)
+    /// ```
+    pub fn on_right_click<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M) + 'static,
+    {
+        self.on_right_click_with(move |model: &mut M, _: &ClickInfo| handler(model))
+    }
+
+    /// Attach a right click event handler that receives click details.
+    pub fn on_right_click_with<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, &ClickInfo) + 'static,
+    {
+        self.on_click_with_button(MouseButton::Right, handler)
+    }
+
+    /// Attach a handler called when a mouse button is pressed over the
+    /// element.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_mouse_down(|m: &mut MyModel, button: MouseButton| {
+    ///     if button == MouseButton::Left {
+    ///         m.start_drag();
+    ///     }
+    /// })
+    /// ```
+    pub fn on_mouse_down<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, MouseButton) + 'static,
     {
         self.on_event(move |model: &mut M, event: &Event| {
-            if matches!(
-                event,
-                Event::Click {
-                    button: MouseButton::Left
+            if let Event::MouseDown { button } = event {
+                handler(model, *button);
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a handler called when a mouse button is released over the
+    /// element, or released anywhere after being pressed on it.
+    pub fn on_mouse_up<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, MouseButton) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::MouseUp { button } = event {
+                handler(model, *button);
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a handler called with the cursor position (in window
+    /// coordinates) when it moves over the element. While a button pressed
+    /// on the element is held, it keeps receiving moves wherever the cursor
+    /// goes.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_mouse_move(|m: &mut MyModel, x: f32, y: f32| m.drag_to(x, y))
+    /// ```
+    pub fn on_mouse_move<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, f32, f32) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::MouseMove { x, y } = event {
+                handler(model, *x, *y);
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a handler called with the element's rect, in window
+    /// coordinates, after a layout pass puts it somewhere new: when it first
+    /// appears, and whenever it moves or changes size. Changes to the model
+    /// show in the next frame.
+    ///
+    /// # Example
+    /// ```
+    /// div()
+    ///     .h(FULL)
+    ///     .on_layout(|m: &mut MyModel, rect: Layout| m.visible_rows = (rect.height / ROW_HEIGHT) as usize)
+    ///     .children(m.rows[m.first_row..][..m.visible_rows].iter().map(row))
+    /// ```
+    pub fn on_layout<M, F>(mut self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, Layout) + 'static,
+    {
+        self.layout_handlers
+            .push(Rc::new(move |model: &mut dyn Any, rect: Layout| {
+                if let Some(m) = model.downcast_mut::<M>() {
+                    handler(m, rect);
+                }
+            }));
+        self
+    }
+
+    /// Attach a handler called with `true` when the element comes into the
+    /// window and `false` when it goes out of it, such as when the window is
+    /// resized or the element is moved. An element counts as in view while
+    /// any part of its rect overlaps the window. Changes to the model show in
+    /// the next frame.
+    ///
+    /// # Example
+    /// ```
+    /// div()
+    ///     .size(px(160.0))
+    ///     .on_visible(move |m: &mut Gallery, visible: bool| {
+    ///         if visible {
+    ///             m.load_thumbnail(index)
+    ///         }
+    ///     })
+    /// ```
+    pub fn on_visible<M, F>(mut self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, bool) + 'static,
+    {
+        self.visibility_handlers
+            .push(Rc::new(move |model: &mut dyn Any, visible: bool| {
+                if let Some(m) = model.downcast_mut::<M>() {
+                    handler(m, visible);
                 }
-            ) {
+            }));
+        self
+    }
+
+    /// Attach a hover handler, called with `true` when the cursor enters the
+    /// element and `false` when it leaves.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_hover(|m: &mut MyModel, entered: bool| m.highlighted = entered)
+    /// ```
+    pub fn on_hover<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, bool) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            match event {
+                Event::MouseEnter => handler(model, true),
+                Event::MouseLeave => handler(model, false),
+                _ => {}
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a handler called when a drag starts on the element: when the
+    /// cursor moves a few pixels with a button pressed on it held.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_drag_start(move |m: &mut MyModel| m.dragging = Some(id))
+    /// ```
+    pub fn on_drag_start<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::DragStart = event {
                 handler(model);
             }
             EventResult::Continue
         })
     }
 
-    /// Attach a right click event handler.
+    /// Attach a handler called with how far the cursor moved, in pixels,
+    /// each time it moves during a drag that started on the element. The
+    /// first call covers the distance from where the button was pressed.
     ///
     /// # Example
     /// ```
-    /// button("Options").on_right_click(MyModel::show_context_menu)
+    /// div().on_drag(|m: &mut MyModel, dx: f32, _dy: f32| m.volume += dx / 200.0)
     /// ```
-    pub fn on_right_click<M, F>(self, handler: F) -> Self
+    pub fn on_drag<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, f32, f32) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::DragMove { dx, dy } = event {
+                handler(model, *dx, *dy);
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a handler called when a drag that started on the element
+    /// ends, wherever the button is released.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_drag_end(|m: &mut MyModel| m.dragging = None)
+    /// ```
+    pub fn on_drag_end<M, F>(self, handler: F) -> Self
     where
         M: 'static,
         F: Fn(&mut M) + 'static,
     {
         self.on_event(move |model: &mut M, event: &Event| {
-            if matches!(
-                event,
-                Event::Click {
-                    button: MouseButton::Right
-                }
-            ) {
+            if let Event::DragEnd = event {
                 handler(model);
             }
             EventResult::Continue
         })
     }
 
-    /// Get the event handler (used internally for event dispatch).
-    pub fn get_event_handler(&self) -> Option<EventHandler> {
-        self.on_event.clone()
+    /// Attach a handler called when the cursor enters the element.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_mouse_enter(|m: &mut MyModel| m.preview = Some(id))
+    /// ```
+    pub fn on_mouse_enter<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::MouseEnter = event {
+                handler(model);
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a handler called when the cursor leaves the element.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_mouse_leave(|m: &mut MyModel| m.preview = None)
+    /// ```
+    pub fn on_mouse_leave<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::MouseLeave = event {
+                handler(model);
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a handler called with the raw motion of the mouse while the
+    /// cursor is over the element, or while a button pressed on it is held.
+    /// Unlike `on_mouse_move`, it keeps getting motion when the cursor
+    /// reaches the edge of the screen or is locked with `set_pointer_lock`,
+    /// for orbiting a camera or dragging a value without limit.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_mouse_delta(|m: &mut MyModel, dx: f32, dy: f32| m.camera.orbit(dx, dy))
+    /// ```
+    pub fn on_mouse_delta<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, f32, f32) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::MouseDelta { dx, dy } = event {
+                handler(model, *dx, *dy);
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a scroll handler, called with the scroll delta in pixels.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_scroll(|m: &mut MyModel, _dx: f32, dy: f32| m.zoom += dy * 0.01)
+    /// ```
+    pub fn on_scroll<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, f32, f32) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::Scroll { dx, dy } = event {
+                handler(model, *dx, *dy);
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a key handler, called on key presses while this element or one
+    /// of its descendants has focus. Clicking an element focuses it, and Tab
+    /// moves focus to the next element with handlers; when nothing is
+    /// focused, key events go to the root element.
+    ///
+    /// # Example
+    /// ```
+    /// div().on_key(|m: &mut MyModel, key: &Key| {
+    ///     if *key == Key::Named(NamedKey::Escape) {
+    ///         m.close();
+    ///     }
+    /// })
+    /// ```
+    pub fn on_key<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, &Key) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::KeyDown { key, .. } = event {
+                handler(model, key);
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Attach a handler for dismissing a modal portal: called when its scrim
+    /// is clicked, or when Escape is pressed and nothing inside the portal
+    /// handles it first.
+    pub fn on_dismiss<M, F>(self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| match event {
+            // Clicks don't bubble, so this one landed on the element itself
+            Event::Click {
+                button: MouseButton::Left,
+                ..
+            } => {
+                handler(model);
+                EventResult::Stop
+            }
+            Event::KeyDown {
+                key: Key::Named(NamedKey::Escape),
+                ..
+            } => {
+                handler(model);
+                EventResult::Stop
+            }
+            _ => EventResult::Continue,
+        })
+    }
+
+    fn on_click_with_button<M, F>(self, button: MouseButton, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, &ClickInfo) + 'static,
+    {
+        self.on_event(move |model: &mut M, event: &Event| {
+            if let Event::Click { button: b, info } = event {
+                if *b == button {
+                    handler(model, info);
+                }
+            }
+            EventResult::Continue
+        })
+    }
+
+    /// Get the event handlers (used internally for event dispatch).
+    pub fn get_event_handlers(&self) -> &[EventHandler] {
+        &self.handlers
+    }
+
+    /// Call `f` on this element and every descendant that has a key.
+    pub(crate) fn visit_keyed_mut(&mut self, f: &mut impl FnMut(&str, &mut ElementBuilder)) {
+        if let Some(key) = self.key.clone() {
+            f(&key, self);
+        }
+        for child in &mut self.children {
+            child.visit_keyed_mut(f);
+        }
+    }
+
+    /// Resolve a path of child indices to a descendant.
+    pub(crate) fn descendant_mut(&mut self, path: &[usize]) -> Option<&mut ElementBuilder> {
+        let mut cur = self;
+        for &index in path {
+            cur = cur.children.get_mut(index)?;
+        }
+        Some(cur)
+    }
+
+    /// Remove the event handlers from this element and its descendants.
+    pub(crate) fn clear_handlers(&mut self) {
+        self.handlers.clear();
+        self.layout_handlers.clear();
+        self.visibility_handlers.clear();
+        for child in &mut self.children {
+            child.clear_handlers();
+        }
     }
 
     pub fn build(self) -> ElementTree {
-        let mut tree = ElementTree::new(self.style.clone(), self.on_event.clone());
+        let mut tree = ElementTree::new(self.style.clone(), self.handlers.clone());
+        tree.arena[tree.root].debug_name = self.debug_name;
+        tree.arena[tree.root].key = self.key;
+        tree.arena[tree.root].layout_handlers = self.layout_handlers;
+        tree.arena[tree.root].visibility_handlers = self.visibility_handlers;
         let mut stack = vec![(tree.root, self.children)];
 
         while let Some((parent_id, mut raw_children)) = stack.pop() {
@@ -444,7 +1284,11 @@ impl ElementBuilder {
                     },
                 };
 
-                let id = tree.add_child(parent_id, node_kind, child_builder.on_event.clone());
+                let id = tree.add_child(parent_id, node_kind, child_builder.handlers);
+                tree.arena[id].debug_name = child_builder.debug_name;
+                tree.arena[id].key = child_builder.key;
+                tree.arena[id].layout_handlers = child_builder.layout_handlers;
+                tree.arena[id].visibility_handlers = child_builder.visibility_handlers;
                 if !child_builder.children.is_empty() {
                     stack.push((id, child_builder.children));
                 }
@@ -1,19 +1,33 @@
+use std::any::Any;
+use std::borrow::Cow;
+
 use generational_arena::{Arena, Index};
 
-use crate::events::EventHandler;
-use crate::layout::Layout;
-use crate::style::Style;
+use crate::events::{Event, EventHandler, EventResult, LayoutHandler, VisibilityHandler};
+use crate::layout::{self, Layout};
+use crate::style::{Position, Style};
 use crate::svg_data::Svg;
 use crate::texture::Texture;
 
 pub type NodeId = Index;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum NodeKind {
-    Element { style: Style },
-    Text { content: String, style: Style },
-    Texture { texture: Texture, style: Style },
-    Svg { svg: Svg, style: Style },
+    Element {
+        style: Style,
+    },
+    Text {
+        content: Cow<'static, str>,
+        style: Style,
+    },
+    Texture {
+        texture: Texture,
+        style: Style,
+    },
+    Svg {
+        svg: Svg,
+        style: Style,
+    },
 }
 
 // Manual Debug implementation to handle EventHandler
@@ -49,10 +63,20 @@ pub struct Node {
     // data
     pub kind: NodeKind,
     pub layout: Layout,
+    /// Whether the node has to be laid out again, having changed since the
+    /// layout it took from the last tree
     pub dirty: bool,
 
-    // event handler
-    pub on_event: Option<EventHandler>,
+    // event handlers, called in the order they were attached
+    pub handlers: Vec<EventHandler>,
+    pub layout_handlers: Vec<LayoutHandler>,
+    pub visibility_handlers: Vec<VisibilityHandler>,
+
+    // debugging
+    pub debug_name: Option<String>,
+
+    /// The key given to the element, for popovers to find it by
+    pub key: Option<String>,
 }
 
 // Manual Debug implementation
@@ -65,13 +89,17 @@ impl std::fmt::Debug for Node {
             .field("kind", &self.kind)
             .field("layout", &self.layout)
             .field("dirty", &self.dirty)
-            .field("on_event", &self.on_event.as_ref().map(|_| "EventHandler"))
+            .field("handlers", &self.handlers.len())
+            .field("layout_handlers", &self.layout_handlers.len())
+            .field("visibility_handlers", &self.visibility_handlers.len())
+            .field("debug_name", &self.debug_name)
+            .field("key", &self.key)
             .finish()
     }
 }
 
 impl Node {
-    fn new_element(style: Style, parent: Option<NodeId>, on_event: Option<EventHandler>) -> Self {
+    fn new_element(style: Style, parent: Option<NodeId>, handlers: Vec<EventHandler>) -> Self {
         Self {
             parent,
             first_child: None,
@@ -79,15 +107,19 @@ impl Node {
             kind: NodeKind::Element { style },
             layout: Layout::default(),
             dirty: true,
-            on_event,
+            handlers,
+            debug_name: None,
+            key: None,
+            layout_handlers: Vec::new(),
+            visibility_handlers: Vec::new(),
         }
     }
 
     fn new_text(
-        content: String,
+        content: Cow<'static, str>,
         style: Style,
         parent: Option<NodeId>,
-        on_event: Option<EventHandler>,
+        handlers: Vec<EventHandler>,
     ) -> Self {
         Self {
             parent,
@@ -96,7 +128,11 @@ impl Node {
             kind: NodeKind::Text { content, style },
             layout: Layout::default(),
             dirty: true,
-            on_event,
+            handlers,
+            debug_name: None,
+            key: None,
+            layout_handlers: Vec::new(),
+            visibility_handlers: Vec::new(),
         }
     }
 
@@ -104,7 +140,7 @@ impl Node {
         texture: Texture,
         style: Style,
         parent: Option<NodeId>,
-        on_event: Option<EventHandler>,
+        handlers: Vec<EventHandler>,
     ) -> Self {
         Self {
             parent,
@@ -113,7 +149,11 @@ impl Node {
             kind: NodeKind::Texture { texture, style },
             layout: Layout::default(),
             dirty: true,
-            on_event,
+            handlers,
+            debug_name: None,
+            key: None,
+            layout_handlers: Vec::new(),
+            visibility_handlers: Vec::new(),
         }
     }
 
@@ -121,7 +161,7 @@ impl Node {
         svg: Svg,
         style: Style,
         parent: Option<NodeId>,
-        on_event: Option<EventHandler>,
+        handlers: Vec<EventHandler>,
     ) -> Self {
         Self {
             parent,
@@ -130,7 +170,36 @@ impl Node {
             kind: NodeKind::Svg { svg, style },
             layout: Layout::default(),
             dirty: true,
-            on_event,
+            handlers,
+            debug_name: None,
+            key: None,
+            layout_handlers: Vec::new(),
+            visibility_handlers: Vec::new(),
+        }
+    }
+
+    /// Call each of the node's handlers in order, stopping as soon as one
+    /// returns `EventResult::Stop`.
+    pub fn dispatch(&self, model: &mut dyn Any, event: &Event) -> EventResult {
+        for handler in &self.handlers {
+            if handler(model, event) == EventResult::Stop {
+                return EventResult::Stop;
+            }
+        }
+        EventResult::Continue
+    }
+
+    /// Call the node's layout handlers with its current layout.
+    pub fn dispatch_layout(&self, model: &mut dyn Any) {
+        for handler in &self.layout_handlers {
+            handler(model, self.layout);
+        }
+    }
+
+    /// Call the node's visibility handlers with whether it is in view.
+    pub fn dispatch_visibility(&self, model: &mut dyn Any, visible: bool) {
+        for handler in &self.visibility_handlers {
+            handler(model, visible);
         }
     }
 
@@ -142,6 +211,35 @@ impl Node {
             NodeKind::Svg { svg: _, style } => Some(style),
         }
     }
+
+    /// Whether this node is laid out the same as `other`, leaving aside
+    /// their children.
+    fn same_layout(&self, other: &Node) -> bool {
+        let kind = match (&self.kind, &other.kind) {
+            (NodeKind::Element { .. }, NodeKind::Element { .. }) => true,
+            (NodeKind::Text { content, .. }, NodeKind::Text { content: other, .. }) => {
+                content == other
+            }
+            (NodeKind::Texture { texture, .. }, NodeKind::Texture { texture: other, .. }) => {
+                (texture.width(), texture.height()) == (other.width(), other.height())
+            }
+            (NodeKind::Svg { svg, .. }, NodeKind::Svg { svg: other, .. }) => {
+                (svg.width(), svg.height()) == (other.width(), other.height())
+            }
+            _ => false,
+        };
+        kind && self.key == other.key
+            && self
+                .style()
+                .zip(other.style())
+                .is_some_and(|(style, other)| style.same_layout(other))
+    }
+
+    /// Whether the node is a portal, laid out against the viewport and
+    /// drawn over the rest of the tree.
+    pub fn is_portal(&self) -> bool {
+        self.style().is_some_and(|s| s.position == Position::Portal)
+    }
 }
 
 pub struct ElementTree {
@@ -150,9 +248,9 @@ pub struct ElementTree {
 }
 
 impl ElementTree {
-    pub fn new(style: Style, on_click: Option<EventHandler>) -> Self {
+    pub fn new(style: Style, handlers: Vec<EventHandler>) -> Self {
         let mut arena = Arena::new();
-        let root = arena.insert(Node::new_element(style, None, on_click));
+        let root = arena.insert(Node::new_element(style, None, handlers));
         Self { arena, root }
     }
 
@@ -160,24 +258,24 @@ impl ElementTree {
         &mut self,
         parent: NodeId,
         node_type: NodeKind,
-        on_click: Option<EventHandler>,
+        handlers: Vec<EventHandler>,
     ) -> NodeId {
         let child_id = match node_type {
             NodeKind::Element { style } => {
                 self.arena
-                    .insert(Node::new_element(style, Some(parent), on_click))
+                    .insert(Node::new_element(style, Some(parent), handlers))
             }
             NodeKind::Text { content, style } => {
                 self.arena
-                    .insert(Node::new_text(content, style, Some(parent), on_click))
+                    .insert(Node::new_text(content, style, Some(parent), handlers))
             }
             NodeKind::Texture { texture, style } => {
                 self.arena
-                    .insert(Node::new_texture(texture, style, Some(parent), on_click))
+                    .insert(Node::new_texture(texture, style, Some(parent), handlers))
             }
             NodeKind::Svg { svg, style } => {
                 self.arena
-                    .insert(Node::new_svg(svg, style, Some(parent), on_click))
+                    .insert(Node::new_svg(svg, style, Some(parent), handlers))
             }
         };
 
@@ -217,4 +315,376 @@ impl ElementTree {
     pub fn get_node(&self, id: NodeId) -> &Node {
         &self.arena[id]
     }
+
+    /// Copy the subtree at `id` without its handlers, to be laid out on
+    /// another thread, with the id in this tree of each node copied, in the
+    /// order they were copied.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn detach(&self, id: NodeId) -> (DetachedSubtree, Vec<NodeId>) {
+        // Breadth first, so parents come before their children
+        let mut originals = vec![id];
+        let mut parents = vec![None];
+        let mut index = 0;
+        while index < originals.len() {
+            for child in self.children(originals[index]) {
+                originals.push(child);
+                parents.push(Some(index));
+            }
+            index += 1;
+        }
+        let nodes = originals
+            .iter()
+            .zip(parents)
+            .map(|(&id, parent)| DetachedNode {
+                parent,
+                kind: self.arena[id].kind.clone(),
+                debug_name: self.arena[id].debug_name.clone(),
+            })
+            .collect();
+        (DetachedSubtree { nodes }, originals)
+    }
+
+    /// Take the layout of `old`, an earlier tree, for the elements that
+    /// would be laid out the same: the same shape, the same text and media
+    /// sizes, and styles that differ only in paint. Where only what's inside
+    /// an element of fixed size changed, the element keeps its old box and
+    /// is marked dirty, for `layout::relayout_dirty` to lay out again on its
+    /// own. Returns whether it did; if not, because something changed that
+    /// moves or resizes what's around it, this tree has to be laid out
+    /// itself.
+    pub fn reuse_layout(&mut self, old: &ElementTree) -> bool {
+        if !self.reuse_subtree(self.root, old, old.root) {
+            return false;
+        }
+        // Portals are laid out against the whole view, and popovers next to
+        // elements that may have moved, so they aren't laid out piecemeal
+        let dirty = self.arena.iter().any(|(_, node)| node.dirty);
+        !dirty || self.portals().is_empty()
+    }
+
+    /// Copy the layout of `old_id` in `old` to `id` and the elements under
+    /// it, marking the ones that have to be laid out again dirty. Returns
+    /// false if something changed that can't be laid out again without
+    /// what's around `id`.
+    fn reuse_subtree(&mut self, id: NodeId, old: &ElementTree, old_id: NodeId) -> bool {
+        let same_node = self.arena[id].same_layout(&old.arena[old_id]);
+        let same = same_node && {
+            let children: Vec<NodeId> = self.children(id).collect();
+            let old_children: Vec<NodeId> = old.children(old_id).collect();
+            children.len() == old_children.len()
+                && children
+                    .iter()
+                    .zip(&old_children)
+                    .all(|(&child, &old_child)| self.reuse_subtree(child, old, old_child))
+        };
+        let node = &mut self.arena[id];
+        node.layout = old.arena[old_id].layout;
+        node.dirty = !same;
+        same || (same_node && layout::lays_out_alone(self, id, old, old_id))
+    }
+
+    /// Dispatch an event to a node and then to each of its ancestors,
+    /// stopping as soon as a handler returns `EventResult::Stop`.
+    pub fn bubble(&self, id: NodeId, model: &mut dyn Any, event: &Event) -> EventResult {
+        self.bubble_within(id, self.root, model, event)
+    }
+
+    /// Like `bubble`, but stop after `scope`, an ancestor of `id`.
+    pub fn bubble_within(
+        &self,
+        id: NodeId,
+        scope: NodeId,
+        model: &mut dyn Any,
+        event: &Event,
+    ) -> EventResult {
+        let mut cur = Some(id);
+        while let Some(node_id) = cur {
+            let node = &self.arena[node_id];
+            let disabled = node.style().is_some_and(|s| s.disabled);
+            if !disabled && node.dispatch(model, event) == EventResult::Stop {
+                return EventResult::Stop;
+            }
+            if node_id == scope {
+                break;
+            }
+            cur = node.parent;
+        }
+        EventResult::Continue
+    }
+
+    /// Whether `id` is `ancestor` or inside it.
+    pub fn is_within(&self, id: NodeId, ancestor: NodeId) -> bool {
+        let mut cur = Some(id);
+        while let Some(node_id) = cur {
+            if node_id == ancestor {
+                return true;
+            }
+            cur = self.arena[node_id].parent;
+        }
+        false
+    }
+
+    /// The topmost modal portal, which events are kept inside of.
+    pub fn top_modal(&self) -> Option<NodeId> {
+        self.portals()
+            .into_iter()
+            .rev()
+            .find(|&id| self.arena[id].style().is_some_and(|s| s.modal))
+    }
+
+    /// The element Tab moves focus to from `current`, or Shift+Tab when
+    /// `backwards`: the next focusable element inside `scope` (in tree
+    /// order, wrapping around) that isn't disabled. Elements are focusable
+    /// if they have event handlers, unless `ElementBuilder::focusable` says
+    /// otherwise.
+    pub fn next_focus(
+        &self,
+        scope: NodeId,
+        current: Option<NodeId>,
+        backwards: bool,
+    ) -> Option<NodeId> {
+        let mut focusable = Vec::new();
+        self.collect_focusable(scope, &mut focusable);
+        let position = current.and_then(|current| focusable.iter().position(|&id| id == current));
+        let len = focusable.len();
+        if len == 0 {
+            return None;
+        }
+        let index = match (position, backwards) {
+            (Some(i), false) => (i + 1) % len,
+            (Some(i), true) => (i + len - 1) % len,
+            (None, false) => 0,
+            (None, true) => len - 1,
+        };
+        Some(focusable[index])
+    }
+
+    fn collect_focusable(&self, id: NodeId, focusable: &mut Vec<NodeId>) {
+        for child in self.children(id) {
+            let node = &self.arena[child];
+            if node.style().is_some_and(|s| s.disabled) {
+                continue;
+            }
+            let has_handlers = !node.handlers.is_empty();
+            if node
+                .style()
+                .and_then(|s| s.focusable)
+                .unwrap_or(has_handlers)
+            {
+                focusable.push(child);
+            }
+            self.collect_focusable(child, focusable);
+        }
+    }
+
+    /// Find the element that receives mouse events at (`x`, `y`): the
+    /// deepest element there with an event handler, frontmost portal first.
+    ///
+    /// Disabled elements and elements without pointer events are passed
+    /// through, and nothing beneath the topmost modal portal can be hit.
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<NodeId> {
+        for portal_id in self.portals().into_iter().rev() {
+            if self.arena[portal_id].style().is_some_and(|s| s.modal) {
+                return self.hit_test_modal(portal_id, x, y);
+            }
+            if let Some(hit) = self.hit_test_node(portal_id, x, y) {
+                return Some(hit);
+            }
+        }
+        self.hit_test_node(self.root, x, y)
+    }
+
+    /// Find the frontmost element drawn at (`x`, `y`), whether or not it
+    /// handles events. Useful for tools and tests; use `hit_test` to find
+    /// where a click would go.
+    pub fn element_at(&self, x: f32, y: f32) -> Option<NodeId> {
+        self.portals()
+            .into_iter()
+            .rev()
+            .chain([self.root])
+            .find_map(|id| self.element_at_node(id, x, y))
+    }
+
+    /// Find the first element given `name` with `ElementBuilder::debug_name`.
+    pub fn find_debug_name(&self, name: &str) -> Option<NodeId> {
+        self.arena
+            .iter()
+            .find(|(_, node)| node.debug_name.as_deref() == Some(name))
+            .map(|(id, _)| id)
+    }
+
+    fn hit_test_node(&self, id: NodeId, x: f32, y: f32) -> Option<NodeId> {
+        let node = &self.arena[id];
+
+        // Disabled elements and their children never receive events, and
+        // the mouse passes through elements without pointer events
+        let ignored = node
+            .style()
+            .is_some_and(|s| s.disabled || !s.pointer_events);
+        if !node.layout.contains(x, y) || ignored {
+            return None;
+        }
+
+        // Check children first (they're on top), skipping portals - they're
+        // handled separately
+        for child in self.children(id) {
+            if self.arena[child].is_portal() {
+                continue;
+            }
+            if let Some(hit) = self.hit_test_node(child, x, y) {
+                return Some(hit);
+            }
+        }
+
+        // If no child was hit, check if this node has a handler
+        (!node.handlers.is_empty()).then_some(id)
+    }
+
+    /// Hit test a modal portal, which nothing beneath it can be hit through.
+    /// Points on its content go to whatever handles them there, or nowhere;
+    /// points anywhere else are on its scrim and go to the portal itself.
+    fn hit_test_modal(&self, portal_id: NodeId, x: f32, y: f32) -> Option<NodeId> {
+        let mut on_content = false;
+        for child_id in self.children(portal_id) {
+            let child = &self.arena[child_id];
+            if child.is_portal() || child.style().is_some_and(|s| !s.pointer_events) {
+                continue;
+            }
+            if let Some(hit) = self.hit_test_node(child_id, x, y) {
+                return Some(hit);
+            }
+            on_content |= child.layout.contains(x, y);
+        }
+        (!on_content).then_some(portal_id)
+    }
+
+    fn element_at_node(&self, id: NodeId, x: f32, y: f32) -> Option<NodeId> {
+        if !self.arena[id].layout.contains(x, y) {
+            return None;
+        }
+        self.children(id)
+            .filter(|&child| !self.arena[child].is_portal())
+            .find_map(|child| self.element_at_node(child, x, y))
+            .or(Some(id))
+    }
+
+    /// Get the path of child indices leading from the root to a node.
+    ///
+    /// Unlike `NodeId`s, paths stay meaningful across tree rebuilds as long as
+    /// the view produces the same structure.
+    pub fn path_of(&self, id: NodeId) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut cur = id;
+        while let Some(parent) = self.arena[cur].parent {
+            let index = self.children(parent).position(|c| c == cur).unwrap();
+            path.push(index);
+            cur = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Resolve a path of child indices (see `path_of`) back to a node.
+    pub fn node_at_path(&self, path: &[usize]) -> Option<NodeId> {
+        let mut cur = self.root;
+        for &index in path {
+            cur = self.children(cur).nth(index)?;
+        }
+        Some(cur)
+    }
+
+    /// All portals in the tree, including ones inside other portals, from
+    /// the bottom of the stack to the top: by layer, then in tree order. A
+    /// portal inside another one is never stacked below it.
+    pub fn portals(&self) -> Vec<NodeId> {
+        let mut portals = Vec::new();
+        self.collect_portals(self.root, i32::MIN, &mut portals);
+        portals.sort_by_key(|&(_, layer)| layer);
+        portals.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Collect the portals below `id` with the layer they are stacked in,
+    /// given the layer of the portal enclosing them.
+    fn collect_portals(&self, id: NodeId, enclosing: i32, portals: &mut Vec<(NodeId, i32)>) {
+        for child in self.children(id) {
+            let mut layer = enclosing;
+            if self.arena[child].is_portal() {
+                let own = self.arena[child].style().map_or(0, |s| s.layer);
+                layer = own.max(enclosing);
+                portals.push((child, layer));
+            }
+            self.collect_portals(child, layer, portals);
+        }
+    }
+
+    /// Find the node built from the element with `key`.
+    pub fn find_key(&self, key: &str) -> Option<NodeId> {
+        self.arena
+            .iter()
+            .find(|(_, node)| node.key.as_deref() == Some(key))
+            .map(|(id, _)| id)
+    }
+
+    /// Describe a node for logs and panic messages, using its debug name if set.
+    pub fn describe(&self, id: NodeId) -> String {
+        let (index, generation) = id.into_raw_parts();
+        match self.arena.get(id).and_then(|n| n.debug_name.as_deref()) {
+            Some(name) => format!("\"{}\" (node {}v{})", name, index, generation),
+            None => format!("node {}v{}", index, generation),
+        }
+    }
+}
+
+/// A copy of a subtree made by `ElementTree::detach`. It holds only what
+/// layout reads, and none of its handlers, so unlike a tree it can be sent to
+/// another thread.
+#[cfg(feature = "parallel")]
+pub(crate) struct DetachedSubtree {
+    nodes: Vec<DetachedNode>,
+}
+
+#[cfg(feature = "parallel")]
+struct DetachedNode {
+    /// The index of the node's parent in the copy, or `None` for its root.
+    parent: Option<usize>,
+    kind: NodeKind,
+    debug_name: Option<String>,
+}
+
+#[cfg(feature = "parallel")]
+impl DetachedSubtree {
+    /// Build the copy into a tree of its own, with the id of each of its
+    /// nodes in the order they were copied.
+    pub(crate) fn attach(self) -> (ElementTree, Vec<NodeId>) {
+        let mut arena = Arena::with_capacity(self.nodes.len());
+        let mut ids: Vec<NodeId> = Vec::with_capacity(self.nodes.len());
+        let mut last_children: Vec<Option<NodeId>> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes {
+            let parent = node.parent.map(|index| ids[index]);
+            let id = arena.insert(Node {
+                parent,
+                first_child: None,
+                next_sibling: None,
+                kind: node.kind,
+                layout: Layout::default(),
+                dirty: true,
+                handlers: Vec::new(),
+                layout_handlers: Vec::new(),
+                visibility_handlers: Vec::new(),
+                debug_name: node.debug_name,
+                key: None,
+            });
+            // Children are copied in order, so each goes after the last
+            if let Some(index) = node.parent {
+                match last_children[index].replace(id) {
+                    Some(previous) => arena[previous].next_sibling = Some(id),
+                    None => arena[ids[index]].first_child = Some(id),
+                }
+            }
+            ids.push(id);
+            last_children.push(None);
+        }
+        let root = ids[0];
+        (ElementTree { arena, root }, ids)
+    }
 }
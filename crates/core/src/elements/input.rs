@@ -0,0 +1,321 @@
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::builder::ElementBuilder;
+use crate::color::Color;
+use crate::elements::{div, text};
+use crate::events::{ClickInfo, Event, EventResult, Key, Modifiers, MouseButton, NamedKey};
+use crate::style::{px, CursorIcon};
+
+/// The border of an input, and its color while the input has focus.
+const BORDER_COLOR: Color = Color::new(0.7, 0.7, 0.7, 1.0);
+const FOCUSED_BORDER_COLOR: Color = Color::new(0.26, 0.52, 0.96, 1.0);
+
+/// Room between an input's border and its text, in pixels.
+const PADDING: f32 = 6.0;
+
+/// The text of an `input`, where its caret and selection are, and whether
+/// it has focus. Keep one in the model for each input.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputState {
+    pub value: String,
+    /// Byte offset of the caret in `value`
+    pub caret: usize,
+    /// Byte offset of the other end of the selection, which is empty when
+    /// this is the caret
+    pub anchor: usize,
+    /// Whether the input has keyboard focus, which shows the caret
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub focused: bool,
+}
+
+impl InputState {
+    /// `value` with the caret after its end.
+    pub fn new(value: impl Into<String>) -> Self {
+        let value = value.into();
+        let end = value.len();
+        Self {
+            value,
+            caret: end,
+            anchor: end,
+            focused: false,
+        }
+    }
+
+    /// The byte range of `value` that is selected, empty if nothing is.
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.value[self.selection()]
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = 0;
+        self.caret = self.value.len();
+    }
+
+    /// Put `text` in place of the selection, or at the caret if nothing is
+    /// selected, and move the caret after it.
+    pub fn insert(&mut self, text: &str) {
+        self.clamp();
+        let selection = self.selection();
+        self.value.replace_range(selection.clone(), text);
+        self.caret = selection.start + text.len();
+        self.anchor = self.caret;
+    }
+
+    /// Edit the text as pressing `key` with `modifiers` held does, returning
+    /// whether the key was one for the input.
+    ///
+    /// Typing replaces the selection, Backspace and Delete remove it or the
+    /// character before or after the caret, the arrow keys, Home and End
+    /// move the caret (selecting as they go with Shift held), and Ctrl+A
+    /// (Cmd+A on macOS) selects everything. Holding Ctrl or Alt makes the
+    /// arrows and deletion go a word at a time.
+    pub fn handle_key(&mut self, key: &Key, modifiers: Modifiers) -> bool {
+        self.clamp();
+        let by_word = modifiers.ctrl || modifiers.alt;
+        match key {
+            Key::Character(c)
+                if (modifiers.ctrl || modifiers.meta) && c.eq_ignore_ascii_case("a") =>
+            {
+                self.select_all();
+                true
+            }
+            // Leave shortcuts such as copy and undo to the app
+            Key::Character(_) if modifiers.ctrl || modifiers.meta => false,
+            Key::Character(c) => {
+                self.insert(c);
+                true
+            }
+            Key::Named(NamedKey::Space) => {
+                self.insert(" ");
+                true
+            }
+            Key::Named(key @ (NamedKey::Backspace | NamedKey::Delete)) => {
+                if self.selection().is_empty() {
+                    let forward = *key == NamedKey::Delete;
+                    self.anchor = self.step(forward, by_word);
+                }
+                self.insert("");
+                true
+            }
+            Key::Named(key @ (NamedKey::ArrowLeft | NamedKey::ArrowRight)) => {
+                let forward = *key == NamedKey::ArrowRight;
+                let selection = self.selection();
+                self.caret = match (modifiers.shift, selection.is_empty()) {
+                    // Collapse the selection to the side the arrow points
+                    (false, false) if forward => selection.end,
+                    (false, false) => selection.start,
+                    _ => self.step(forward, by_word),
+                };
+                if !modifiers.shift {
+                    self.anchor = self.caret;
+                }
+                true
+            }
+            Key::Named(key @ (NamedKey::Home | NamedKey::End)) => {
+                self.caret = if *key == NamedKey::Home {
+                    0
+                } else {
+                    self.value.len()
+                };
+                if !modifiers.shift {
+                    self.anchor = self.caret;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Move the caret where a click described by `info` lands: to the
+    /// nearest character boundary, or with Shift held, selecting up to it.
+    /// A double click selects the word there, and a triple click selects
+    /// everything.
+    pub fn click(&mut self, info: &ClickInfo) {
+        self.clamp();
+        if info.count >= 3 {
+            self.select_all();
+            return;
+        }
+        let Some(offset) = info.text_offset else {
+            return;
+        };
+        self.caret = offset.min(self.value.len());
+        self.clamp();
+        if info.count == 2 {
+            self.anchor = self.word_edge(self.caret, false);
+            self.caret = self.word_edge(self.caret, true);
+        } else if !info.modifiers.shift {
+            self.anchor = self.caret;
+        }
+    }
+
+    /// Keep the caret and anchor on character boundaries inside the text,
+    /// in case the text was changed without them.
+    fn clamp(&mut self) {
+        for offset in [&mut self.caret, &mut self.anchor] {
+            *offset = (*offset).min(self.value.len());
+            while !self.value.is_char_boundary(*offset) {
+                *offset -= 1;
+            }
+        }
+    }
+
+    /// Where the caret goes when moved one character (or word) back or
+    /// `forward`.
+    fn step(&self, forward: bool, by_word: bool) -> usize {
+        let at = self.caret;
+        if by_word {
+            // Past any spaces, then to the far side of the word
+            let mut at = at;
+            loop {
+                let next = self.char_step(at, forward);
+                let crossed = if forward {
+                    self.value[at..next].chars().next()
+                } else {
+                    self.value[next..at].chars().next()
+                };
+                match crossed {
+                    Some(c) if c.is_whitespace() => at = next,
+                    _ => break,
+                }
+            }
+            self.word_edge(at, forward)
+        } else {
+            self.char_step(at, forward)
+        }
+    }
+
+    /// The character boundary next to `at`, after it if `forward`.
+    fn char_step(&self, at: usize, forward: bool) -> usize {
+        if forward {
+            self.value[at..]
+                .chars()
+                .next()
+                .map_or(at, |c| at + c.len_utf8())
+        } else {
+            self.value[..at]
+                .char_indices()
+                .next_back()
+                .map_or(0, |(i, _)| i)
+        }
+    }
+
+    /// The end of the run of word characters (or of other characters)
+    /// around `at`, after it if `forward`.
+    fn word_edge(&self, at: usize, forward: bool) -> usize {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let kind = if forward {
+            self.value[at..].chars().next()
+        } else {
+            self.value[..at].chars().next_back()
+        }
+        .map(is_word);
+        let Some(kind) = kind else {
+            return at;
+        };
+        if forward {
+            self.value[at..]
+                .char_indices()
+                .find(|&(_, c)| is_word(c) != kind || c.is_whitespace())
+                .map_or(self.value.len(), |(i, _)| at + i)
+        } else {
+            self.value[..at]
+                .char_indices()
+                .rev()
+                .find(|&(_, c)| is_word(c) != kind || c.is_whitespace())
+                .map_or(0, |(i, c)| i + c.len_utf8())
+        }
+    }
+}
+
+/// A text field showing `state`, which can be edited with the keyboard
+/// once it is clicked or tabbed to. `on_change` gets the new state after
+/// each edit, click and change of focus, to keep in the model.
+///
+/// The input is as wide as its text unless given a width, and its text
+/// wraps onto more lines when it doesn't fit. Keys it doesn't use, such as
+/// Enter and Tab, bubble up to the elements around it.
+///
+/// # Example
+/// ```
+/// input(&model.name, |m: &mut Signup, name| m.name = name)
+///     .w(px(240.0))
+///     .on_key(|m: &mut Signup, key| {
+///         if *key == Key::Named(NamedKey::Enter) {
+///             m.submit();
+///         }
+///     })
+/// ```
+pub fn input<M, F>(state: &InputState, on_change: F) -> ElementBuilder
+where
+    M: 'static,
+    F: Fn(&mut M, InputState) + 'static,
+{
+    let mut content = text(state.value.clone());
+    if state.focused {
+        let selection = state.selection();
+        content = content.caret(state.caret.min(state.value.len()));
+        if !selection.is_empty() && selection.end <= state.value.len() {
+            content = content.selection(selection);
+        }
+    }
+
+    // Events can come faster than the view is rebuilt, so each one starts
+    // from what the one before it left
+    let latest = Rc::new(RefCell::new(state.clone()));
+    div()
+        .p(px(PADDING))
+        .bg(Color::WHITE)
+        .border(
+            1.0,
+            if state.focused {
+                FOCUSED_BORDER_COLOR
+            } else {
+                BORDER_COLOR
+            },
+        )
+        .radius(4.0)
+        .cursor(CursorIcon::Text)
+        .child(content)
+        .on_event(move |model: &mut M, event: &Event| {
+            let mut changed = latest.borrow().clone();
+            let result = match event {
+                Event::KeyDown { key, modifiers, .. } => {
+                    if !changed.handle_key(key, *modifiers) {
+                        return EventResult::Continue;
+                    }
+                    changed.focused = true;
+                    EventResult::Stop
+                }
+                Event::Click {
+                    button: MouseButton::Left,
+                    info,
+                } => {
+                    changed.click(info);
+                    changed.focused = true;
+                    EventResult::Continue
+                }
+                Event::FocusGained => {
+                    changed.focused = true;
+                    EventResult::Continue
+                }
+                Event::FocusLost => {
+                    changed.focused = false;
+                    EventResult::Continue
+                }
+                _ => return EventResult::Continue,
+            };
+            if changed != *latest.borrow() {
+                latest.replace(changed.clone());
+                on_change(model, changed);
+            }
+            result
+        })
+}
@@ -1,11 +1,15 @@
 pub mod div;
 pub mod img;
+pub mod input;
+pub mod popover;
 pub mod portal;
 pub mod svg;
 pub mod text;
 
 pub use div::div;
 pub use img::img;
+pub use input::{input, InputState};
+pub use popover::popover;
 pub use portal::portal;
 pub use svg::svg;
 pub use text::text;
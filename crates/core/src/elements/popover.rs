@@ -0,0 +1,25 @@
+use crate::builder::ElementBuilder;
+use crate::elements::portal;
+use crate::style::{Anchor, Placement};
+
+/// Create a portal placed next to the element with key `anchor`, on the
+/// side given by `placement` and lined up with the anchor's left (or top)
+/// edge. If it doesn't fit on that side of the window it goes on the
+/// opposite side instead, and it is kept inside the window.
+///
+/// A popover whose anchor isn't in the view is placed like a plain portal.
+///
+/// # Example
+/// ```
+/// div()
+///     .child(button("Sort").key("sort-button"))
+///     .child(popover("sort-button", Placement::Bottom).child(sort_menu()))
+/// ```
+pub fn popover(anchor: impl Into<String>, placement: Placement) -> ElementBuilder {
+    let mut element = portal();
+    element.style.anchor = Some(Anchor {
+        key: anchor.into(),
+        placement,
+    });
+    element
+}
@@ -1,5 +1,9 @@
+use std::borrow::Cow;
+
 use crate::builder::ElementBuilder;
 
-pub fn text(content: impl Into<String>) -> ElementBuilder {
+/// A text element. Static strings are borrowed rather than copied, so
+/// fixed labels don't allocate each time the view is built.
+pub fn text(content: impl Into<Cow<'static, str>>) -> ElementBuilder {
     ElementBuilder::new_text(content.into())
 }
@@ -1,6 +1,8 @@
 use std::any::Any;
 use std::rc::Rc;
 
+use crate::layout::Layout;
+
 /// Result of handling an event, controls propagation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EventResult {
@@ -64,15 +66,129 @@ pub enum MouseButton {
     Middle,
 }
 
+/// Keyboard modifier state at the time of an event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    /// Command on macOS, Windows key elsewhere.
+    pub meta: bool,
+}
+
+/// Details about a click, passed to `on_left_click_with` and friends.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClickInfo {
+    /// Cursor position in window coordinates.
+    pub x: f32,
+    pub y: f32,
+    /// Cursor position relative to the clicked element's top-left corner.
+    pub local_x: f32,
+    pub local_y: f32,
+    /// Modifier keys held when the click happened.
+    pub modifiers: Modifiers,
+    /// Number of consecutive clicks (1 = single, 2 = double, ...).
+    pub count: u32,
+    /// The byte offset of the caret position nearest the click in the text
+    /// of the clicked element, or of the first text inside it, if there is
+    /// any.
+    pub text_offset: Option<usize>,
+}
+
 /// All possible events.
 #[derive(Debug, Clone)]
 pub enum Event {
-    Click { button: MouseButton },
-    MouseDown { button: MouseButton },
-    MouseUp { button: MouseButton },
-    KeyDown { key: Key, repeat: bool },
-    KeyUp { key: Key },
+    Click {
+        button: MouseButton,
+        info: ClickInfo,
+    },
+    MouseDown {
+        button: MouseButton,
+    },
+    /// Sent to the element under the cursor, and also to the element the
+    /// button was pressed on if the cursor has left it since.
+    MouseUp {
+        button: MouseButton,
+    },
+    /// The cursor moved, in window coordinates. Sent to the element under
+    /// the cursor, or while a button is held, to the element it was pressed
+    /// on (pointer capture), so drags keep tracking outside its bounds.
+    MouseMove {
+        x: f32,
+        y: f32,
+    },
+    /// Raw motion of the mouse, in the device's own units rather than
+    /// pixels, so unlike other positions it isn't scaled to logical pixels.
+    /// Keeps coming when the cursor is stopped by the edge of the screen or
+    /// locked in place. Sent to the same element as `MouseMove`.
+    MouseDelta {
+        dx: f32,
+        dy: f32,
+    },
+    /// The cursor moved far enough with a button held on the element for
+    /// it to be a drag rather than a click. `DragMove` follows right away.
+    DragStart,
+    /// The cursor moved during a drag, by `dx` and `dy` pixels since the
+    /// last `DragMove` (or since the button was pressed, for the first).
+    /// Sent to the element the drag started on, wherever the cursor is.
+    DragMove {
+        dx: f32,
+        dy: f32,
+    },
+    /// The button that started a drag was released.
+    DragEnd,
+    /// The cursor entered the element's bounds.
+    MouseEnter,
+    /// The cursor left the element's bounds.
+    MouseLeave,
+    /// Scroll wheel or trackpad motion, in pixels.
+    Scroll {
+        dx: f32,
+        dy: f32,
+    },
+    KeyDown {
+        key: Key,
+        repeat: bool,
+        /// Modifier keys held when the key was pressed.
+        modifiers: Modifiers,
+    },
+    KeyUp {
+        key: Key,
+    },
+    /// The element got keyboard focus, by being clicked or tabbed to.
+    FocusGained,
+    /// The element lost keyboard focus.
+    FocusLost,
+    /// The window gained or lost keyboard focus. This and the other window
+    /// events go to the app's window handler rather than to elements.
+    WindowFocused {
+        focused: bool,
+    },
+    /// The window moved, to `x` and `y` on the desktop in physical pixels.
+    WindowMoved {
+        x: i32,
+        y: i32,
+    },
+    /// The window's scale factor changed, such as by being moved to a
+    /// monitor with a different DPI.
+    ScaleFactorChanged {
+        scale_factor: f64,
+    },
+    /// The window's contents were resized, to `width` by `height` physical
+    /// pixels.
+    Resized {
+        width: u32,
+        height: u32,
+    },
 }
 
 /// Event handler that can update the model.
 pub type EventHandler = Rc<dyn Fn(&mut dyn Any, &Event) -> EventResult>;
+
+/// Handler called with an element's new rect after layout (see
+/// `ElementBuilder::on_layout`).
+pub type LayoutHandler = Rc<dyn Fn(&mut dyn Any, Layout)>;
+
+/// Handler called with whether an element is in the window after it comes
+/// into view or goes out of it (see `ElementBuilder::on_visible`).
+pub type VisibilityHandler = Rc<dyn Fn(&mut dyn Any, bool)>;
@@ -0,0 +1,262 @@
+use crate::element::{ElementTree, NodeId};
+use crate::layout::Layout;
+
+/// Smallest width and height of the squares the root element is divided
+/// into.
+const CELL_SIZE: f32 = 64.0;
+
+/// Most squares to divide the root element into; a taller or wider one gets
+/// bigger squares instead.
+const MAX_CELLS: f32 = 4096.0;
+
+/// The elements of a laid-out tree that can be hit, sorted into a grid of
+/// squares by where they are, so finding the element under a point only
+/// looks at the few elements in its square instead of walking the tree.
+///
+/// Gives the same answers as `ElementTree::hit_test`. The index is a
+/// snapshot: once the tree is laid out again, build a new one.
+///
+/// # Example
+/// ```
+/// let index = HitIndex::new(&tree);
+/// assert_eq!(index.hit_test(x, y), tree.hit_test(x, y));
+/// ```
+pub struct HitIndex {
+    /// Elements with handlers, in the order `hit_test` tries them: the
+    /// first one whose rect holds a point is the one hit.
+    targets: Vec<Target>,
+    /// The topmost modal portal and the rects of its content, if one is
+    /// open. Points on its content that hit nothing go nowhere; anywhere
+    /// else they go to the portal.
+    modal: Option<(NodeId, Vec<Rect>)>,
+    /// Where the grid starts, the size of its squares, and how many it has
+    /// across and down.
+    origin: (f32, f32),
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    /// The indices of the targets that reach into each square, in order,
+    /// square after square, row by row.
+    cell_targets: Vec<u32>,
+    /// Where each square's targets start in `cell_targets`, plus where the
+    /// last one's end.
+    cell_starts: Vec<u32>,
+}
+
+struct Target {
+    id: NodeId,
+    /// The part of the element inside all of its ancestors, which is where
+    /// it can be hit.
+    rect: Rect,
+}
+
+/// A rectangle by its edges, counting the edges as inside.
+#[derive(Clone, Copy)]
+struct Rect {
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+}
+
+impl Rect {
+    const EVERYWHERE: Rect = Rect {
+        left: f32::NEG_INFINITY,
+        top: f32::NEG_INFINITY,
+        right: f32::INFINITY,
+        bottom: f32::INFINITY,
+    };
+
+    fn of(layout: Layout) -> Rect {
+        Rect {
+            left: layout.x,
+            top: layout.y,
+            right: layout.x + layout.width,
+            bottom: layout.y + layout.height,
+        }
+    }
+
+    fn intersect(self, other: Rect) -> Rect {
+        Rect {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom),
+        }
+    }
+
+    fn is_empty(self) -> bool {
+        self.left > self.right || self.top > self.bottom
+    }
+
+    fn contains(self, x: f32, y: f32) -> bool {
+        x >= self.left && x <= self.right && y >= self.top && y <= self.bottom
+    }
+}
+
+impl HitIndex {
+    /// Index the elements of `tree`, which has to be laid out.
+    pub fn new(tree: &ElementTree) -> Self {
+        let mut targets = Vec::new();
+        let mut modal = None;
+        for portal_id in tree.portals().into_iter().rev() {
+            if tree.arena[portal_id].style().is_some_and(|s| s.modal) {
+                modal = Some((portal_id, collect_modal(tree, portal_id, &mut targets)));
+                break;
+            }
+            collect(tree, portal_id, Rect::EVERYWHERE, &mut targets);
+        }
+        if modal.is_none() {
+            collect(tree, tree.root, Rect::EVERYWHERE, &mut targets);
+        }
+
+        // The grid covers the root element; points outside of it are rare
+        // enough to check against every target
+        let root = tree.arena[tree.root].layout;
+        let cell_size = CELL_SIZE.max((root.width * root.height / MAX_CELLS).sqrt());
+        let columns = (root.width / cell_size).floor() as usize + 1;
+        let rows = (root.height / cell_size).floor() as usize + 1;
+        let mut index = HitIndex {
+            targets,
+            modal,
+            origin: (root.x, root.y),
+            cell_size,
+            columns,
+            rows,
+            cell_targets: Vec::new(),
+            cell_starts: vec![0; columns * rows + 1],
+        };
+
+        // Count the targets in each square, then put them in place
+        let ranges: Vec<_> = index
+            .targets
+            .iter()
+            .map(|target| index.cell_range(target.rect))
+            .collect();
+        for cell in ranges
+            .iter()
+            .flatten()
+            .flat_map(|range| cells_in(columns, *range))
+        {
+            index.cell_starts[cell + 1] += 1;
+        }
+        for cell in 0..columns * rows {
+            index.cell_starts[cell + 1] += index.cell_starts[cell];
+        }
+        let mut next = index.cell_starts.clone();
+        index.cell_targets = vec![0; index.cell_starts[columns * rows] as usize];
+        for (i, range) in ranges.into_iter().enumerate() {
+            for cell in range.into_iter().flat_map(|range| cells_in(columns, range)) {
+                index.cell_targets[next[cell] as usize] = i as u32;
+                next[cell] += 1;
+            }
+        }
+        index
+    }
+
+    /// Find the element a click at (`x`, `y`) goes to, like
+    /// `ElementTree::hit_test`.
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<NodeId> {
+        let hit = match self.cell_at(x, y) {
+            Some(cell) => cell
+                .iter()
+                .map(|&i| &self.targets[i as usize])
+                .find(|target| target.rect.contains(x, y)),
+            None => self
+                .targets
+                .iter()
+                .find(|target| target.rect.contains(x, y)),
+        };
+        if let Some(target) = hit {
+            return Some(target.id);
+        }
+        let (portal_id, content) = self.modal.as_ref()?;
+        let on_content = content.iter().any(|rect| rect.contains(x, y));
+        (!on_content).then_some(*portal_id)
+    }
+
+    /// The targets in the square holding (`x`, `y`), if it is on the grid.
+    fn cell_at(&self, x: f32, y: f32) -> Option<&[u32]> {
+        let column = ((x - self.origin.0) / self.cell_size).floor();
+        let row = ((y - self.origin.1) / self.cell_size).floor();
+        if column < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (column, row) = (column as usize, row as usize);
+        if column >= self.columns || row >= self.rows {
+            return None;
+        }
+        let cell = row * self.columns + column;
+        let (start, end) = (self.cell_starts[cell], self.cell_starts[cell + 1]);
+        Some(&self.cell_targets[start as usize..end as usize])
+    }
+
+    /// The first and last columns and rows of the squares `rect` reaches
+    /// into, or `None` if it is off the grid.
+    fn cell_range(&self, rect: Rect) -> Option<(usize, usize, usize, usize)> {
+        let first_column = ((rect.left - self.origin.0) / self.cell_size)
+            .floor()
+            .max(0.0);
+        let first_row = ((rect.top - self.origin.1) / self.cell_size)
+            .floor()
+            .max(0.0);
+        let last_column = ((rect.right - self.origin.0) / self.cell_size).floor();
+        let last_row = ((rect.bottom - self.origin.1) / self.cell_size).floor();
+        if last_column < 0.0 || last_row < 0.0 {
+            return None;
+        }
+        let last_column = (last_column as usize).min(self.columns - 1);
+        let last_row = (last_row as usize).min(self.rows - 1);
+        let (first_column, first_row) = (first_column as usize, first_row as usize);
+        if first_column > last_column || first_row > last_row {
+            return None;
+        }
+        Some((first_column, last_column, first_row, last_row))
+    }
+}
+
+/// The squares in a range from `cell_range`, on a grid `columns` wide.
+fn cells_in(
+    columns: usize,
+    (first_column, last_column, first_row, last_row): (usize, usize, usize, usize),
+) -> impl Iterator<Item = usize> {
+    (first_row..=last_row)
+        .flat_map(move |row| (first_column..=last_column).map(move |column| row * columns + column))
+}
+
+/// Add the elements under `id` that can be hit to `targets`, in the order
+/// `ElementTree::hit_test` tries them: children before their parent, and
+/// earlier children first. `clip` is where the element's ancestors are.
+fn collect(tree: &ElementTree, id: NodeId, clip: Rect, targets: &mut Vec<Target>) {
+    let node = &tree.arena[id];
+    let ignored = node
+        .style()
+        .is_some_and(|s| s.disabled || !s.pointer_events);
+    let rect = clip.intersect(Rect::of(node.layout));
+    if ignored || rect.is_empty() {
+        return;
+    }
+    for child in tree.children(id) {
+        if !tree.arena[child].is_portal() {
+            collect(tree, child, rect, targets);
+        }
+    }
+    if !node.handlers.is_empty() {
+        targets.push(Target { id, rect });
+    }
+}
+
+/// Add the content of the modal portal at `portal_id` to `targets`, and
+/// return the rects it covers.
+fn collect_modal(tree: &ElementTree, portal_id: NodeId, targets: &mut Vec<Target>) -> Vec<Rect> {
+    let mut content = Vec::new();
+    for child_id in tree.children(portal_id) {
+        let child = &tree.arena[child_id];
+        if child.is_portal() || child.style().is_some_and(|s| !s.pointer_events) {
+            continue;
+        }
+        collect(tree, child_id, Rect::EVERYWHERE, targets);
+        content.push(Rect::of(child.layout));
+    }
+    content
+}
@@ -1,7 +1,10 @@
-use crate::element::{ElementTree, NodeId, NodeKind};
-use crate::style::{Align, Direction, Distribute, Length, Position};
+use std::ops::Range;
 
-#[derive(Clone, Copy, Debug, Default)]
+use crate::builder::ElementBuilder;
+use crate::element::{ElementTree, Node, NodeId, NodeKind};
+use crate::style::{Align, Direction, Distribute, Length, ObjectFit, Placement, Position, Style};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Layout {
     pub x: f32,
     pub y: f32,
@@ -9,6 +12,16 @@ pub struct Layout {
     pub height: f32,
 }
 
+impl Layout {
+    /// Whether the point (`x`, `y`) is inside, counting the edges.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+}
+
+/// The space an element is laid out in, in logical pixels: the window's
+/// size in physical pixels divided by its scale factor, so a layout comes
+/// out the same size on screens of any pixel density.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Constraints {
     pub max_w: f32,
@@ -19,6 +32,52 @@ pub struct Constraints {
 /// Implemented by the renderer to provide font-aware text measurement.
 pub trait TextMeasurer {
     fn measure(&mut self, text: &str, max_width: Option<f32>) -> (f32, f32);
+
+    /// Measure text drawn in the monospace font (see
+    /// `ElementBuilder::monospace`). Measurers without one measure it like
+    /// any other text.
+    fn measure_monospace(&mut self, text: &str, max_width: Option<f32>) -> (f32, f32) {
+        self.measure(text, max_width)
+    }
+
+    /// Measure the text of a text element styled with `style`, which can
+    /// change its font size, line height and number of lines. Measurers
+    /// that don't handle those measure it with `measure` or
+    /// `measure_monospace`, wrapping it unless it is `no_wrap`.
+    fn measure_styled(&mut self, text: &str, max_width: Option<f32>, style: &Style) -> (f32, f32) {
+        let max_width = max_width.filter(|_| !style.no_wrap);
+        if style.monospace {
+            self.measure_monospace(text, max_width)
+        } else {
+            self.measure(text, max_width)
+        }
+    }
+
+    /// A measurer that measures the same way on another thread, for laying
+    /// out the children of wide rows and columns in parallel (with the
+    /// `parallel` feature). Measurers that can't be used elsewhere return
+    /// `None`, the default, keeping layout on this thread.
+    fn fork(&self) -> Option<Box<dyn TextMeasurer + Send>> {
+        None
+    }
+}
+
+impl<T: TextMeasurer + ?Sized> TextMeasurer for Box<T> {
+    fn measure(&mut self, text: &str, max_width: Option<f32>) -> (f32, f32) {
+        (**self).measure(text, max_width)
+    }
+
+    fn measure_monospace(&mut self, text: &str, max_width: Option<f32>) -> (f32, f32) {
+        (**self).measure_monospace(text, max_width)
+    }
+
+    fn measure_styled(&mut self, text: &str, max_width: Option<f32>, style: &Style) -> (f32, f32) {
+        (**self).measure_styled(text, max_width, style)
+    }
+
+    fn fork(&self) -> Option<Box<dyn TextMeasurer + Send>> {
+        (**self).fork()
+    }
 }
 
 /// No-op text measurer that returns zero dimensions.
@@ -28,6 +87,107 @@ impl TextMeasurer for NoOpMeasurer {
     fn measure(&mut self, _text: &str, _max_width: Option<f32>) -> (f32, f32) {
         (0.0, 0.0)
     }
+
+    fn fork(&self) -> Option<Box<dyn TextMeasurer + Send>> {
+        Some(Box::new(NoOpMeasurer))
+    }
+}
+
+/// Text measurer for tests that gives every character the same width and
+/// every line the same height, so sizes are the same on every machine.
+/// Text wraps between words, like it does with real fonts.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedMeasurer {
+    pub char_width: f32,
+    pub line_height: f32,
+}
+
+impl Default for FixedMeasurer {
+    fn default() -> Self {
+        FixedMeasurer {
+            char_width: 8.0,
+            line_height: 16.0,
+        }
+    }
+}
+
+impl TextMeasurer for FixedMeasurer {
+    fn measure(&mut self, text: &str, max_width: Option<f32>) -> (f32, f32) {
+        let max_chars = max_width.map(|max| (max / self.char_width) as usize);
+        let mut lines = 0;
+        let mut widest = 0;
+        for paragraph in text.split('\n') {
+            lines += 1;
+            // Characters on the current line, or none before its first word
+            let mut line: Option<usize> = None;
+            for word in paragraph.split(' ') {
+                let len = word.chars().count();
+                line = Some(match line {
+                    // A word that doesn't fit goes on the next line, unless
+                    // the line is empty, in which case it sticks out
+                    Some(chars) if max_chars.is_some_and(|max| chars + 1 + len > max) => {
+                        widest = widest.max(chars);
+                        lines += 1;
+                        len
+                    }
+                    Some(chars) => chars + 1 + len,
+                    None => len,
+                });
+            }
+            widest = widest.max(line.unwrap_or(0));
+        }
+        (
+            widest as f32 * self.char_width,
+            lines as f32 * self.line_height,
+        )
+    }
+
+    fn measure_styled(&mut self, text: &str, max_width: Option<f32>, style: &Style) -> (f32, f32) {
+        let (w, h) = self.measure(text, max_width.filter(|_| !style.no_wrap));
+        match style.max_lines {
+            Some(lines) => (w, h.min(lines as f32 * self.line_height)),
+            None => (w, h),
+        }
+    }
+
+    fn fork(&self) -> Option<Box<dyn TextMeasurer + Send>> {
+        Some(Box::new(*self))
+    }
+}
+
+/// Build `root` and lay it out in a `width` by `height` window, measuring
+/// text with `measurer`.
+///
+/// This is what the renderer does before drawing, without a window or GPU,
+/// so views can be tested by checking where their elements end up.
+/// `vitae_render::layout_tree` does the same with the renderer's fonts.
+///
+/// # Example
+/// ```
+/// let tree = layout_tree(view(&model), 800.0, 600.0, &mut FixedMeasurer::default());
+/// let save = tree.find_debug_name("save-button").unwrap();
+/// assert_eq!(tree.get_node(save).layout.width, 120.0);
+/// ```
+pub fn layout_tree<M: TextMeasurer>(
+    root: ElementBuilder,
+    width: f32,
+    height: f32,
+    measurer: &mut M,
+) -> ElementTree {
+    let mut tree = root.build();
+    let root = tree.root;
+    layout(
+        &mut tree,
+        root,
+        Constraints {
+            max_w: width,
+            max_h: height,
+        },
+        0.0,
+        0.0,
+        measurer,
+    );
+    tree
 }
 
 /// Main entry point for layout. Lays out the tree and handles portals.
@@ -44,13 +204,14 @@ pub fn layout<M: TextMeasurer>(
         tree,
         id,
         constraints,
-        cursor_x,
-        cursor_y,
+        (cursor_x, cursor_y),
+        (None, None),
         measurer,
         &mut portals,
     );
 
     // Layout portals relative to viewport (using root constraints)
+    let mut popovers = Vec::new();
     for portal_id in portals {
         layout_portal(
             tree,
@@ -58,24 +219,196 @@ pub fn layout<M: TextMeasurer>(
             constraints.max_w,
             constraints.max_h,
             measurer,
+            &mut popovers,
         );
     }
 
+    // Move popovers next to their anchors, now that everything they could
+    // be anchored to has been laid out
+    for popover_id in popovers {
+        place_popover(tree, popover_id, constraints.max_w, constraints.max_h);
+    }
+
     result
 }
 
+/// Lay out again the elements `ElementTree::reuse_layout` marked dirty, each
+/// in the box it had before, leaving the rest of the tree as it was laid
+/// out. Returns the elements laid out again.
+pub fn relayout_dirty<M: TextMeasurer>(tree: &mut ElementTree, measurer: &mut M) -> Vec<NodeId> {
+    let mut relaid = Vec::new();
+    let mut stack = vec![tree.root];
+    while let Some(id) = stack.pop() {
+        if !tree.arena[id].dirty {
+            stack.extend(tree.children(id));
+            continue;
+        }
+        // Laid out at the size it was given before, which may have come from
+        // growing or shrinking rather than from its style
+        let Layout {
+            x,
+            y,
+            width,
+            height,
+        } = tree.arena[id].layout;
+        let style = tree.arena[id].style().unwrap();
+        let cursor_x = x - style.translate_x - style.margin.left.as_px();
+        let cursor_y = y - style.translate_y - style.margin.top.as_px();
+        let constraints = Constraints {
+            max_w: width,
+            max_h: height,
+        };
+        let mut portals = Vec::new();
+        layout_inner(
+            tree,
+            id,
+            constraints,
+            (cursor_x, cursor_y),
+            (Some(width), Some(height)),
+            measurer,
+            &mut portals,
+        );
+        debug_assert!(portals.is_empty(), "portals are never laid out again alone");
+        relaid.push(id);
+    }
+    relaid
+}
+
+/// Whether the element at `id`, laid out the same as the one at `old_id` in
+/// `old` but with different insides, can be laid out again without what's
+/// around it: a box in the flow whose size comes from neither its children
+/// nor its text.
+pub(crate) fn lays_out_alone(
+    tree: &ElementTree,
+    id: NodeId,
+    old: &ElementTree,
+    old_id: NodeId,
+) -> bool {
+    let node = &tree.arena[id];
+    let Some(style) = node.style() else {
+        return false;
+    };
+    let layout = old.arena[old_id].layout;
+    // A zero size is taken as no size, so it comes from the children
+    let fixed = |length: Length| matches!(length, Length::Px(px) if px > 0.0);
+    // Margins in percent are of a container it's laid out without
+    let margin = &style.margin;
+    let margins_fixed = [margin.left, margin.right, margin.top, margin.bottom]
+        .iter()
+        .all(|length| !matches!(length, Length::Percent(_)));
+    matches!(node.kind, NodeKind::Element { .. })
+        && style.position == Position::Relative
+        && fixed(style.width)
+        && fixed(style.height)
+        && margins_fixed
+        && layout.width > 0.0
+        && layout.height > 0.0
+        // How far it shrinks depends on its children
+        && (style.shrink == 0.0
+            || min_size_sized(tree, id, false) == min_size_sized(old, old_id, false))
+}
+
+/// The smallest size an element can be laid out at without its fixed-size
+/// parts overlapping: explicit pixel sizes, padding, margins and gaps,
+/// added up along each row or column (or the longest child, for ones that
+/// wrap). Text and images count for nothing, as they can wrap or be scaled.
+/// Absolute and portal children don't take up room, so they are left out
+/// too.
+pub fn min_size(tree: &ElementTree, id: NodeId) -> (f32, f32) {
+    min_size_sized(tree, id, true)
+}
+
+/// Like `min_size`, leaving out the element's own pixel sizes unless
+/// `sized`, for the least it can shrink to.
+fn min_size_sized(tree: &ElementTree, id: NodeId, sized: bool) -> (f32, f32) {
+    let Some(style) = tree.arena[id].style() else {
+        return (0.0, 0.0);
+    };
+
+    let mut main: f32 = 0.0;
+    let mut cross: f32 = 0.0;
+    let mut flow_count = 0;
+    for child in tree.children(id) {
+        let Some(child_style) = tree.arena[child].style() else {
+            continue;
+        };
+        if child_style.position != Position::Relative {
+            continue;
+        }
+        let (mut cw, mut ch) = min_size(tree, child);
+        // A child sized in percent needs its parent big enough that the
+        // percentage still leaves it its own minimum
+        if let Length::Percent(p) = child_style.width {
+            if p > 0.0 {
+                cw *= 100.0 / p;
+            }
+        }
+        if let Length::Percent(p) = child_style.height {
+            if p > 0.0 {
+                ch *= 100.0 / p;
+            }
+        }
+        let (child_main, child_cross) = match style.direction {
+            Direction::Row => (cw, ch),
+            Direction::Column => (ch, cw),
+        };
+        // Children that wrap only need room for the longest of them
+        if style.wrap {
+            main = main.max(child_main);
+        } else {
+            main += child_main;
+        }
+        cross = cross.max(child_cross);
+        flow_count += 1;
+    }
+    if flow_count > 1 && !style.wrap {
+        let gap = match style.direction {
+            Direction::Row => style.gap_x.as_px(),
+            Direction::Column => style.gap_y.as_px(),
+        };
+        main += gap * (flow_count - 1) as f32;
+    }
+
+    let (content_w, content_h) = match style.direction {
+        Direction::Row => (main, cross),
+        Direction::Column => (cross, main),
+    };
+    let w = match style.width {
+        Length::Px(px) if sized => px,
+        _ => content_w + style.padding.left.as_px() + style.padding.right.as_px(),
+    };
+    let h = match style.height {
+        Length::Px(px) if sized => px,
+        _ => content_h + style.padding.top.as_px() + style.padding.bottom.as_px(),
+    };
+    (
+        w + style.margin.left.as_px() + style.margin.right.as_px(),
+        h + style.margin.top.as_px() + style.margin.bottom.as_px(),
+    )
+}
+
 /// Internal layout function that collects portals.
+///
+/// The element's top-left corner, margin included, goes at `cursor`, and
+/// its width and height are taken from `given` where set, in place of its
+/// style, as when it grows or shrinks.
 fn layout_inner<M: TextMeasurer>(
     tree: &mut ElementTree,
     id: NodeId,
     constraints: Constraints,
-    cursor_x: f32,
-    cursor_y: f32,
+    (cursor_x, cursor_y): (f32, f32),
+    given: (Option<f32>, Option<f32>),
     measurer: &mut M,
     portals: &mut Vec<NodeId>,
 ) -> (f32, f32) {
     let node = &tree.arena[id];
-    let style = node.style().unwrap().clone();
+    let mut style = node.style().unwrap().clone();
+    if let Some(width) = given.0 {
+        style.width = Length::Px(width);
+    }
+    if let Some(height) = given.1 {
+        style.height = Length::Px(height);
+    }
     let dir = style.direction;
 
     // Get intrinsic size based on node type
@@ -86,13 +419,15 @@ fn layout_inner<M: TextMeasurer>(
                 Length::Px(px) => Some(px),
                 Length::Percent(p) => Some(p / 100.0 * constraints.max_w),
             };
-            let (w, h) = measurer.measure(content, max_w);
+            let (w, h) = measurer.measure_styled(content, max_w, &style);
             (w, h, None)
         }
         NodeKind::Texture { texture, .. } => {
             let w = texture.width() as f32;
             let h = texture.height() as f32;
-            (w, h, Some(texture.aspect_ratio()))
+            // An image shown at its own size doesn't scale with the element
+            let scales = style.object_fit != ObjectFit::None;
+            (w, h, scales.then(|| texture.aspect_ratio()))
         }
         NodeKind::Svg { svg, .. } => {
             let w = svg.width();
@@ -102,10 +437,12 @@ fn layout_inner<M: TextMeasurer>(
         _ => (0.0, 0.0, None),
     };
 
-    let margin_left = style.margin.left.as_px();
-    let margin_right = style.margin.right.as_px();
-    let margin_top = style.margin.top.as_px();
-    let margin_bottom = style.margin.bottom.as_px();
+    // Percentages of margins are of the width of the element's container,
+    // across as well as along
+    let margin_left = resolve_length(&style.margin.left, constraints.max_w);
+    let margin_right = resolve_length(&style.margin.right, constraints.max_w);
+    let margin_top = resolve_length(&style.margin.top, constraints.max_w);
+    let margin_bottom = resolve_length(&style.margin.bottom, constraints.max_w);
 
     let padding_left = style.padding.left.as_px();
     let padding_right = style.padding.right.as_px();
@@ -151,16 +488,33 @@ fn layout_inner<M: TextMeasurer>(
     let mut flow_children: Vec<NodeId> = Vec::new();
     let mut child_sizes: Vec<(f32, f32)> = Vec::new();
 
-    let mut child_cursor_x = content_x;
-    let mut child_cursor_y = content_y;
-
     // Resolve gap for main axis
     let main_gap_value = match dir {
         Direction::Row => resolve_length(&style.gap_x, w - padding_left - padding_right),
         Direction::Column => resolve_length(&style.gap_y, h - padding_top - padding_bottom),
     };
 
+    // Padding wider than the element leaves no room for children, rather
+    // than negative room
+    let child_constraints = Constraints {
+        max_w: (w - padding_left - padding_right).max(0.0),
+        max_h: (h - padding_top - padding_bottom).max(0.0),
+    };
+
+    // Children of wide rows and columns are laid out on other threads first,
+    // each as if it were the first, to be moved into place below
+    #[cfg(feature = "parallel")]
+    let mut laid_apart =
+        lay_out_apart(tree, &children, child_constraints, measurer).map(Vec::into_iter);
+
     // First pass: layout children sequentially (at Start alignment positions)
+    // and at their own sizes, noting where each went and which portals were
+    // found in it
+    let portal_count = portals.len();
+    let mut child_origins: Vec<(f32, f32)> = Vec::new();
+    let mut child_portals: Vec<(Option<usize>, Range<usize>)> = Vec::new();
+    let mut child_cursor_x = content_x;
+    let mut child_cursor_y = content_y;
     for child in &children {
         let child_style = tree.arena[*child].style().unwrap();
         match child_style.position {
@@ -169,17 +523,13 @@ fn layout_inner<M: TextMeasurer>(
                 continue;
             }
             Position::Portal => {
+                child_portals.push((None, portals.len()..portals.len() + 1));
                 portals.push(*child);
                 continue;
             }
             Position::Relative => {}
         }
 
-        let child_constraints = Constraints {
-            max_w: w - padding_left - padding_right,
-            max_h: h - padding_top - padding_bottom,
-        };
-
         // Add gap before this child (except for the first one)
         if !flow_children.is_empty() {
             match dir {
@@ -188,16 +538,29 @@ fn layout_inner<M: TextMeasurer>(
             }
         }
 
-        let (cw, ch) = layout_inner(
-            tree,
-            *child,
-            child_constraints,
-            child_cursor_x,
-            child_cursor_y,
-            measurer,
-            portals,
-        );
-
+        let first_portal = portals.len();
+        #[cfg(feature = "parallel")]
+        let apart = laid_apart.as_mut().and_then(Iterator::next);
+        #[cfg(not(feature = "parallel"))]
+        let apart: Option<LaidApart> = None;
+        let (cw, ch) = match apart {
+            Some((size, found)) => {
+                offset_subtree(tree, *child, child_cursor_x, child_cursor_y);
+                portals.extend(found);
+                size
+            }
+            None => layout_inner(
+                tree,
+                *child,
+                child_constraints,
+                (child_cursor_x, child_cursor_y),
+                (None, None),
+                measurer,
+                portals,
+            ),
+        };
+        child_portals.push((Some(flow_children.len()), first_portal..portals.len()));
+        child_origins.push((child_cursor_x, child_cursor_y));
         flow_children.push(*child);
         child_sizes.push((cw, ch));
 
@@ -208,25 +571,112 @@ fn layout_inner<M: TextMeasurer>(
         }
     }
 
-    // Calculate totals (including gaps between children)
-    let mut main_total: f32 = 0.0;
-    let mut max_cross: f32 = 0.0;
-    for &(cw, ch) in &child_sizes {
-        match dir {
-            Direction::Row => {
-                main_total += cw;
-                max_cross = max_cross.max(ch);
+    // Children can only grow into, or shrink to fit, a main-axis size that
+    // doesn't come from them. Children that don't fit a wrapping element go
+    // on the next line rather than shrink.
+    let main_size = match dir {
+        Direction::Row => w - padding_left - padding_right,
+        Direction::Column => h - padding_top - padding_bottom,
+    };
+    let free = main_size - main_extent(&child_sizes, dir, main_gap_value);
+    let main_overrides = if main_size > 0.0 && !(style.wrap && free < 0.0) {
+        flex_sizes(tree, &flow_children, &child_sizes, dir, free)
+    } else {
+        Vec::new()
+    };
+
+    // Second pass: lay out again only the children that grow or shrink, at
+    // their new sizes, and move the rest along to make room
+    if main_overrides.iter().any(Option::is_some) {
+        let found = portals.split_off(portal_count);
+        let mut child_cursor_x = content_x;
+        let mut child_cursor_y = content_y;
+        for (index, range) in child_portals {
+            let Some(index) = index else {
+                portals.extend_from_slice(&found[range]);
+                continue;
+            };
+            if index > 0 {
+                match dir {
+                    Direction::Row => child_cursor_x += main_gap_value,
+                    Direction::Column => child_cursor_y += main_gap_value,
+                }
+            }
+            let child = flow_children[index];
+            if let Some(size) = main_overrides[index] {
+                // Laid out as if it had been given its new size, margins
+                // aside
+                let margin = &tree.arena[child].style().unwrap().margin;
+                let given = match dir {
+                    Direction::Row => {
+                        let margins = resolve_length(&margin.left, child_constraints.max_w)
+                            + resolve_length(&margin.right, child_constraints.max_w);
+                        (Some((size - margins).max(0.0)), None)
+                    }
+                    Direction::Column => {
+                        let margins = resolve_length(&margin.top, child_constraints.max_w)
+                            + resolve_length(&margin.bottom, child_constraints.max_w);
+                        (None, Some((size - margins).max(0.0)))
+                    }
+                };
+                child_sizes[index] = layout_inner(
+                    tree,
+                    child,
+                    child_constraints,
+                    (child_cursor_x, child_cursor_y),
+                    given,
+                    measurer,
+                    portals,
+                );
+            } else {
+                let (x, y) = child_origins[index];
+                let (dx, dy) = (child_cursor_x - x, child_cursor_y - y);
+                if dx != 0.0 || dy != 0.0 {
+                    offset_subtree(tree, child, dx, dy);
+                }
+                portals.extend_from_slice(&found[range]);
             }
-            Direction::Column => {
-                main_total += ch;
-                max_cross = max_cross.max(cw);
+            match dir {
+                Direction::Row => child_cursor_x += child_sizes[index].0,
+                Direction::Column => child_cursor_y += child_sizes[index].1,
             }
         }
     }
-    // Add gaps between children to main_total
-    if child_sizes.len() > 1 {
-        main_total += main_gap_value * (child_sizes.len() - 1) as f32;
-    }
+
+    // Calculate totals (including gaps between children)
+    let main_total = main_extent(&child_sizes, dir, main_gap_value);
+    let cross_of = |(cw, ch): (f32, f32)| match dir {
+        Direction::Row => ch,
+        Direction::Column => cw,
+    };
+
+    // Wrapping starts a new line (or column) where the next child would run
+    // past the end of the content box; otherwise the children share one
+    let wrap_size = match dir {
+        Direction::Row => w - padding_left - padding_right,
+        Direction::Column => h - padding_top - padding_bottom,
+    };
+    let wrap_size = if style.wrap && wrap_size > 0.0 {
+        wrap_size
+    } else {
+        f32::INFINITY
+    };
+    let lines = wrap_lines(&child_sizes, dir, main_gap_value, wrap_size);
+    let line_crosses: Vec<f32> = lines
+        .iter()
+        .map(|line| {
+            child_sizes[line.clone()]
+                .iter()
+                .map(|&size| cross_of(size))
+                .fold(0.0, f32::max)
+        })
+        .collect();
+    let cross_gap_value = match dir {
+        Direction::Row => resolve_length(&style.gap_y, h - padding_top - padding_bottom),
+        Direction::Column => resolve_length(&style.gap_x, w - padding_left - padding_right),
+    };
+    let max_cross = line_crosses.iter().sum::<f32>()
+        + cross_gap_value * line_crosses.len().saturating_sub(1) as f32;
 
     // Determine container size
     match dir {
@@ -251,68 +701,81 @@ fn layout_inner<M: TextMeasurer>(
     let content_w = w - padding_left - padding_right;
     let content_h = h - padding_top - padding_bottom;
 
-    // Calculate alignment offsets and apply to children
-    let main_size = match dir {
-        Direction::Row => content_w,
-        Direction::Column => content_h,
-    };
-    let free_space = (main_size - main_total).max(0.0);
-    let child_count = flow_children.len();
-
-    // Main-axis offset for all children (distribute gap is additional spacing from free space)
-    let (main_offset, distribute_gap) = match style.distribute {
-        Distribute::Start => (0.0, 0.0),
-        Distribute::End => (free_space, 0.0),
-        Distribute::Center => (free_space / 2.0, 0.0),
-        Distribute::Between => {
-            if child_count > 1 {
-                (0.0, free_space / (child_count - 1) as f32)
-            } else {
-                (0.0, 0.0)
-            }
-        }
-        Distribute::Around => {
-            let gap = free_space / child_count as f32;
-            (gap / 2.0, gap)
-        }
-        Distribute::Evenly => {
-            let gap = free_space / (child_count + 1) as f32;
-            (gap, gap)
-        }
+    // Calculate alignment offsets and apply to children, a line at a time
+    let (main_size, cross_size) = match dir {
+        Direction::Row => (content_w, content_h),
+        Direction::Column => (content_h, content_w),
     };
-
-    // Apply alignment offsets to each child
-    let mut accumulated_distribute_gap = 0.0;
-    for (i, &child_id) in flow_children.iter().enumerate() {
-        let (cw, ch) = child_sizes[i];
-
-        // Cross-axis alignment offset
-        let cross_offset = match dir {
-            Direction::Row => match style.align {
-                Align::Start => 0.0,
-                Align::End => content_h - ch,
-                Align::Center => (content_h - ch) / 2.0,
-            },
-            Direction::Column => match style.align {
-                Align::Start => 0.0,
-                Align::End => content_w - cw,
-                Align::Center => (content_w - cw) / 2.0,
-            },
+    let mut line_cross_start = 0.0;
+    for (line, &line_cross) in lines.iter().zip(&line_crosses) {
+        let sizes = &child_sizes[line.clone()];
+        let free_space = (main_size - main_extent(sizes, dir, main_gap_value)).max(0.0);
+        let child_count = line.len();
+
+        // Main-axis offset for all children (distribute gap is additional spacing from free space)
+        let (main_offset, distribute_gap) = match style.distribute {
+            Distribute::Start => (0.0, 0.0),
+            Distribute::End => (free_space, 0.0),
+            Distribute::Center => (free_space / 2.0, 0.0),
+            Distribute::Between => {
+                if child_count > 1 {
+                    (0.0, free_space / (child_count - 1) as f32)
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            Distribute::Around => {
+                let gap = free_space / child_count as f32;
+                (gap / 2.0, gap)
+            }
+            Distribute::Evenly => {
+                let gap = free_space / (child_count + 1) as f32;
+                (gap, gap)
+            }
         };
 
-        // Calculate delta from where child was placed to where it should be
-        // (explicit gap was already applied during positioning, distribute_gap is additional)
-        let (dx, dy) = match dir {
-            Direction::Row => (main_offset + accumulated_distribute_gap, cross_offset),
-            Direction::Column => (cross_offset, main_offset + accumulated_distribute_gap),
+        // The first pass put each line on from the end of the one before,
+        // so it moves back to the start of the main axis
+        let line_start = if line.start > 0 {
+            main_extent(&child_sizes[..line.start], dir, main_gap_value) + main_gap_value
+        } else {
+            0.0
         };
+        // A single line lines its children up across the whole content box
+        let cross_space = if lines.len() > 1 {
+            line_cross
+        } else {
+            cross_size
+        };
+
+        // Apply alignment offsets to each child
+        let mut accumulated_distribute_gap = 0.0;
+        for (&child_id, &size) in flow_children[line.clone()].iter().zip(sizes) {
+            // Cross-axis alignment offset
+            let cross_offset = line_cross_start
+                + match style.align {
+                    Align::Start => 0.0,
+                    Align::End => cross_space - cross_of(size),
+                    Align::Center => (cross_space - cross_of(size)) / 2.0,
+                };
+
+            // Calculate delta from where child was placed to where it should be
+            // (explicit gap was already applied during positioning, distribute_gap is additional)
+            let main_delta = main_offset + accumulated_distribute_gap - line_start;
+            let (dx, dy) = match dir {
+                Direction::Row => (main_delta, cross_offset),
+                Direction::Column => (cross_offset, main_delta),
+            };
 
-        // Apply offset if non-zero
-        if dx != 0.0 || dy != 0.0 {
-            offset_subtree(tree, child_id, dx, dy);
+            // Apply offset if non-zero
+            if dx != 0.0 || dy != 0.0 {
+                offset_subtree(tree, child_id, dx, dy);
+            }
+
+            accumulated_distribute_gap += distribute_gap;
         }
 
-        accumulated_distribute_gap += distribute_gap;
+        line_cross_start += line_cross + cross_gap_value;
     }
 
     let final_w = w + margin_left + margin_right;
@@ -324,12 +787,21 @@ fn layout_inner<M: TextMeasurer>(
     let parent_w = w;
     let parent_h = h;
 
+    debug_assert!(
+        parent_w.is_finite() && parent_h.is_finite(),
+        "layout produced a non-finite size ({} x {}) for {}",
+        parent_w,
+        parent_h,
+        tree.describe(id)
+    );
+
     tree.arena[id].layout = Layout {
         x: parent_x,
         y: parent_y,
         width: parent_w,
         height: parent_h,
     };
+    tree.arena[id].dirty = false;
 
     // Second pass: layout absolute children relative to parent
     for child in absolute_children {
@@ -338,24 +810,209 @@ fn layout_inner<M: TextMeasurer>(
             child,
             parent_x + padding_left,
             parent_y + padding_top,
-            parent_w - padding_left - padding_right,
-            parent_h - padding_top - padding_bottom,
+            (parent_w - padding_left - padding_right).max(0.0),
+            (parent_h - padding_top - padding_bottom).max(0.0),
             measurer,
             portals,
         );
     }
 
+    // Visual translation moves the laid-out subtree without affecting siblings
+    if style.translate_x != 0.0 || style.translate_y != 0.0 {
+        offset_subtree(tree, id, style.translate_x, style.translate_y);
+    }
+
     (final_w, final_h)
 }
 
-/// Layout a portal element relative to the viewport.
+/// The size of a child laid out on another thread, with the portals found
+/// in it.
+type LaidApart = ((f32, f32), Vec<NodeId>);
+
+/// Flow children a row or column needs for them to be laid out in parallel;
+/// fewer aren't worth sending to other threads.
+#[cfg(feature = "parallel")]
+const PARALLEL_CHILDREN: usize = 16;
+
+/// Lay out the flow children of `children` in parallel, each at the top
+/// left of the content box within `constraints`, if there are enough of
+/// them and `measurer` can be used on other threads. Returns the size of
+/// each child with the portals found in it, in order.
+#[cfg(feature = "parallel")]
+fn lay_out_apart<M: TextMeasurer>(
+    tree: &mut ElementTree,
+    children: &[NodeId],
+    constraints: Constraints,
+    measurer: &M,
+) -> Option<Vec<LaidApart>> {
+    use rayon::prelude::*;
+
+    let flow: Vec<NodeId> = children
+        .iter()
+        .copied()
+        .filter(|&child| tree.arena[child].style().unwrap().position == Position::Relative)
+        .collect();
+    if flow.len() < PARALLEL_CHILDREN {
+        return None;
+    }
+    // A measurer for each child before copying any of them, so a measurer
+    // that can't be forked costs nothing
+    let measurers: Vec<_> = flow
+        .iter()
+        .map(|_| measurer.fork())
+        .collect::<Option<_>>()?;
+    let (subtrees, originals): (Vec<_>, Vec<_>) =
+        flow.iter().map(|&child| tree.detach(child)).unzip();
+
+    // Each copy is laid out into a tree of its own on its thread, and sent
+    // back as the layout of each of its nodes and the indices of its portals
+    let laid: Vec<_> = subtrees
+        .into_par_iter()
+        .zip(measurers)
+        .map(|(subtree, mut measurer)| {
+            let (mut copy, ids) = subtree.attach();
+            let root = copy.root;
+            let mut portals = Vec::new();
+            let size = layout_inner(
+                &mut copy,
+                root,
+                constraints,
+                (0.0, 0.0),
+                (None, None),
+                &mut measurer,
+                &mut portals,
+            );
+            let layouts: Vec<Layout> = ids.iter().map(|&id| copy.arena[id].layout).collect();
+            let portals: Vec<usize> = portals
+                .iter()
+                .map(|portal| ids.iter().position(|id| id == portal).unwrap())
+                .collect();
+            (size, layouts, portals)
+        })
+        .collect();
+
+    let results = originals
+        .into_iter()
+        .zip(laid)
+        .map(|(originals, (size, layouts, portals))| {
+            for (&id, layout) in originals.iter().zip(layouts) {
+                let node = &mut tree.arena[id];
+                node.layout = layout;
+                node.dirty = false;
+            }
+            let portals = portals.into_iter().map(|index| originals[index]).collect();
+            (size, portals)
+        })
+        .collect();
+    Some(results)
+}
+
+/// How far children of `sizes` reach along `dir`, with `gap` between each.
+fn main_extent(sizes: &[(f32, f32)], dir: Direction, gap: f32) -> f32 {
+    let total: f32 = sizes
+        .iter()
+        .map(|&(w, h)| match dir {
+            Direction::Row => w,
+            Direction::Column => h,
+        })
+        .sum();
+    total + gap * sizes.len().saturating_sub(1) as f32
+}
+
+/// Split children of `sizes` into lines no longer than `limit` along `dir`,
+/// with `gap` between each child. A child longer than `limit` gets a line
+/// of its own.
+fn wrap_lines(sizes: &[(f32, f32)], dir: Direction, gap: f32, limit: f32) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut extent = 0.0;
+    for (i, &(w, h)) in sizes.iter().enumerate() {
+        let main = match dir {
+            Direction::Row => w,
+            Direction::Column => h,
+        };
+        // Leeway for percentages that add up to the whole line
+        if i > start && extent + gap + main > limit + 0.01 {
+            lines.push(start..i);
+            start = i;
+            extent = main;
+        } else if i > start {
+            extent += gap + main;
+        } else {
+            extent = main;
+        }
+    }
+    lines.push(start..sizes.len());
+    lines
+}
+
+/// The main-axis sizes `children`, laid out at `sizes`, take when `free`
+/// pixels are shared out among the ones that grow or, when `free` is
+/// negative, taken from the ones that shrink. `None` for the children that
+/// keep their size.
+fn flex_sizes(
+    tree: &ElementTree,
+    children: &[NodeId],
+    sizes: &[(f32, f32)],
+    dir: Direction,
+    free: f32,
+) -> Vec<Option<f32>> {
+    let main = |(w, h): (f32, f32)| match dir {
+        Direction::Row => w,
+        Direction::Column => h,
+    };
+    let styles: Vec<&Style> = children
+        .iter()
+        .map(|&child| tree.arena[child].style().unwrap())
+        .collect();
+
+    if free > 0.0 {
+        let total: f32 = styles.iter().map(|style| style.grow).sum();
+        styles
+            .iter()
+            .zip(sizes)
+            .map(|(style, &size)| {
+                (style.grow > 0.0).then(|| main(size) + free * style.grow / total)
+            })
+            .collect()
+    } else if free < 0.0 {
+        // Bigger children give up more, so the small ones aren't squeezed
+        // to nothing first
+        let total: f32 = styles
+            .iter()
+            .zip(sizes)
+            .map(|(style, &size)| style.shrink * main(size))
+            .sum();
+        children
+            .iter()
+            .zip(&styles)
+            .zip(sizes)
+            .map(|((&child, style), &size)| {
+                (style.shrink > 0.0 && total > 0.0).then(|| {
+                    let share = style.shrink * main(size) / total;
+                    (main(size) + free * share).max(main(min_size_sized(tree, child, false)))
+                })
+            })
+            .collect()
+    } else {
+        vec![None; children.len()]
+    }
+}
+
+/// Layout a portal element relative to the viewport, collecting popovers
+/// to be placed once the rest of the tree is laid out.
 fn layout_portal<M: TextMeasurer>(
     tree: &mut ElementTree,
     id: NodeId,
     viewport_w: f32,
     viewport_h: f32,
     measurer: &mut M,
+    popovers: &mut Vec<NodeId>,
 ) {
+    if tree.arena[id].style().is_some_and(|s| s.anchor.is_some()) {
+        popovers.push(id);
+    }
+
     // Portals are laid out exactly like absolute elements, but relative to viewport
     let mut nested_portals = Vec::new();
     layout_positioned(
@@ -371,10 +1028,56 @@ fn layout_portal<M: TextMeasurer>(
 
     // Layout any nested portals (they also use viewport coordinates)
     for nested_id in nested_portals {
-        layout_portal(tree, nested_id, viewport_w, viewport_h, measurer);
+        layout_portal(tree, nested_id, viewport_w, viewport_h, measurer, popovers);
     }
 }
 
+/// Move a laid-out popover next to its anchor, on the side it asks for if
+/// it fits there, otherwise on the opposite side if that fits, and keep it
+/// inside the viewport.
+fn place_popover(tree: &mut ElementTree, id: NodeId, viewport_w: f32, viewport_h: f32) {
+    let style = tree.arena[id].style().unwrap();
+    let Some(anchor) = &style.anchor else {
+        return;
+    };
+    let (translate_x, translate_y) = (style.translate_x, style.translate_y);
+    let Some(anchor_id) = tree.find_key(&anchor.key) else {
+        return;
+    };
+    let target = tree.arena[anchor_id].layout;
+    let Layout {
+        x,
+        y,
+        width,
+        height,
+    } = tree.arena[id].layout;
+
+    let fits = |placement: Placement| match placement {
+        Placement::Top => target.y - height >= 0.0,
+        Placement::Bottom => target.y + target.height + height <= viewport_h,
+        Placement::Left => target.x - width >= 0.0,
+        Placement::Right => target.x + target.width + width <= viewport_w,
+    };
+    let placement = if fits(anchor.placement) || !fits(anchor.placement.flipped()) {
+        anchor.placement
+    } else {
+        anchor.placement.flipped()
+    };
+
+    let (new_x, new_y) = match placement {
+        Placement::Top => (target.x, target.y - height),
+        Placement::Bottom => (target.x, target.y + target.height),
+        Placement::Left => (target.x - width, target.y),
+        Placement::Right => (target.x + target.width, target.y),
+    };
+    let new_x = new_x.min(viewport_w - width).max(0.0);
+    let new_y = new_y.min(viewport_h - height).max(0.0);
+
+    // Keep the popover's translation, so enter and exit transitions still
+    // slide it
+    offset_subtree(tree, id, new_x + translate_x - x, new_y + translate_y - y);
+}
+
 /// Layout an absolutely positioned element within its parent's content box.
 fn layout_absolute<M: TextMeasurer>(
     tree: &mut ElementTree,
@@ -413,13 +1116,15 @@ fn layout_positioned<M: TextMeasurer>(
                 Length::Px(px) => Some(px),
                 Length::Percent(p) => Some(p / 100.0 * parent_w),
             };
-            let (w, h) = measurer.measure(content, max_w);
+            let (w, h) = measurer.measure_styled(content, max_w, &style);
             (w, h, None)
         }
         NodeKind::Texture { texture, .. } => {
             let w = texture.width() as f32;
             let h = texture.height() as f32;
-            (w, h, Some(texture.aspect_ratio()))
+            // An image shown at its own size doesn't scale with the element
+            let scales = style.object_fit != ObjectFit::None;
+            (w, h, scales.then(|| texture.aspect_ratio()))
         }
         NodeKind::Svg { svg, .. } => {
             let w = svg.width();
@@ -466,7 +1171,7 @@ fn layout_positioned<M: TextMeasurer>(
         if let (Some(left), Some(right)) = (&style.left, &style.right) {
             let left_px = resolve_length(left, parent_w);
             let right_px = resolve_length(right, parent_w);
-            w = parent_w - left_px - right_px;
+            w = (parent_w - left_px - right_px).max(0.0);
         }
     }
 
@@ -475,7 +1180,7 @@ fn layout_positioned<M: TextMeasurer>(
         if let (Some(top), Some(bottom)) = (&style.top, &style.bottom) {
             let top_px = resolve_length(top, parent_h);
             let bottom_px = resolve_length(bottom, parent_h);
-            h = parent_h - top_px - bottom_px;
+            h = (parent_h - top_px - bottom_px).max(0.0);
         }
     }
 
@@ -497,12 +1202,21 @@ fn layout_positioned<M: TextMeasurer>(
         parent_y // Default to parent's top edge
     };
 
+    debug_assert!(
+        w.is_finite() && h.is_finite(),
+        "layout produced a non-finite size ({} x {}) for positioned {}",
+        w,
+        h,
+        tree.describe(id)
+    );
+
     tree.arena[id].layout = Layout {
         x,
         y,
         width: w,
         height: h,
     };
+    tree.arena[id].dirty = false;
 
     // Layout children of this absolute element
     let padding_left = style.padding.left.as_px();
@@ -531,16 +1245,16 @@ fn layout_positioned<M: TextMeasurer>(
         }
 
         let child_constraints = Constraints {
-            max_w: w - padding_left - padding_right,
-            max_h: h - padding_top - padding_bottom,
+            max_w: (w - padding_left - padding_right).max(0.0),
+            max_h: (h - padding_top - padding_bottom).max(0.0),
         };
 
         let (cw, ch) = layout_inner(
             tree,
             *child,
             child_constraints,
-            child_cursor_x,
-            child_cursor_y,
+            (child_cursor_x, child_cursor_y),
+            (None, None),
             measurer,
             portals,
         );
@@ -558,12 +1272,61 @@ fn layout_positioned<M: TextMeasurer>(
             child,
             x + padding_left,
             y + padding_top,
-            w - padding_left - padding_right,
-            h - padding_top - padding_bottom,
+            (w - padding_left - padding_right).max(0.0),
+            (h - padding_top - padding_bottom).max(0.0),
             measurer,
             portals,
         );
     }
+
+    // Visual translation moves the laid-out subtree without affecting siblings
+    if style.translate_x != 0.0 || style.translate_y != 0.0 {
+        offset_subtree(tree, id, style.translate_x, style.translate_y);
+    }
+}
+
+/// Round every laid-out element's edges to whole physical pixels, of which
+/// there are `scale_factor` to each logical pixel, so edges and thin
+/// borders cover pixels exactly instead of blurring across two. Edges are
+/// rounded rather than sizes, so elements that touch keep touching.
+pub fn snap_to_pixels(tree: &mut ElementTree, scale_factor: f32) {
+    for (_, node) in tree.arena.iter_mut() {
+        snap_node(node, scale_factor);
+    }
+}
+
+/// Like `snap_to_pixels`, for the element at `id` and the ones inside it,
+/// such as after `relayout_dirty` lays them out again.
+pub fn snap_subtree_to_pixels(tree: &mut ElementTree, id: NodeId, scale_factor: f32) {
+    snap_node(&mut tree.arena[id], scale_factor);
+    let children: Vec<NodeId> = tree.children(id).collect();
+    for child in children {
+        snap_subtree_to_pixels(tree, child, scale_factor);
+    }
+}
+
+fn snap_node(node: &mut Node, scale_factor: f32) {
+    let layout = node.layout;
+    let (x, y) = (layout.x * scale_factor, layout.y * scale_factor);
+    let (width, height) = (layout.width * scale_factor, layout.height * scale_factor);
+    let (left, top) = (x.round(), y.round());
+    // Text is wrapped at its element's width when painted, so its right
+    // edge is rounded up from the snapped left edge, never down. In f64, so
+    // neither the sum nor dividing the width back into logical pixels can
+    // come out short of the width it was measured with
+    let width = if matches!(node.kind, NodeKind::Text { .. }) {
+        let scale_factor = scale_factor as f64;
+        let right = (left as f64 + layout.width as f64 * scale_factor).ceil();
+        ((right - left as f64) / scale_factor) as f32
+    } else {
+        ((x + width).round() - left) / scale_factor
+    };
+    node.layout = Layout {
+        x: left / scale_factor,
+        y: top / scale_factor,
+        width,
+        height: ((y + height).round() - top) / scale_factor,
+    };
 }
 
 /// Resolve a Length to pixels given a parent dimension.
@@ -1,22 +1,34 @@
+pub mod animation;
 pub mod builder;
 pub mod color;
 pub mod element;
 pub mod elements;
 pub mod events;
+mod hit_index;
 pub mod layout;
 pub mod style;
 mod svg_data;
 pub mod texture;
+pub mod transition;
 
+pub use animation::{Easing, Interpolate, Keyframes, PlaybackDirection, Repeat, Spring};
 pub use builder::ElementBuilder;
 pub use color::Color;
 pub use element::{ElementTree, Node, NodeId, NodeKind};
-pub use elements::{div, img, portal, svg, text};
-pub use events::{Event, EventHandler, EventResult, Key, MouseButton, NamedKey};
-pub use layout::{layout, Constraints, Layout, NoOpMeasurer, TextMeasurer};
+pub use elements::{div, img, input, popover, portal, svg, text, InputState};
+pub use events::{
+    ClickInfo, Event, EventHandler, EventResult, Key, LayoutHandler, Modifiers, MouseButton,
+    NamedKey, VisibilityHandler,
+};
+pub use hit_index::HitIndex;
+pub use layout::{
+    layout, layout_tree, min_size, relayout_dirty, snap_subtree_to_pixels, snap_to_pixels,
+    Constraints, FixedMeasurer, Layout, NoOpMeasurer, TextMeasurer,
+};
 pub use style::{
-    pc, px, Align, Border, BorderEdge, BorderRadius, Direction, Distribute, EdgeSizes, Length,
-    Position, Style,
+    pc, px, Align, Anchor, Border, BorderEdge, BorderRadius, CursorIcon, Direction, Distribute,
+    EdgeSizes, Length, ObjectFit, Overflow, Placement, Position, Style, TextAlign,
 };
 pub use svg_data::Svg;
 pub use texture::Texture;
+pub use transition::{Transition, TransitionEffect, TransitionTracker};
@@ -1,6 +1,7 @@
 use crate::color::Color;
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BorderEdge {
     pub width: f32,
     pub color: Color,
@@ -12,7 +13,8 @@ impl BorderEdge {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Border {
     pub top: BorderEdge,
     pub right: BorderEdge,
@@ -32,7 +34,8 @@ impl Border {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BorderRadius {
     pub top_left: f32,
     pub top_right: f32,
@@ -87,7 +90,8 @@ impl BorderRadius {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Length {
     Percent(f32),
     Px(f32),
@@ -120,6 +124,7 @@ impl Default for Length {
 }
 
 #[derive(Clone, Debug, PartialEq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Column,
     Row,
@@ -127,6 +132,7 @@ pub enum Direction {
 
 /// Cross-axis alignment for children (CSS: align-items).
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Align {
     #[default]
     Start,
@@ -136,6 +142,7 @@ pub enum Align {
 
 /// Main-axis distribution of children (CSS: justify-content).
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Distribute {
     #[default]
     Start,
@@ -150,6 +157,7 @@ pub enum Distribute {
 }
 
 #[derive(Clone, Debug, PartialEq, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Position {
     #[default]
     Relative,
@@ -158,7 +166,108 @@ pub enum Position {
     Portal,
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+/// The side of its anchor a popover is placed on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Placement {
+    Top,
+    #[default]
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Placement {
+    /// The opposite side, tried when the popover doesn't fit on this one.
+    pub fn flipped(self) -> Placement {
+        match self {
+            Placement::Top => Placement::Bottom,
+            Placement::Bottom => Placement::Top,
+            Placement::Left => Placement::Right,
+            Placement::Right => Placement::Left,
+        }
+    }
+}
+
+/// Where a popover is placed: next to the element with `key`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Anchor {
+    pub key: String,
+    pub placement: Placement,
+}
+
+/// Where the lines of a text element go across its width.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextAlign {
+    /// The side the text's direction starts on: the left for English, the
+    /// right for Arabic or Hebrew.
+    #[default]
+    Start,
+    Left,
+    Center,
+    Right,
+}
+
+/// How an image is sized to the element showing it. Images that don't
+/// cover the element are centered in it, and the parts of images that
+/// overflow it are cut off.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ObjectFit {
+    /// Stretch the image to the element's width and height.
+    #[default]
+    Fill,
+    /// Scale the image to fit inside the element, keeping its shape.
+    Contain,
+    /// Scale the image to cover the element, keeping its shape.
+    Cover,
+    /// Show the image at its own size.
+    None,
+}
+
+/// Whether the children of an element are drawn where they go outside it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Overflow {
+    #[default]
+    Visible,
+    /// Cut the children off at the inside of the element's border,
+    /// following its rounded corners.
+    Hidden,
+}
+
+/// Mouse cursor shown while hovering an element.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CursorIcon {
+    #[default]
+    Default,
+    /// A pointing hand, for links and other clickable elements.
+    Pointer,
+    /// Arrows pointing left and right, for dividers dragged sideways.
+    ColResize,
+    /// Arrows pointing up and down, for dividers dragged up and down.
+    RowResize,
+    /// An I-beam, for text that can be edited or selected.
+    Text,
+    /// An open hand, for things that can be picked up and dragged.
+    Grab,
+    /// A closed hand, while something is being dragged.
+    Grabbing,
+    /// Arrows pointing every way, for things that can be moved around.
+    Move,
+    /// Arrows pointing up-left and down-right, for resizing from a corner.
+    NwseResize,
+    /// Arrows pointing up-right and down-left, for resizing from a corner.
+    NeswResize,
+    /// A slashed circle, for things that can't be used right now.
+    NotAllowed,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EdgeSizes {
     pub top: Length,
     pub right: Length,
@@ -186,7 +295,9 @@ impl EdgeSizes {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Style {
     pub margin: EdgeSizes,
     pub padding: EdgeSizes,
@@ -195,10 +306,19 @@ pub struct Style {
 
     pub border: Border,
     pub radius: BorderRadius,
+    pub overflow: Overflow,
 
     pub width: Length,
     pub height: Length,
     pub aspect_ratio: Option<f32>,
+    pub object_fit: ObjectFit,
+    /// How much of its parent's leftover main-axis space the element takes,
+    /// relative to its siblings. Zero keeps it at its own size.
+    pub grow: f32,
+    /// How much the element gives up when its siblings overflow its parent
+    /// along the main axis, relative to them and weighted by its size. Zero
+    /// keeps it at its own size.
+    pub shrink: f32,
 
     pub direction: Direction,
     pub align: Align,
@@ -209,14 +329,118 @@ pub struct Style {
     pub gap_y: Length,
 
     pub font_size: Option<f32>,
+    /// Font family to draw text in, tried before the configured fonts.
+    pub font_family: Option<String>,
+    /// Weight of text from 1 to 1000, such as 400 for regular or 700 for
+    /// bold, or `None` for regular.
+    pub font_weight: Option<u16>,
+    pub italic: bool,
+    pub text_align: TextAlign,
+    /// Height of each line of text, as a multiple of the font size, or
+    /// `None` for the default of 1.2.
+    pub line_height: Option<f32>,
+    /// Most lines of text to show, cutting the last one short with an
+    /// ellipsis if there is more.
+    pub max_lines: Option<usize>,
+    /// Keep text on one line rather than wrapping it at the element's width.
+    pub no_wrap: bool,
+    pub underline: bool,
+    /// Draw text in the monospace font instead of the configured ones.
+    pub monospace: bool,
+    /// Byte offset in a text element's content where a blinking caret is
+    /// drawn.
+    pub caret: Option<usize>,
+    /// Byte range of a text element's content drawn as selected.
+    pub selection: Option<(usize, usize)>,
 
     pub position: Position,
     pub top: Option<Length>,
     pub right: Option<Length>,
     pub bottom: Option<Length>,
     pub left: Option<Length>,
+    /// Stacking order of a portal: higher layers are drawn over lower ones
+    /// and get events first. Portals in the same layer stack in tree order,
+    /// and a portal inside another one is never stacked below it.
+    pub layer: i32,
+    /// Places a portal next to another element instead of by its offsets.
+    pub anchor: Option<Anchor>,
+    /// While a modal portal is the topmost one open, nothing beneath it
+    /// gets mouse or key events.
+    pub modal: bool,
 
     pub opacity: f32,
+
+    /// Visual offset applied after layout; does not affect siblings.
+    pub translate_x: f32,
+    pub translate_y: f32,
+    /// Visual scale around the element's center; does not affect layout.
+    pub scale: f32,
+
+    /// Disabled elements are greyed out and ignored by hit testing.
+    pub disabled: bool,
+
+    /// Whether the mouse can hit the element and its children. Off for
+    /// decorations, so the mouse reaches whatever is underneath.
+    pub pointer_events: bool,
+
+    /// Whether Tab stops at the element, or `None` to stop at it if it has
+    /// event handlers.
+    pub focusable: Option<bool>,
+
+    pub cursor: CursorIcon,
+}
+
+/// Opacity multiplier applied to disabled elements.
+pub const DISABLED_OPACITY: f32 = 0.4;
+
+impl Style {
+    /// The opacity to paint with, after applying the disabled dimming.
+    pub fn paint_opacity(&self) -> f32 {
+        if self.disabled {
+            self.opacity * DISABLED_OPACITY
+        } else {
+            self.opacity
+        }
+    }
+
+    /// Whether an element styled with `other` is laid out the same as one
+    /// styled with this, so switching between them only needs a repaint.
+    /// Colors, borders, opacity and scale are paint-only; translation moves
+    /// the element's layout, so it counts.
+    pub fn same_layout(&self, other: &Style) -> bool {
+        self.margin == other.margin
+            && self.padding == other.padding
+            && self.width == other.width
+            && self.height == other.height
+            && self.aspect_ratio == other.aspect_ratio
+            && self.object_fit == other.object_fit
+            && self.grow == other.grow
+            && self.shrink == other.shrink
+            && self.direction == other.direction
+            && self.align == other.align
+            && self.distribute == other.distribute
+            && self.wrap == other.wrap
+            && self.reverse == other.reverse
+            && self.gap_x == other.gap_x
+            && self.gap_y == other.gap_y
+            && self.font_size == other.font_size
+            && self.font_family == other.font_family
+            && self.font_weight == other.font_weight
+            && self.italic == other.italic
+            && self.text_align == other.text_align
+            && self.line_height == other.line_height
+            && self.max_lines == other.max_lines
+            && self.no_wrap == other.no_wrap
+            && self.monospace == other.monospace
+            && self.position == other.position
+            && self.top == other.top
+            && self.right == other.right
+            && self.bottom == other.bottom
+            && self.left == other.left
+            && self.anchor == other.anchor
+            && self.translate_x == other.translate_x
+            && self.translate_y == other.translate_y
+    }
 }
 
 impl Default for Style {
@@ -227,6 +451,9 @@ impl Default for Style {
             width: Length::Auto,
             height: Length::Auto,
             aspect_ratio: None,
+            object_fit: ObjectFit::Fill,
+            grow: 0.0,
+            shrink: 0.0,
             direction: Direction::Column,
             align: Align::default(),
             distribute: Distribute::default(),
@@ -234,17 +461,39 @@ impl Default for Style {
             text_color: Color::BLACK,
             border: Border::default(),
             radius: BorderRadius::default(),
+            overflow: Overflow::Visible,
             wrap: false,
             reverse: false,
             gap_x: Length::Px(0.0),
             gap_y: Length::Px(0.0),
             font_size: None,
+            font_family: None,
+            font_weight: None,
+            italic: false,
+            text_align: TextAlign::Start,
+            line_height: None,
+            max_lines: None,
+            no_wrap: false,
+            underline: false,
+            monospace: false,
+            caret: None,
+            selection: None,
             position: Position::default(),
             top: None,
             right: None,
             bottom: None,
             left: None,
+            layer: 0,
+            anchor: None,
+            modal: false,
             opacity: 1.0,
+            translate_x: 0.0,
+            translate_y: 0.0,
+            scale: 1.0,
+            disabled: false,
+            pointer_events: true,
+            focusable: None,
+            cursor: CursorIcon::Default,
         }
     }
 }
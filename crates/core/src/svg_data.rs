@@ -4,7 +4,8 @@
 /// They participate in layout like normal elements - if no size is specified,
 /// they use their natural dimensions; if one dimension is specified, aspect
 /// ratio is preserved; if both are specified, the SVG scales to fit.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Svg {
     data: String,
     width: f32,
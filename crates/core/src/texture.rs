@@ -1,16 +1,31 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
 /// A texture holding RGBA pixel data.
 ///
 /// Textures can be displayed using the `img()` element helper.
 /// They participate in layout like normal elements - if no size is specified,
 /// they use their natural dimensions; if one dimension is specified, aspect
 /// ratio is preserved; if both are specified, the texture stretches to fit.
+///
+/// Clones share the pixel data rather than copying it, so a texture can be
+/// kept in the model and shown with `img()` in every view cheaply.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Texture {
-    data: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "next_id"))]
+    id: u64,
+    data: Arc<Vec<u8>>,
     width: u32,
     height: u32,
 }
 
+/// A new texture id, different from all the others.
+fn next_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 impl Texture {
     /// Create a texture from raw RGBA pixel data.
     ///
@@ -32,12 +47,19 @@ impl Texture {
             data.len()
         );
         Self {
-            data,
+            id: next_id(),
+            data: Arc::new(data),
             width,
             height,
         }
     }
 
+    /// An id shared by this texture and its clones, and by no other
+    /// texture, for the renderer to keep what it uploads to the GPU under.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
     /// Get the width of the texture in pixels.
     pub fn width(&self) -> u32 {
         self.width
@@ -57,4 +79,19 @@ impl Texture {
     pub fn data(&self) -> &[u8] {
         &self.data
     }
+
+    /// The pixel data as shared between this texture and its clones, to
+    /// hand to the GPU without copying it.
+    pub fn shared_data(&self) -> &Arc<Vec<u8>> {
+        &self.data
+    }
+}
+
+impl PartialEq for Texture {
+    /// Textures are equal when their pixels are, which clones of the same
+    /// texture are without comparing them.
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            || (self.width, self.height, &self.data) == (other.width, other.height, &other.data)
+    }
 }
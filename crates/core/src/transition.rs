@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::animation::Easing;
+use crate::builder::ElementBuilder;
+use crate::style::Style;
+
+/// Visual effect played when an element appears or disappears.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransitionEffect {
+    /// Fade in from (or out to) fully transparent.
+    Fade,
+    /// Slide in from (or out to) an offset in pixels.
+    Slide { dx: f32, dy: f32 },
+    /// Scale up from (or down to) the given factor.
+    Scale(f32),
+}
+
+/// An appear or disappear transition attached to an element.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transition {
+    pub effect: TransitionEffect,
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+/// Default duration for the transition presets.
+const DEFAULT_TRANSITION_DURATION: Duration = Duration::from_millis(200);
+
+impl Transition {
+    pub fn new(effect: TransitionEffect) -> Self {
+        Self {
+            effect,
+            duration: DEFAULT_TRANSITION_DURATION,
+            easing: Easing::EaseOut,
+        }
+    }
+
+    pub fn fade() -> Self {
+        Self::new(TransitionEffect::Fade)
+    }
+
+    pub fn slide(dx: f32, dy: f32) -> Self {
+        Self::new(TransitionEffect::Slide { dx, dy })
+    }
+
+    pub fn scale(from: f32) -> Self {
+        Self::new(TransitionEffect::Scale(from))
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Linear progress in `0.0..=1.0` since `start`.
+    fn progress(&self, start: Instant, now: Instant) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        let elapsed = now.saturating_duration_since(start);
+        (elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    /// Adjust a style for an element that is `visible` (0.0 = gone,
+    /// 1.0 = fully shown) of the way through the transition.
+    fn apply(&self, style: &mut Style, visible: f32) {
+        match self.effect {
+            TransitionEffect::Fade => style.opacity *= visible,
+            TransitionEffect::Slide { dx, dy } => {
+                style.translate_x += dx * (1.0 - visible);
+                style.translate_y += dy * (1.0 - visible);
+            }
+            TransitionEffect::Scale(from) => style.scale *= from + (1.0 - from) * visible,
+        }
+    }
+}
+
+/// A keyed element in the current view that has transitions attached.
+struct Tracked {
+    /// Child indices from the root to the element.
+    path: Vec<usize>,
+    /// The element itself, kept so it can be shown while it exits.
+    exit: Option<(Transition, ElementBuilder)>,
+}
+
+struct Entering {
+    start: Instant,
+    transition: Transition,
+}
+
+struct Exiting {
+    key: String,
+    element: ElementBuilder,
+    path: Vec<usize>,
+    start: Instant,
+    transition: Transition,
+}
+
+/// Plays enter/exit transitions across view rebuilds.
+///
+/// Elements are matched between rebuilds by their `key`. When a keyed element
+/// with an enter transition shows up, it animates in; when one with an exit
+/// transition disappears, the tracker keeps a copy of it in the tree (without
+/// event handlers) until its transition finishes.
+#[derive(Default)]
+pub struct TransitionTracker {
+    previous: HashMap<String, Tracked>,
+    entering: HashMap<String, Entering>,
+    exiting: Vec<Exiting>,
+}
+
+impl TransitionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply running transitions to a freshly built view.
+    ///
+    /// Call this every time the view is rebuilt. Returns `true` while any
+    /// transition is still running, in which case the caller should rebuild
+    /// and call this again on the next frame.
+    pub fn apply(&mut self, root: &mut ElementBuilder, now: Instant) -> bool {
+        let mut current = HashMap::new();
+        collect_tracked(root, &mut Vec::new(), &mut current);
+
+        // Elements that came back while exiting enter again from where the
+        // exit left off, so toggling quickly doesn't jump.
+        let mut revived = HashMap::new();
+        self.exiting.retain(|exiting| {
+            if !current.contains_key(&exiting.key) {
+                return true;
+            }
+            let remaining = 1.0 - exiting.transition.progress(exiting.start, now);
+            revived.insert(exiting.key.clone(), remaining);
+            false
+        });
+
+        let previous = &self.previous;
+        let entering = &mut self.entering;
+        root.visit_keyed_mut(&mut |key, element| {
+            let Some(transition) = element.enter else {
+                return;
+            };
+            let is_new = !previous.contains_key(key) || revived.contains_key(key);
+            if !is_new || entering.contains_key(key) {
+                return;
+            }
+            let start = match revived.get(key) {
+                Some(remaining) => now - transition.duration.mul_f32(*remaining),
+                None => now,
+            };
+            entering.insert(key.to_string(), Entering { start, transition });
+        });
+
+        // Elements that disappeared start exiting, unless an ancestor is
+        // already exiting and takes them along.
+        let removed: Vec<(String, Tracked)> = self
+            .previous
+            .drain()
+            .filter(|(key, tracked)| !current.contains_key(key) && tracked.exit.is_some())
+            .collect();
+        for (key, tracked) in &removed {
+            let ancestor_exiting = removed.iter().any(|(_, other)| {
+                other.path.len() < tracked.path.len() && tracked.path.starts_with(&other.path)
+            });
+            if ancestor_exiting {
+                continue;
+            }
+            let (transition, element) = tracked.exit.clone().unwrap();
+            self.exiting.push(Exiting {
+                key: key.clone(),
+                element,
+                path: tracked.path.clone(),
+                start: now,
+                transition,
+            });
+        }
+
+        self.entering.retain(|key, entering| {
+            current.contains_key(key) && entering.transition.progress(entering.start, now) < 1.0
+        });
+        self.exiting
+            .retain(|exiting| exiting.transition.progress(exiting.start, now) < 1.0);
+
+        // Apply enter effects before reinserting exiting elements, so the
+        // copies being removed aren't animated in again.
+        let entering = &self.entering;
+        root.visit_keyed_mut(&mut |key, element| {
+            if let Some(entering) = entering.get(key) {
+                let t = entering.transition.progress(entering.start, now);
+                let visible = entering.transition.easing.apply(t);
+                entering.transition.apply(&mut element.style, visible);
+            }
+        });
+
+        self.exiting.sort_by(|a, b| a.path.cmp(&b.path));
+        for exiting in &self.exiting {
+            let Some((&index, parent_path)) = exiting.path.split_last() else {
+                continue;
+            };
+            let Some(parent) = root.descendant_mut(parent_path) else {
+                continue;
+            };
+            let t = exiting.transition.progress(exiting.start, now);
+            let visible = 1.0 - exiting.transition.easing.apply(t);
+            let mut element = exiting.element.clone();
+            element.clear_handlers();
+            exiting.transition.apply(&mut element.style, visible);
+            let index = index.min(parent.children.len());
+            parent.children.insert(index, element);
+        }
+
+        self.previous = current;
+        !self.entering.is_empty() || !self.exiting.is_empty()
+    }
+}
+
+/// Record every keyed element with transitions under `element`.
+fn collect_tracked(
+    element: &ElementBuilder,
+    path: &mut Vec<usize>,
+    out: &mut HashMap<String, Tracked>,
+) {
+    if let Some(key) = &element.key {
+        if element.enter.is_some() || element.exit.is_some() {
+            let exit = element.exit.map(|transition| (transition, element.clone()));
+            out.insert(
+                key.to_string(),
+                Tracked {
+                    path: path.clone(),
+                    exit,
+                },
+            );
+        }
+    }
+    for (index, child) in element.children.iter().enumerate() {
+        path.push(index);
+        collect_tracked(child, path, out);
+        path.pop();
+    }
+}
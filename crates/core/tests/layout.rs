@@ -0,0 +1,655 @@
+//! Invariants of the layout engine, checked on randomly generated trees.
+
+use proptest::prelude::*;
+use vitae_core::{
+    div, layout, pc, px, relayout_dirty, snap_to_pixels, text, Align, Constraints, Distribute,
+    ElementBuilder, ElementTree, EventResult, FixedMeasurer, HitIndex, Layout, Length,
+    NoOpMeasurer, NodeId, Position,
+};
+
+const VIEWPORT_W: f32 = 800.0;
+const VIEWPORT_H: f32 = 600.0;
+
+/// Leeway for float rounding when comparing positions.
+const EPSILON: f32 = 0.01;
+
+/// A container to build, with the parts of its style that affect layout.
+#[derive(Clone, Debug)]
+struct Spec {
+    row: bool,
+    width: Length,
+    height: Length,
+    padding: f32,
+    gap: f32,
+    align: Align,
+    distribute: Distribute,
+    aspect_ratio: Option<f32>,
+    children: Vec<Spec>,
+}
+
+impl Spec {
+    fn build(&self) -> ElementBuilder {
+        let element = if self.row { div().row() } else { div().col() };
+        let element = element
+            .w(self.width)
+            .h(self.height)
+            .p(px(self.padding))
+            .gap(px(self.gap))
+            .align(self.align)
+            .distribute(self.distribute)
+            .children(self.children.iter().map(Spec::build));
+        match self.aspect_ratio {
+            Some(ratio) => element.aspect_ratio(ratio),
+            None => element,
+        }
+    }
+}
+
+fn length() -> impl Strategy<Value = Length> {
+    prop_oneof![
+        Just(Length::Auto),
+        (0.0f32..400.0).prop_map(Length::Px),
+        (0.0f32..100.0).prop_map(Length::Percent),
+    ]
+}
+
+fn align() -> impl Strategy<Value = Align> {
+    prop_oneof![Just(Align::Start), Just(Align::Center), Just(Align::End)]
+}
+
+fn distribute() -> impl Strategy<Value = Distribute> {
+    prop_oneof![
+        Just(Distribute::Start),
+        Just(Distribute::End),
+        Just(Distribute::Center),
+        Just(Distribute::Between),
+        Just(Distribute::Around),
+        Just(Distribute::Evenly),
+    ]
+}
+
+/// A container with no children, styled any way.
+fn container() -> impl Strategy<Value = Spec> {
+    (
+        any::<bool>(),
+        length(),
+        length(),
+        0.0f32..40.0,
+        0.0f32..20.0,
+        align(),
+        distribute(),
+        prop::option::of(0.25f32..4.0),
+    )
+        .prop_map(
+            |(row, width, height, padding, gap, align, distribute, aspect_ratio)| Spec {
+                row,
+                width,
+                height,
+                padding,
+                gap,
+                align,
+                distribute,
+                aspect_ratio,
+                children: Vec::new(),
+            },
+        )
+}
+
+/// Any tree of containers, styled any way.
+fn any_tree() -> impl Strategy<Value = Spec> {
+    container().prop_recursive(4, 48, 6, |inner| {
+        (container(), prop::collection::vec(inner, 0..6))
+            .prop_map(|(spec, children)| Spec { children, ..spec })
+    })
+}
+
+/// A tree of containers aligned to the start, each sized in pixels big
+/// enough for its children, or left to fit them.
+fn fitting_tree() -> impl Strategy<Value = Spec> {
+    let leaf = (any::<bool>(), 0.0f32..100.0, 0.0f32..100.0).prop_map(|(row, w, h)| Spec {
+        row,
+        width: Length::Px(w),
+        height: Length::Px(h),
+        padding: 0.0,
+        gap: 0.0,
+        align: Align::Start,
+        distribute: Distribute::Start,
+        aspect_ratio: None,
+        children: Vec::new(),
+    });
+    leaf.prop_recursive(4, 48, 6, |inner| {
+        (
+            any::<bool>(),
+            0.0f32..20.0,
+            0.0f32..10.0,
+            prop::option::of((0.0f32..50.0, 0.0f32..50.0)),
+            prop::collection::vec(inner, 0..6),
+        )
+            .prop_map(|(row, padding, gap, slack, children)| {
+                let mut spec = Spec {
+                    row,
+                    width: Length::Auto,
+                    height: Length::Auto,
+                    padding,
+                    gap,
+                    align: Align::Start,
+                    distribute: Distribute::Start,
+                    aspect_ratio: None,
+                    children,
+                };
+                if let Some((slack_w, slack_h)) = slack {
+                    let (w, h) = needed_size(&spec);
+                    spec.width = Length::Px(w + slack_w);
+                    spec.height = Length::Px(h + slack_h);
+                }
+                spec
+            })
+    })
+}
+
+/// The size `spec` takes up, for a tree from `fitting_tree`.
+fn needed_size(spec: &Spec) -> (f32, f32) {
+    if let (Length::Px(w), Length::Px(h)) = (spec.width, spec.height) {
+        return (w, h);
+    }
+    let sizes: Vec<(f32, f32)> = spec.children.iter().map(needed_size).collect();
+    let gaps = spec.gap * sizes.len().saturating_sub(1) as f32;
+    let (main, cross) = if spec.row {
+        (
+            sizes.iter().map(|s| s.0).sum::<f32>() + gaps,
+            sizes.iter().map(|s| s.1).fold(0.0, f32::max),
+        )
+    } else {
+        (
+            sizes.iter().map(|s| s.1).sum::<f32>() + gaps,
+            sizes.iter().map(|s| s.0).fold(0.0, f32::max),
+        )
+    };
+    let (w, h) = if spec.row {
+        (main, cross)
+    } else {
+        (cross, main)
+    };
+    (w + 2.0 * spec.padding, h + 2.0 * spec.padding)
+}
+
+/// How an element takes mouse events, for checking hit testing.
+#[derive(Clone, Copy, Debug)]
+struct Hits {
+    handler: bool,
+    disabled: bool,
+    pointer_events: bool,
+    /// Made a portal, on this layer and modal or not.
+    portal: Option<(i32, bool)>,
+}
+
+fn hits() -> impl Strategy<Value = Hits> {
+    (
+        prop::bool::weighted(0.6),
+        prop::bool::weighted(0.1),
+        prop::bool::weighted(0.9),
+        prop::option::weighted(0.15, (0i32..3, prop::bool::weighted(0.3))),
+    )
+        .prop_map(|(handler, disabled, pointer_events, portal)| Hits {
+            handler,
+            disabled,
+            pointer_events,
+            portal,
+        })
+}
+
+/// Build `spec`, styling its elements for the mouse with `hits` in turn,
+/// parents before their children. The root is never made a portal.
+fn build_with_hits<'a>(
+    spec: &Spec,
+    hits: &mut impl Iterator<Item = &'a Hits>,
+    root: bool,
+) -> ElementBuilder {
+    let hit = *hits.next().unwrap();
+    let children: Vec<_> = spec
+        .children
+        .iter()
+        .map(|child| build_with_hits(child, hits, false))
+        .collect();
+    let childless = Spec {
+        children: Vec::new(),
+        ..spec.clone()
+    };
+    let mut element = childless
+        .build()
+        .children(children)
+        .disabled(hit.disabled)
+        .pointer_events(hit.pointer_events);
+    if hit.handler {
+        element = element.on_event_any(|_, _| EventResult::Continue);
+    }
+    match hit.portal {
+        Some((layer, modal)) if !root => {
+            element = element.position(Position::Portal).layer(layer);
+            if modal {
+                element = element.modal();
+            }
+            element
+        }
+        _ => element,
+    }
+}
+
+fn lay_out(root: ElementBuilder) -> ElementTree {
+    let mut tree = root.build();
+    let root = tree.root;
+    layout(
+        &mut tree,
+        root,
+        Constraints {
+            max_w: VIEWPORT_W,
+            max_h: VIEWPORT_H,
+        },
+        0.0,
+        0.0,
+        &mut NoOpMeasurer,
+    );
+    tree
+}
+
+/// Every element in the tree, parents before their children.
+fn nodes(tree: &ElementTree) -> Vec<NodeId> {
+    let mut nodes = vec![tree.root];
+    let mut i = 0;
+    while i < nodes.len() {
+        nodes.extend(tree.children(nodes[i]));
+        i += 1;
+    }
+    nodes
+}
+
+proptest! {
+    #[test]
+    fn sizes_are_finite_and_non_negative(spec in any_tree()) {
+        let tree = lay_out(spec.build());
+        for id in nodes(&tree) {
+            let layout = tree.get_node(id).layout;
+            prop_assert!(layout.width.is_finite() && layout.height.is_finite(), "{layout:?}");
+            prop_assert!(layout.width >= 0.0 && layout.height >= 0.0, "{layout:?}");
+        }
+    }
+
+    #[test]
+    fn snapped_edges_land_on_physical_pixels(
+        spec in any_tree(),
+        scale_factor in prop_oneof![Just(1.0f32), Just(1.25), Just(1.5), Just(2.0), Just(3.0)],
+    ) {
+        let mut tree = lay_out(spec.build());
+        snap_to_pixels(&mut tree, scale_factor);
+        let on_pixel = |edge: f32| {
+            let physical = edge * scale_factor;
+            (physical - physical.round()).abs() < 0.01
+        };
+        for id in nodes(&tree) {
+            let layout = tree.get_node(id).layout;
+            prop_assert!(on_pixel(layout.x) && on_pixel(layout.y), "{layout:?}");
+            prop_assert!(on_pixel(layout.x + layout.width), "{layout:?}");
+            prop_assert!(on_pixel(layout.y + layout.height), "{layout:?}");
+        }
+    }
+
+    #[test]
+    fn snapped_text_keeps_its_width_on_physical_pixels(
+        (x, pixels) in (0.0f32..50.0, 1u32..2000),
+        scale_factor in prop_oneof![Just(1.0f32), Just(1.25), Just(1.5), Just(2.0), Just(3.0)],
+    ) {
+        // A width just over a whole number of physical pixels, which rounds
+        // down when multiplied back out
+        let char_width = (pixels as f32 / scale_factor).next_up();
+        let mut tree = div().pl(px(x)).child(text("x")).build();
+        let root = tree.root;
+        let mut measurer = FixedMeasurer { char_width, line_height: 16.0 };
+        let constraints = Constraints { max_w: VIEWPORT_W * 4.0, max_h: VIEWPORT_H };
+        layout(&mut tree, root, constraints, 0.0, 0.0, &mut measurer);
+        snap_to_pixels(&mut tree, scale_factor);
+
+        let label = tree.children(tree.root).next().unwrap();
+        let layout = tree.get_node(label).layout;
+        let right = (layout.x + layout.width) * scale_factor;
+        prop_assert!((right - right.round()).abs() < 0.01, "{layout:?}");
+        prop_assert!(layout.width >= char_width, "{layout:?} {char_width}");
+    }
+
+    #[test]
+    fn children_stay_within_parent_content_box(spec in fitting_tree()) {
+        let tree = lay_out(spec.build());
+        for id in nodes(&tree) {
+            let parent = tree.get_node(id).layout;
+            let padding = tree.get_node(id).style().unwrap().padding.left.as_px();
+            let left = parent.x + padding - EPSILON;
+            let top = parent.y + padding - EPSILON;
+            let right = parent.x + parent.width - padding + EPSILON;
+            let bottom = parent.y + parent.height - padding + EPSILON;
+            for child in tree.children(id) {
+                let child = tree.get_node(child).layout;
+                prop_assert!(child.x >= left && child.y >= top, "{child:?} in {parent:?}");
+                prop_assert!(
+                    child.x + child.width <= right && child.y + child.height <= bottom,
+                    "{child:?} in {parent:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn percent_widths_split_the_content_box(
+        width in 100.0f32..800.0,
+        padding in 0.0f32..20.0,
+        weights in prop::collection::vec(1.0f32..10.0, 1..6),
+    ) {
+        let total: f32 = weights.iter().sum();
+        let percents: Vec<f32> = weights.iter().map(|weight| weight / total * 100.0).collect();
+        let row = div()
+            .row()
+            .w(px(width))
+            .h(px(50.0))
+            .p(px(padding))
+            .children(percents.iter().map(|&percent| div().w(pc(percent)).h(pc(100.0))));
+        let tree = lay_out(row);
+
+        let content_w = width - 2.0 * padding;
+        let mut x = padding;
+        for (child, percent) in tree.children(tree.root).zip(&percents) {
+            let layout = tree.get_node(child).layout;
+            prop_assert!((layout.width - percent / 100.0 * content_w).abs() < EPSILON);
+            prop_assert!((layout.height - (50.0 - 2.0 * padding)).abs() < EPSILON);
+            prop_assert!((layout.x - x).abs() < EPSILON);
+            x += layout.width;
+        }
+        // Percentages adding up to 100 fill the row exactly
+        prop_assert!((x - (width - padding)).abs() < EPSILON * percents.len() as f32);
+    }
+
+    #[test]
+    fn gaps_separate_children_and_size_the_container(
+        row in any::<bool>(),
+        gap in 0.0f32..30.0,
+        lengths in prop::collection::vec(0.0f32..80.0, 1..6),
+        distribute in distribute(),
+    ) {
+        let container = || if row { div().row() } else { div().col() }.gap(px(gap));
+        let children = || {
+            lengths.iter().map(|&length| {
+                if row {
+                    div().w(px(length)).h(px(10.0))
+                } else {
+                    div().w(px(10.0)).h(px(length))
+                }
+            })
+        };
+        let main = |layout: Layout| {
+            if row {
+                (layout.x, layout.width)
+            } else {
+                (layout.y, layout.height)
+            }
+        };
+
+        // Sized to fit its children and the gaps between them
+        let fitted = lay_out(div().child(container().children(children())));
+        let fitted_id = fitted.children(fitted.root).next().unwrap();
+        let total = lengths.iter().sum::<f32>() + gap * (lengths.len() - 1) as f32;
+        let (_, length) = main(fitted.get_node(fitted_id).layout);
+        prop_assert!((length - total).abs() < EPSILON * lengths.len() as f32);
+
+        // With room to spare, `distribute` only adds to the gaps
+        let spare = lay_out(
+            container()
+                .size(px(600.0))
+                .distribute(distribute)
+                .children(children()),
+        );
+        for (tree, parent) in [(&fitted, fitted_id), (&spare, spare.root)] {
+            let spans: Vec<(f32, f32)> = tree
+                .children(parent)
+                .map(|child| main(tree.get_node(child).layout))
+                .collect();
+            for pair in spans.windows(2) {
+                let ((start, length), (next, _)) = (pair[0], pair[1]);
+                prop_assert!(next - (start + length) >= gap - EPSILON, "{spans:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn growing_children_fill_the_row(
+        width in 100.0f32..800.0,
+        gap in 0.0f32..10.0,
+        children in prop::collection::vec((0.0f32..20.0, 0.0f32..4.0, 0.0f32..2.0), 1..6),
+    ) {
+        let row = div()
+            .row()
+            .w(px(width))
+            .h(px(50.0))
+            .gap(px(gap))
+            .children(
+                children
+                    .iter()
+                    .map(|&(w, grow, margin)| div().w(px(w)).grow(grow).m(pc(margin))),
+            );
+        let tree = lay_out(row);
+
+        let layouts: Vec<Layout> = tree
+            .children(tree.root)
+            .map(|child| tree.get_node(child).layout)
+            .collect();
+        let sizes: Vec<f32> = layouts.iter().map(|layout| layout.width).collect();
+        let margins: f32 = children.iter().map(|&(_, _, margin)| margin / 50.0 * width).sum();
+        let total_grow: f32 = children.iter().map(|&(_, grow, _)| grow).sum();
+        let gaps = gap * (sizes.len() - 1) as f32;
+        let natural = children.iter().map(|&(w, _, _)| w).sum::<f32>() + margins + gaps;
+        let used = sizes.iter().sum::<f32>() + margins + gaps;
+        // Children only grow into space that's left over
+        let grows = total_grow > 0.0 && natural < width;
+        if grows {
+            prop_assert!((used - width).abs() < EPSILON * sizes.len() as f32, "{sizes:?}");
+        }
+        // Children that don't grow keep their own width
+        for (&size, &(w, grow, _)) in sizes.iter().zip(&children) {
+            if grow == 0.0 || !grows {
+                prop_assert!((size - w).abs() < EPSILON);
+            } else {
+                prop_assert!(size >= w - EPSILON);
+            }
+        }
+        // and make room for the ones that do
+        for pair in layouts.windows(2) {
+            prop_assert!(pair[1].x >= pair[0].x + pair[0].width + gap - EPSILON, "{pair:?}");
+        }
+    }
+
+    #[test]
+    fn shrinking_children_fit_the_row(
+        width in 100.0f32..400.0,
+        widths in prop::collection::vec(50.0f32..200.0, 2..6),
+    ) {
+        let row = div()
+            .row()
+            .w(px(width))
+            .h(px(50.0))
+            .children(widths.iter().map(|&w| div().w(px(w)).shrink(1.0)));
+        let tree = lay_out(row);
+
+        let sizes: Vec<f32> = tree
+            .children(tree.root)
+            .map(|child| tree.get_node(child).layout.width)
+            .collect();
+        let total: f32 = widths.iter().sum();
+        let used: f32 = sizes.iter().sum();
+        prop_assert!((used - total.min(width)).abs() < EPSILON * sizes.len() as f32, "{sizes:?}");
+        for (&size, &w) in sizes.iter().zip(&widths) {
+            prop_assert!(size <= w + EPSILON && size >= 0.0);
+        }
+    }
+
+    #[test]
+    fn wrapped_children_fit_the_row_without_overlapping(
+        width in 100.0f32..400.0,
+        gap in 0.0f32..10.0,
+        sizes in prop::collection::vec((10.0f32..100.0, 10.0f32..60.0), 1..20),
+        align in align(),
+        distribute in distribute(),
+    ) {
+        let row = div()
+            .row()
+            .wrap()
+            .w(px(width))
+            .gap(px(gap))
+            .align(align)
+            .distribute(distribute)
+            .children(sizes.iter().map(|&(w, h)| div().w(px(w)).h(px(h))));
+        let tree = lay_out(row);
+
+        let root = tree.get_node(tree.root).layout;
+        let layouts: Vec<_> = tree
+            .children(tree.root)
+            .map(|child| tree.get_node(child).layout)
+            .collect();
+        for (i, a) in layouts.iter().enumerate() {
+            prop_assert!(a.x >= -EPSILON && a.x + a.width <= width + EPSILON, "{a:?}");
+            prop_assert!(a.y + a.height <= root.height + EPSILON, "{a:?} in {root:?}");
+            for b in &layouts[i + 1..] {
+                let apart = a.x + a.width <= b.x + EPSILON
+                    || b.x + b.width <= a.x + EPSILON
+                    || a.y + a.height <= b.y + EPSILON
+                    || b.y + b.height <= a.y + EPSILON;
+                prop_assert!(apart, "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn text_keeps_to_its_line_limit(
+        words in prop::collection::vec("[a-z]{1,12}", 1..40),
+        width in 40.0f32..400.0,
+        max_lines in 1usize..5,
+        no_wrap in any::<bool>(),
+    ) {
+        let mut label = text(words.join(" ")).max_lines(max_lines);
+        if no_wrap {
+            label = label.no_wrap();
+        }
+        let mut tree = div().col().w(px(width)).child(label).build();
+        let root = tree.root;
+        let mut measurer = FixedMeasurer::default();
+        let constraints = Constraints { max_w: VIEWPORT_W, max_h: VIEWPORT_H };
+        layout(&mut tree, root, constraints, 0.0, 0.0, &mut measurer);
+
+        let child = tree.children(tree.root).next().unwrap();
+        let layout = tree.get_node(child).layout;
+        let lines = if no_wrap { 1 } else { max_lines };
+        prop_assert!(layout.height <= lines as f32 * measurer.line_height + EPSILON, "{layout:?}");
+    }
+
+    #[test]
+    fn aspect_ratio_is_honored(
+        size in 1.0f32..400.0,
+        ratio in 0.25f32..4.0,
+        by_width in any::<bool>(),
+    ) {
+        let element = if by_width { div().w(px(size)) } else { div().h(px(size)) };
+        let tree = lay_out(div().col().child(element.aspect_ratio(ratio)));
+        let child = tree.children(tree.root).next().unwrap();
+        let layout = tree.get_node(child).layout;
+        prop_assert!((layout.width / layout.height - ratio).abs() < 0.001, "{layout:?}");
+    }
+
+    #[test]
+    fn relaying_out_a_fixed_size_element_matches_a_full_layout(
+        outer in any_tree(),
+        (width, height) in (1.0f32..300.0, 1.0f32..300.0),
+        (before, after) in (any_tree(), any_tree()),
+    ) {
+        let view = |inside: &Spec| {
+            let card = div().w(px(width)).h(px(height)).child(inside.build());
+            outer.build().child(card)
+        };
+        let old = lay_out(view(&before));
+        let mut tree = view(&after).build();
+        prop_assert!(tree.reuse_layout(&old));
+        relayout_dirty(&mut tree, &mut NoOpMeasurer);
+
+        let full = lay_out(view(&after));
+        for (id, full_id) in nodes(&tree).into_iter().zip(nodes(&full)) {
+            let (layout, expected) = (tree.get_node(id).layout, full.get_node(full_id).layout);
+            prop_assert!(!tree.get_node(id).dirty);
+            prop_assert!((layout.x - expected.x).abs() < EPSILON, "{layout:?} {expected:?}");
+            prop_assert!((layout.y - expected.y).abs() < EPSILON, "{layout:?} {expected:?}");
+            prop_assert!((layout.width - expected.width).abs() < EPSILON, "{layout:?}");
+            prop_assert!((layout.height - expected.height).abs() < EPSILON, "{layout:?}");
+        }
+    }
+
+    #[test]
+    fn hit_index_matches_hit_testing_the_tree(
+        spec in any_tree(),
+        hits in prop::collection::vec(hits(), 1..64),
+        points in prop::collection::vec((-50.0f32..850.0, -50.0f32..650.0), 0..64),
+    ) {
+        let tree = lay_out(build_with_hits(&spec, &mut hits.iter().cycle(), true));
+        let index = HitIndex::new(&tree);
+
+        // Random points, the corners of the grid's squares, and the corners
+        // of every element
+        let grid = (0..14).flat_map(|i| (0..11).map(move |j| (i as f32 * 64.0, j as f32 * 64.0)));
+        let corners = nodes(&tree).into_iter().flat_map(|id| {
+            let layout = tree.get_node(id).layout;
+            let (right, bottom) = (layout.x + layout.width, layout.y + layout.height);
+            [(layout.x, layout.y), (right, layout.y), (layout.x, bottom), (right, bottom)]
+        });
+        for (x, y) in points.into_iter().chain(grid).chain(corners) {
+            prop_assert_eq!(index.hit_test(x, y), tree.hit_test(x, y), "at ({}, {})", x, y);
+        }
+    }
+}
+
+/// `FixedMeasurer` kept to the thread it's on, as it can't be forked.
+#[cfg(feature = "parallel")]
+struct OneThread(vitae_core::FixedMeasurer);
+
+#[cfg(feature = "parallel")]
+impl vitae_core::TextMeasurer for OneThread {
+    fn measure(&mut self, text: &str, max_width: Option<f32>) -> (f32, f32) {
+        self.0.measure(text, max_width)
+    }
+}
+
+#[cfg(feature = "parallel")]
+proptest! {
+    #[test]
+    fn parallel_layout_matches_one_thread(
+        cards in prop::collection::vec((any_tree(), "[a-z ]{0,40}"), 16..40),
+        wrap in any::<bool>(),
+    ) {
+        use vitae_core::{text, FixedMeasurer};
+
+        let view = || {
+            let grid = div().row().w(px(VIEWPORT_W)).children(
+                cards.iter().map(|(spec, words)| spec.build().child(text(words.clone()))),
+            );
+            if wrap { grid.wrap() } else { grid }
+        };
+        let constraints = Constraints { max_w: VIEWPORT_W, max_h: VIEWPORT_H };
+        let mut parallel = view().build();
+        let root = parallel.root;
+        layout(&mut parallel, root, constraints, 0.0, 0.0, &mut FixedMeasurer::default());
+        let mut one_thread = view().build();
+        let root = one_thread.root;
+        let mut measurer = OneThread(FixedMeasurer::default());
+        layout(&mut one_thread, root, constraints, 0.0, 0.0, &mut measurer);
+
+        for (id, other_id) in nodes(&parallel).into_iter().zip(nodes(&one_thread)) {
+            let layout = parallel.get_node(id).layout;
+            let expected = one_thread.get_node(other_id).layout;
+            prop_assert!((layout.x - expected.x).abs() < EPSILON, "{layout:?} {expected:?}");
+            prop_assert!((layout.y - expected.y).abs() < EPSILON, "{layout:?} {expected:?}");
+            prop_assert!((layout.width - expected.width).abs() < EPSILON, "{layout:?}");
+            prop_assert!((layout.height - expected.height).abs() < EPSILON, "{layout:?}");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use vitae::core::{Key, NamedKey};
 use vitae::prelude::*;
 
 #[derive(Clone)]
@@ -5,7 +6,10 @@ struct Model {
     counter: i32,
     selected_tab: usize,
     items: Vec<String>,
+    new_item: InputState,
     toggle_states: Vec<bool>,
+    selected_row: Option<usize>,
+    dock: DockLayout,
 }
 
 impl Model {
@@ -20,7 +24,16 @@ impl Model {
                 "Date".to_string(),
                 "Elderberry".to_string(),
             ],
+            new_item: InputState::default(),
             toggle_states: vec![false, true, false],
+            selected_row: None,
+            dock: {
+                let mut dock = DockLayout::new();
+                dock.dock("explorer", DockSide::Left);
+                dock.dock("outline", DockSide::Left);
+                dock.dock("problems", DockSide::Bottom);
+                dock
+            },
         }
     }
 }
@@ -50,7 +63,7 @@ fn header() -> ElementBuilder {
         .align(Align::Center)
         .distribute(Distribute::Between)
         .p(MD)
-        .child(text("Vitae Kitchen Sink").font_size(24.0).bg(WHITE))
+        .child(text("Vitae Kitchen Sink").font_size(24.0).bold().bg(WHITE))
         .child(
             div()
                 .row()
@@ -61,7 +74,7 @@ fn header() -> ElementBuilder {
         )
 }
 
-fn nav_button(label: &str) -> ElementBuilder {
+fn nav_button(label: &'static str) -> ElementBuilder {
     div()
         .bg(Color::from_hex("#34495e"))
         .p(SM)
@@ -69,7 +82,17 @@ fn nav_button(label: &str) -> ElementBuilder {
 }
 
 fn sidebar(model: &Model) -> ElementBuilder {
-    let tabs = ["Layout", "Colors", "Alignment", "Borders", "Interactive"];
+    let tabs = [
+        "Layout",
+        "Colors",
+        "Alignment",
+        "Borders",
+        "Interactive",
+        "Charts",
+        "Code",
+        "Data Grid",
+        "Dock",
+    ];
 
     div()
         .w(px(200.0))
@@ -89,6 +112,7 @@ fn sidebar(model: &Model) -> ElementBuilder {
                 })
                 .p(SM)
                 .child(text(*label).bg(if selected { WHITE } else { BLACK }))
+                .cursor(CursorIcon::Pointer)
                 .on_left_click(move |m: &mut Model| {
                     m.selected_tab = i;
                 })
@@ -102,6 +126,10 @@ fn main_content(model: &Model) -> ElementBuilder {
         2 => alignment_demo(),
         3 => borders_demo(),
         4 => interactive_demo(model),
+        5 => charts_demo(),
+        6 => code_demo(),
+        7 => data_grid_demo(model),
+        8 => dock_demo(model),
         _ => div(),
     };
 
@@ -482,7 +510,7 @@ fn alignment_demo() -> ElementBuilder {
         )
 }
 
-fn alignment_box(label: &str, align: Align) -> ElementBuilder {
+fn alignment_box(label: &'static str, align: Align) -> ElementBuilder {
     div()
         .w(px(120.0))
         .h(px(100.0))
@@ -494,7 +522,7 @@ fn alignment_box(label: &str, align: Align) -> ElementBuilder {
         .child(small_box("#e74c3c"))
 }
 
-fn distribute_box(label: &str, distribute: Distribute) -> ElementBuilder {
+fn distribute_box(label: &'static str, distribute: Distribute) -> ElementBuilder {
     div()
         .w(FULL)
         .h(px(50.0))
@@ -720,6 +748,18 @@ fn borders_demo() -> ElementBuilder {
                                 .radius(8.0)
                                 .center()
                                 .child(text("Button").bg(WHITE)),
+                        )
+                        .child(
+                            // The header band is cut off at the rounded corners
+                            div()
+                                .size(px(80.0))
+                                .bg(Color::from_hex("#ecf0f1"))
+                                .border(2.0, Color::from_hex("#7f8c8d"))
+                                .radius(16.0)
+                                .clip()
+                                .col()
+                                .child(div().w(FULL).h(px(28.0)).bg(Color::from_hex("#9b59b6")))
+                                .child(text("clip")),
                         ),
                 ),
         )
@@ -730,7 +770,8 @@ fn borders_demo() -> ElementBuilder {
 // ============================================================================
 
 fn interactive_demo(model: &Model) -> ElementBuilder {
-    let hover_state = use_signal(|| None::<usize>);
+    // Only built while its tab is open, so found by key rather than by order
+    let hover_state = use_keyed_signal("hover-list", || None::<usize>);
 
     div()
         .size(FULL)
@@ -754,6 +795,7 @@ fn interactive_demo(model: &Model) -> ElementBuilder {
                                 .bg(Color::from_hex("#e74c3c"))
                                 .p(MD)
                                 .child(text("-").bg(WHITE))
+                                .cursor(CursorIcon::Pointer)
                                 .on_left_click(|m: &mut Model| m.counter -= 1),
                         )
                         .child(
@@ -769,6 +811,7 @@ fn interactive_demo(model: &Model) -> ElementBuilder {
                                 .bg(Color::from_hex("#2ecc71"))
                                 .p(MD)
                                 .child(text("+").bg(WHITE))
+                                .cursor(CursorIcon::Pointer)
                                 .on_left_click(|m: &mut Model| m.counter += 1),
                         ),
                 ),
@@ -795,21 +838,45 @@ fn interactive_demo(model: &Model) -> ElementBuilder {
                                 })
                                 .center()
                                 .child(text(if on { "ON" } else { "OFF" }).bg(WHITE))
+                                .cursor(CursorIcon::Pointer)
                                 .on_left_click(move |m: &mut Model| {
                                     m.toggle_states[i] = !m.toggle_states[i];
                                 })
                         })),
                 ),
         )
+        // Text input
+        .child(
+            div()
+                .w(FULL)
+                .col()
+                .gap(SM)
+                .child(text("Text Input (Enter adds to the list)"))
+                .child(
+                    input(&model.new_item, |m: &mut Model, item| m.new_item = item)
+                        .w(px(300.0))
+                        .on_key(|m: &mut Model, key| {
+                            let item = m.new_item.value.trim();
+                            if *key == Key::Named(NamedKey::Enter) && !item.is_empty() {
+                                m.items.push(item.to_string());
+                                m.new_item = InputState {
+                                    focused: m.new_item.focused,
+                                    ..InputState::default()
+                                };
+                            }
+                        }),
+                ),
+        )
         // List with selection
         .child(
             div()
                 .w(FULL)
                 .col()
                 .gap(SM)
-                .child(text("List with Hover (use_signal)"))
+                .child(text("List with Hover (use_keyed_signal)"))
                 .child(
                     div()
+                        .key("hover-list")
                         .w(px(200.0))
                         .bg(Color::from_hex("#ecf0f1"))
                         .col()
@@ -828,6 +895,12 @@ fn interactive_demo(model: &Model) -> ElementBuilder {
                                 } else {
                                     BLACK
                                 }))
+                                .on_mouse_enter(move |_: &mut Model| hover_state.set(Some(i)))
+                                .on_mouse_leave(move |_: &mut Model| {
+                                    if hover_state.get() == Some(i) {
+                                        hover_state.set(None);
+                                    }
+                                })
                         })),
                 ),
         )
@@ -865,11 +938,157 @@ fn interactive_demo(model: &Model) -> ElementBuilder {
         )
 }
 
+// ============================================================================
+// Charts Demo
+// ============================================================================
+
+fn charts_demo() -> ElementBuilder {
+    const MONTHS: [&str; 6] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun"];
+    let chart_box = |title: &'static str, chart: Chart| {
+        div()
+            .w(FULL)
+            .col()
+            .gap(SM)
+            .child(text(title))
+            .child(div().w(FULL).h(px(220.0)).child(chart))
+    };
+
+    div()
+        .size(FULL)
+        .col()
+        .gap(MD)
+        .child(section_title("Charts"))
+        .child(chart_box(
+            "Line (hover a point)",
+            chart(
+                ChartKind::Line,
+                [
+                    Series::new("Revenue", [12.0, 19.0, 15.0, 24.0, 22.0, 30.0]),
+                    Series::new("Costs", [10.0, 12.0, 14.0, 13.0, 18.0, 17.0]),
+                ],
+            )
+            .key("line-chart")
+            .labels(MONTHS),
+        ))
+        .child(chart_box(
+            "Bar",
+            chart(
+                ChartKind::Bar,
+                [
+                    Series::new("Sold", [5.0, 8.0, 3.0, 9.0, 6.0, 7.0]),
+                    Series::new("Returned", [1.0, 0.0, 2.0, 1.0, 1.0, 3.0]),
+                ],
+            )
+            .key("bar-chart")
+            .labels(MONTHS),
+        ))
+        .child(chart_box(
+            "Pie",
+            chart(
+                ChartKind::Pie,
+                [Series::new("Browsers", [64.0, 20.0, 9.0, 7.0])],
+            )
+            .key("pie-chart")
+            .labels(["Chrome", "Safari", "Firefox", "Other"]),
+        ))
+}
+
+// ============================================================================
+// Code Demo
+// ============================================================================
+
+fn code_demo() -> ElementBuilder {
+    div()
+        .size(FULL)
+        .col()
+        .gap(MD)
+        .child(section_title("Code (scroll to see more)"))
+        .child(
+            div()
+                .w(FULL)
+                .h(px(480.0))
+                .child(code_view(include_str!("main.rs"), "rust").key("source")),
+        )
+}
+
+// ============================================================================
+// Data Grid Demo
+// ============================================================================
+
+fn data_grid_demo(model: &Model) -> ElementBuilder {
+    const CITIES: [&str; 5] = ["Amsterdam", "Berlin", "Lisbon", "Oslo", "Vienna"];
+    let selected = match model.selected_row {
+        Some(row) => format!("Selected order #{}", row + 1),
+        None => "Click a row to select it".to_string(),
+    };
+
+    div()
+        .size(FULL)
+        .col()
+        .gap(MD)
+        .child(section_title("Data Grid (10,000 rows)"))
+        .child(text(selected))
+        .child(
+            div().w(FULL).h(px(480.0)).child(
+                data_grid(
+                    [
+                        Column::new("Order").width(100.0),
+                        Column::new("City"),
+                        Column::new("Items").width(100.0),
+                        Column::new("Total").width(120.0),
+                    ],
+                    (0..10_000).map(|i| {
+                        [
+                            format!("{}", i + 1),
+                            CITIES[i * 7 % CITIES.len()].to_string(),
+                            format!("{}", i * 13 % 9 + 1),
+                            format!("{:.2}", (i * 7919 % 10_000) as f32 / 100.0),
+                        ]
+                    }),
+                )
+                .key("orders")
+                .on_select(|m: &mut Model, row| m.selected_row = Some(row)),
+            ),
+        )
+}
+
+// ============================================================================
+// Dock Demo
+// ============================================================================
+
+fn dock_demo(model: &Model) -> ElementBuilder {
+    let pane = |hex: &str, label: &'static str| {
+        div()
+            .size(FULL)
+            .bg(Color::from_hex(hex))
+            .center()
+            .child(text(label).color(WHITE))
+    };
+
+    div()
+        .size(FULL)
+        .col()
+        .gap(MD)
+        .child(section_title(
+            "Dock (drag a tab to a side, or away to float it)",
+        ))
+        .child(
+            div().w(FULL).h(px(480.0)).child(
+                dock(&model.dock, pane("#2c3e50", "Editor"))
+                    .key("workbench")
+                    .panel("explorer", "Explorer", pane("#34495e", "Files"))
+                    .panel("outline", "Outline", pane("#34495e", "Symbols"))
+                    .panel("problems", "Problems", pane("#3d3d3d", "No problems"))
+                    .on_change(|m: &mut Model, layout| m.dock = layout),
+            ),
+        )
+}
+
 // ============================================================================
 // Helper Components
 // ============================================================================
 
-fn section_title(title: &str) -> ElementBuilder {
+fn section_title(title: &'static str) -> ElementBuilder {
     div()
         .w(FULL)
         .p(SM)
@@ -877,7 +1096,7 @@ fn section_title(title: &str) -> ElementBuilder {
         .child(text(title).font_size(20.0).bg(WHITE))
 }
 
-fn colored_box(hex: &str, label: &str) -> ElementBuilder {
+fn colored_box(hex: &str, label: &'static str) -> ElementBuilder {
     div()
         .size(px(30.0))
         .bg(Color::from_hex(hex))
@@ -889,7 +1108,7 @@ fn small_box(hex: &str) -> ElementBuilder {
     div().size(px(30.0)).bg(Color::from_hex(hex))
 }
 
-fn color_swatch(color: Color, name: &str, dark_text: bool) -> ElementBuilder {
+fn color_swatch(color: Color, name: &'static str, dark_text: bool) -> ElementBuilder {
     div().w(px(70.0)).h(px(50.0)).bg(color).center().child(
         text(name)
             .font_size(10.0)
@@ -897,7 +1116,7 @@ fn color_swatch(color: Color, name: &str, dark_text: bool) -> ElementBuilder {
     )
 }
 
-fn hex_swatch(hex: &str, name: &str) -> ElementBuilder {
+fn hex_swatch(hex: &'static str, name: &'static str) -> ElementBuilder {
     div()
         .w(px(80.0))
         .h(px(60.0))
@@ -921,7 +1140,7 @@ fn rgb_swatch(r: u8, g: u8, b: u8) -> ElementBuilder {
         )
 }
 
-fn main() {
-    let app = App::new(Model::new(), view);
-    app.run();
+fn main() -> Result<(), vitae::Error> {
+    let app = App::new(Model::new(), view)?;
+    app.run()
 }
@@ -0,0 +1,149 @@
+//! Non-destructive edits: kept as a description of what to do to a photo,
+//! and applied to its texture whenever it is shown.
+
+use image::{imageops, RgbaImage};
+use serde::{Deserialize, Serialize};
+use vitae::prelude::Texture;
+
+/// The smallest crop, as a fraction of either side of the photo.
+const MIN_CROP: f32 = 0.05;
+
+/// Rotation, then flip, then crop.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Edit {
+    /// Quarter turns clockwise, from 0 to 3
+    #[serde(default)]
+    pub rotation: u8,
+    /// Mirror left to right, after rotating
+    #[serde(default)]
+    pub flip: bool,
+    #[serde(default)]
+    pub crop: Option<Crop>,
+}
+
+/// The part of a rotated and flipped photo to keep, in fractions of its
+/// width and height.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Crop {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// The part of the crop rectangle being dragged.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CropHandle {
+    /// The whole rectangle
+    Move,
+    /// One corner, moving the two edges that meet there
+    Corner { left: bool, top: bool },
+}
+
+impl Crop {
+    pub const FULL: Crop = Crop {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    /// The same crop after turning the photo a quarter clockwise.
+    pub fn rotated(self) -> Crop {
+        Crop {
+            x: 1.0 - self.y - self.height,
+            y: self.x,
+            width: self.height,
+            height: self.width,
+        }
+    }
+
+    /// The same crop after mirroring the photo left to right.
+    pub fn flipped(self) -> Crop {
+        Crop {
+            x: 1.0 - self.x - self.width,
+            ..self
+        }
+    }
+
+    /// Drag `handle` by `dx` and `dy`, in fractions of the photo's size,
+    /// keeping the crop inside the photo.
+    pub fn drag(&mut self, handle: CropHandle, dx: f32, dy: f32) {
+        match handle {
+            CropHandle::Move => {
+                self.x = (self.x + dx).clamp(0.0, 1.0 - self.width);
+                self.y = (self.y + dy).clamp(0.0, 1.0 - self.height);
+            }
+            CropHandle::Corner { left, top } => {
+                let right = self.x + self.width;
+                let bottom = self.y + self.height;
+                if left {
+                    self.x = (self.x + dx).clamp(0.0, right - MIN_CROP);
+                    self.width = right - self.x;
+                } else {
+                    self.width = (self.width + dx).clamp(MIN_CROP, 1.0 - self.x);
+                }
+                if top {
+                    self.y = (self.y + dy).clamp(0.0, bottom - MIN_CROP);
+                    self.height = bottom - self.y;
+                } else {
+                    self.height = (self.height + dy).clamp(MIN_CROP, 1.0 - self.y);
+                }
+            }
+        }
+    }
+}
+
+impl Edit {
+    /// Turn the edited photo a quarter clockwise.
+    pub fn rotate(&mut self) {
+        // Turning a mirrored photo clockwise is the same as mirroring it
+        // after turning it counterclockwise
+        self.rotation = (self.rotation + if self.flip { 3 } else { 1 }) % 4;
+        self.crop = self.crop.map(Crop::rotated);
+    }
+
+    /// Mirror the edited photo left to right.
+    pub fn flip(&mut self) {
+        self.flip = !self.flip;
+        self.crop = self.crop.map(Crop::flipped);
+    }
+
+    /// The edit with its crop left out, to show the photo while cropping.
+    pub fn uncropped(self) -> Edit {
+        Edit { crop: None, ..self }
+    }
+
+    /// `texture` with the edit applied.
+    pub fn apply(&self, texture: &Texture) -> Texture {
+        if *self == Edit::default() {
+            return texture.clone();
+        }
+        let Some(mut image) =
+            RgbaImage::from_raw(texture.width(), texture.height(), texture.data().to_vec())
+        else {
+            return texture.clone();
+        };
+
+        image = match self.rotation % 4 {
+            1 => imageops::rotate90(&image),
+            2 => imageops::rotate180(&image),
+            3 => imageops::rotate270(&image),
+            _ => image,
+        };
+        if self.flip {
+            imageops::flip_horizontal_in_place(&mut image);
+        }
+        if let Some(crop) = self.crop {
+            let (width, height) = image.dimensions();
+            let x = ((crop.x * width as f32) as u32).min(width - 1);
+            let y = ((crop.y * height as f32) as u32).min(height - 1);
+            let crop_width = ((crop.width * width as f32) as u32).clamp(1, width - x);
+            let crop_height = ((crop.height * height as f32) as u32).clamp(1, height - y);
+            image = imageops::crop_imm(&image, x, y, crop_width, crop_height).to_image();
+        }
+
+        let (width, height) = image.dimensions();
+        Texture::from_rgba(image.into_raw(), width, height)
+    }
+}
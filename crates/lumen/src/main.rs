@@ -1,53 +1,761 @@
+mod edit;
+mod preview;
+mod raw;
+mod sidecar;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use edit::{Crop, CropHandle, Edit};
+use preview::Cancel;
+use sidecar::{Flag, Label, Sidecar};
+use vitae::core::{Key, NamedKey};
+use vitae::dialog;
 use vitae::prelude::*;
+use vitae::toast::{self, Toast};
 
-const FILMSTRIP_HEIGHT: Length = Length::Px(200.0);
+/// The share of the window the photo gets above the filmstrip, until the
+/// divider between them is moved.
+const PREVIEW_RATIO: f32 = 0.75;
 const THUMBNAIL_SIZE: Length = Length::Px(80.0);
 
+/// The height the photo is shown at.
+const PREVIEW_HEIGHT: f32 = 600.0;
+/// How many thumbnails the filmstrip shows around the selected photo.
+const FILMSTRIP_LENGTH: usize = 9;
+/// How far the mouse wheel or trackpad scrolls the filmstrip to move the
+/// selection one photo along, in pixels.
+const FILMSTRIP_SCROLL_STEP: f32 = 40.0;
+/// Thumbnails are decoded to at most this many pixels on either side.
+const THUMBNAIL_PIXELS: u32 = 256;
+/// File extensions of the images that can be decoded, in lowercase, besides
+/// RAW files.
+const PHOTO_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png"];
+
+/// Which photos the filmstrip shows.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    All,
+    Picked,
+    /// Photos with at least this many stars
+    Rated(u8),
+}
+
+const FILTERS: &[Filter] = &[
+    Filter::All,
+    Filter::Picked,
+    Filter::Rated(1),
+    Filter::Rated(2),
+    Filter::Rated(3),
+    Filter::Rated(4),
+    Filter::Rated(5),
+];
+
+impl Filter {
+    fn matches(self, label: Label) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Picked => label.flag == Some(Flag::Pick),
+            Filter::Rated(stars) => label.rating >= stars,
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            Filter::All => "All".to_string(),
+            Filter::Picked => "Picked".to_string(),
+            Filter::Rated(5) => stars(5),
+            Filter::Rated(stars_at_least) => format!("{}+", stars(stars_at_least)),
+        }
+    }
+}
+
+fn stars(rating: u8) -> String {
+    "\u{2605}".repeat(rating as usize)
+}
+
+#[derive(Clone)]
+struct Photo {
+    path: PathBuf,
+    /// `None` until it has been generated, or if the file couldn't be
+    /// decoded
+    thumbnail: Option<Texture>,
+    /// The thumbnail with the edit applied, if there is an edit
+    edited_thumbnail: Option<Texture>,
+    label: Label,
+    edit: Edit,
+}
+
+impl Photo {
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    fn shown_thumbnail(&self) -> Option<&Texture> {
+        self.edited_thumbnail.as_ref().or(self.thumbnail.as_ref())
+    }
+
+    fn apply_edit_to_thumbnail(&mut self) {
+        self.edited_thumbnail = match &self.thumbnail {
+            Some(thumbnail) if self.edit != Edit::default() => Some(self.edit.apply(thumbnail)),
+            _ => None,
+        };
+    }
+}
+
 #[derive(Clone)]
 struct Model {
-    images: Vec<String>,
+    folder: Option<PathBuf>,
+    photos: Vec<Photo>,
+    /// The labels and edits of the photos in the folder, as saved
+    sidecar: Sidecar,
+    filter: Filter,
     selected: usize,
-    test_texture: Option<Texture>,
+    /// The selected photo at full size, once it has been decoded
+    preview_source: Option<Texture>,
+    /// `preview_source` with the edit applied, leaving out the crop while
+    /// it is being changed
+    preview: Option<Texture>,
+    preview_loading: bool,
+    /// Stops decoding the preview, when another photo is selected first
+    preview_cancel: Cancel,
+    /// The crop being changed, shown over the uncropped photo
+    cropping: Option<Crop>,
+    crop_drag: Option<CropHandle>,
+    /// Where the cursor was last over the crop rectangle, in window
+    /// coordinates
+    crop_cursor: Option<(f32, f32)>,
+    /// How far the filmstrip has been scrolled towards the next photo
+    /// without reaching it
+    filmstrip_scroll: f32,
+    /// Showing only the photo, with the window filling the screen
+    fullscreen: bool,
+    scanning: bool,
+    error: Option<String>,
+    /// Counts the folders opened, so work for a folder that has since been
+    /// replaced is dropped when it finishes.
+    scan_id: u64,
+}
+
+impl Model {
+    fn new() -> Self {
+        Self {
+            folder: None,
+            photos: Vec::new(),
+            sidecar: Sidecar::default(),
+            filter: Filter::All,
+            selected: 0,
+            preview_source: None,
+            preview: None,
+            preview_loading: false,
+            preview_cancel: Cancel::default(),
+            cropping: None,
+            crop_drag: None,
+            crop_cursor: None,
+            filmstrip_scroll: 0.0,
+            fullscreen: false,
+            scanning: false,
+            error: None,
+            scan_id: 0,
+        }
+    }
+
+    /// Look for photos in `folder` on a background thread and show them once
+    /// found.
+    fn open_folder(&mut self, folder: PathBuf) {
+        self.scan_id += 1;
+        self.folder = Some(folder.clone());
+        self.photos.clear();
+        self.selected = 0;
+        self.preview_source = None;
+        self.preview = None;
+        self.preview_loading = false;
+        self.preview_cancel.cancel();
+        self.cropping = None;
+        self.scanning = true;
+        self.error = None;
+
+        let scan_id = self.scan_id;
+        spawn_task(
+            move || (find_photos(&folder), Sidecar::load(&folder)),
+            move |m: &mut Model, (photos, sidecar)| {
+                if m.scan_id != scan_id {
+                    return;
+                }
+                m.scanning = false;
+                let paths = match photos {
+                    Ok(paths) => paths,
+                    Err(err) => {
+                        m.error = Some(format!("Couldn't read folder: {}", err));
+                        return;
+                    }
+                };
+                m.sidecar = sidecar.unwrap_or_else(|err| {
+                    toast::push(Toast::error(format!(
+                        "Couldn't read ratings and edits: {}",
+                        err
+                    )));
+                    Sidecar::default()
+                });
+                let folder = m.folder.clone().unwrap_or_default();
+                m.photos = paths
+                    .into_iter()
+                    .map(|path| Photo {
+                        label: m.sidecar.label(&folder, &path),
+                        edit: m.sidecar.edit(&folder, &path),
+                        path,
+                        thumbnail: None,
+                        edited_thumbnail: None,
+                    })
+                    .collect();
+                m.select_first_shown();
+                m.generate_thumbnails(0);
+            },
+        );
+    }
+
+    /// The photos the filmstrip shows, with their indices.
+    fn shown_photos(&self) -> impl Iterator<Item = (usize, &Photo)> {
+        self.photos
+            .iter()
+            .enumerate()
+            .filter(|(_, photo)| self.filter.matches(photo.label))
+    }
+
+    fn select_first_shown(&mut self) {
+        let first = self.shown_photos().next().map_or(0, |(index, _)| index);
+        self.select(first);
+    }
+
+    /// Select the shown photo `by` places after the selected one (before it
+    /// if negative), stopping at either end.
+    fn step_selection(&mut self, by: isize) {
+        let shown: Vec<usize> = self.shown_photos().map(|(index, _)| index).collect();
+        let Some(position) = shown.iter().position(|&index| index == self.selected) else {
+            return;
+        };
+        let target = position
+            .saturating_add_signed(by)
+            .min(shown.len().saturating_sub(1));
+        if target != position {
+            self.select(shown[target]);
+        }
+    }
+
+    /// Scroll the filmstrip by `delta` pixels, moving the selection a photo
+    /// along for each `FILMSTRIP_SCROLL_STEP` of it.
+    fn scroll_filmstrip(&mut self, delta: f32) {
+        self.filmstrip_scroll += delta;
+        let steps = (self.filmstrip_scroll / FILMSTRIP_SCROLL_STEP).trunc();
+        self.filmstrip_scroll -= steps * FILMSTRIP_SCROLL_STEP;
+        if steps != 0.0 {
+            self.step_selection(steps as isize);
+        }
+    }
+
+    fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+        let selected_shown = self
+            .photos
+            .get(self.selected)
+            .is_some_and(|photo| filter.matches(photo.label));
+        if !selected_shown {
+            self.select_first_shown();
+        }
+    }
+
+    /// Give the selected photo `rating` stars, or take them away if it
+    /// already has that many.
+    fn rate(&mut self, rating: u8) {
+        self.relabel(|label| {
+            label.rating = if label.rating == rating { 0 } else { rating };
+        });
+    }
+
+    /// Flag the selected photo, or unflag it if it already has `flag`.
+    fn toggle_flag(&mut self, flag: Flag) {
+        self.relabel(|label| {
+            label.flag = if label.flag == Some(flag) {
+                None
+            } else {
+                Some(flag)
+            };
+        });
+    }
+
+    fn relabel(&mut self, change: impl FnOnce(&mut Label)) {
+        let (Some(folder), Some(photo)) = (&self.folder, self.photos.get_mut(self.selected)) else {
+            return;
+        };
+        change(&mut photo.label);
+        self.sidecar.set_label(folder, &photo.path, photo.label);
+        self.save_sidecar();
+    }
+
+    fn change_edit(&mut self, change: impl FnOnce(&mut Edit)) {
+        let (Some(folder), Some(photo)) = (&self.folder, self.photos.get_mut(self.selected)) else {
+            return;
+        };
+        change(&mut photo.edit);
+        photo.apply_edit_to_thumbnail();
+        self.sidecar.set_edit(folder, &photo.path, photo.edit);
+        self.save_sidecar();
+        self.apply_edit_to_preview();
+    }
+
+    fn save_sidecar(&mut self) {
+        let Some(folder) = &self.folder else {
+            return;
+        };
+        if let Err(err) = self.sidecar.save(folder) {
+            toast::push(Toast::error(format!(
+                "Couldn't save ratings and edits: {}",
+                err
+            )));
+        }
+    }
+
+    fn apply_edit_to_preview(&mut self) {
+        let Some(photo) = self.photos.get(self.selected) else {
+            return;
+        };
+        let edit = if self.cropping.is_some() {
+            photo.edit.uncropped()
+        } else {
+            photo.edit
+        };
+        self.preview = self
+            .preview_source
+            .as_ref()
+            .map(|source| edit.apply(source));
+    }
+
+    fn rotate(&mut self) {
+        self.cropping = self.cropping.map(Crop::rotated);
+        self.change_edit(Edit::rotate);
+    }
+
+    fn flip(&mut self) {
+        self.cropping = self.cropping.map(Crop::flipped);
+        self.change_edit(Edit::flip);
+    }
+
+    /// Show the whole photo with the crop rectangle over it, once the photo
+    /// has loaded.
+    fn start_crop(&mut self) {
+        let Some(photo) = self.photos.get(self.selected) else {
+            return;
+        };
+        if self.preview_source.is_none() || self.cropping.is_some() {
+            return;
+        }
+        self.cropping = Some(photo.edit.crop.unwrap_or(Crop::FULL));
+        self.apply_edit_to_preview();
+    }
+
+    /// Stop changing the crop, keeping the new one if `apply` is set.
+    fn finish_crop(&mut self, apply: bool) {
+        let Some(crop) = self.cropping.take() else {
+            return;
+        };
+        self.crop_drag = None;
+        if apply {
+            self.change_edit(|edit| edit.crop = Some(crop).filter(|&crop| crop != Crop::FULL));
+        } else {
+            self.apply_edit_to_preview();
+        }
+    }
+
+    fn reset_edit(&mut self) {
+        self.cropping = None;
+        self.crop_drag = None;
+        self.change_edit(|edit| *edit = Edit::default());
+    }
+
+    /// Follow the cursor, dragging the crop rectangle if a part of it is
+    /// held.
+    fn move_crop_cursor(&mut self, x: f32, y: f32) {
+        if let (Some(handle), Some((last_x, last_y)), Some(crop), Some(preview)) = (
+            self.crop_drag,
+            self.crop_cursor,
+            &mut self.cropping,
+            &self.preview,
+        ) {
+            let (width, height) = preview_size(preview);
+            crop.drag(handle, (x - last_x) / width, (y - last_y) / height);
+        }
+        self.crop_cursor = Some((x, y));
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+        set_fullscreen(fullscreen);
+    }
+
+    fn press_key(&mut self, key: &Key) {
+        let character = match key {
+            Key::Character(character) => character.to_lowercase(),
+            Key::Named(NamedKey::Enter) => return self.finish_crop(true),
+            Key::Named(NamedKey::Escape) if self.cropping.is_some() => {
+                return self.finish_crop(false)
+            }
+            Key::Named(NamedKey::Escape) => return self.set_fullscreen(false),
+            Key::Named(NamedKey::F11) => return self.set_fullscreen(!self.fullscreen),
+            _ => return,
+        };
+        match character.as_str() {
+            "f" => self.set_fullscreen(!self.fullscreen),
+            "p" => self.toggle_flag(Flag::Pick),
+            "x" => self.toggle_flag(Flag::Reject),
+            "r" => self.rotate(),
+            "h" => self.flip(),
+            "c" if self.cropping.is_some() => self.finish_crop(true),
+            "c" => self.start_crop(),
+            digit => {
+                if let Ok(rating @ 1..=5) = digit.parse() {
+                    self.rate(rating);
+                }
+            }
+        }
+    }
+
+    /// Generate the thumbnail of the photo at `index` on a background
+    /// thread, then move on to the next one.
+    fn generate_thumbnails(&mut self, index: usize) {
+        let Some(photo) = self.photos.get(index) else {
+            return;
+        };
+        let path = photo.path.clone();
+        let scan_id = self.scan_id;
+        spawn_task(
+            move || decode_thumbnail(&path),
+            move |m: &mut Model, thumbnail| {
+                if m.scan_id != scan_id {
+                    return;
+                }
+                let photo = &mut m.photos[index];
+                photo.thumbnail = thumbnail;
+                photo.apply_edit_to_thumbnail();
+                m.generate_thumbnails(index + 1);
+            },
+        );
+    }
+
+    /// Show the photo at `index`: its thumbnail right away, and the photo
+    /// itself once it has been decoded in the background.
+    fn select(&mut self, index: usize) {
+        self.selected = index;
+        self.preview_source = None;
+        self.preview = None;
+        self.cropping = None;
+        self.crop_drag = None;
+        // Skipping ahead quickly shouldn't leave every photo passed over
+        // decoding
+        self.preview_cancel.cancel();
+        let Some(photo) = self.photos.get(index) else {
+            self.preview_loading = false;
+            return;
+        };
+
+        let cancel = Cancel::default();
+        self.preview_cancel = cancel.clone();
+        self.preview_loading = true;
+        let path = photo.path.clone();
+        let current = cancel.clone();
+        spawn_task(
+            move || preview::decode(&path, &cancel),
+            move |m: &mut Model, texture| {
+                // Cancelled when another photo or folder was chosen since
+                if current.is_cancelled() {
+                    return;
+                }
+                m.preview_loading = false;
+                m.preview_source = texture.ok();
+                m.apply_edit_to_preview();
+            },
+        );
+    }
+}
+
+/// The photos in `folder` and its subfolders, sorted by path. Hidden files
+/// and folders are skipped.
+fn find_photos(folder: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut photos = Vec::new();
+    collect_photos(folder, &mut photos)?;
+    photos.sort();
+    Ok(photos)
+}
+
+fn collect_photos(folder: &Path, photos: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        // Symlinks aren't followed, so a link back up the tree can't loop
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            // An unreadable subfolder shouldn't hide the rest of the photos
+            let _ = collect_photos(&path, photos);
+        } else if file_type.is_file() && is_photo(&path) {
+            photos.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_photo(path: &Path) -> bool {
+    raw::is_raw(path)
+        || path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .is_some_and(|extension| PHOTO_EXTENSIONS.contains(&extension.as_str()))
+}
+
+fn decode_thumbnail(path: &Path) -> Option<Texture> {
+    #[cfg(feature = "raw")]
+    if raw::is_raw(path) {
+        return raw::decode(path, THUMBNAIL_PIXELS).ok();
+    }
+    load_thumbnail(path, THUMBNAIL_PIXELS).ok()
 }
 
 fn view(model: &Model) -> ElementBuilder {
-    div()
+    let root = div()
         .size(FULL)
         .bg(Color::from_hex("#1a1a1a"))
-        .child(image_preview(model))
-        .child(filmstrip_portal(model))
+        .on_key(|m: &mut Model, key: &Key| m.press_key(key))
+        .child(toast::stack());
+    if model.fullscreen {
+        return root.child(image_preview(model));
+    }
+    root.child(
+        split(false, image_preview(model), filmstrip_pane(model))
+            .key("filmstrip-split")
+            .ratio(PREVIEW_RATIO)
+            .min_first(240.0)
+            .min_second(120.0),
+    )
+    .child(toolbar_portal(model))
+    .child(filter_bar_portal(model))
 }
 
 fn image_preview(model: &Model) -> ElementBuilder {
-    let current = model.images.get(model.selected);
+    let photo = model.photos.get(model.selected);
+    // Until the photo is decoded, its thumbnail stands in, blurry from being
+    // scaled up
+    let texture = model
+        .preview
+        .as_ref()
+        .or_else(|| photo.and_then(Photo::shown_thumbnail));
+
+    let message = if let Some(error) = &model.error {
+        error.clone()
+    } else if model.scanning {
+        "Looking for photos...".to_string()
+    } else if model.folder.is_none() {
+        "Open a folder to view its photos".to_string()
+    } else if let Some(photo) = photo {
+        if model.preview_loading {
+            format!("Loading {}...", photo.name())
+        } else {
+            format!("Couldn't load {}", photo.name())
+        }
+    } else {
+        "No photos in this folder".to_string()
+    };
+
+    let bg = if model.fullscreen {
+        BLACK
+    } else {
+        Color::from_hex("#2a2a2a")
+    };
+
+    div()
+        .size(FULL)
+        .child(
+            div()
+                .size(FULL)
+                .bg(bg)
+                .center()
+                .child(match (texture, model.cropping) {
+                    (Some(texture), Some(crop)) => {
+                        let (width, height) = preview_size(texture);
+                        div()
+                            .w(px(width))
+                            .h(px(height))
+                            .child(img(texture).size(FULL))
+                            .children(crop_overlay(crop))
+                    }
+                    (Some(texture), None) if model.fullscreen => img(texture).h(FULL),
+                    (Some(texture), None) => img(texture).h(px(PREVIEW_HEIGHT)),
+                    (None, _) => text(message).color(Color::from_hex("#b0b0b0")),
+                }),
+        )
+}
+
+/// The size the photo is shown at, in pixels.
+fn preview_size(texture: &Texture) -> (f32, f32) {
+    (PREVIEW_HEIGHT * texture.aspect_ratio(), PREVIEW_HEIGHT)
+}
 
-    div().size(FULL).child(
+/// The crop rectangle, with the part of the photo outside it dimmed. It is
+/// moved by dragging it, and resized by dragging its corners.
+fn crop_overlay(crop: Crop) -> Vec<ElementBuilder> {
+    let dim = |left: f32, top: f32, width: f32, height: f32| {
         div()
-            .size(FULL)
-            .bg(Color::from_hex("#2a2a2a"))
-            .center()
-            .child(if let Some(texture) = &model.test_texture {
-                // Display the loaded texture
-                img(texture).h(px(600.0))
-            } else {
-                text(format!(
-                    "Image: {}",
-                    current.unwrap_or(&"(none)".to_string())
-                ))
-            }),
-    )
+            .absolute()
+            .left(pc(left * 100.0))
+            .top(pc(top * 100.0))
+            .w(pc(width * 100.0))
+            .h(pc(height * 100.0))
+            .bg(Color::new(0.0, 0.0, 0.0, 0.6))
+    };
+    let right = crop.x + crop.width;
+    let bottom = crop.y + crop.height;
+    let corner = |left: bool, top: bool| {
+        let corner = div().absolute().size(px(14.0)).bg(WHITE);
+        let corner = if left {
+            corner.left(px(0.0))
+        } else {
+            corner.right(px(0.0))
+        };
+        let corner = if top {
+            corner.top(px(0.0))
+        } else {
+            corner.bottom(px(0.0))
+        };
+        crop_handle(corner, CropHandle::Corner { left, top })
+    };
+
+    vec![
+        dim(0.0, 0.0, 1.0, crop.y),
+        dim(0.0, bottom, 1.0, 1.0 - bottom),
+        dim(0.0, crop.y, crop.x, crop.height),
+        dim(right, crop.y, 1.0 - right, crop.height),
+        crop_handle(
+            div()
+                .absolute()
+                .left(pc(crop.x * 100.0))
+                .top(pc(crop.y * 100.0))
+                .w(pc(crop.width * 100.0))
+                .h(pc(crop.height * 100.0))
+                .border(2.0, WHITE),
+            CropHandle::Move,
+        )
+        .child(corner(true, true))
+        .child(corner(false, true))
+        .child(corner(true, false))
+        .child(corner(false, false)),
+    ]
+}
+
+/// Make `element` drag `handle` of the crop rectangle.
+fn crop_handle(element: ElementBuilder, handle: CropHandle) -> ElementBuilder {
+    element
+        .on_mouse_down(move |m: &mut Model, button| {
+            if button == MouseButton::Left {
+                m.crop_drag = Some(handle);
+            }
+        })
+        .on_mouse_move(|m: &mut Model, x, y| m.move_crop_cursor(x, y))
+        .on_mouse_up(|m: &mut Model, _| m.crop_drag = None)
 }
 
-fn filmstrip_portal(model: &Model) -> ElementBuilder {
+fn toolbar_portal(model: &Model) -> ElementBuilder {
+    let folder = match &model.folder {
+        Some(folder) => format!("{} ({} photos)", folder.display(), model.photos.len()),
+        None => String::new(),
+    };
+
     portal()
         .left(MD)
+        .top(MD)
+        .row()
+        .gap(MD)
+        .align(Align::Center)
+        .child(toolbar_button("Open Folder", |m| {
+            if let Some(folder) = dialog::pick_folder() {
+                m.open_folder(folder);
+            }
+        }))
+        .children(edit_buttons(model))
+        .child(text(folder).color(Color::from_hex("#b0b0b0")))
+}
+
+fn toolbar_button(label: &'static str, on_click: fn(&mut Model)) -> ElementBuilder {
+    div()
+        .bg(Color::from_hex("#444444"))
+        .radius(8.0)
+        .p(SM)
+        .child(text(label).color(Color::from_hex("#e0e0e0")))
+        .on_left_click(on_click)
+}
+
+/// Buttons editing the selected photo, once it has loaded.
+fn edit_buttons(model: &Model) -> Vec<ElementBuilder> {
+    if model.preview.is_none() {
+        return Vec::new();
+    }
+    let crop = if model.cropping.is_some() {
+        toolbar_button("Apply Crop", |m| m.finish_crop(true))
+    } else {
+        toolbar_button("Crop", Model::start_crop)
+    };
+    vec![
+        toolbar_button("Rotate", Model::rotate),
+        toolbar_button("Flip", Model::flip),
+        crop,
+        toolbar_button("Reset", Model::reset_edit),
+    ]
+}
+
+fn filter_bar_portal(model: &Model) -> ElementBuilder {
+    portal()
         .right(MD)
-        .bottom(MD)
-        .h(FILMSTRIP_HEIGHT)
+        .top(MD)
+        .row()
+        .gap(SM)
+        .children(FILTERS.iter().map(|&filter| {
+            let bg = if filter == model.filter {
+                Color::from_hex("#666666")
+            } else {
+                Color::from_hex("#444444")
+            };
+            div()
+                .bg(bg)
+                .radius(8.0)
+                .p(SM)
+                .child(text(filter.label()).color(Color::from_hex("#e0e0e0")))
+                .on_left_click(move |m: &mut Model| m.set_filter(filter))
+        }))
+}
+
+fn filmstrip_pane(model: &Model) -> ElementBuilder {
+    // Covers what of the photo doesn't fit above it
+    div()
+        .size(FULL)
+        .bg(Color::from_hex("#1a1a1a"))
+        .p(MD)
         .child(filmstrip(model))
 }
 
 fn filmstrip(model: &Model) -> ElementBuilder {
+    let shown: Vec<_> = model.shown_photos().collect();
+    let position = shown
+        .iter()
+        .position(|&(index, _)| index == model.selected)
+        .unwrap_or(0);
+    // Keep the selected photo in the middle, except near either end
+    let start = position
+        .saturating_sub(FILMSTRIP_LENGTH / 2)
+        .min(shown.len().saturating_sub(FILMSTRIP_LENGTH));
+
     div()
         .size(FULL)
         .row()
@@ -57,15 +765,17 @@ fn filmstrip(model: &Model) -> ElementBuilder {
         .p(MD)
         .gap(MD)
         .children(
-            model
-                .images
-                .iter()
-                .enumerate()
-                .map(|(i, path)| thumbnail(i, path, i == model.selected)),
+            shown
+                .into_iter()
+                .skip(start)
+                .take(FILMSTRIP_LENGTH)
+                .map(|(i, photo)| thumbnail(i, photo, i == model.selected)),
         )
+        // Sideways trackpad swipes scroll it too
+        .on_scroll(|m: &mut Model, dx, dy| m.scroll_filmstrip(dx + dy))
 }
 
-fn thumbnail(index: usize, path: &str, selected: bool) -> ElementBuilder {
+fn thumbnail(index: usize, photo: &Photo, selected: bool) -> ElementBuilder {
     let bg = if selected {
         Color::from_hex("#666666")
     } else {
@@ -76,27 +786,29 @@ fn thumbnail(index: usize, path: &str, selected: bool) -> ElementBuilder {
         .h(FULL)
         .square()
         .bg(bg)
-        .child(text(format!("{}", index + 1)))
-        .on_left_click(move |m: &mut Model| {
-            m.selected = index;
+        .center()
+        .child(match photo.shown_thumbnail() {
+            Some(texture) => img(texture).size(FULL).object_fit(ObjectFit::Contain),
+            None => text(format!("{}", index + 1)),
         })
+        .border(
+            3.0,
+            match photo.label.flag {
+                Some(Flag::Pick) => Color::from_hex("#4caf50"),
+                Some(Flag::Reject) => Color::from_hex("#c0392b"),
+                None => bg,
+            },
+        )
+        .child(
+            text(stars(photo.label.rating))
+                .color(Color::from_hex("#ffcc00"))
+                .absolute()
+                .left(px(6.0))
+                .bottom(px(4.0)),
+        )
+        .on_left_click(move |m: &mut Model| m.select(index))
 }
 
-fn main() {
-    // Try to load a test texture
-    let test_texture = load_texture("test.jpg").ok();
-
-    let model = Model {
-        images: vec![
-            "photo1.jpg".to_string(),
-            "photo2.jpg".to_string(),
-            "photo3.jpg".to_string(),
-            "photo4.jpg".to_string(),
-            "photo5.jpg".to_string(),
-        ],
-        selected: 0,
-        test_texture,
-    };
-
-    App::new(model, view).run();
+fn main() -> Result<(), vitae::Error> {
+    App::new(Model::new(), view)?.run()
 }
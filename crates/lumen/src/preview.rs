@@ -0,0 +1,68 @@
+//! Full-size photos for the preview, decoded on a background thread that
+//! can be told to give up.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use image::{ImageReader, ImageResult};
+use vitae::prelude::Texture;
+
+/// Tells a decode to stop. Clones share the same flag.
+#[derive(Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Decode the photo at `path`, giving up as soon as `cancel` is set rather
+/// than finishing a photo that is no longer wanted. RAW files are always
+/// decoded in full, since imagepipe can't be interrupted.
+pub fn decode(path: &Path, cancel: &Cancel) -> ImageResult<Texture> {
+    #[cfg(feature = "raw")]
+    if crate::raw::is_raw(path) {
+        return crate::raw::decode(path, 0);
+    }
+
+    let file = CancellableFile {
+        file: File::open(path)?,
+        cancel: cancel.clone(),
+    };
+    let rgba = ImageReader::new(BufReader::new(file))
+        .with_guessed_format()?
+        .decode()?
+        .into_rgba8();
+    let (width, height) = rgba.dimensions();
+    Ok(Texture::from_rgba(rgba.into_raw(), width, height))
+}
+
+/// A file whose reads fail once its decode is cancelled, which makes the
+/// decoder reading it stop early.
+struct CancellableFile {
+    file: File,
+    cancel: Cancel,
+}
+
+impl Read for CancellableFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.is_cancelled() {
+            return Err(io::Error::other("decode cancelled"));
+        }
+        self.file.read(buf)
+    }
+}
+
+impl Seek for CancellableFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
@@ -0,0 +1,46 @@
+//! Camera RAW files. They are only listed and decoded when built with the
+//! `raw` feature, which demosaics them with imagepipe.
+
+use std::path::Path;
+
+#[cfg(feature = "raw")]
+use image::error::{DecodingError, ImageFormatHint};
+#[cfg(feature = "raw")]
+use image::{ImageError, ImageResult};
+#[cfg(feature = "raw")]
+use vitae::prelude::Texture;
+
+/// File extensions of the RAW formats imagepipe reads, in lowercase.
+const RAW_EXTENSIONS: &[&str] = &[
+    "arw", "cr2", "dng", "nef", "nrw", "orf", "pef", "raf", "rw2", "sr2", "srf", "srw",
+];
+
+/// Whether `path` is a RAW file this build can decode.
+pub fn is_raw(path: &Path) -> bool {
+    cfg!(feature = "raw")
+        && path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .is_some_and(|extension| RAW_EXTENSIONS.contains(&extension.as_str()))
+}
+
+/// Develop the RAW file at `path` with default settings, scaled down to at
+/// most `max_size` pixels on either side, or at full size if it is 0.
+#[cfg(feature = "raw")]
+pub fn decode(path: &Path, max_size: u32) -> ImageResult<Texture> {
+    let max_size = max_size as usize;
+    let image = imagepipe::simple_decode_8bit(path, max_size, max_size)
+        .map_err(|err| ImageError::Decoding(DecodingError::new(ImageFormatHint::Unknown, err)))?;
+
+    // imagepipe gives RGB; textures are RGBA
+    let rgba = image
+        .data
+        .chunks_exact(3)
+        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+        .collect();
+    Ok(Texture::from_rgba(
+        rgba,
+        image.width as u32,
+        image.height as u32,
+    ))
+}
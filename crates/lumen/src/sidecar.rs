@@ -0,0 +1,105 @@
+//! Ratings, flags and edits, kept in a file inside the photo folder so they
+//! move along with it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::edit::Edit;
+
+/// Hidden, so scanning the folder skips it.
+const SIDECAR_NAME: &str = ".lumen.json";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Flag {
+    Pick,
+    Reject,
+}
+
+/// What the user marked a photo with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Label {
+    /// From 1 to 5 stars, or 0 if unrated
+    #[serde(default)]
+    pub rating: u8,
+    #[serde(default)]
+    pub flag: Option<Flag>,
+}
+
+/// The labels and edits of the photos in a folder, by their path relative
+/// to it. Those of photos that have gone missing are kept, in case they
+/// return.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Sidecar {
+    photos: BTreeMap<String, Label>,
+    #[serde(default)]
+    edits: BTreeMap<String, Edit>,
+}
+
+impl Sidecar {
+    /// Read the sidecar of `folder`. A folder without one has no labels or
+    /// edits yet.
+    pub fn load(folder: &Path) -> io::Result<Sidecar> {
+        match fs::read_to_string(folder.join(SIDECAR_NAME)) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Sidecar::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save(&self, folder: &Path) -> io::Result<()> {
+        fs::write(
+            folder.join(SIDECAR_NAME),
+            serde_json::to_string_pretty(self)?,
+        )
+    }
+
+    pub fn label(&self, folder: &Path, photo: &Path) -> Label {
+        get(&self.photos, folder, photo)
+    }
+
+    pub fn set_label(&mut self, folder: &Path, photo: &Path, label: Label) {
+        set(&mut self.photos, folder, photo, label);
+    }
+
+    pub fn edit(&self, folder: &Path, photo: &Path) -> Edit {
+        get(&self.edits, folder, photo)
+    }
+
+    pub fn set_edit(&mut self, folder: &Path, photo: &Path, edit: Edit) {
+        set(&mut self.edits, folder, photo, edit);
+    }
+}
+
+fn get<T: Copy + Default>(map: &BTreeMap<String, T>, folder: &Path, photo: &Path) -> T {
+    map.get(&key(folder, photo)).copied().unwrap_or_default()
+}
+
+/// Store `value` for `photo`, leaving it out if it's the default.
+fn set<T: Default + PartialEq>(
+    map: &mut BTreeMap<String, T>,
+    folder: &Path,
+    photo: &Path,
+    value: T,
+) {
+    if value == T::default() {
+        map.remove(&key(folder, photo));
+    } else {
+        map.insert(key(folder, photo), value);
+    }
+}
+
+/// The path of `photo` relative to `folder`, with `/` between components
+/// on every platform.
+fn key(folder: &Path, photo: &Path) -> String {
+    let relative = photo.strip_prefix(folder).unwrap_or(photo);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,115 @@
+//! Laying out element trees with real text measurement, and encoding them
+//! into Vello scenes. Needs no window or GPU. Run with
+//! `cargo bench -p vitae_render`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use vitae_core::{div, pc, px, svg, text, Color, ElementBuilder, Svg};
+use vitae_render::{encode_scene, layout_tree};
+
+const WIDTH: f32 = 1280.0;
+const HEIGHT: f32 = 800.0;
+
+const PIECE: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="45" height="45"><path d="M22.5 9c-2.2 0-4 1.8-4 4 0 .9.3 1.7.8 2.4-1.9 1.1-3.3 3.2-3.3 5.6 0 2 1 3.8 2.5 5-3 1.1-7.5 5.6-7.5 13.5h23c0-7.9-4.5-12.4-7.5-13.5 1.5-1.2 2.5-3 2.5-5 0-2.4-1.4-4.5-3.3-5.6.5-.7.8-1.5.8-2.4 0-2.2-1.8-4-4-4z" fill="#FFF" stroke="#000" stroke-width="1.5"/></svg>"##;
+
+/// A column of `count` rows, each with a few children.
+fn wide_tree(count: usize) -> ElementBuilder {
+    div().col().w(pc(100.0)).children((0..count).map(|i| {
+        div()
+            .row()
+            .gap(px(8.0))
+            .p(px(4.0))
+            .child(div().size(px(16.0)).radius(4.0).bg(Color::BLACK))
+            .child(text(format!("Row {i}")))
+            .child(div().w(px(40.0)).h(px(16.0)).bg(Color::from_hex("#dddddd")))
+    }))
+}
+
+/// Paragraphs of wrapping text in a column.
+fn text_tree(paragraphs: usize) -> ElementBuilder {
+    let paragraph = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+    div()
+        .col()
+        .gap(px(12.0))
+        .p(px(16.0))
+        .w(px(600.0))
+        .children((0..paragraphs).map(|_| text(paragraph.clone())))
+}
+
+/// A chess board like the chess example's: 64 squares holding SVG pieces
+/// and coordinates, next to a move list.
+fn chess_board() -> ElementBuilder {
+    let piece = Svg::new(PIECE.to_string(), 45.0, 45.0);
+    let board = div().col().size(px(640.0)).children((0..8).map(|rank| {
+        let piece = piece.clone();
+        div()
+            .row()
+            .w(pc(100.0))
+            .h(pc(12.5))
+            .children((0..8).map(move |file| {
+                let light = (rank + file) % 2 == 0;
+                let mut square = div()
+                    .w(pc(12.5))
+                    .h(pc(100.0))
+                    .center()
+                    .bg(Color::from_hex(if light { "#f0d9b5" } else { "#b58863" }));
+                if !(2..=5).contains(&rank) {
+                    square = square.child(svg(&piece).size(pc(90.0)));
+                }
+                if file == 0 {
+                    square = square.child(text(format!("{}", 8 - rank)).absolute().top(px(2.0)));
+                }
+                square
+            }))
+    }));
+    let moves = div().col().w(px(240.0)).children((1..=40).map(|number| {
+        div()
+            .row()
+            .gap(px(8.0))
+            .child(text(format!("{number}.")))
+            .child(text("Nf3"))
+            .child(text("Nf6"))
+    }));
+    div()
+        .row()
+        .gap(px(16.0))
+        .p(px(16.0))
+        .child(board)
+        .child(moves)
+}
+
+fn bench_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("layout_tree");
+    for count in [100, 1000] {
+        let root = wide_tree(count);
+        group.bench_with_input(BenchmarkId::new("wide", count), &root, |b, root| {
+            b.iter(|| layout_tree(root.clone(), WIDTH, HEIGHT, true))
+        });
+    }
+    let root = text_tree(200);
+    group.bench_function("text", |b| {
+        b.iter(|| layout_tree(root.clone(), WIDTH, HEIGHT, true))
+    });
+    let root = chess_board();
+    group.bench_function("chess_board", |b| {
+        b.iter(|| layout_tree(root.clone(), WIDTH, HEIGHT, true))
+    });
+    group.finish();
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_scene");
+    for count in [100, 1000] {
+        let tree = layout_tree(wide_tree(count), WIDTH, HEIGHT, true);
+        group.bench_with_input(BenchmarkId::new("wide", count), &tree, |b, tree| {
+            b.iter(|| black_box(encode_scene(tree)))
+        });
+    }
+    let tree = layout_tree(text_tree(200), WIDTH, HEIGHT, true);
+    group.bench_function("text", |b| b.iter(|| black_box(encode_scene(&tree))));
+    let tree = layout_tree(chess_board(), WIDTH, HEIGHT, true);
+    group.bench_function("chess_board", |b| b.iter(|| black_box(encode_scene(&tree))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_layout, bench_encode);
+criterion_main!(benches);
@@ -0,0 +1,147 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use parley::{Affinity, BoundingBox, Cursor, Layout, Selection};
+
+use crate::text::layout_text;
+
+/// How long the caret stays visible, and then hidden, in each blink.
+pub const CARET_BLINK_INTERVAL: Duration = Duration::from_millis(530);
+
+/// Width of the caret in pixels.
+pub const CARET_WIDTH: f32 = 1.5;
+
+/// Position and size of a caret or a piece of a selection, relative to the
+/// top-left corner of its text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CaretRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Compute where the caret goes for byte `offset` in `text`, laid out at
+/// `font_size` and wrapped at `max_width` like a text element.
+///
+/// Offsets past the end of the text place the caret after the last
+/// character.
+///
+/// # Example
+/// ```
+/// let caret = vitae::text::caret_rect(&model.input, 16.0, None, model.cursor);
+/// ```
+pub fn caret_rect(text: &str, font_size: f32, max_width: Option<f32>, offset: usize) -> CaretRect {
+    caret_rect_in(&layout_text(text, font_size, max_width), offset)
+}
+
+pub(crate) fn caret_rect_in(text_layout: &Layout<()>, offset: usize) -> CaretRect {
+    let bounds = Cursor::from_byte_index(text_layout, offset, Affinity::Downstream)
+        .geometry(text_layout, CARET_WIDTH);
+    rect_from(bounds)
+}
+
+/// The byte offset the caret moves to from `offset` when the Left arrow
+/// (or, when `right`, the Right arrow) is pressed.
+///
+/// The caret moves in the order the text is shown rather than stored, so
+/// it moves left through Hebrew or Arabic embedded in English just like
+/// through the English, even though those words are stored right to left.
+///
+/// # Example
+/// ```
+/// let right = *key == Key::Named(NamedKey::ArrowRight);
+/// model.cursor = vitae::text::move_caret(&model.input, 16.0, None, model.cursor, right);
+/// ```
+pub fn move_caret(
+    text: &str,
+    font_size: f32,
+    max_width: Option<f32>,
+    offset: usize,
+    right: bool,
+) -> usize {
+    let text_layout = layout_text(text, font_size, max_width);
+    let cursor = Cursor::from_byte_index(&text_layout, offset, Affinity::Downstream);
+    let moved = if right {
+        cursor.next_visual(&text_layout)
+    } else {
+        cursor.previous_visual(&text_layout)
+    };
+    moved.index()
+}
+
+/// The rectangles covering the selected bytes `range` of `text`, laid out
+/// like `caret_rect`, in order from top to bottom and left to right.
+///
+/// A selection across a change of direction is shown as several pieces on
+/// the same line, since the characters it covers aren't next to each other
+/// on screen.
+pub fn selection_rects(
+    text: &str,
+    font_size: f32,
+    max_width: Option<f32>,
+    range: Range<usize>,
+) -> Vec<CaretRect> {
+    selection_rects_in(&layout_text(text, font_size, max_width), range)
+}
+
+pub(crate) fn selection_rects_in(text_layout: &Layout<()>, range: Range<usize>) -> Vec<CaretRect> {
+    let selection = Selection::new(
+        Cursor::from_byte_index(text_layout, range.start, Affinity::Downstream),
+        Cursor::from_byte_index(text_layout, range.end, Affinity::Upstream),
+    );
+    selection
+        .geometry(text_layout)
+        .into_iter()
+        .map(|(bounds, _line)| rect_from(bounds))
+        .collect()
+}
+
+fn rect_from(bounds: BoundingBox) -> CaretRect {
+    CaretRect {
+        x: bounds.x0 as f32,
+        y: bounds.y0 as f32,
+        width: (bounds.x1 - bounds.x0) as f32,
+        height: (bounds.y1 - bounds.y0) as f32,
+    }
+}
+
+/// Blink state of the caret. The caret is solid right after it appears or
+/// moves, then alternates between shown and hidden.
+pub(crate) struct CaretBlink {
+    start: Instant,
+    rect: Option<CaretRect>,
+}
+
+impl CaretBlink {
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            rect: None,
+        }
+    }
+
+    /// Record where the caret is drawn at `now` and return whether it is
+    /// visible. Moving the caret restarts the blink.
+    pub(crate) fn update(&mut self, rect: CaretRect, now: Instant) -> bool {
+        if self.rect != Some(rect) {
+            self.rect = Some(rect);
+            self.start = now;
+        }
+        let phase = now.saturating_duration_since(self.start).as_millis()
+            / CARET_BLINK_INTERVAL.as_millis();
+        phase.is_multiple_of(2)
+    }
+
+    /// Forget the caret's position, so it starts solid when it reappears.
+    pub(crate) fn reset(&mut self) {
+        self.rect = None;
+    }
+
+    /// When the caret next switches between shown and hidden.
+    pub(crate) fn next_toggle(&self, now: Instant) -> Instant {
+        let elapsed = now.saturating_duration_since(self.start);
+        let phase = elapsed.as_millis() / CARET_BLINK_INTERVAL.as_millis();
+        self.start + CARET_BLINK_INTERVAL * (phase as u32 + 1)
+    }
+}
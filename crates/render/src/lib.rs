@@ -1,5 +1,7 @@
+pub mod caret;
 mod renderer;
+pub mod text;
 mod texture;
 
-pub use renderer::Renderer;
-pub use texture::{load_svg, load_texture};
+pub use renderer::{encode_scene, layout_tree, RenderError, Renderer};
+pub use texture::{load_svg, load_texture, load_thumbnail};
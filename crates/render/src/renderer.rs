@@ -1,310 +1,766 @@
-use std::borrow::Cow;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Weak};
+use std::time::Instant;
 
-use parley::{FontContext, LayoutContext, LineHeight, StyleProperty};
+use parley::Cursor;
 use pollster::FutureExt;
 use vello::kurbo::{Affine, Cap, Join, Rect, RoundedRect, RoundedRectRadii, Stroke};
 use vello::peniko::{
     color::palette, BlendMode, Blob, Fill, ImageAlphaType, ImageBrush, ImageData, ImageFormat,
 };
-use vello::wgpu::{self, CommandEncoderDescriptor};
+use vello::wgpu::{self, CommandEncoderDescriptor, SurfaceError};
 use vello::{AaConfig, NormalizedCoord, RenderParams, RendererOptions, Scene};
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
 use vitae_core::{
-    layout, Constraints, ElementBuilder, ElementTree, NodeId, NodeKind, Position, Svg,
-    TextMeasurer, Texture,
+    layout, relayout_dirty, snap_subtree_to_pixels, snap_to_pixels, Constraints, ElementBuilder,
+    ElementTree, HitIndex, Layout, NodeId, NodeKind, ObjectFit, Overflow, Svg, TextMeasurer,
+    Texture,
 };
 
-// Sensible defaults (TODO: replace with theme system)
-const DEFAULT_FONT_SIZE: f32 = 24.0;
+use crate::caret::{caret_rect_in, selection_rects_in, CaretBlink};
+use crate::text::{layout_styled, measure, measure_monospace, measure_styled, DEFAULT_FONT_SIZE};
+
+/// The color behind selected text, as RGBA.
+const SELECTION_COLOR: [f32; 4] = [0.26, 0.52, 0.96, 0.35];
+
+/// Why the renderer couldn't start, or couldn't draw a frame.
+#[derive(Debug)]
+pub enum RenderError {
+    /// Vello couldn't set up the window's surface or draw the scene, such
+    /// as when there is no suitable GPU adapter
+    Vello(vello::Error),
+    /// The window's surface couldn't give a texture to draw to, other than
+    /// by being lost or outdated (those are recovered from)
+    Surface(SurfaceError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Vello(err) => write!(f, "renderer error: {err}"),
+            RenderError::Surface(err) => write!(f, "surface error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::Vello(err) => Some(err),
+            RenderError::Surface(err) => Some(err),
+        }
+    }
+}
+
+impl From<vello::Error> for RenderError {
+    fn from(err: vello::Error) -> Self {
+        RenderError::Vello(err)
+    }
+}
+
+impl From<SurfaceError> for RenderError {
+    fn from(err: SurfaceError) -> Self {
+        RenderError::Surface(err)
+    }
+}
 
 /// Text measurer that uses Parley for font-aware text measurement.
-struct ParleyMeasurer<'a> {
-    font_cx: &'a mut FontContext,
-    layout_cx: &'a mut LayoutContext<()>,
+struct ParleyMeasurer {
     font_size: f32,
 }
 
-impl TextMeasurer for ParleyMeasurer<'_> {
+impl TextMeasurer for ParleyMeasurer {
     fn measure(&mut self, text: &str, max_width: Option<f32>) -> (f32, f32) {
-        let mut builder = self.layout_cx.ranged_builder(self.font_cx, text, 1.0, true);
-
-        // Use font stack with system UI font first, then symbol fonts as fallback
-        // This way regular text uses the nice system font, but chess symbols still work
-        builder.push_default(StyleProperty::FontStack(parley::style::FontStack::List(
-            Cow::Borrowed(&[
-                parley::style::FontFamily::Generic(parley::style::GenericFamily::SystemUi),
-                parley::style::FontFamily::Named(Cow::Borrowed("Noto Sans Symbols 2")),
-                parley::style::FontFamily::Named(Cow::Borrowed("Segoe UI Symbol")),
-                parley::style::FontFamily::Named(Cow::Borrowed("Apple Symbols")),
-                parley::style::FontFamily::Generic(parley::style::GenericFamily::SansSerif),
-            ]),
-        )));
-
-        builder.push_default(StyleProperty::FontSize(self.font_size));
-        let mut text_layout = builder.build(text);
-        text_layout.break_all_lines(max_width);
-
-        (text_layout.width(), text_layout.height())
+        measure(text, self.font_size, max_width)
+    }
+
+    fn measure_monospace(&mut self, text: &str, max_width: Option<f32>) -> (f32, f32) {
+        measure_monospace(text, self.font_size, max_width)
+    }
+
+    fn measure_styled(
+        &mut self,
+        text: &str,
+        max_width: Option<f32>,
+        style: &vitae_core::Style,
+    ) -> (f32, f32) {
+        measure_styled(text, style, max_width)
+    }
+
+    fn fork(&self) -> Option<Box<dyn TextMeasurer + Send>> {
+        Some(Box::new(ParleyMeasurer {
+            font_size: self.font_size,
+        }))
+    }
+}
+
+/// Build `root` and lay it out in a `width` by `height` window, measuring
+/// text with the same fonts as the renderer.
+///
+/// The result is the tree the renderer would draw, made without a window or
+/// GPU, so tests and tools can look up where elements end up. For sizes
+/// that don't depend on the fonts installed, use `vitae_core::layout_tree`
+/// with a `FixedMeasurer`.
+///
+/// # Example
+/// ```
+/// let tree = layout_tree(view(&model), 800.0, 600.0, true);
+/// let button = tree.find_debug_name("save-button").unwrap();
+/// assert_eq!(tree.hit_test(tree.get_node(button).layout.x + 1.0, 10.0), Some(button));
+/// ```
+pub fn layout_tree(
+    root: ElementBuilder,
+    width: f32,
+    height: f32,
+    pixel_snapping: bool,
+) -> ElementTree {
+    let mut tree = build_tree(root);
+    lay_out(&mut tree, width, height, pixel_snapping, 1.0);
+    tree
+}
+
+fn build_tree(root: ElementBuilder) -> ElementTree {
+    let _span = tracing::debug_span!("build_tree").entered();
+    root.build()
+}
+
+/// Lay out `tree` in a `width` by `height` window, in logical pixels, of
+/// which there are `scale_factor` physical pixels to each. A tree already
+/// laid out is laid out again from scratch, as after the window is resized.
+fn lay_out(
+    tree: &mut ElementTree,
+    width: f32,
+    height: f32,
+    pixel_snapping: bool,
+    scale_factor: f32,
+) {
+    let root = tree.root;
+    let _span = tracing::debug_span!("layout", nodes = tree.arena.len()).entered();
+
+    let mut measurer = ParleyMeasurer {
+        font_size: DEFAULT_FONT_SIZE,
+    };
+
+    layout(
+        tree,
+        root,
+        Constraints {
+            max_w: width,
+            max_h: height,
+        },
+        0.0,
+        0.0,
+        &mut measurer,
+    );
+    if pixel_snapping {
+        snap_to_pixels(tree, scale_factor);
+    }
+}
+
+/// Lay out again the elements of `tree` marked dirty by
+/// `ElementTree::reuse_layout`, rounding them to whole pixels as `lay_out`
+/// does.
+fn lay_out_dirty(tree: &mut ElementTree, pixel_snapping: bool, scale_factor: f32) {
+    let _span = tracing::debug_span!("relayout").entered();
+
+    let mut measurer = ParleyMeasurer {
+        font_size: DEFAULT_FONT_SIZE,
+    };
+
+    for id in relayout_dirty(tree, &mut measurer) {
+        if pixel_snapping {
+            snap_subtree_to_pixels(tree, id, scale_factor);
+        }
     }
 }
 
 pub struct Renderer<'a> {
     // Vello rendering
     context: vello::util::RenderContext,
-    surface: vello::util::RenderSurface<'a>,
+    // None while the app is suspended
+    surface: Option<vello::util::RenderSurface<'a>>,
     vello_renderer: vello::Renderer,
-    scene: Scene,
-
-    // Text
-    font_cx: FontContext,
-    layout_cx: LayoutContext<()>,
+    painter: Painter,
 
     // Window state
     size: PhysicalSize<u32>,
+    scale_factor: f64,
     window: Arc<Window>,
+    // The painted scene scaled from logical to physical pixels, for screens
+    // with more than one physical pixel to each logical one
+    scaled_scene: Scene,
+    // Whether the window shows the last frame painted, so a frame that
+    // paints the same needn't be drawn again
+    presented: bool,
+
+    // UI tree, built once from each root element, and laid out again
+    // whenever the window changes size
+    tree: ElementTree,
+    layout_dirty: bool,
+    pixel_snapping: bool,
+    // Where the tree's elements can be hit, built on the first hit test
+    // after each layout
+    hit_index: Option<HitIndex>,
+
+    // The tree painted last frame, if `tree` has replaced it since, or else
+    // whether `tree` was painted last frame without being laid out again
+    painted: Option<ElementTree>,
+    tree_painted: bool,
+}
+
+/// Encodes an element tree into a Vello scene, keeping track of the text
+/// caret's blink between frames.
+///
+/// Subtrees that paint the same as in the last frame's tree are encoded
+/// into fragments of their own, which are appended to later frames as they
+/// are for as long as the subtree stays the same.
+struct Painter {
+    scene: Scene,
+    caret_blink: CaretBlink,
+    caret_drawn: bool,
+    next_frame_at: Option<Instant>,
+    /// Elements of the tree being painted that paint the same as in the
+    /// last frame's tree, with their ids there.
+    unchanged: HashMap<NodeId, NodeId>,
+    /// Fragments from the last frame, by id in the last frame's tree.
+    fragments: HashMap<NodeId, Fragment>,
+    /// Fragments used this frame, kept for the next one.
+    next_fragments: HashMap<NodeId, Fragment>,
+    /// Whether a new fragment is being encoded, so the subtrees inside it
+    /// aren't encoded again into fragments of their own.
+    encoding_fragment: bool,
+    /// Images made from textures, by texture id, with the textures' pixels.
+    /// Painting the same image every frame lets Vello upload it to the GPU
+    /// once rather than every frame.
+    images: HashMap<u64, (Weak<Vec<u8>>, ImageData)>,
+}
+
+/// A subtree encoded into a scene of its own.
+struct Fragment {
+    scene: Scene,
+    /// Where the subtree's root was when it was encoded; the scene is moved
+    /// by however far the root has moved since.
+    x: f32,
+    y: f32,
+    /// The opacity the subtree inherited from its ancestors, which is baked
+    /// into the scene.
+    opacity: f32,
+}
+
+/// Encode `tree`, already laid out, into a Vello scene: what the renderer
+/// draws for it, made without a window or GPU.
+pub fn encode_scene(tree: &ElementTree) -> Scene {
+    let mut painter = Painter::new();
+    painter.paint_tree(tree, None);
+    painter.scene
+}
 
-    // UI tree
-    root_element: ElementBuilder,
-    cached_tree: Option<ElementTree>,
-    tree_dirty: bool,
+/// Where the children of an element styled with `style` that clips them
+/// can be seen: inside its border, with its corners rounded to match.
+fn clip_shape(style: &vitae_core::Style, layout: Layout) -> RoundedRect {
+    let border = &style.border;
+    let left = layout.x + border.left.width;
+    let top = layout.y + border.top.width;
+    let right = (layout.x + layout.width - border.right.width).max(left);
+    let bottom = (layout.y + layout.height - border.bottom.width).max(top);
+    let (tl, tr, br, bl) = style.radius.resolve(layout.width, layout.height);
+    let inner = |radius: f32, a: f32, b: f32| (radius - a.max(b)).max(0.0) as f64;
+    RoundedRect::from_rect(
+        Rect::new(left as f64, top as f64, right as f64, bottom as f64),
+        RoundedRectRadii::new(
+            inner(tl, border.top.width, border.left.width),
+            inner(tr, border.top.width, border.right.width),
+            inner(br, border.bottom.width, border.right.width),
+            inner(bl, border.bottom.width, border.left.width),
+        ),
+    )
+}
+
+/// Record in `unchanged` the elements under `id` in `tree` that paint the
+/// same as the element at `last_id` in `last`, the tree painted last frame:
+/// with the same kind, content, style and size, and with children that
+/// paint the same and sit in the same places relative to it. Returns
+/// whether the element at `id` does.
+fn find_unchanged(
+    tree: &ElementTree,
+    id: NodeId,
+    last: &ElementTree,
+    last_id: NodeId,
+    unchanged: &mut HashMap<NodeId, NodeId>,
+) -> bool {
+    let (node, last_node) = (tree.get_node(id), last.get_node(last_id));
+    let (layout, last_layout) = (node.layout, last_node.layout);
+    let mut same = node.kind == last_node.kind
+        && layout.width == last_layout.width
+        && layout.height == last_layout.height
+        // The caret blinks, so text showing one is painted every frame
+        && node.style().is_some_and(|style| style.caret.is_none());
+
+    let mut children = tree.children(id);
+    let mut last_children = last.children(last_id);
+    loop {
+        match (children.next(), last_children.next()) {
+            (Some(child), Some(last_child)) => {
+                let offset = tree.get_node(child).layout;
+                let last_offset = last.get_node(last_child).layout;
+                same &= find_unchanged(tree, child, last, last_child, unchanged)
+                    && offset.x - layout.x == last_offset.x - last_layout.x
+                    && offset.y - layout.y == last_offset.y - last_layout.y;
+            }
+            (None, None) => break,
+            _ => {
+                same = false;
+                break;
+            }
+        }
+    }
+
+    // A lone box is as cheap to paint again as to reuse
+    let worth_keeping =
+        node.first_child.is_some() || !matches!(node.kind, NodeKind::Element { .. });
+    if same && worth_keeping {
+        unchanged.insert(id, last_id);
+    }
+    same
+}
+
+/// Set up a surface to draw to `window`, at `size`.
+fn create_surface<'a>(
+    context: &mut vello::util::RenderContext,
+    window: &Arc<Window>,
+    size: PhysicalSize<u32>,
+) -> Result<vello::util::RenderSurface<'a>, RenderError> {
+    let surface = context
+        .create_surface(
+            window.clone(),
+            size.width,
+            size.height,
+            wgpu::PresentMode::AutoVsync,
+        )
+        .block_on()?;
+    Ok(surface)
 }
 
 impl<'a> Renderer<'a> {
-    pub fn new(window: Window, root_element: ElementBuilder) -> Self {
+    pub fn new(window: Window, root_element: ElementBuilder) -> Result<Self, RenderError> {
         let window = Arc::new(window);
         let size = window.inner_size();
+        let scale_factor = window.scale_factor();
 
         let mut context = vello::util::RenderContext::new();
 
-        let surface = context
-            .create_surface(
-                window.clone(),
-                size.width,
-                size.height,
-                wgpu::PresentMode::AutoVsync,
-            )
-            .block_on()
-            .expect("Failed to create surface");
+        let surface = create_surface(&mut context, &window, size)?;
 
         let device = &context.devices[surface.dev_id].device;
 
-        let vello_renderer = vello::Renderer::new(device, RendererOptions::default())
-            .expect("Failed to create Vello renderer");
+        let vello_renderer = vello::Renderer::new(device, RendererOptions::default())?;
 
-        let font_cx = FontContext::new();
-        let layout_cx = LayoutContext::new();
-
-        Self {
+        Ok(Self {
             context,
-            surface,
+            surface: Some(surface),
             vello_renderer,
-            scene: Scene::new(),
-            font_cx,
-            layout_cx,
+            painter: Painter::new(),
             size,
+            scale_factor,
             window,
-            root_element,
-            cached_tree: None,
-            tree_dirty: true,
-        }
+            scaled_scene: Scene::new(),
+            presented: false,
+            tree: build_tree(root_element),
+            layout_dirty: true,
+            pixel_snapping: true,
+            hit_index: None,
+            painted: None,
+            tree_painted: false,
+        })
     }
 
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
-            self.context
-                .resize_surface(&mut self.surface, new_size.width, new_size.height);
-            // Invalidate tree since layout depends on window size
-            self.tree_dirty = true;
+            if let Some(surface) = &mut self.surface {
+                self.context
+                    .resize_surface(surface, new_size.width, new_size.height);
+            }
+            // Invalidate layout since it depends on window size, and the
+            // frame since the surface was cleared
+            self.layout_dirty = true;
+            self.presented = false;
         }
     }
 
+    /// Use `scale_factor` physical pixels to each logical pixel, as after
+    /// the window moves to a screen of a different pixel density. The view
+    /// is laid out in logical pixels, so it keeps its size on screen.
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        if self.scale_factor != scale_factor {
+            self.scale_factor = scale_factor;
+            self.layout_dirty = true;
+        }
+    }
+
+    /// Physical pixels to each logical pixel. Layouts, hit testing and the
+    /// positions in events are all in logical pixels.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Let go of the window's surface, which the system takes back while the
+    /// app is suspended (on Android and iOS, when it goes to the
+    /// background). Nothing is drawn until `resume`.
+    pub fn suspend(&mut self) {
+        self.surface = None;
+    }
+
+    /// Set up the window's surface again after `suspend`, at the window's
+    /// current size, and ask for a frame to be drawn to it.
+    pub fn resume(&mut self) -> Result<(), RenderError> {
+        if self.surface.is_some() {
+            return Ok(());
+        }
+        let size = self.window.inner_size();
+        let surface = create_surface(&mut self.context, &self.window, size)?;
+        // The new surface may be on a different device than the last one,
+        // such as after the GPU was reset
+        let device = &self.context.devices[surface.dev_id].device;
+        self.vello_renderer = vello::Renderer::new(device, RendererOptions::default())?;
+        self.surface = Some(surface);
+        self.resize(size);
+        self.window.request_redraw();
+        Ok(())
+    }
+
     /// Update the root element (used when model/signals change)
+    ///
+    /// When only paint changed, like a hover color, the new tree takes the
+    /// current layout and is repainted without being laid out again. When
+    /// only what's inside an element of fixed size changed, like the text
+    /// in a card, only that element is laid out again.
     pub fn set_root(&mut self, root_element: ElementBuilder) {
-        self.root_element = root_element;
-        self.tree_dirty = true;
+        let mut tree = build_tree(root_element);
+        if !self.layout_dirty {
+            if tree.reuse_layout(&self.tree) {
+                lay_out_dirty(&mut tree, self.pixel_snapping, self.scale_factor as f32);
+            } else {
+                self.layout_dirty = true;
+            }
+        }
+        let old = std::mem::replace(&mut self.tree, tree);
+        self.hit_index = None;
+        if self.tree_painted {
+            // Keep it to compare with, for reusing what is the same
+            self.painted = Some(old);
+            self.tree_painted = false;
+        }
+    }
+
+    /// When the next frame has to be drawn even if nothing changes, such as
+    /// the next blink of a text caret. The caller should wait until then and
+    /// call `render` again.
+    pub fn next_frame_at(&self) -> Option<Instant> {
+        self.painter.next_frame_at
     }
 
-    /// Mark the tree as dirty, forcing a rebuild on next render
+    /// Whether to round layout to whole pixels before painting (on by
+    /// default). See `vitae_core::snap_to_pixels`.
+    pub fn set_pixel_snapping(&mut self, enabled: bool) {
+        if self.pixel_snapping != enabled {
+            self.pixel_snapping = enabled;
+            self.layout_dirty = true;
+        }
+    }
+
+    /// Mark the layout as dirty, forcing a relayout and a new frame on next
+    /// render
     pub fn invalidate(&mut self) {
-        self.tree_dirty = true;
+        self.layout_dirty = true;
+        self.presented = false;
     }
 
-    /// Build and layout the tree if dirty, otherwise return cached tree
+    /// Lay out the tree if dirty, otherwise return it as it was laid out
     fn ensure_tree(&mut self) -> &ElementTree {
-        if self.tree_dirty || self.cached_tree.is_none() {
-            let mut tree = self.root_element.clone().build();
-            let root = tree.root;
-
-            let mut measurer = ParleyMeasurer {
-                font_cx: &mut self.font_cx,
-                layout_cx: &mut self.layout_cx,
-                font_size: DEFAULT_FONT_SIZE,
-            };
-
-            layout(
-                &mut tree,
-                root,
-                Constraints {
-                    max_w: self.size.width as f32,
-                    max_h: self.size.height as f32,
-                },
-                0.0,
-                0.0,
-                &mut measurer,
+        if self.layout_dirty {
+            let scale_factor = self.scale_factor as f32;
+            lay_out(
+                &mut self.tree,
+                self.size.width as f32 / scale_factor,
+                self.size.height as f32 / scale_factor,
+                self.pixel_snapping,
+                scale_factor,
             );
-
-            self.cached_tree = Some(tree);
-            self.tree_dirty = false;
+            self.layout_dirty = false;
+            self.tree_painted = false;
+            self.hit_index = None;
         }
-        self.cached_tree.as_ref().unwrap()
+        &self.tree
     }
 
-    pub fn render(&mut self) -> Result<(), vello::wgpu::SurfaceError> {
-        // Ensure tree is built and laid out (uses cache if clean)
+    /// Draw the tree to the window. A lost or outdated surface is set up
+    /// again and the frame drawn on the next redraw, which this requests.
+    ///
+    /// Nothing is drawn when the tree paints the same as the frame the
+    /// window already shows.
+    pub fn render(&mut self) -> Result<(), RenderError> {
+        // Ensure the tree is laid out (keeps the last layout if clean)
         self.ensure_tree();
 
-        // Take the tree temporarily to avoid borrow conflicts with scene mutation
-        let tree = self.cached_tree.take().unwrap();
-        let root = tree.root;
+        // Nothing has changed since the frame on screen, and no caret is
+        // due to blink
+        if self.presented
+            && self.tree_painted
+            && self.painted.is_none()
+            && self.painter.next_frame_at.is_none()
+        {
+            return Ok(());
+        }
 
         // Build the Vello scene from the tree
-        self.scene.reset();
-        let mut portals = Vec::new();
-        self.render_node(&tree, root, 1.0, &mut portals);
-
-        // Render portals last (on top of everything)
-        for portal_id in portals {
-            self.render_node_and_children(&tree, portal_id, 1.0);
+        let encode_span = tracing::debug_span!("encode_scene").entered();
+        let last = match &self.painted {
+            Some(painted) => Some(painted),
+            None => self.tree_painted.then_some(&self.tree),
+        };
+        let changed = self.painter.paint_tree(&self.tree, last);
+        self.painted = None;
+        self.tree_painted = true;
+        drop(encode_span);
+        if !changed && self.presented {
+            return Ok(());
         }
-
-        // Put the tree back
-        self.cached_tree = Some(tree);
+        self.presented = false;
 
         // Render to surface
-        let device_handle = &self.context.devices[self.surface.dev_id];
-
-        self.vello_renderer
-            .render_to_texture(
-                &device_handle.device,
-                &device_handle.queue,
-                &self.scene,
-                &self.surface.target_view,
-                &RenderParams {
-                    base_color: palette::css::WHITE,
-                    width: self.size.width,
-                    height: self.size.height,
-                    antialiasing_method: AaConfig::Msaa16,
-                },
-            )
-            .expect("Failed to render to surface");
-
-        let surface_texture = self.surface.surface.get_current_texture()?;
+        let Some(surface) = &mut self.surface else {
+            return Ok(());
+        };
+        let _span = tracing::debug_span!("gpu_submit").entered();
+        let device_handle = &self.context.devices[surface.dev_id];
+
+        // The scene is painted in logical pixels and drawn in physical ones
+        let scene = if self.scale_factor == 1.0 {
+            &self.painter.scene
+        } else {
+            self.scaled_scene.reset();
+            self.scaled_scene
+                .append(&self.painter.scene, Some(Affine::scale(self.scale_factor)));
+            &self.scaled_scene
+        };
+        self.vello_renderer.render_to_texture(
+            &device_handle.device,
+            &device_handle.queue,
+            scene,
+            &surface.target_view,
+            &RenderParams {
+                base_color: palette::css::WHITE,
+                width: self.size.width,
+                height: self.size.height,
+                antialiasing_method: AaConfig::Msaa16,
+            },
+        )?;
+
+        let surface_texture = match surface.surface.get_current_texture() {
+            Ok(texture) => texture,
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                // The window changed under the surface, e.g. by being
+                // resized; set it up again and retry
+                self.context
+                    .resize_surface(surface, self.size.width, self.size.height);
+                self.window.request_redraw();
+                return Ok(());
+            }
+            Err(SurfaceError::Timeout) => {
+                // Drop this frame and try again
+                self.window.request_redraw();
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        };
         let mut encoder = device_handle
             .device
             .create_command_encoder(&CommandEncoderDescriptor {
                 label: Some("Blit encoder"),
             });
-        self.surface.blitter.copy(
+        surface.blitter.copy(
             &device_handle.device,
             &mut encoder,
-            &self.surface.target_view,
+            &surface.target_view,
             &surface_texture
                 .texture
                 .create_view(&wgpu::TextureViewDescriptor::default()),
         );
         device_handle.queue.submit(Some(encoder.finish()));
         surface_texture.present();
+        self.presented = true;
 
         Ok(())
     }
 
-    fn render_node(
-        &mut self,
-        tree: &ElementTree,
-        id: NodeId,
-        parent_opacity: f32,
-        portals: &mut Vec<NodeId>,
-    ) {
-        let node = tree.get_node(id);
-        let layout = node.layout;
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
 
-        // Get the node's own opacity and combine with parent opacity
-        let node_opacity = node.style().map(|s| s.opacity).unwrap_or(1.0);
-        let effective_opacity = parent_opacity * node_opacity;
+    /// Get the laid-out element tree, laying it out first if needed.
+    pub fn tree(&mut self) -> &ElementTree {
+        self.ensure_tree()
+    }
 
-        match &node.kind {
-            NodeKind::Element { style } => {
-                self.render_element_box(
-                    style,
-                    layout.x,
-                    layout.y,
-                    layout.width,
-                    layout.height,
-                    effective_opacity,
-                );
-            }
-            NodeKind::Text { content, style } => {
-                let text_color = style.text_color.to_array();
-                let font_size = style.font_size.unwrap_or(DEFAULT_FONT_SIZE);
-                self.render_text(
-                    content,
-                    layout.x,
-                    layout.y,
-                    layout.width,
-                    font_size,
-                    [text_color[0], text_color[1], text_color[2], text_color[3]],
-                    effective_opacity,
-                );
-            }
-            NodeKind::Texture { texture, style: _ } => {
-                self.render_texture(
-                    texture,
-                    layout.x,
-                    layout.y,
-                    layout.width,
-                    layout.height,
-                    effective_opacity,
-                );
+    /// Perform hit-testing to find which element was clicked.
+    /// Returns the deepest element under the point that has an event handler
+    /// (see `ElementTree::hit_test`).
+    pub fn hit_test(&mut self, x: f32, y: f32) -> Option<NodeId> {
+        self.ensure_tree();
+        self.hit_index
+            .get_or_insert_with(|| HitIndex::new(&self.tree))
+            .hit_test(x, y)
+    }
+
+    /// The frontmost element drawn at a point, whether or not it handles
+    /// events (see `ElementTree::element_at`).
+    pub fn element_at(&mut self, x: f32, y: f32) -> Option<NodeId> {
+        self.ensure_tree().element_at(x, y)
+    }
+
+    /// The byte offset of the character boundary nearest to a point in the
+    /// text of `node`, or of the first text inside it, as a click there
+    /// would place the caret. `None` if there is no text.
+    pub fn text_offset_at(&mut self, node: NodeId, x: f32, y: f32) -> Option<usize> {
+        let tree = self.ensure_tree();
+        let mut pending = vec![node];
+        while let Some(id) = pending.pop() {
+            let node = tree.get_node(id);
+            if let NodeKind::Text { content, style } = &node.kind {
+                let layout = node.layout;
+                let text_layout = layout_styled(content, style, Some(layout.width));
+                let cursor = Cursor::from_point(&text_layout, x - layout.x, y - layout.y);
+                return Some(cursor.index());
             }
-            NodeKind::Svg { svg, style: _ } => {
-                self.render_svg(
-                    svg,
-                    layout.x,
-                    layout.y,
-                    layout.width,
-                    layout.height,
-                    effective_opacity,
-                );
+            let children: Vec<NodeId> = tree.children(id).collect();
+            pending.extend(children.into_iter().rev());
+        }
+        None
+    }
+
+    /// Get the event handlers for the root element.
+    pub fn get_root_handlers(&self) -> &[vitae_core::EventHandler] {
+        &self.tree.get_node(self.tree.root).handlers
+    }
+}
+
+impl Painter {
+    fn new() -> Self {
+        Painter {
+            scene: Scene::new(),
+            caret_blink: CaretBlink::new(),
+            caret_drawn: false,
+            next_frame_at: None,
+            unchanged: HashMap::new(),
+            fragments: HashMap::new(),
+            next_fragments: HashMap::new(),
+            encoding_fragment: false,
+            images: HashMap::new(),
+        }
+    }
+
+    /// Ask for another frame at `at` (see `Renderer::next_frame_at`).
+    fn schedule_frame(&mut self, at: Instant) {
+        self.next_frame_at = Some(self.next_frame_at.map_or(at, |next| next.min(at)));
+    }
+
+    /// Encode `tree` into the scene, replacing what was there. `last` is
+    /// the tree painted last frame, if it is still laid out as it was then.
+    /// Returns whether the scene differs from the last frame's.
+    fn paint_tree(&mut self, tree: &ElementTree, last: Option<&ElementTree>) -> bool {
+        self.scene.reset();
+        self.caret_drawn = false;
+        self.next_frame_at = None;
+        self.unchanged.clear();
+        if let Some(last) = last {
+            find_unchanged(tree, tree.root, last, last.root, &mut self.unchanged);
+        }
+        self.render_node(tree, tree.root, 1.0);
+
+        // Render portals last (on top of everything), lowest layer first
+        for portal_id in tree.portals() {
+            self.render_node(tree, portal_id, 1.0);
+        }
+
+        // Fragments that weren't used this frame are dropped
+        self.fragments = std::mem::take(&mut self.next_fragments);
+
+        // So are images of textures that nothing but the image holds anymore
+        self.images
+            .retain(|_, (pixels, _)| pixels.strong_count() > 1);
+
+        if !self.caret_drawn {
+            self.caret_blink.reset();
+        }
+        !self.unchanged.contains_key(&tree.root)
+    }
+
+    fn render_node(&mut self, tree: &ElementTree, id: NodeId, parent_opacity: f32) {
+        if let Some(&last_id) = self.unchanged.get(&id) {
+            if self.paint_fragment(tree, id, last_id, parent_opacity) {
+                return;
             }
         }
+        self.paint_scaled(tree, id, parent_opacity);
+    }
 
-        // Render children, collecting portals
-        let mut child = node.first_child;
-        while let Some(child_id) = child {
-            let child_node = tree.get_node(child_id);
-            if let Some(style) = child_node.style() {
-                if style.position == Position::Portal {
-                    portals.push(child_id);
-                    child = child_node.next_sibling;
-                    continue;
+    /// Paint the subtree at `id`, unchanged since the last frame, where it
+    /// was `last_id`, by appending its fragment: the one from the last frame
+    /// if there is one, or else a new one. Returns false, leaving the
+    /// subtree to be painted as usual, if it needs a new fragment but is
+    /// inside one being encoded already.
+    fn paint_fragment(
+        &mut self,
+        tree: &ElementTree,
+        id: NodeId,
+        last_id: NodeId,
+        parent_opacity: f32,
+    ) -> bool {
+        let layout = tree.get_node(id).layout;
+        let fragment = match self.fragments.remove(&last_id) {
+            Some(fragment) if fragment.opacity == parent_opacity => fragment,
+            _ if self.encoding_fragment => return false,
+            _ => {
+                let outer = std::mem::take(&mut self.scene);
+                self.encoding_fragment = true;
+                self.paint_scaled(tree, id, parent_opacity);
+                self.encoding_fragment = false;
+                Fragment {
+                    scene: std::mem::replace(&mut self.scene, outer),
+                    x: layout.x,
+                    y: layout.y,
+                    opacity: parent_opacity,
                 }
             }
-            self.render_node(tree, child_id, effective_opacity, portals);
-            child = tree.get_node(child_id).next_sibling;
-        }
+        };
+        let offset = (
+            (layout.x - fragment.x) as f64,
+            (layout.y - fragment.y) as f64,
+        );
+        self.scene
+            .append(&fragment.scene, Some(Affine::translate(offset)));
+        self.next_fragments.insert(id, fragment);
+        true
+    }
+
+    fn paint_scaled(&mut self, tree: &ElementTree, id: NodeId, parent_opacity: f32) {
+        let node = tree.get_node(id);
+        let scale = node.style().map(|s| s.scale).unwrap_or(1.0);
+        self.with_scale(scale, node.layout, |this| {
+            this.paint_node(tree, id, parent_opacity)
+        });
     }
 
-    /// Render a node and all its children (used for portals, no portal collection).
-    fn render_node_and_children(&mut self, tree: &ElementTree, id: NodeId, parent_opacity: f32) {
+    fn paint_node(&mut self, tree: &ElementTree, id: NodeId, parent_opacity: f32) {
         let node = tree.get_node(id);
         let layout = node.layout;
 
         // Get the node's own opacity and combine with parent opacity
-        let node_opacity = node.style().map(|s| s.opacity).unwrap_or(1.0);
+        let node_opacity = node.style().map(|s| s.paint_opacity()).unwrap_or(1.0);
         let effective_opacity = parent_opacity * node_opacity;
 
         match &node.kind {
@@ -319,27 +775,17 @@ impl<'a> Renderer<'a> {
                 );
             }
             NodeKind::Text { content, style } => {
-                let text_color = style.text_color.to_array();
-                let font_size = style.font_size.unwrap_or(DEFAULT_FONT_SIZE);
                 self.render_text(
                     content,
+                    style,
                     layout.x,
                     layout.y,
                     layout.width,
-                    font_size,
-                    [text_color[0], text_color[1], text_color[2], text_color[3]],
                     effective_opacity,
                 );
             }
-            NodeKind::Texture { texture, style: _ } => {
-                self.render_texture(
-                    texture,
-                    layout.x,
-                    layout.y,
-                    layout.width,
-                    layout.height,
-                    effective_opacity,
-                );
+            NodeKind::Texture { texture, style } => {
+                self.render_texture(texture, style.object_fit, layout, effective_opacity);
             }
             NodeKind::Svg { svg, style: _ } => {
                 self.render_svg(
@@ -353,11 +799,46 @@ impl<'a> Renderer<'a> {
             }
         }
 
-        let mut child = node.first_child;
-        while let Some(child_id) = child {
-            self.render_node_and_children(tree, child_id, effective_opacity);
-            child = tree.get_node(child_id).next_sibling;
+        let clip = node
+            .style()
+            .filter(|style| style.overflow == Overflow::Hidden)
+            .map(|style| clip_shape(style, layout));
+        if let Some(clip) = &clip {
+            self.scene
+                .push_layer(BlendMode::default(), 1.0, Affine::IDENTITY, clip);
         }
+
+        // Render children, leaving portals to be rendered on top
+        for child_id in tree.children(id) {
+            if !tree.get_node(child_id).is_portal() {
+                self.render_node(tree, child_id, effective_opacity);
+            }
+        }
+
+        if clip.is_some() {
+            self.scene.pop_layer();
+        }
+    }
+
+    /// Run `paint`, scaling everything it draws by `scale` around the center
+    /// of `layout`.
+    fn with_scale(&mut self, scale: f32, layout: Layout, paint: impl FnOnce(&mut Self)) {
+        if scale == 1.0 {
+            paint(self);
+            return;
+        }
+
+        // Paint into a separate scene, then append it with the transform
+        let outer = std::mem::take(&mut self.scene);
+        paint(self);
+        let inner = std::mem::replace(&mut self.scene, outer);
+
+        let cx = (layout.x + layout.width / 2.0) as f64;
+        let cy = (layout.y + layout.height / 2.0) as f64;
+        let transform = Affine::translate((cx, cy))
+            * Affine::scale(scale as f64)
+            * Affine::translate((-cx, -cy));
+        self.scene.append(&inner, Some(transform));
     }
 
     /// Render an element's background and border.
@@ -429,11 +910,16 @@ impl<'a> Renderer<'a> {
             let stroke = Stroke::new(border.top.width as f64)
                 .with_caps(Cap::Butt)
                 .with_join(Join::Miter);
+            // Stroke inside the element's edge, like the individual borders,
+            // so a border on a whole-pixel edge covers whole pixels
+            let half = border.top.width as f64 / 2.0;
+            let rect = rect.inset(-half);
 
             if has_radius {
+                let inner = |radius: f32| (radius as f64 - half).max(0.0);
                 let rounded_rect = RoundedRect::from_rect(
                     rect,
-                    RoundedRectRadii::new(tl as f64, tr as f64, br as f64, bl as f64),
+                    RoundedRectRadii::new(inner(tl), inner(tr), inner(br), inner(bl)),
                 );
                 self.scene
                     .stroke(&stroke, Affine::IDENTITY, vello_color, None, &rounded_rect);
@@ -519,41 +1005,39 @@ impl<'a> Renderer<'a> {
     fn render_text(
         &mut self,
         text: &str,
+        style: &vitae_core::Style,
         x: f32,
         y: f32,
         max_width: f32,
-        font_size: f32,
-        color: [f32; 4],
         opacity: f32,
     ) {
-        let line_height = 1.2;
-
-        let mut builder = self
-            .layout_cx
-            .ranged_builder(&mut self.font_cx, text, 1.0, true);
-
-        // Set font family stack with system UI font first, then symbol fonts as fallback
-        // This way regular text uses the nice system font, but chess symbols still work
-        builder.push_default(StyleProperty::FontStack(parley::style::FontStack::List(
-            Cow::Borrowed(&[
-                parley::style::FontFamily::Generic(parley::style::GenericFamily::SystemUi),
-                parley::style::FontFamily::Named(Cow::Borrowed("Noto Sans Symbols 2")),
-                parley::style::FontFamily::Named(Cow::Borrowed("Segoe UI Symbol")),
-                parley::style::FontFamily::Named(Cow::Borrowed("Apple Symbols")),
-                parley::style::FontFamily::Generic(parley::style::GenericFamily::SansSerif),
-            ]),
-        )));
-
-        builder.push_default(StyleProperty::FontSize(font_size));
-        builder.push_default(StyleProperty::LineHeight(LineHeight::FontSizeRelative(
-            line_height,
-        )));
-        let mut text_layout = builder.build(text);
-        text_layout.break_all_lines(Some(max_width));
+        let text_layout = layout_styled(text, style, Some(max_width));
 
+        let color = style.text_color.to_array();
         let text_color =
             vello::peniko::Color::new([color[0], color[1], color[2], color[3] * opacity]);
 
+        // The selection goes behind the glyphs it covers
+        if let Some((start, end)) = style.selection {
+            let [r, g, b, a] = SELECTION_COLOR;
+            let selection_color = vello::peniko::Color::new([r, g, b, a * opacity]);
+            for piece in selection_rects_in(&text_layout, start..end) {
+                let rect = Rect::new(
+                    (x + piece.x) as f64,
+                    (y + piece.y) as f64,
+                    (x + piece.x + piece.width) as f64,
+                    (y + piece.y + piece.height) as f64,
+                );
+                self.scene.fill(
+                    Fill::NonZero,
+                    Affine::IDENTITY,
+                    selection_color,
+                    None,
+                    &rect,
+                );
+            }
+        }
+
         for line in text_layout.lines() {
             for item in line.items() {
                 if let parley::PositionedLayoutItem::GlyphRun(glyph_run) = item {
@@ -571,6 +1055,24 @@ impl<'a> Renderer<'a> {
                     let mut gx = x + glyph_run.offset();
                     let gy = y + glyph_run.baseline();
 
+                    if style.underline {
+                        let metrics = run.metrics();
+                        let underline_y = gy - metrics.underline_offset;
+                        let underline = Rect::new(
+                            gx as f64,
+                            underline_y as f64,
+                            (gx + glyph_run.advance()) as f64,
+                            (underline_y + metrics.underline_size) as f64,
+                        );
+                        self.scene.fill(
+                            Fill::NonZero,
+                            Affine::IDENTITY,
+                            text_color,
+                            None,
+                            &underline,
+                        );
+                    }
+
                     self.scene
                         .draw_glyphs(font)
                         .font_size(font_size)
@@ -594,37 +1096,84 @@ impl<'a> Renderer<'a> {
                 }
             }
         }
+
+        if let Some(offset) = style.caret {
+            let caret = caret_rect_in(&text_layout, offset);
+            let now = Instant::now();
+            if self.caret_blink.update(caret, now) {
+                let rect = Rect::new(
+                    (x + caret.x) as f64,
+                    (y + caret.y) as f64,
+                    (x + caret.x + caret.width) as f64,
+                    (y + caret.y + caret.height) as f64,
+                );
+                self.scene
+                    .fill(Fill::NonZero, Affine::IDENTITY, text_color, None, &rect);
+            }
+            self.caret_drawn = true;
+            let next_toggle = self.caret_blink.next_toggle(now);
+            self.schedule_frame(next_toggle);
+        }
     }
 
-    fn render_texture(
-        &mut self,
-        texture: &Texture,
-        x: f32,
-        y: f32,
-        width: f32,
-        height: f32,
-        opacity: f32,
-    ) {
-        // Create peniko ImageData from texture data
-        let blob: Blob<u8> = texture.data().to_vec().into();
-        let image_data = ImageData {
-            data: blob,
-            format: ImageFormat::Rgba8,
-            alpha_type: ImageAlphaType::Alpha,
-            width: texture.width(),
-            height: texture.height(),
+    fn render_texture(&mut self, texture: &Texture, fit: ObjectFit, layout: Layout, opacity: f32) {
+        let image_brush = ImageBrush::new(self.image_data(texture)).with_alpha(opacity);
+
+        // Scale the image as `fit` says, and center it in the element
+        let image_w = texture.width() as f32;
+        let image_h = texture.height() as f32;
+        let (scale_x, scale_y) = match fit {
+            ObjectFit::Fill => (layout.width / image_w, layout.height / image_h),
+            ObjectFit::Contain => {
+                let scale = (layout.width / image_w).min(layout.height / image_h);
+                (scale, scale)
+            }
+            ObjectFit::Cover => {
+                let scale = (layout.width / image_w).max(layout.height / image_h);
+                (scale, scale)
+            }
+            ObjectFit::None => (1.0, 1.0),
         };
-        let image_brush = ImageBrush::new(image_data).with_alpha(opacity);
-
-        // Calculate scale to fit the layout dimensions
-        let scale_x = width / texture.width() as f32;
-        let scale_y = height / texture.height() as f32;
+        let x = layout.x + (layout.width - image_w * scale_x) / 2.0;
+        let y = layout.y + (layout.height - image_h * scale_y) / 2.0;
 
         // Create transform: scale first, then translate to position
         let transform = Affine::scale_non_uniform(scale_x as f64, scale_y as f64)
             .then_translate((x as f64, y as f64).into());
 
-        self.scene.draw_image(image_brush.as_ref(), transform);
+        // Cut off the parts of the image outside the element
+        let overflows = x < layout.x || y < layout.y;
+        if overflows {
+            let clip_rect = Rect::new(
+                layout.x as f64,
+                layout.y as f64,
+                (layout.x + layout.width) as f64,
+                (layout.y + layout.height) as f64,
+            );
+            self.scene
+                .push_layer(BlendMode::default(), 1.0, Affine::IDENTITY, &clip_rect);
+            self.scene.draw_image(image_brush.as_ref(), transform);
+            self.scene.pop_layer();
+        } else {
+            self.scene.draw_image(image_brush.as_ref(), transform);
+        }
+    }
+
+    /// The image of `texture`'s pixels, made the first time it is painted
+    /// and shared with the texture rather than copied.
+    fn image_data(&mut self, texture: &Texture) -> ImageData {
+        let (_, image_data) = self.images.entry(texture.id()).or_insert_with(|| {
+            let pixels = texture.shared_data();
+            let image_data = ImageData {
+                data: Blob::new(pixels.clone()),
+                format: ImageFormat::Rgba8,
+                alpha_type: ImageAlphaType::Alpha,
+                width: texture.width(),
+                height: texture.height(),
+            };
+            (Arc::downgrade(pixels), image_data)
+        });
+        image_data.clone()
     }
 
     fn render_svg(&mut self, svg: &Svg, x: f32, y: f32, width: f32, height: f32, opacity: f32) {
@@ -658,128 +1207,4 @@ impl<'a> Renderer<'a> {
             self.scene.append(&svg_scene, Some(transform));
         }
     }
-
-    pub fn window(&self) -> &Window {
-        &self.window
-    }
-
-    /// Perform hit-testing to find which element was clicked
-    /// Returns the event handler if an element with a click handler was hit
-    pub fn hit_test(&mut self, x: f32, y: f32) -> Option<vitae_core::EventHandler> {
-        // Use cached tree (ensures it's built and laid out)
-        self.ensure_tree();
-        let tree = self.cached_tree.as_ref().unwrap();
-
-        // Collect portals first, then check them (they're rendered on top)
-        let mut portals = Vec::new();
-        self.collect_portals(tree, tree.root, &mut portals);
-
-        // Check portals first (last rendered = frontmost)
-        for portal_id in portals.iter().rev() {
-            if let Some(handler) = self.hit_test_node_all(tree, *portal_id, x, y) {
-                return Some(handler);
-            }
-        }
-
-        // Then check the normal tree
-        self.hit_test_node(tree, tree.root, x, y, &portals)
-    }
-
-    fn collect_portals(
-        &self,
-        tree: &vitae_core::ElementTree,
-        node_id: vitae_core::NodeId,
-        portals: &mut Vec<vitae_core::NodeId>,
-    ) {
-        let node = tree.get_node(node_id);
-
-        let mut child = node.first_child;
-        while let Some(child_id) = child {
-            let child_node = tree.get_node(child_id);
-            if let Some(style) = child_node.style() {
-                if style.position == Position::Portal {
-                    portals.push(child_id);
-                    child = child_node.next_sibling;
-                    continue;
-                }
-            }
-            self.collect_portals(tree, child_id, portals);
-            child = tree.get_node(child_id).next_sibling;
-        }
-    }
-
-    fn hit_test_node(
-        &self,
-        tree: &vitae_core::ElementTree,
-        node_id: vitae_core::NodeId,
-        x: f32,
-        y: f32,
-        portals: &[vitae_core::NodeId],
-    ) -> Option<vitae_core::EventHandler> {
-        let node = tree.get_node(node_id);
-        let layout = &node.layout;
-
-        // Check if point is inside this node's bounds
-        let in_bounds = x >= layout.x
-            && x <= layout.x + layout.width
-            && y >= layout.y
-            && y <= layout.y + layout.height;
-
-        if !in_bounds {
-            return None;
-        }
-
-        // Check children first (they're on top), skipping portals
-        let mut child = node.first_child;
-        while let Some(child_id) = child {
-            // Skip portals - they're handled separately
-            if portals.contains(&child_id) {
-                child = tree.get_node(child_id).next_sibling;
-                continue;
-            }
-            if let Some(handler) = self.hit_test_node(tree, child_id, x, y, portals) {
-                return Some(handler);
-            }
-            child = tree.get_node(child_id).next_sibling;
-        }
-
-        // If no child was hit, check if this node has a handler
-        node.on_event.clone()
-    }
-
-    /// Hit test a node and all children (used for portals, no skipping)
-    fn hit_test_node_all(
-        &self,
-        tree: &vitae_core::ElementTree,
-        node_id: vitae_core::NodeId,
-        x: f32,
-        y: f32,
-    ) -> Option<vitae_core::EventHandler> {
-        let node = tree.get_node(node_id);
-        let layout = &node.layout;
-
-        let in_bounds = x >= layout.x
-            && x <= layout.x + layout.width
-            && y >= layout.y
-            && y <= layout.y + layout.height;
-
-        if !in_bounds {
-            return None;
-        }
-
-        let mut child = node.first_child;
-        while let Some(child_id) = child {
-            if let Some(handler) = self.hit_test_node_all(tree, child_id, x, y) {
-                return Some(handler);
-            }
-            child = tree.get_node(child_id).next_sibling;
-        }
-
-        node.on_event.clone()
-    }
-
-    /// Get the event handler for the root element.
-    pub fn get_root_handler(&self) -> Option<vitae_core::EventHandler> {
-        self.root_element.get_event_handler()
-    }
 }
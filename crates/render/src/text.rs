@@ -0,0 +1,475 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use parley::fontique::{Blob, FallbackKey, Language, Script};
+use parley::style::{FontFamily, FontStack, FontStyle, FontWeight, GenericFamily};
+use parley::{
+    Alignment, AlignmentOptions, FontContext, Layout, LayoutContext, LineHeight, StyleProperty,
+};
+use vitae_core::TextAlign;
+
+// Sensible defaults (TODO: replace with theme system)
+/// The size of text without a font size of its own, which is also the size
+/// text is measured at for layout.
+pub const DEFAULT_FONT_SIZE: f32 = 24.0;
+const LINE_HEIGHT: f32 = 1.2;
+
+/// The fonts used for all text, in the order they are tried.
+///
+/// Family names are either installed font names or one of the CSS generic
+/// families (`system-ui`, `sans-serif`, `serif`, `monospace`, ...). Families
+/// that aren't installed are skipped, so a single list can name fonts for
+/// several platforms.
+///
+/// When none of the families has a glyph, the system fallback for the
+/// character's script is used. `fallback` and `language_fallback` override
+/// that per script, e.g. to prefer Japanese over Chinese forms of Han
+/// characters.
+///
+/// # Example
+/// ```
+/// FontConfig::new(["Inter", "system-ui", "sans-serif"])
+///     .fallback("Arab", ["Noto Naskh Arabic", "Geeza Pro"])
+///     .language_fallback("Hani", "ja", ["Hiragino Sans", "Noto Sans CJK JP"])
+///     .locale("ja")
+/// ```
+#[derive(Clone, Debug)]
+pub struct FontConfig {
+    families: Vec<String>,
+    fallbacks: Vec<ScriptFallback>,
+    locale: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct ScriptFallback {
+    script: [u8; 4],
+    language: Option<String>,
+    families: Vec<String>,
+}
+
+impl Default for FontConfig {
+    /// The system UI font, followed by symbol fonts so chess pieces and
+    /// other symbols render on every platform.
+    fn default() -> Self {
+        Self::new([
+            "system-ui",
+            "Noto Sans Symbols 2",
+            "Segoe UI Symbol",
+            "Apple Symbols",
+            "sans-serif",
+        ])
+    }
+}
+
+impl FontConfig {
+    /// Use `families` as the font stack, in priority order.
+    pub fn new(families: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            families: families.into_iter().map(Into::into).collect(),
+            fallbacks: Vec::new(),
+            locale: None,
+        }
+    }
+
+    /// Fonts to try for characters in `script` that the main stack can't
+    /// render. `script` is a four-letter ISO 15924 tag such as `"Hani"`,
+    /// `"Arab"` or `"Cyrl"`.
+    ///
+    /// # Panics
+    /// Panics if `script` is not four ASCII letters.
+    pub fn fallback(
+        mut self,
+        script: &str,
+        families: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.fallbacks.push(ScriptFallback {
+            script: script_tag(script),
+            language: None,
+            families: families.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Like `fallback`, but only for text in `language` (a BCP 47 tag such
+    /// as `"ja"` or `"zh-Hant"`). Set the language of the text with
+    /// `locale`.
+    ///
+    /// # Panics
+    /// Panics if `script` is not four ASCII letters.
+    pub fn language_fallback(
+        mut self,
+        script: &str,
+        language: &str,
+        families: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.fallbacks.push(ScriptFallback {
+            script: script_tag(script),
+            language: Some(language.to_string()),
+            families: families.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// The language of the application's text, as a BCP 47 tag. Selects the
+    /// matching `language_fallback` fonts and language-specific glyph forms.
+    pub fn locale(mut self, language: &str) -> Self {
+        self.locale = Some(language.to_string());
+        self
+    }
+}
+
+/// The family named `name`, which may be a CSS generic family such as
+/// `serif`.
+fn family(name: &str) -> FontFamily<'static> {
+    match GenericFamily::parse(name) {
+        Some(generic) => FontFamily::Generic(generic),
+        None => FontFamily::Named(Cow::Owned(name.to_string())),
+    }
+}
+
+fn script_tag(script: &str) -> [u8; 4] {
+    match <[u8; 4]>::try_from(script.as_bytes()) {
+        Ok(tag) if tag.iter().all(u8::is_ascii_alphabetic) => tag,
+        _ => panic!("{script:?} is not a four-letter ISO 15924 script tag"),
+    }
+}
+
+/// Parley state shared by layout measurement, rendering and `measure`.
+struct TextContext {
+    font_cx: FontContext,
+    layout_cx: LayoutContext<()>,
+    /// Families registered from font data, tried before the configured stack.
+    embedded: Vec<FontFamily<'static>>,
+    families: Vec<FontFamily<'static>>,
+    locale: Option<String>,
+    /// The `SharedFonts::generation` this context's fonts match.
+    generation: u64,
+}
+
+impl TextContext {
+    /// A context with the fonts set up so far with `set_font_config` and
+    /// `register_font`, on whichever thread they were set up.
+    fn new(shared: &SharedFonts) -> Self {
+        let mut cx = Self {
+            font_cx: FontContext::new(),
+            layout_cx: LayoutContext::new(),
+            embedded: Vec::new(),
+            families: Vec::new(),
+            locale: None,
+            generation: shared.generation,
+        };
+        cx.configure(shared.config.clone().unwrap_or_default());
+        for data in &shared.registered {
+            cx.register_font(data);
+        }
+        cx
+    }
+
+    fn configure(&mut self, config: FontConfig) {
+        self.families = self.embedded.clone();
+        self.families
+            .extend(config.families.iter().map(|name| family(name)));
+        self.locale = config.locale;
+
+        let collection = &mut self.font_cx.collection;
+        for fallback in config.fallbacks {
+            let language = fallback
+                .language
+                .and_then(|language| Language::try_from_utf8(language.as_bytes()).ok());
+            let key = FallbackKey::new(Script(fallback.script), language.as_ref());
+            // Fonts that aren't installed on this system are skipped.
+            let families: Vec<_> = fallback
+                .families
+                .iter()
+                .filter_map(|name| collection.family_id(name))
+                .collect();
+            collection.set_fallbacks(key, families.into_iter());
+        }
+    }
+
+    fn register_font(&mut self, data: &'static [u8]) -> Vec<String> {
+        let collection = &mut self.font_cx.collection;
+        let names: Vec<String> = collection
+            .register_fonts(Blob::new(Arc::new(data)), None)
+            .into_iter()
+            .filter_map(|(id, _)| collection.family_name(id).map(str::to_string))
+            .collect();
+        for name in &names {
+            let family = FontFamily::Named(Cow::Owned(name.clone()));
+            if !self.embedded.contains(&family) {
+                self.families.insert(self.embedded.len(), family.clone());
+                self.embedded.push(family);
+            }
+        }
+        names
+    }
+}
+
+/// The fonts set up with `set_font_config` and `register_font`, which each
+/// thread's context is built from.
+struct SharedFonts {
+    config: Option<FontConfig>,
+    registered: Vec<&'static [u8]>,
+    /// Bumped on every change, so contexts built before it can tell.
+    generation: u64,
+}
+
+static SHARED_FONTS: Mutex<SharedFonts> = Mutex::new(SharedFonts {
+    config: None,
+    registered: Vec::new(),
+    generation: 0,
+});
+
+/// `SharedFonts::generation`, readable without taking the lock on every
+/// piece of text laid out.
+static FONT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn shared_fonts() -> MutexGuard<'static, SharedFonts> {
+    SHARED_FONTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+thread_local! {
+    static TEXT_CONTEXT: RefCell<Option<TextContext>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with this thread's context, building it again first if the fonts
+/// have changed since it was built, such as on another thread.
+fn with_text_context<R>(f: impl FnOnce(&mut TextContext) -> R) -> R {
+    TEXT_CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        let generation = FONT_GENERATION.load(Ordering::Acquire);
+        if context
+            .as_ref()
+            .is_none_or(|cx| cx.generation != generation)
+        {
+            *context = Some(TextContext::new(&shared_fonts()));
+        }
+        f(context.as_mut().unwrap())
+    })
+}
+
+/// Apply a change already made to `cx` to the shared fonts with `change`,
+/// so other threads pick it up too.
+fn share(cx: &mut TextContext, change: impl FnOnce(&mut SharedFonts)) {
+    let mut shared = shared_fonts();
+    change(&mut shared);
+    shared.generation += 1;
+    // Only a context that had every earlier change is now up to date
+    if cx.generation + 1 == shared.generation {
+        cx.generation = shared.generation;
+    }
+    FONT_GENERATION.store(shared.generation, Ordering::Release);
+}
+
+/// Replace the fonts used for all text, on every thread.
+///
+/// Applications normally set this through `App::fonts` before the window
+/// opens; changing it later only affects text laid out afterwards.
+pub fn set_font_config(config: FontConfig) {
+    with_text_context(|cx| {
+        cx.configure(config.clone());
+        share(cx, |shared| shared.config = Some(config));
+    });
+}
+
+/// Register a font from its file contents (TTF, OTF or a collection) and
+/// return the names of the families it contains.
+///
+/// Registered fonts are tried before the configured font stack, so text
+/// renders the same everywhere regardless of which fonts are installed.
+pub fn register_font(data: &'static [u8]) -> Vec<String> {
+    with_text_context(|cx| {
+        let names = cx.register_font(data);
+        share(cx, |shared| shared.registered.push(data));
+        names
+    })
+}
+
+/// Shape `text` and break it into lines no wider than `max_width`.
+///
+/// Runs of right-to-left text (Arabic, Hebrew) are reordered for display
+/// within each line, and lines break where Unicode allows, which includes
+/// between most CJK characters but not before closing punctuation. Lines
+/// start on the side the paragraph's direction starts on, so a Hebrew
+/// paragraph lines up on the right of `max_width`.
+pub(crate) fn layout_text(text: &str, font_size: f32, max_width: Option<f32>) -> Layout<()> {
+    layout_text_in(text, font_size, max_width, false)
+}
+
+/// Like `layout_text`, in the monospace font if `monospace`, falling back
+/// to the configured fonts for characters it doesn't have.
+pub(crate) fn layout_text_in(
+    text: &str,
+    font_size: f32,
+    max_width: Option<f32>,
+    monospace: bool,
+) -> Layout<()> {
+    let style = vitae_core::Style {
+        font_size: Some(font_size),
+        monospace,
+        ..Default::default()
+    };
+    build_layout(text, &style, |text_layout| {
+        text_layout.break_all_lines(max_width);
+        text_layout.align(max_width, Alignment::Start, AlignmentOptions::default());
+    })
+}
+
+/// Lay out the text of a text element styled with `style` in `max_width`:
+/// in its font, at its size and line height, lined up as its `text_align`
+/// says, and left on one line if it is `no_wrap`.
+///
+/// Text that takes more lines than its `max_lines` is cut short at the end
+/// of the last of them, with an ellipsis in place of the rest.
+pub(crate) fn layout_styled(
+    text: &str,
+    style: &vitae_core::Style,
+    max_width: Option<f32>,
+) -> Layout<()> {
+    let break_width = max_width.filter(|_| !style.no_wrap);
+    let alignment = match style.text_align {
+        TextAlign::Start => Alignment::Start,
+        TextAlign::Left => Alignment::Left,
+        TextAlign::Center => Alignment::Center,
+        TextAlign::Right => Alignment::Right,
+    };
+    let lay_out = |text: &str| {
+        build_layout(text, style, |text_layout| {
+            text_layout.break_all_lines(break_width);
+            text_layout.align(max_width, alignment, AlignmentOptions::default());
+        })
+    };
+
+    let text_layout = lay_out(text);
+    let Some(max_lines) = style.max_lines.filter(|&n| text_layout.len() > n) else {
+        return text_layout;
+    };
+
+    // The longest start of the text that fits with the ellipsis after it
+    let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    let truncated = |count: usize| format!("{}…", text[..boundaries[count]].trim_end());
+    let (mut fits, mut overflows) = (0, boundaries.len());
+    while overflows - fits > 1 {
+        let count = (fits + overflows) / 2;
+        if lay_out(&truncated(count)).len() <= max_lines {
+            fits = count;
+        } else {
+            overflows = count;
+        }
+    }
+    lay_out(&truncated(fits))
+}
+
+/// Shape `text` in the font, size and line height of `style`, and let
+/// `finish` break and align it.
+fn build_layout(
+    text: &str,
+    style: &vitae_core::Style,
+    finish: impl FnOnce(&mut Layout<()>),
+) -> Layout<()> {
+    with_text_context(|cx| {
+        let mut builder = cx
+            .layout_cx
+            .ranged_builder(&mut cx.font_cx, text, 1.0, true);
+
+        let mut preferred = Vec::new();
+        if let Some(name) = &style.font_family {
+            preferred.push(family(name));
+        }
+        if style.monospace {
+            preferred.push(FontFamily::Generic(GenericFamily::Monospace));
+        }
+        let families = if preferred.is_empty() {
+            Cow::Borrowed(&cx.families[..])
+        } else {
+            preferred.extend(cx.families.iter().cloned());
+            Cow::Owned(preferred)
+        };
+        builder.push_default(StyleProperty::FontStack(FontStack::List(families)));
+        if let Some(locale) = &cx.locale {
+            builder.push_default(StyleProperty::Locale(Some(locale.as_str())));
+        }
+        builder.push_default(StyleProperty::FontSize(
+            style.font_size.unwrap_or(DEFAULT_FONT_SIZE),
+        ));
+        builder.push_default(StyleProperty::LineHeight(LineHeight::FontSizeRelative(
+            style.line_height.unwrap_or(LINE_HEIGHT),
+        )));
+        if let Some(weight) = style.font_weight {
+            builder.push_default(StyleProperty::FontWeight(FontWeight::new(weight as f32)));
+        }
+        if style.italic {
+            builder.push_default(StyleProperty::FontStyle(FontStyle::Italic));
+        }
+        let mut text_layout = builder.build(text);
+        finish(&mut text_layout);
+        text_layout
+    })
+}
+
+/// Measure the text of a text element styled with `style` as
+/// `layout_styled` lays it out.
+pub(crate) fn measure_styled(
+    text: &str,
+    style: &vitae_core::Style,
+    max_width: Option<f32>,
+) -> (f32, f32) {
+    let text_layout = layout_styled(text, style, max_width);
+    (text_layout.width(), text_layout.height())
+}
+
+/// Measure the size `text` takes up when rendered at `font_size`, wrapping at
+/// `max_width` if given.
+///
+/// Uses the same fonts and shaping as the renderer, so the result matches
+/// what ends up on screen.
+///
+/// # Example
+/// ```
+/// let (w, _) = vitae::text::measure(&label, 16.0, None);
+/// let label = if w > 200.0 { short_label } else { label };
+/// ```
+pub fn measure(text: &str, font_size: f32, max_width: Option<f32>) -> (f32, f32) {
+    let text_layout = layout_text(text, font_size, max_width);
+    (text_layout.width(), text_layout.height())
+}
+
+/// Like `measure`, for text drawn in the monospace font.
+pub fn measure_monospace(text: &str, font_size: f32, max_width: Option<f32>) -> (f32, f32) {
+    let text_layout = layout_text_in(text, font_size, max_width, true);
+    (text_layout.width(), text_layout.height())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn threads_measure_with_fonts_set_after_they_started() {
+        let text = "iiii mmmm";
+        let (ask, asked) = mpsc::channel::<()>();
+        let (answer, answers) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            for () in asked {
+                answer.send(measure(text, 16.0, None)).unwrap();
+            }
+        });
+
+        // The worker builds its context with the default fonts first
+        ask.send(()).unwrap();
+        answers.recv().unwrap();
+
+        set_font_config(FontConfig::new(["monospace"]));
+        ask.send(()).unwrap();
+        assert_eq!(answers.recv().unwrap(), measure(text, 16.0, None));
+
+        drop(ask);
+        worker.join().unwrap();
+    }
+}
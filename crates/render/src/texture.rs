@@ -18,6 +18,30 @@ pub fn load_texture<P: AsRef<Path>>(path: P) -> Result<Texture, image::ImageErro
     Ok(Texture::from_rgba(rgba.into_raw(), width, height))
 }
 
+/// Load an image file as a texture no larger than `max_size` pixels on
+/// either side, keeping its aspect ratio.
+///
+/// Smaller images keep their size. The image is still decoded in full, so
+/// call this off the UI thread when loading many images.
+///
+/// # Example
+/// ```no_run
+/// let thumbnail = load_thumbnail("photo.jpg", 256)?;
+/// ```
+pub fn load_thumbnail<P: AsRef<Path>>(
+    path: P,
+    max_size: u32,
+) -> Result<Texture, image::ImageError> {
+    let img = image::open(path)?;
+    let rgba = if img.width() > max_size || img.height() > max_size {
+        img.thumbnail(max_size, max_size).into_rgba8()
+    } else {
+        img.into_rgba8()
+    };
+    let (width, height) = rgba.dimensions();
+    Ok(Texture::from_rgba(rgba.into_raw(), width, height))
+}
+
 /// Load an SVG from a file.
 ///
 /// # Example
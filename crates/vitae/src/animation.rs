@@ -0,0 +1,350 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use vitae_core::{Easing, Interpolate, Keyframes, Spring};
+
+use crate::signal::{request_redraw, use_signal, Signal};
+
+/// How many interrupted springs keep moving under the current one. Older
+/// ones stop where they are.
+const MAX_MOVING_ORIGINS: usize = 8;
+
+/// How a transition moves from its start value to its target.
+#[derive(Clone, Copy)]
+enum Motion {
+    Tween { duration: Duration, easing: Easing },
+    Spring(Spring),
+}
+
+/// Where a transition starts from.
+#[derive(Clone)]
+enum Origin<T> {
+    At(T),
+    /// A spring given a new target before it settled. It keeps moving under
+    /// the new one, which starts at rest, so the value keeps its velocity.
+    Moving(Rc<Tween<T>>),
+}
+
+/// An in-flight (or finished) transition between two values.
+#[derive(Clone)]
+struct Tween<T> {
+    from: Origin<T>,
+    to: T,
+    start: Instant,
+    motion: Motion,
+}
+
+impl<T: Interpolate> Tween<T> {
+    /// Progress along the motion curve, or `None` once it has finished.
+    fn progress(&self, now: Instant) -> Option<f32> {
+        let elapsed = now.saturating_duration_since(self.start);
+        match self.motion {
+            Motion::Tween { duration, easing } => {
+                let t = if duration.is_zero() {
+                    1.0
+                } else {
+                    elapsed.as_secs_f32() / duration.as_secs_f32()
+                };
+                (t < 1.0).then(|| easing.apply(t))
+            }
+            Motion::Spring(spring) => {
+                let t = elapsed.as_secs_f32();
+                (!spring.is_settled(t)).then(|| spring.progress(t))
+            }
+        }
+    }
+
+    fn value(&self, now: Instant) -> T {
+        match self.progress(now) {
+            Some(t) => {
+                let from = match &self.from {
+                    Origin::At(value) => value.clone(),
+                    Origin::Moving(tween) => tween.value(now),
+                };
+                from.lerp(&self.to, t)
+            }
+            None => self.to.clone(),
+        }
+    }
+
+    /// The transition to `target` from wherever this one is at `now`.
+    /// Springs carry on from their current velocity; tweens start afresh.
+    fn retarget(&self, target: T, now: Instant) -> Tween<T> {
+        let from = match self.motion {
+            Motion::Spring(_) if self.progress(now).is_some() => {
+                Origin::Moving(Rc::new(self.trim(now, MAX_MOVING_ORIGINS - 1)))
+            }
+            _ => Origin::At(self.value(now)),
+        };
+        Tween {
+            from,
+            to: target,
+            start: now,
+            motion: self.motion,
+        }
+    }
+
+    /// This transition with the springs under it stopped where they are at
+    /// `now` once they have settled or are more than `depth` deep.
+    fn trim(&self, now: Instant, depth: usize) -> Tween<T> {
+        let from = match &self.from {
+            Origin::Moving(tween) if depth > 0 && tween.progress(now).is_some() => {
+                Origin::Moving(Rc::new(tween.trim(now, depth - 1)))
+            }
+            Origin::Moving(tween) => Origin::At(tween.value(now)),
+            Origin::At(value) => Origin::At(value.clone()),
+        };
+        Tween {
+            from,
+            to: self.to.clone(),
+            ..*self
+        }
+    }
+}
+
+/// A value that animates towards its target over time.
+///
+/// Backed by a signal, so it keeps its state across renders. Reading an
+/// animation that is still running schedules another frame, which keeps the
+/// view rebuilding until the transition settles.
+pub struct Animated<T> {
+    tween: Signal<Tween<T>>,
+}
+
+impl<T> Clone for Animated<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Animated<T> {}
+
+impl<T: Interpolate + 'static> Animated<T> {
+    /// Get the current (interpolated) value.
+    pub fn get(&self) -> T {
+        let tween = self.tween.get();
+        let now = Instant::now();
+        if tween.progress(now).is_some() {
+            request_redraw();
+        }
+        tween.value(now)
+    }
+
+    /// Get the value the animation is heading towards.
+    pub fn target(&self) -> T {
+        self.tween.get().to
+    }
+
+    /// Whether a transition is still in progress.
+    pub fn is_animating(&self) -> bool {
+        self.tween.get().progress(Instant::now()).is_some()
+    }
+
+    /// Animate from the current value to `target`.
+    ///
+    /// Call this from event handlers rather than from the view: every call
+    /// restarts the transition from wherever it currently is. A spring that
+    /// is still moving keeps its velocity.
+    pub fn set(&self, target: T) {
+        let tween = self.tween.get();
+        self.tween.set(tween.retarget(target, Instant::now()));
+    }
+
+    /// Jump straight to `value` without animating.
+    pub fn jump(&self, value: T) {
+        let tween = self.tween.get();
+        self.tween.set(Tween {
+            from: Origin::At(value.clone()),
+            to: value,
+            start: Instant::now(),
+            ..tween
+        });
+    }
+}
+
+/// Create an animated value that transitions over `duration` using `easing`
+/// whenever it is given a new target.
+///
+/// Like `use_signal`, this should be called during the view function.
+///
+/// # Example
+/// ```
+/// let bg = use_animated(|| GRAY, Duration::from_millis(150), Easing::EaseOut);
+///
+/// div()
+///     .bg(bg.get())
+///     .on_hover(move |_: &mut MyModel, entered| bg.set(if entered { BLUE } else { GRAY }))
+/// ```
+pub fn use_animated<T: Interpolate + 'static>(
+    init: impl FnOnce() -> T,
+    duration: Duration,
+    easing: Easing,
+) -> Animated<T> {
+    use_motion(init, Motion::Tween { duration, easing })
+}
+
+/// Create an animated value that follows `spring` whenever it is given a new
+/// target. Springs have no fixed duration; they run until they settle.
+///
+/// # Example
+/// ```
+/// let width = use_spring(|| 100.0, Spring::WOBBLY);
+///
+/// div()
+///     .w(px(width.get()))
+///     .on_left_click(move |_: &mut MyModel| {
+///         width.set(if width.target() == 100.0 { 300.0 } else { 100.0 })
+///     })
+/// ```
+pub fn use_spring<T: Interpolate + 'static>(
+    init: impl FnOnce() -> T,
+    spring: Spring,
+) -> Animated<T> {
+    use_motion(init, Motion::Spring(spring))
+}
+
+fn use_motion<T: Interpolate + 'static>(init: impl FnOnce() -> T, motion: Motion) -> Animated<T> {
+    let tween = use_signal(|| {
+        let value = init();
+        Tween {
+            from: Origin::At(value.clone()),
+            to: value,
+            start: Instant::now(),
+            motion,
+        }
+    });
+    Animated { tween }
+}
+
+/// A keyframe animation that plays from the moment it is created (or last
+/// restarted).
+pub struct KeyframeAnimation<T> {
+    state: Signal<(Keyframes<T>, Instant)>,
+}
+
+impl<T> Clone for KeyframeAnimation<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for KeyframeAnimation<T> {}
+
+impl<T: Interpolate + 'static> KeyframeAnimation<T> {
+    /// Get the current value.
+    pub fn get(&self) -> T {
+        let (keyframes, start) = self.state.get();
+        let elapsed = start.elapsed();
+        if !keyframes.is_finished(elapsed) {
+            request_redraw();
+        }
+        keyframes.sample(elapsed)
+    }
+
+    /// Whether the animation is still playing.
+    pub fn is_running(&self) -> bool {
+        let (keyframes, start) = self.state.get();
+        !keyframes.is_finished(start.elapsed())
+    }
+
+    /// Play the animation again from the start.
+    pub fn restart(&self) {
+        let (keyframes, _) = self.state.get();
+        self.state.set((keyframes, Instant::now()));
+    }
+}
+
+/// Create a keyframe animation. It starts playing on the first render.
+///
+/// Like `use_signal`, this should be called during the view function.
+///
+/// # Example
+/// ```
+/// let pulse = use_keyframes(|| {
+///     keyframes![(0.0, 1.0), (0.5, 0.4), (1.0, 1.0)]
+///         .duration(Duration::from_millis(800))
+///         .looping()
+/// });
+///
+/// div().opacity(pulse.get())
+/// ```
+pub fn use_keyframes<T: Interpolate + 'static>(
+    init: impl FnOnce() -> Keyframes<T>,
+) -> KeyframeAnimation<T> {
+    let state = use_signal(|| (init(), Instant::now()));
+    KeyframeAnimation { state }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: Duration = Duration::from_millis(1);
+
+    fn spring_to(to: f32, start: Instant) -> Tween<f32> {
+        Tween {
+            from: Origin::At(0.0),
+            to,
+            start,
+            motion: Motion::Spring(Spring::DEFAULT),
+        }
+    }
+
+    fn velocity(tween: &Tween<f32>, at: Instant) -> f32 {
+        (tween.value(at + STEP) - tween.value(at)) / STEP.as_secs_f32()
+    }
+
+    fn depth(tween: &Tween<f32>) -> usize {
+        match &tween.from {
+            Origin::At(_) => 0,
+            Origin::Moving(tween) => 1 + depth(tween),
+        }
+    }
+
+    #[test]
+    fn retargeted_springs_keep_their_velocity() {
+        let start = Instant::now();
+        let tween = spring_to(100.0, start);
+        let now = start + Duration::from_millis(100);
+        let before = velocity(&tween, now - STEP);
+        assert!(before > 100.0, "{before}");
+
+        let retargeted = tween.retarget(-50.0, now);
+        assert!((retargeted.value(now) - tween.value(now)).abs() < 0.01);
+        let after = velocity(&retargeted, now);
+        assert!((after - before).abs() < before * 0.05, "{before} {after}");
+
+        let later = now + Duration::from_secs(10);
+        assert!(retargeted.progress(later).is_none());
+        assert_eq!(retargeted.value(later), -50.0);
+    }
+
+    #[test]
+    fn settled_springs_and_tweens_start_from_where_they_are() {
+        let start = Instant::now();
+        let settled = spring_to(100.0, start).retarget(0.0, start + Duration::from_secs(10));
+        assert_eq!(depth(&settled), 0);
+
+        let tween = Tween {
+            motion: Motion::Tween {
+                duration: Duration::from_secs(1),
+                easing: Easing::Linear,
+            },
+            ..spring_to(100.0, start)
+        };
+        let retargeted = tween.retarget(0.0, start + Duration::from_millis(500));
+        assert_eq!(depth(&retargeted), 0);
+    }
+
+    #[test]
+    fn only_the_last_interrupted_springs_keep_moving() {
+        let start = Instant::now();
+        let mut tween = spring_to(100.0, start);
+        for i in 1..=20 {
+            let now = start + Duration::from_millis(i * 10);
+            tween = tween.retarget(i as f32, now);
+            assert!(depth(&tween) <= MAX_MOVING_ORIGINS);
+        }
+        assert_eq!(depth(&tween), MAX_MOVING_ORIGINS);
+    }
+}
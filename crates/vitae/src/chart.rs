@@ -0,0 +1,555 @@
+//! Line, bar and pie charts of series of numbers, drawn as vector shapes
+//! with labeled axes and a tooltip for the data point under the cursor.
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use vitae_core::{div, pc, px, svg, text, Color, ElementBuilder, Event, EventResult, Svg};
+
+use crate::signal::use_keyed_signal;
+use crate::size::{use_element_origin, use_element_size};
+
+/// Colors given to series (or pie slices) without one of their own, in
+/// turn.
+pub const CHART_PALETTE: [Color; 6] = [
+    Color::new(0.26, 0.52, 0.96, 1.0),
+    Color::new(0.96, 0.55, 0.18, 1.0),
+    Color::new(0.3, 0.69, 0.31, 1.0),
+    Color::new(0.9, 0.26, 0.27, 1.0),
+    Color::new(0.61, 0.4, 0.83, 1.0),
+    Color::new(0.1, 0.68, 0.72, 1.0),
+];
+
+/// The color of axis labels, and of the axes and gridlines at a lower
+/// opacity. Gray reads on light and dark backgrounds alike.
+pub const CHART_TEXT_COLOR: Color = Color::GRAY;
+
+/// The key of a chart that isn't given one.
+const DEFAULT_KEY: &str = "vitae-chart";
+
+/// Room kept for the value labels left of the plot, and the category labels
+/// below it, in pixels.
+const VALUE_LABEL_WIDTH: f32 = 48.0;
+const CATEGORY_LABEL_HEIGHT: f32 = 24.0;
+
+/// Room kept around the rest of the chart, in pixels.
+const MARGIN: f32 = 12.0;
+
+/// Font size of the labels and the tooltip.
+const LABEL_SIZE: f32 = 12.0;
+
+/// How far from a point on a line the cursor can be and still hover it, in
+/// pixels.
+const HOVER_DISTANCE: f32 = 16.0;
+
+/// About how many gridlines to mark the value axis with.
+const GRIDLINES: usize = 5;
+
+/// How a chart shows its series.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChartKind {
+    /// Each series as a line through its values, left to right
+    Line,
+    /// Each value as a bar, with the series' bars side by side for each
+    /// category
+    Bar,
+    /// The values of the first series as slices of a circle
+    Pie,
+}
+
+/// Named numbers to chart, one for each category.
+#[derive(Clone, Debug)]
+pub struct Series {
+    pub name: String,
+    pub values: Vec<f32>,
+    /// The color to draw it in, or `None` for the next one in
+    /// `CHART_PALETTE`
+    pub color: Option<Color>,
+}
+
+impl Series {
+    pub fn new(name: impl Into<String>, values: impl IntoIterator<Item = f32>) -> Self {
+        Self {
+            name: name.into(),
+            values: values.into_iter().collect(),
+            color: None,
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
+
+/// A chart made by `chart`. Use it as a child like any other element.
+pub struct Chart {
+    kind: ChartKind,
+    series: Vec<Series>,
+    labels: Vec<String>,
+    key: String,
+}
+
+/// Chart `series` as lines, bars or a pie. The chart fills its parent, and
+/// hovering a data point shows its value in a tooltip.
+///
+/// # Example
+/// ```
+/// chart(
+///     ChartKind::Bar,
+///     [
+///         Series::new("Visitors", model.visitors.iter().copied()),
+///         Series::new("Signups", model.signups.iter().copied()).color(GREEN),
+///     ],
+/// )
+/// .key("traffic")
+/// .labels(["Mon", "Tue", "Wed", "Thu", "Fri"])
+/// ```
+pub fn chart(kind: ChartKind, series: impl IntoIterator<Item = Series>) -> Chart {
+    Chart {
+        kind,
+        series: series.into_iter().collect(),
+        labels: Vec::new(),
+        key: DEFAULT_KEY.to_string(),
+    }
+}
+
+impl Chart {
+    /// Tell this chart apart from others in the view. Each chart needs a
+    /// key of its own, which is also the key of its element.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+
+    /// Name the categories, in the order of the values in each series: the
+    /// labels along the bottom of a line or bar chart, or the names of a
+    /// pie's slices. Categories are numbered from 1 if not named.
+    pub fn labels<S: Into<String>>(mut self, labels: impl IntoIterator<Item = S>) -> Self {
+        self.labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Where a chart's data goes, in pixels from its top left corner.
+#[derive(Clone)]
+struct Plot {
+    kind: ChartKind,
+    /// The values of each series
+    values: Vec<Vec<f32>>,
+    /// How many categories there are
+    count: usize,
+    /// The edges of the area the data is drawn in
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+    /// The values at the bottom and top of the value axis
+    low: f32,
+    high: f32,
+}
+
+impl Plot {
+    fn new(kind: ChartKind, series: &[Series], width: f32, height: f32, ticks: &[f32]) -> Self {
+        let mut values: Vec<Vec<f32>> = series.iter().map(|s| s.values.clone()).collect();
+        if kind == ChartKind::Pie {
+            values.truncate(1);
+        }
+        let count = values.iter().map(Vec::len).max().unwrap_or(0);
+        let (left, bottom) = match kind {
+            ChartKind::Pie => (MARGIN, height - MARGIN),
+            _ => (VALUE_LABEL_WIDTH, height - CATEGORY_LABEL_HEIGHT),
+        };
+        Plot {
+            kind,
+            values,
+            count,
+            left,
+            top: MARGIN,
+            right: width - MARGIN,
+            bottom,
+            low: ticks.first().copied().unwrap_or(0.0),
+            high: ticks.last().copied().unwrap_or(1.0),
+        }
+    }
+
+    /// The width of each category's part of the plot.
+    fn band(&self) -> f32 {
+        (self.right - self.left) / self.count.max(1) as f32
+    }
+
+    /// The height of `value` on the value axis.
+    fn y(&self, value: f32) -> f32 {
+        let share = (value - self.low) / (self.high - self.low);
+        self.bottom - share * (self.bottom - self.top)
+    }
+
+    /// The point of the `index`th value of line `series`.
+    fn point(&self, series: usize, index: usize) -> (f32, f32) {
+        let x = self.left + (index as f32 + 0.5) * self.band();
+        (x, self.y(self.values[series][index]))
+    }
+
+    /// The left and right edges of the bar of the `index`th value of
+    /// `series`.
+    fn bar(&self, series: usize, index: usize) -> (f32, f32) {
+        // Leave a tenth of each band free on either side
+        let band = self.band();
+        let width = band * 0.8 / self.values.len() as f32;
+        let left = self.left + index as f32 * band + band * 0.1 + series as f32 * width;
+        (left, left + width)
+    }
+
+    /// The center and radius of a pie.
+    fn circle(&self) -> (f32, f32, f32) {
+        let radius = ((self.right - self.left).min(self.bottom - self.top) / 2.0).max(0.0);
+        (
+            (self.left + self.right) / 2.0,
+            (self.top + self.bottom) / 2.0,
+            radius,
+        )
+    }
+
+    /// The angles, clockwise from the top, where each slice of a pie starts
+    /// and ends.
+    fn slices(&self) -> Vec<(f32, f32)> {
+        let values = self.values.first().map_or(&[][..], Vec::as_slice);
+        let total: f32 = values.iter().map(|v| v.max(0.0)).sum();
+        let mut start = 0.0;
+        values
+            .iter()
+            .map(|value| {
+                let sweep = if total > 0.0 {
+                    value.max(0.0) / total * TAU
+                } else {
+                    0.0
+                };
+                let slice = (start, start + sweep);
+                start += sweep;
+                slice
+            })
+            .collect()
+    }
+
+    /// The point at `angle` clockwise from the top, `distance` from the
+    /// center of a pie.
+    fn on_circle(&self, angle: f32, distance: f32) -> (f32, f32) {
+        let (cx, cy, _) = self.circle();
+        let angle = angle - FRAC_PI_2;
+        (cx + distance * angle.cos(), cy + distance * angle.sin())
+    }
+
+    /// The series and index of the data point at (`x`, `y`), if any.
+    fn hit(&self, x: f32, y: f32) -> Option<(usize, usize)> {
+        match self.kind {
+            ChartKind::Line => {
+                let mut nearest = None;
+                let mut nearest_distance = HOVER_DISTANCE;
+                for (series, values) in self.values.iter().enumerate() {
+                    for index in 0..values.len() {
+                        let (px, py) = self.point(series, index);
+                        let distance = (px - x).hypot(py - y);
+                        if distance <= nearest_distance {
+                            nearest = Some((series, index));
+                            nearest_distance = distance;
+                        }
+                    }
+                }
+                nearest
+            }
+            ChartKind::Bar => {
+                let base = self.y(0.0);
+                self.values.iter().enumerate().find_map(|(series, values)| {
+                    values.iter().enumerate().find_map(|(index, &value)| {
+                        let (left, right) = self.bar(series, index);
+                        let top = self.y(value);
+                        let inside = (left..=right).contains(&x)
+                            && (top.min(base)..=top.max(base)).contains(&y);
+                        inside.then_some((series, index))
+                    })
+                })
+            }
+            ChartKind::Pie => {
+                let (cx, cy, radius) = self.circle();
+                if (x - cx).hypot(y - cy) > radius {
+                    return None;
+                }
+                let angle = ((y - cy).atan2(x - cx) + FRAC_PI_2).rem_euclid(TAU);
+                let index = self
+                    .slices()
+                    .iter()
+                    .position(|&(start, end)| angle >= start && angle < end)?;
+                Some((0, index))
+            }
+        }
+    }
+
+    /// Where the tooltip of a data point points to.
+    fn anchor(&self, series: usize, index: usize) -> (f32, f32) {
+        match self.kind {
+            ChartKind::Line => self.point(series, index),
+            ChartKind::Bar => {
+                let (left, right) = self.bar(series, index);
+                let value = self.values[series][index];
+                ((left + right) / 2.0, self.y(value.max(0.0)))
+            }
+            ChartKind::Pie => {
+                let (start, end) = self.slices()[index];
+                let (_, _, radius) = self.circle();
+                self.on_circle((start + end) / 2.0, radius * 0.6)
+            }
+        }
+    }
+}
+
+/// Round numbers to mark the value axis with, from at or below `min` to at
+/// or above `max`, about `count` of them.
+fn ticks(min: f32, max: f32, count: usize) -> Vec<f32> {
+    let span = if max > min { max - min } else { 1.0 };
+    let rough = span / count as f32;
+    let magnitude = 10_f32.powf(rough.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= rough)
+        .unwrap_or(rough);
+    let first = (min / step).floor();
+    let last = (max / step).ceil().max(first + 1.0);
+    (0..=(last - first) as usize)
+        .map(|i| (first + i as f32) * step)
+        .collect()
+}
+
+/// `value` with as many decimals as the gap between gridlines needs.
+fn format_tick(value: f32, step: f32) -> String {
+    let decimals = if step >= 1.0 {
+        0
+    } else {
+        (-step.log10()).ceil() as usize
+    };
+    format!("{value:.decimals$}")
+}
+
+/// SVG attributes painting the fill or stroke, as `attribute` says, in
+/// `color`.
+fn paint(attribute: &str, color: Color) -> String {
+    let [r, g, b, a] = color.to_array();
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        r##"{attribute}="#{:02x}{:02x}{:02x}" {attribute}-opacity="{a}""##,
+        channel(r),
+        channel(g),
+        channel(b),
+    )
+}
+
+impl From<Chart> for ElementBuilder {
+    fn from(chart: Chart) -> Self {
+        let Chart {
+            kind,
+            series,
+            labels,
+            key,
+        } = chart;
+        let hovered = use_keyed_signal(&key, || None::<(usize, usize)>);
+        let origin = use_element_origin(&key);
+        let (width, height) = use_element_size(&key).get();
+
+        let element = div().key(key).size(pc(100.0));
+        // Nothing is drawn until the chart's size is known
+        if width <= 0.0 || height <= 0.0 {
+            return element;
+        }
+
+        let (min, max) = series
+            .iter()
+            .flat_map(|s| s.values.iter().copied())
+            .fold((0.0_f32, 0.0_f32), |(min, max), v| (min.min(v), max.max(v)));
+        let ticks = ticks(min, max, GRIDLINES);
+        let plot = Plot::new(kind, &series, width, height, &ticks);
+        let hover = hovered
+            .get()
+            .filter(|&(s, i)| plot.values.get(s).is_some_and(|values| i < values.len()));
+        let color = |index: usize| {
+            let own = series.get(index).and_then(|s| s.color);
+            own.unwrap_or(CHART_PALETTE[index % CHART_PALETTE.len()])
+        };
+        let label = |index: usize| {
+            labels
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| (index + 1).to_string())
+        };
+
+        let mut shapes = String::new();
+        let mut texts = Vec::new();
+        let axis = Color::new(0.5, 0.5, 0.5, 0.6);
+        let grid = Color::new(0.5, 0.5, 0.5, 0.2);
+        if kind != ChartKind::Pie {
+            let step = ticks.get(1).map_or(1.0, |second| second - ticks[0]);
+            for &tick in &ticks {
+                let y = plot.y(tick);
+                let stroke = paint("stroke", if tick == 0.0 { axis } else { grid });
+                shapes.push_str(&format!(
+                    r#"<line x1="{}" y1="{y}" x2="{}" y2="{y}" {stroke} stroke-width="1"/>"#,
+                    plot.left, plot.right,
+                ));
+                texts.push(
+                    text(format_tick(tick, step))
+                        .font_size(LABEL_SIZE)
+                        .color(CHART_TEXT_COLOR)
+                        .absolute()
+                        .left(px(4.0))
+                        .top(px(y - LABEL_SIZE * 0.6)),
+                );
+            }
+            shapes.push_str(&format!(
+                r#"<line x1="{x}" y1="{}" x2="{x}" y2="{}" {} stroke-width="1"/>"#,
+                plot.top,
+                plot.bottom,
+                paint("stroke", axis),
+                x = plot.left,
+            ));
+            let band = plot.band();
+            for index in 0..plot.count {
+                texts.push(
+                    div()
+                        .absolute()
+                        .left(px(plot.left + index as f32 * band))
+                        .top(px(plot.bottom))
+                        .w(px(band))
+                        .h(px(CATEGORY_LABEL_HEIGHT))
+                        .center()
+                        .child(
+                            text(label(index))
+                                .font_size(LABEL_SIZE)
+                                .color(CHART_TEXT_COLOR),
+                        ),
+                );
+            }
+        }
+
+        match kind {
+            ChartKind::Line => {
+                for (s, values) in plot.values.iter().enumerate() {
+                    let points: Vec<String> = (0..values.len())
+                        .map(|i| {
+                            let (x, y) = plot.point(s, i);
+                            format!("{x},{y}")
+                        })
+                        .collect();
+                    shapes.push_str(&format!(
+                        r#"<polyline points="{}" fill="none" {} stroke-width="2" stroke-linejoin="round"/>"#,
+                        points.join(" "),
+                        paint("stroke", color(s)),
+                    ));
+                    for i in 0..values.len() {
+                        let (x, y) = plot.point(s, i);
+                        let radius = if hover == Some((s, i)) { 5.0 } else { 3.0 };
+                        shapes.push_str(&format!(
+                            r#"<circle cx="{x}" cy="{y}" r="{radius}" {}/>"#,
+                            paint("fill", color(s)),
+                        ));
+                    }
+                }
+            }
+            ChartKind::Bar => {
+                let base = plot.y(0.0);
+                for (s, values) in plot.values.iter().enumerate() {
+                    for (i, &value) in values.iter().enumerate() {
+                        let (left, right) = plot.bar(s, i);
+                        let top = plot.y(value);
+                        let opacity = if hover == Some((s, i)) { 0.75 } else { 1.0 };
+                        shapes.push_str(&format!(
+                            r#"<rect x="{left}" y="{}" width="{}" height="{}" {} opacity="{opacity}"/>"#,
+                            top.min(base),
+                            right - left,
+                            (top - base).abs(),
+                            paint("fill", color(s)),
+                        ));
+                    }
+                }
+            }
+            ChartKind::Pie => {
+                let (cx, cy, radius) = plot.circle();
+                for (i, (start, end)) in plot.slices().into_iter().enumerate() {
+                    // Slices are colored in turn rather than by series
+                    let fill = paint("fill", CHART_PALETTE[i % CHART_PALETTE.len()]);
+                    let opacity = if hover == Some((0, i)) { 0.75 } else { 1.0 };
+                    if end - start >= TAU - f32::EPSILON {
+                        shapes.push_str(&format!(
+                            r#"<circle cx="{cx}" cy="{cy}" r="{radius}" {fill} opacity="{opacity}"/>"#,
+                        ));
+                    } else if end > start {
+                        let (x0, y0) = plot.on_circle(start, radius);
+                        let (x1, y1) = plot.on_circle(end, radius);
+                        let large = u8::from(end - start > TAU / 2.0);
+                        shapes.push_str(&format!(
+                            r#"<path d="M {cx} {cy} L {x0} {y0} A {radius} {radius} 0 {large} 1 {x1} {y1} Z" {fill} opacity="{opacity}"/>"#,
+                        ));
+                    }
+                }
+            }
+        }
+
+        let tooltip = hover.map(|(s, i)| {
+            let value = plot.values[s][i];
+            let name = series.get(s).map_or("", |s| s.name.as_str());
+            let mut content = match kind {
+                ChartKind::Pie => {
+                    let total: f32 = plot.values[0].iter().map(|v| v.max(0.0)).sum();
+                    format!("{}: {value} ({:.0}%)", label(i), value / total * 100.0)
+                }
+                _ => format!("{}: {value}", label(i)),
+            };
+            if !name.is_empty() && (kind == ChartKind::Pie || series.len() > 1) {
+                content = format!("{name} - {content}");
+            }
+            let (x, y) = plot.anchor(s, i);
+            // Beside the point, on whichever side has more room
+            let tooltip = div()
+                .absolute()
+                .top(px((y - LABEL_SIZE * 2.5).max(0.0)))
+                .bg(Color::new(0.1, 0.1, 0.1, 0.9))
+                .radius(4.0)
+                .p(px(6.0))
+                .pointer_events(false)
+                .child(text(content).font_size(LABEL_SIZE).color(Color::WHITE));
+            if x < width / 2.0 {
+                tooltip.left(px(x + 8.0))
+            } else {
+                tooltip.right(px(width - x + 8.0))
+            }
+        });
+
+        let data = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="{width}" height="{height}">{shapes}</svg>"#,
+        );
+        element
+            .child(
+                svg(&Svg::new(data, width, height))
+                    .absolute()
+                    .size(pc(100.0)),
+            )
+            .children(texts)
+            .child(
+                div()
+                    .absolute()
+                    .size(pc(100.0))
+                    .on_event_any(move |_, event| {
+                        match *event {
+                            Event::MouseMove { x, y } => {
+                                let (left, top) = origin.get();
+                                let hit = plot.hit(x - left, y - top);
+                                if hovered.get() != hit {
+                                    hovered.set(hit);
+                                }
+                            }
+                            Event::MouseLeave if hovered.get().is_some() => hovered.set(None),
+                            _ => {}
+                        }
+                        EventResult::Continue
+                    }),
+            )
+            .children(tooltip)
+    }
+}
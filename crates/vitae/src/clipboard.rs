@@ -0,0 +1,38 @@
+use std::cell::RefCell;
+use std::io;
+
+use arboard::Clipboard;
+
+thread_local! {
+    // Kept alive for the whole program: on some platforms (X11) copied text
+    // is only available while the clipboard handle that set it exists.
+    static CLIPBOARD: RefCell<Option<Clipboard>> = const { RefCell::new(None) };
+}
+
+fn with_clipboard<T>(f: impl FnOnce(&mut Clipboard) -> Result<T, arboard::Error>) -> io::Result<T> {
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+        if clipboard.is_none() {
+            *clipboard = Some(Clipboard::new().map_err(io::Error::other)?);
+        }
+        f(clipboard.as_mut().unwrap()).map_err(io::Error::other)
+    })
+}
+
+/// Get the text currently on the clipboard.
+pub fn get_text() -> io::Result<String> {
+    with_clipboard(|clipboard| clipboard.get_text())
+}
+
+/// Put `text` on the clipboard.
+///
+/// # Example
+/// ```
+/// button("Copy").on_left_click(|m: &mut MyModel| {
+///     let _ = clipboard::set_text(m.link.clone());
+/// })
+/// ```
+pub fn set_text(text: impl Into<String>) -> io::Result<()> {
+    let text = text.into();
+    with_clipboard(|clipboard| clipboard.set_text(text))
+}
@@ -0,0 +1,510 @@
+//! Source code in a monospace font, with syntax highlighting and line
+//! numbers, scrolled with the mouse wheel or trackpad. Read-only for now.
+
+use vitae_core::{div, pc, px, text, Color, ElementBuilder, Event, EventResult};
+use vitae_render::text::{measure_monospace, DEFAULT_FONT_SIZE};
+
+use crate::signal::use_keyed_signal;
+use crate::size::use_element_size;
+
+/// The key of a code view that isn't given one.
+const DEFAULT_KEY: &str = "vitae-code-view";
+
+/// How many columns a tab takes up.
+const TAB_WIDTH: usize = 4;
+
+/// The colors of a code view.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CodeTheme {
+    pub background: Color,
+    /// Identifiers, punctuation and anything else not colored below
+    pub text: Color,
+    pub line_number: Color,
+    pub keyword: Color,
+    /// Names starting with a capital letter, which are types in most
+    /// languages
+    pub type_name: Color,
+    pub string: Color,
+    pub number: Color,
+    pub comment: Color,
+}
+
+impl CodeTheme {
+    pub const DARK: CodeTheme = CodeTheme {
+        background: Color::new(0.118, 0.118, 0.118, 1.0),
+        text: Color::new(0.831, 0.831, 0.831, 1.0),
+        line_number: Color::new(0.522, 0.522, 0.522, 1.0),
+        keyword: Color::new(0.337, 0.612, 0.839, 1.0),
+        type_name: Color::new(0.306, 0.788, 0.69, 1.0),
+        string: Color::new(0.808, 0.569, 0.471, 1.0),
+        number: Color::new(0.71, 0.808, 0.659, 1.0),
+        comment: Color::new(0.416, 0.6, 0.333, 1.0),
+    };
+
+    pub const LIGHT: CodeTheme = CodeTheme {
+        background: Color::WHITE,
+        text: Color::new(0.122, 0.122, 0.122, 1.0),
+        line_number: Color::new(0.6, 0.6, 0.6, 1.0),
+        keyword: Color::new(0.0, 0.0, 1.0, 1.0),
+        type_name: Color::new(0.149, 0.498, 0.6, 1.0),
+        string: Color::new(0.639, 0.082, 0.082, 1.0),
+        number: Color::new(0.035, 0.525, 0.345, 1.0),
+        comment: Color::new(0.0, 0.502, 0.0, 1.0),
+    };
+
+    fn color(&self, token: Token) -> Color {
+        match token {
+            Token::Plain => self.text,
+            Token::Keyword => self.keyword,
+            Token::TypeName => self.type_name,
+            Token::String => self.string,
+            Token::Number => self.number,
+            Token::Comment => self.comment,
+        }
+    }
+}
+
+/// What a piece of a line of code is, which sets its color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Plain,
+    Keyword,
+    TypeName,
+    String,
+    Number,
+    Comment,
+}
+
+/// How to highlight a language.
+struct Syntax {
+    /// The names and file extensions the language is found by
+    names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    /// The characters strings start and end with
+    quotes: &'static [char],
+    /// Whether `'` quotes single characters, and otherwise starts a
+    /// lifetime, as in Rust
+    char_quotes: bool,
+}
+
+const SYNTAXES: &[Syntax] = &[
+    Syntax {
+        names: &["rust", "rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+            "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait",
+            "true", "type", "unsafe", "use", "where", "while",
+        ],
+        line_comment: Some("//"),
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+        char_quotes: true,
+    },
+    Syntax {
+        names: &["python", "py"],
+        keywords: &[
+            "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+            "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+            "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise",
+            "return", "try", "while", "with", "yield",
+        ],
+        line_comment: Some("#"),
+        block_comment: None,
+        quotes: &['"', '\''],
+        char_quotes: false,
+    },
+    Syntax {
+        names: &["javascript", "js", "typescript", "ts"],
+        keywords: &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "else",
+            "export",
+            "extends",
+            "false",
+            "finally",
+            "for",
+            "from",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "interface",
+            "let",
+            "new",
+            "null",
+            "return",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "type",
+            "typeof",
+            "undefined",
+            "var",
+            "void",
+            "while",
+            "yield",
+        ],
+        line_comment: Some("//"),
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        char_quotes: false,
+    },
+    Syntax {
+        names: &["json"],
+        keywords: &["false", "null", "true"],
+        line_comment: None,
+        block_comment: None,
+        quotes: &['"'],
+        char_quotes: false,
+    },
+    Syntax {
+        names: &["toml"],
+        keywords: &["false", "true"],
+        line_comment: Some("#"),
+        block_comment: None,
+        quotes: &['"', '\''],
+        char_quotes: false,
+    },
+    Syntax {
+        names: &["shell", "sh", "bash"],
+        keywords: &[
+            "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+            "in", "local", "return", "then", "until", "while",
+        ],
+        line_comment: Some("#"),
+        block_comment: None,
+        quotes: &['"', '\''],
+        char_quotes: false,
+    },
+];
+
+/// The syntax of the language named `language`, by name or file extension.
+fn find_syntax(language: &str) -> Option<&'static Syntax> {
+    let language = language.to_ascii_lowercase();
+    SYNTAXES
+        .iter()
+        .find(|syntax| syntax.names.contains(&language.as_str()))
+}
+
+/// Whether `line` has `pattern` at `at`.
+fn starts_with(line: &[char], at: usize, pattern: &str) -> bool {
+    let mut rest = line[at..].iter();
+    pattern.chars().all(|c| rest.next() == Some(&c))
+}
+
+/// Where the string starting with the quote at `start` ends, just past its
+/// closing quote or at the end of the line if it isn't closed.
+fn closing_quote(line: &[char], start: usize) -> usize {
+    let quote = line[start];
+    let mut i = start + 1;
+    while i < line.len() {
+        match line[i] {
+            '\\' => i += 2,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    line.len()
+}
+
+/// Where the character literal starting with the `'` at `start` ends, or
+/// `None` if it is a lifetime instead.
+fn char_literal_end(line: &[char], start: usize) -> Option<usize> {
+    match line.get(start + 1..)? {
+        ['\\', ..] => line[start + 2..]
+            .iter()
+            .position(|&c| c == '\'')
+            .map(|end| start + 2 + end + 1),
+        [_, '\'', ..] => Some(start + 3),
+        _ => None,
+    }
+}
+
+/// Split `line` into pieces to color. `in_comment` says whether the line
+/// starts inside a block comment, and is left saying whether the next one
+/// does.
+fn highlight_line(syntax: &Syntax, line: &[char], in_comment: &mut bool) -> Vec<(Token, String)> {
+    let mut tokens: Vec<(Token, String)> = Vec::new();
+    let mut push = |token: Token, chars: &[char]| match tokens.last_mut() {
+        Some((last, content)) if *last == token => content.extend(chars),
+        _ => tokens.push((token, chars.iter().collect())),
+    };
+
+    let mut i = 0;
+    while i < line.len() {
+        let start = i;
+        if *in_comment {
+            let (_, close) = syntax.block_comment.unwrap_or(("", ""));
+            while i < line.len() && !starts_with(line, i, close) {
+                i += 1;
+            }
+            if i < line.len() {
+                i += close.chars().count();
+                *in_comment = false;
+            }
+            push(Token::Comment, &line[start..i]);
+            continue;
+        }
+
+        let c = line[i];
+        if let Some((open, _)) = syntax
+            .block_comment
+            .filter(|(open, _)| starts_with(line, i, open))
+        {
+            i += open.chars().count();
+            *in_comment = true;
+            push(Token::Comment, &line[start..i]);
+        } else if syntax
+            .line_comment
+            .is_some_and(|open| starts_with(line, i, open))
+        {
+            push(Token::Comment, &line[start..]);
+            break;
+        } else if syntax.quotes.contains(&c) {
+            i = closing_quote(line, i).min(line.len());
+            push(Token::String, &line[start..i]);
+        } else if c == '\'' && syntax.char_quotes {
+            match char_literal_end(line, i) {
+                Some(end) => {
+                    i = end.min(line.len());
+                    push(Token::String, &line[start..i]);
+                }
+                None => {
+                    i += 1;
+                    push(Token::Plain, &line[start..i]);
+                }
+            }
+        } else if c.is_ascii_digit() {
+            while i < line.len() && (line[i].is_alphanumeric() || matches!(line[i], '_' | '.')) {
+                i += 1;
+            }
+            push(Token::Number, &line[start..i]);
+        } else if c.is_alphabetic() || c == '_' {
+            while i < line.len() && (line[i].is_alphanumeric() || line[i] == '_') {
+                i += 1;
+            }
+            let word: String = line[start..i].iter().collect();
+            let token = if syntax.keywords.contains(&word.as_str()) {
+                Token::Keyword
+            } else if c.is_uppercase() {
+                Token::TypeName
+            } else {
+                Token::Plain
+            };
+            push(token, &line[start..i]);
+        } else {
+            i += 1;
+            push(Token::Plain, &line[start..i]);
+        }
+    }
+    tokens
+}
+
+/// The characters of `line`, with tabs turned into spaces up to the next
+/// tab stop.
+fn expand_tabs(line: &str) -> Vec<char> {
+    let mut chars = Vec::with_capacity(line.len());
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = TAB_WIDTH - chars.len() % TAB_WIDTH;
+            chars.extend(std::iter::repeat_n(' ', spaces));
+        } else {
+            chars.push(c);
+        }
+    }
+    chars
+}
+
+/// Source code shown by `code_view`. Use it as a child like any other
+/// element.
+pub struct CodeView {
+    source: String,
+    language: String,
+    key: String,
+    theme: CodeTheme,
+    line_numbers: bool,
+}
+
+/// Show `source` in the monospace font, highlighted as `language`, with
+/// line numbers down the left. The view fills its parent, and scrolls both
+/// ways with the mouse wheel or trackpad when the code doesn't fit.
+///
+/// `language` is a name or file extension: `rust`, `python`, `javascript`,
+/// `typescript`, `json`, `toml` and `sh` are highlighted, and anything else
+/// is shown as plain text.
+///
+/// # Example
+/// ```
+/// code_view(&model.source, "rs")
+///     .key("source")
+///     .theme(CodeTheme::LIGHT)
+/// ```
+pub fn code_view(source: impl Into<String>, language: &str) -> CodeView {
+    CodeView {
+        source: source.into(),
+        language: language.to_string(),
+        key: DEFAULT_KEY.to_string(),
+        theme: CodeTheme::DARK,
+        line_numbers: true,
+    }
+}
+
+impl CodeView {
+    /// Tell this view apart from others in the view. Each code view needs
+    /// a key of its own, which is also the key of its element.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+
+    /// Set the colors to draw in. `CodeTheme::DARK` unless set.
+    pub fn theme(mut self, theme: CodeTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Show or hide the line numbers. Shown unless hidden.
+    pub fn line_numbers(mut self, shown: bool) -> Self {
+        self.line_numbers = shown;
+        self
+    }
+}
+
+impl From<CodeView> for ElementBuilder {
+    fn from(view: CodeView) -> Self {
+        let CodeView {
+            source,
+            language,
+            key,
+            theme,
+            line_numbers,
+        } = view;
+
+        let (char_width, line_height) = measure_monospace("0", DEFAULT_FONT_SIZE, None);
+        let (width, height) = use_element_size(&key).get();
+        let lines: Vec<Vec<char>> = source.lines().map(expand_tabs).collect();
+        let longest = lines.iter().map(Vec::len).max().unwrap_or(0);
+
+        // The code starts a column in from the left edge, or from the line
+        // numbers, which are right-aligned with a column either side
+        let digits = lines.len().max(1).to_string().len();
+        let code_column = if line_numbers { digits + 2 } else { 1 };
+
+        // Without clipping, only whole lines and columns that fit are drawn,
+        // so scrolling moves by whole lines and columns as well
+        let (rows, columns) = if char_width > 0.0 && line_height > 0.0 {
+            (
+                (height / line_height).floor() as usize,
+                ((width / char_width).floor() as usize).saturating_sub(code_column + 1),
+            )
+        } else {
+            (0, 0)
+        };
+        let max_scroll = (
+            longest.saturating_sub(columns) as f32 * char_width,
+            lines.len().saturating_sub(rows) as f32 * line_height,
+        );
+        let clamp =
+            move |(x, y): (f32, f32)| (x.clamp(0.0, max_scroll.0), y.clamp(0.0, max_scroll.1));
+
+        // How far the code is scrolled, in pixels so trackpads can scroll
+        // less than a line at a time
+        let scroll = use_keyed_signal(&key, || (0.0_f32, 0.0_f32));
+        let (scroll_x, scroll_y) = clamp(scroll.get());
+        let first_line = (scroll_y / line_height.max(1.0)) as usize;
+        let first_column = (scroll_x / char_width.max(1.0)) as usize;
+        let last_line = (first_line + rows).min(lines.len());
+
+        let mut children = Vec::new();
+        let mut in_comment = false;
+        let syntax = find_syntax(&language);
+        for (index, line) in lines[..last_line].iter().enumerate() {
+            // Lines above the view are highlighted too, to know whether the
+            // first one shown starts inside a block comment
+            let tokens = match syntax {
+                Some(syntax) => highlight_line(syntax, line, &mut in_comment),
+                None => vec![(Token::Plain, line.iter().collect())],
+            };
+            if index < first_line {
+                continue;
+            }
+            let top = px((index - first_line) as f32 * line_height);
+
+            if line_numbers {
+                let number = (index + 1).to_string();
+                children.push(
+                    text(number.clone())
+                        .monospace()
+                        .color(theme.line_number)
+                        .absolute()
+                        .top(top)
+                        .left(px((digits + 1 - number.len()) as f32 * char_width)),
+                );
+            }
+
+            let mut column = 0;
+            for (token, content) in tokens {
+                let length = content.chars().count();
+                let (start, end) = (column, column + length);
+                column = end;
+                if end <= first_column || start >= first_column + columns {
+                    continue;
+                }
+                // Cut the token to the columns in view, leaving out spaces
+                // at its start
+                let skip = first_column.saturating_sub(start);
+                let take = (first_column + columns).min(end) - start - skip;
+                let visible: String = content.chars().skip(skip).take(take).collect();
+                let trimmed = visible.trim_start();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                let spaces = visible.chars().count() - trimmed.chars().count();
+                let offset = start + skip + spaces - first_column;
+                children.push(
+                    text(trimmed.to_string())
+                        .monospace()
+                        .color(theme.color(token))
+                        .absolute()
+                        .top(top)
+                        .left(px((code_column + offset) as f32 * char_width)),
+                );
+            }
+        }
+
+        div()
+            .key(key)
+            .size(pc(100.0))
+            .bg(theme.background)
+            .on_event_any(move |_, event| {
+                let Event::Scroll { dx, dy } = *event else {
+                    return EventResult::Continue;
+                };
+                let (x, y) = clamp(scroll.get());
+                let scrolled = clamp((x + dx, y + dy));
+                if scrolled == (x, y) {
+                    // Let whatever is around the view scroll instead
+                    return EventResult::Continue;
+                }
+                scroll.set(scrolled);
+                EventResult::Stop
+            })
+            .children(children)
+    }
+}
@@ -0,0 +1,513 @@
+//! Rows of text under column headers, for the big tables of tooling UIs:
+//! click a header to sort by its column, drag the edge of one to resize
+//! it, and click a row to select it. Only the rows in view are built, so
+//! the grid stays quick with many thousands of rows.
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::rc::Rc;
+
+use vitae_core::{
+    div, pc, px, text, Color, CursorIcon, ElementBuilder, Event, EventResult, Key, MouseButton,
+    NamedKey,
+};
+use vitae_render::text::{measure, DEFAULT_FONT_SIZE};
+
+use crate::signal::use_keyed_signal;
+use crate::size::use_element_size;
+
+/// The background of the header row.
+pub const DATA_GRID_HEADER_COLOR: Color = Color::new(0.92, 0.92, 0.92, 1.0);
+
+/// The color of the lines between header cells.
+pub const DATA_GRID_LINE_COLOR: Color = Color::new(0.8, 0.8, 0.8, 1.0);
+
+/// The background of the selected row.
+pub const DATA_GRID_SELECTED_COLOR: Color = Color::new(0.26, 0.52, 0.96, 0.3);
+
+/// The background of every other row, to help follow a row across.
+const STRIPE_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.04);
+
+/// The key of a grid that isn't given one.
+const DEFAULT_KEY: &str = "vitae-data-grid";
+
+/// Room left around the text of a cell, in pixels.
+const CELL_PADDING: f32 = 8.0;
+
+/// How wide the part of a header's right edge is that resizes its column,
+/// and how narrow a column can be made, in pixels.
+const RESIZE_HANDLE_WIDTH: f32 = 8.0;
+const MIN_COLUMN_WIDTH: f32 = 32.0;
+
+/// The handler given to `DataGrid::on_select`, taking the model as `Any`.
+type SelectHandler = Rc<dyn Fn(&mut dyn Any, usize)>;
+
+/// A column of a `data_grid`.
+#[derive(Clone, Debug)]
+pub struct Column {
+    pub title: String,
+    /// How wide the column starts out, in pixels
+    pub width: f32,
+    /// Whether clicking the header sorts the rows by this column
+    pub sortable: bool,
+}
+
+impl Column {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            width: 160.0,
+            sortable: true,
+        }
+    }
+
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = width.max(MIN_COLUMN_WIDTH);
+        self
+    }
+
+    pub fn sortable(mut self, sortable: bool) -> Self {
+        self.sortable = sortable;
+        self
+    }
+}
+
+/// A grid made by `data_grid`. Use it as a child like any other element.
+pub struct DataGrid {
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+    key: String,
+    on_select: Option<SelectHandler>,
+}
+
+/// What the user has done to a grid, kept between views under its key.
+#[derive(Clone)]
+struct GridState {
+    /// How wide each column is, and the widths given with `Column::width`
+    /// when they were last set to them
+    widths: Vec<f32>,
+    given: Vec<f32>,
+    /// The column the rows are sorted by, and whether they go up
+    sort: Option<(usize, bool)>,
+    /// The rows in the order shown, as indices into the grid's rows, and
+    /// the rows and sort they were put in order for
+    order: Rc<Vec<usize>>,
+    sorted: Option<(u64, Option<(usize, bool)>)>,
+    /// The selected row, as an index into the grid's rows, so it stays
+    /// selected when the rows are sorted
+    selected: Option<usize>,
+    /// How far the grid is scrolled across and down, in pixels
+    scroll: (f32, f32),
+    /// Where across the grid the cursor last was, in window coordinates
+    cursor: f32,
+    /// The column being resized, where the cursor was and how wide the
+    /// column was when its edge was pressed
+    drag: Option<(usize, f32, f32)>,
+}
+
+/// Show `rows` of text under `columns`, one cell for each column in each
+/// row. The grid fills its parent and scrolls both ways with the mouse
+/// wheel or trackpad. Clicking a header sorts the rows by its column, going
+/// up, then down the next time. Cells that parse as numbers sort as
+/// numbers, and others alphabetically. Dragging the right edge of a header
+/// makes its column wider or narrower. Clicking a row selects it, and the
+/// up and down arrow keys move the selection.
+///
+/// Only the rows that fit in the grid are built, however many there are.
+///
+/// # Example
+/// ```
+/// data_grid(
+///     [
+///         Column::new("Name").width(240.0),
+///         Column::new("Size").width(80.0),
+///         Column::new("Modified"),
+///     ],
+///     model.files.iter().map(|f| [f.name.clone(), f.size.to_string(), f.modified.clone()]),
+/// )
+/// .key("files")
+/// .on_select(|m: &mut Browser, row| m.selected_file = Some(row))
+/// ```
+pub fn data_grid<R, S>(
+    columns: impl IntoIterator<Item = Column>,
+    rows: impl IntoIterator<Item = R>,
+) -> DataGrid
+where
+    R: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    DataGrid {
+        columns: columns.into_iter().collect(),
+        rows: rows
+            .into_iter()
+            .map(|row| row.into_iter().map(Into::into).collect())
+            .collect(),
+        key: DEFAULT_KEY.to_string(),
+        on_select: None,
+    }
+}
+
+impl DataGrid {
+    /// Tell this grid apart from others in the view. Each grid needs a key
+    /// of its own, which is also the key of its element.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+
+    /// Call `handler` with the index of a row, in the rows given to
+    /// `data_grid`, each time it is selected.
+    pub fn on_select<M, F>(mut self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, usize) + 'static,
+    {
+        self.on_select = Some(Rc::new(move |model: &mut dyn Any, row| {
+            if let Some(model) = model.downcast_mut::<M>() {
+                handler(model, row);
+            }
+        }));
+        self
+    }
+}
+
+/// Order two cells, as numbers if both are, and otherwise alphabetically
+/// without regard to case.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a
+            .chars()
+            .flat_map(char::to_lowercase)
+            .cmp(b.chars().flat_map(char::to_lowercase)),
+    }
+}
+
+/// The indices of `rows` in the order to show them, sorted by `sort`.
+fn sort_order(rows: &[Vec<String>], sort: Option<(usize, bool)>) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    if let Some((column, ascending)) = sort {
+        let cell = |row: usize| rows[row].get(column).map_or("", String::as_str);
+        order.sort_by(|&a, &b| {
+            let ordering = compare_cells(cell(a), cell(b));
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+    order
+}
+
+/// A hash of `rows`, to tell when they have changed and need sorting again.
+fn fingerprint(rows: &[Vec<String>]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    rows.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// `content`, cut short with an ellipsis if it is wider than `width`
+/// pixels.
+fn fit(content: &str, width: f32) -> String {
+    let fits = |content: &str| measure(content, DEFAULT_FONT_SIZE, None).0 <= width;
+    if fits(content) {
+        return content.to_string();
+    }
+    let chars: Vec<char> = content.chars().collect();
+    let shortened = |count: usize| {
+        chars[..count]
+            .iter()
+            .chain(['…'].iter())
+            .collect::<String>()
+    };
+
+    // The most characters that fit with the ellipsis after them
+    let (mut low, mut high) = (0, chars.len());
+    while low < high {
+        let middle = (low + high).div_ceil(2);
+        if fits(&shortened(middle)) {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    let content = shortened(low);
+    if fits(&content) {
+        content
+    } else {
+        String::new()
+    }
+}
+
+impl From<DataGrid> for ElementBuilder {
+    fn from(grid: DataGrid) -> Self {
+        let DataGrid {
+            columns,
+            rows,
+            key,
+            on_select,
+        } = grid;
+
+        let given: Vec<f32> = columns.iter().map(|column| column.width).collect();
+        let state = use_keyed_signal(&key, || GridState {
+            widths: given.clone(),
+            given: given.clone(),
+            sort: None,
+            order: Rc::new(Vec::new()),
+            sorted: None,
+            selected: None,
+            scroll: (0.0, 0.0),
+            cursor: 0.0,
+            drag: None,
+        });
+        let mut current = state.get();
+        let mut changed = false;
+        if current.given != given {
+            current.widths = given.clone();
+            current.given = given;
+            current.drag = None;
+            changed = true;
+        }
+        let sortable = |column: usize| columns.get(column).is_some_and(|c| c.sortable);
+        if current.sort.is_some_and(|(column, _)| !sortable(column)) {
+            current.sort = None;
+            changed = true;
+        }
+        if current.selected.is_some_and(|row| row >= rows.len()) {
+            current.selected = None;
+            changed = true;
+        }
+        // Sorting only when the rows or the sort change keeps views that
+        // change neither, such as while hovering, quick
+        let sorted = (fingerprint(&rows), current.sort);
+        if current.sorted != Some(sorted) {
+            current.order = Rc::new(sort_order(&rows, current.sort));
+            current.sorted = Some(sorted);
+            changed = true;
+        }
+        if changed {
+            state.set(current.clone());
+        }
+
+        let (width, height) = use_element_size(&key).get();
+        let row_height = measure("Ag", DEFAULT_FONT_SIZE, None).1 + CELL_PADDING;
+        let text_top = px(CELL_PADDING / 2.0);
+
+        // Where each column starts, and where the last one ends
+        let mut lefts = vec![0.0];
+        for width in &current.widths {
+            lefts.push(lefts[lefts.len() - 1] + width);
+        }
+        let total = lefts[lefts.len() - 1];
+
+        // Without clipping, the grid scrolls by whole columns and rows, and
+        // only draws those that start inside it
+        let last_first_column = (0..lefts.len())
+            .find(|&column| total - lefts[column] <= width)
+            .unwrap_or(0);
+        let visible_rows = ((height - row_height) / row_height).floor().max(0.0) as usize;
+        let max_scroll = (
+            lefts[last_first_column],
+            rows.len().saturating_sub(visible_rows) as f32 * row_height,
+        );
+        let clamp =
+            move |(x, y): (f32, f32)| (x.clamp(0.0, max_scroll.0), y.clamp(0.0, max_scroll.1));
+        let (scroll_x, scroll_y) = clamp(current.scroll);
+        let first_column = (0..=last_first_column)
+            .rev()
+            .find(|&column| lefts[column] <= scroll_x)
+            .unwrap_or(0);
+        let first_row = (scroll_y / row_height) as usize;
+        let last_row = (first_row + visible_rows).min(rows.len());
+
+        // The columns in view, with where each starts and how much of it
+        // fits
+        let shown: Vec<(usize, f32, f32)> = (first_column..columns.len())
+            .map(|column| (column, lefts[column] - lefts[first_column]))
+            .take_while(|&(_, left)| left < width)
+            .map(|(column, left)| (column, left, current.widths[column].min(width - left)))
+            .collect();
+
+        let mut children = Vec::new();
+        for &(column, left, shown_width) in &shown {
+            let arrow = match current.sort {
+                Some((sorted, true)) if sorted == column => " ▲",
+                Some((sorted, false)) if sorted == column => " ▼",
+                _ => "",
+            };
+            let title = fit(
+                &format!("{}{arrow}", columns[column].title),
+                shown_width - CELL_PADDING * 2.0,
+            );
+
+            let handle = div()
+                .absolute()
+                .top(px(0.0))
+                .right(px(0.0))
+                .w(px(RESIZE_HANDLE_WIDTH))
+                .h(pc(100.0))
+                .cursor(CursorIcon::ColResize)
+                .on_event_any(move |_, event| {
+                    let mut current = state.get();
+                    match *event {
+                        Event::MouseMove { x, .. } => {
+                            current.cursor = x;
+                            if let Some((column, start, width)) = current.drag {
+                                current.widths[column] = (width + x - start).max(MIN_COLUMN_WIDTH);
+                            }
+                        }
+                        Event::MouseDown {
+                            button: MouseButton::Left,
+                        } => {
+                            current.drag = Some((column, current.cursor, current.widths[column]));
+                        }
+                        Event::MouseUp {
+                            button: MouseButton::Left,
+                        } => current.drag = None,
+                        _ => return EventResult::Continue,
+                    }
+                    state.set(current);
+                    EventResult::Continue
+                });
+
+            let header = div()
+                .absolute()
+                .top(px(0.0))
+                .left(px(left))
+                .w(px(shown_width))
+                .h(px(row_height))
+                .bg(DATA_GRID_HEADER_COLOR)
+                .border_r(1.0, DATA_GRID_LINE_COLOR)
+                .children(
+                    (!title.is_empty())
+                        .then(|| text(title).absolute().top(text_top).left(px(CELL_PADDING))),
+                )
+                .child(handle);
+            let header = if columns[column].sortable {
+                header
+                    .cursor(CursorIcon::Pointer)
+                    .on_event_any(move |_, event| {
+                        let Event::Click {
+                            button: MouseButton::Left,
+                            ..
+                        } = *event
+                        else {
+                            return EventResult::Continue;
+                        };
+                        let mut current = state.get();
+                        let ascending = current.sort != Some((column, true));
+                        current.sort = Some((column, ascending));
+                        state.set(current);
+                        EventResult::Continue
+                    })
+            } else {
+                header
+            };
+            children.push(header);
+        }
+
+        for (position, &row) in current.order[first_row..last_row].iter().enumerate() {
+            let on_select = on_select.clone();
+            let element = div()
+                .absolute()
+                .top(px((position + 1) as f32 * row_height))
+                .left(px(0.0))
+                .w(pc(100.0))
+                .h(px(row_height))
+                .children(shown.iter().filter_map(|&(column, left, shown_width)| {
+                    let cell = rows[row].get(column)?;
+                    let content = fit(cell, shown_width - CELL_PADDING * 2.0);
+                    (!content.is_empty()).then(|| {
+                        text(content)
+                            .absolute()
+                            .top(text_top)
+                            .left(px(left + CELL_PADDING))
+                    })
+                }))
+                .on_event_any(move |model, event| {
+                    let Event::Click {
+                        button: MouseButton::Left,
+                        ..
+                    } = *event
+                    else {
+                        return EventResult::Continue;
+                    };
+                    let mut current = state.get();
+                    current.selected = Some(row);
+                    state.set(current);
+                    if let Some(on_select) = &on_select {
+                        on_select(model, row);
+                    }
+                    EventResult::Continue
+                });
+            let element = if current.selected == Some(row) {
+                element.bg(DATA_GRID_SELECTED_COLOR)
+            } else if (first_row + position) % 2 == 1 {
+                element.bg(STRIPE_COLOR)
+            } else {
+                element
+            };
+            children.push(element);
+        }
+
+        div()
+            .key(key)
+            .size(pc(100.0))
+            .on_event_any(move |model, event| {
+                let mut current = state.get();
+                match *event {
+                    Event::Scroll { dx, dy } => {
+                        let (x, y) = clamp(current.scroll);
+                        let scrolled = clamp((x + dx, y + dy));
+                        if scrolled == (x, y) {
+                            // Let whatever is around the grid scroll instead
+                            return EventResult::Continue;
+                        }
+                        current.scroll = scrolled;
+                    }
+                    Event::KeyDown {
+                        key: Key::Named(key @ (NamedKey::ArrowUp | NamedKey::ArrowDown)),
+                        ..
+                    } => {
+                        let order = current.order.clone();
+                        let position = current
+                            .selected
+                            .and_then(|row| order.iter().position(|&r| r == row));
+                        let position = match (position, key) {
+                            (None, _) => 0,
+                            (Some(position), NamedKey::ArrowUp) => position.saturating_sub(1),
+                            (Some(position), _) => {
+                                (position + 1).min(order.len().saturating_sub(1))
+                            }
+                        };
+                        let Some(&row) = order.get(position) else {
+                            return EventResult::Continue;
+                        };
+                        current.selected = Some(row);
+
+                        // Bring the selected row into view
+                        let (x, y) = clamp(current.scroll);
+                        let top = position as f32 * row_height;
+                        let bottom = (position + 1) as f32 * row_height;
+                        let view = visible_rows as f32 * row_height;
+                        current.scroll = if top < y {
+                            (x, top)
+                        } else if bottom > y + view {
+                            (x, bottom - view)
+                        } else {
+                            (x, y)
+                        };
+                        if let Some(on_select) = &on_select {
+                            on_select(model, row);
+                        }
+                    }
+                    _ => return EventResult::Continue,
+                }
+                state.set(current);
+                EventResult::Stop
+            })
+            .children(children)
+    }
+}
@@ -0,0 +1,320 @@
+//! Developer tools drawn over the app. The time-travel panel lists the
+//! events dispatched so far and can put the model back the way it was after
+//! any of them (see `App::time_travel`), and the inspector shows the model
+//! and signals as they are (see `App::inspector`).
+
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+use vitae_core::{
+    div, pc, portal, px, text, Color, CursorIcon, ElementBuilder, Event, EventResult, MouseButton,
+};
+
+use crate::signal::{describe_signals, request_redraw};
+
+/// The portal layer of the developer tools, above the toast stack.
+const DEVTOOLS_LAYER: i32 = 2000;
+
+/// How many events are kept.
+const MAX_ENTRIES: usize = 1000;
+
+/// How many events the panel lists at once.
+const SHOWN_ENTRIES: usize = 30;
+
+/// How many lines of the model the inspector shows.
+const MAX_MODEL_LINES: usize = 200;
+
+/// The width of each panel.
+const PANEL_WIDTH: f32 = 360.0;
+
+thread_local! {
+    static OPEN: Cell<bool> = const { Cell::new(false) };
+    /// The entry to show the model as it was after, if one was clicked
+    static JUMP: Cell<Option<usize>> = const { Cell::new(None) };
+    /// Whether the event just dispatched went to the panel itself, and so
+    /// shouldn't be recorded
+    static PANEL_EVENT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Show or hide the developer tools. F12 does the same.
+pub(crate) fn toggle() {
+    OPEN.set(!OPEN.get());
+    request_redraw();
+}
+
+struct Entry<M> {
+    /// When the event happened, from the start of the app
+    at: Duration,
+    event: String,
+    /// How many events in a row the entry stands for, as mouse moves are
+    /// listed once per run
+    count: usize,
+    /// The model right after the event
+    model: M,
+}
+
+/// The events dispatched so far, each with the model it left behind.
+pub(crate) struct Recorder<M> {
+    started: Instant,
+    entries: Vec<Entry<M>>,
+    /// The entry whose model is shown, when scrubbed back from the latest
+    position: Option<usize>,
+}
+
+impl<M: Clone> Recorder<M> {
+    pub(crate) fn new() -> Self {
+        Recorder {
+            started: Instant::now(),
+            entries: Vec::new(),
+            position: None,
+        }
+    }
+
+    /// Record the model the app starts with.
+    pub(crate) fn start(&mut self, model: &M) {
+        if self.entries.is_empty() {
+            self.entries.push(Entry {
+                at: Duration::ZERO,
+                event: "Start".to_string(),
+                count: 1,
+                model: model.clone(),
+            });
+        }
+    }
+
+    /// Record `event`, just dispatched, and the model it left behind.
+    pub(crate) fn record(&mut self, event: &Event, model: &M) {
+        if PANEL_EVENT.take() {
+            return;
+        }
+        // Moving the mouse over an earlier model to look at it doesn't count
+        // as carrying on from it, which drops the events that followed it
+        if self.position.is_some()
+            && matches!(event, Event::MouseMove { .. } | Event::MouseDelta { .. })
+        {
+            return;
+        }
+        if let Some(position) = self.position.take() {
+            self.entries.truncate(position + 1);
+        }
+
+        let at = self.started.elapsed();
+        let event = describe(event);
+        if let Some(last) = self.entries.last_mut() {
+            if matches!(event.as_str(), "MouseMove") && last.event == event {
+                last.at = at;
+                last.count += 1;
+                last.model = model.clone();
+                return;
+            }
+        }
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.entries.push(Entry {
+            at,
+            event,
+            count: 1,
+            model: model.clone(),
+        });
+    }
+
+    /// Show the model from the entry clicked in the panel, if any. Returns
+    /// whether the model was replaced.
+    pub(crate) fn apply_request(&mut self, model: &mut M) -> bool {
+        let Some(entry) = JUMP.take().and_then(|index| {
+            self.position = (index + 1 < self.entries.len()).then_some(index);
+            self.entries.get(index)
+        }) else {
+            return false;
+        };
+        *model = entry.model.clone();
+        true
+    }
+
+    /// The panel, if it's open. It's a portal, so it can go anywhere in the
+    /// view.
+    pub(crate) fn panel(&self) -> Option<ElementBuilder> {
+        if !OPEN.get() || self.entries.is_empty() {
+            return None;
+        }
+        let len = self.entries.len();
+        let current = self.position.unwrap_or(len - 1);
+        // Keep the shown entry in the middle of the list where possible
+        let end = (current + SHOWN_ENTRIES / 2 + 1).clamp(SHOWN_ENTRIES.min(len), len);
+        let start = end.saturating_sub(SHOWN_ENTRIES);
+
+        let controls = div()
+            .row()
+            .gap(px(4.0))
+            .child(button("Back", current.checked_sub(1)))
+            .child(button(
+                "Forward",
+                (current + 1 < len).then_some(current + 1),
+            ))
+            .child(button("Latest", (current + 1 < len).then_some(len - 1)));
+
+        Some(
+            side_panel()
+                .right(px(0.0))
+                .child(text("Events (F12 to hide)").color(Color::WHITE))
+                .child(controls)
+                .children((start..end).map(|index| {
+                    let entry = &self.entries[index];
+                    entry_row(index, entry, index.cmp(&current))
+                })),
+        )
+    }
+}
+
+/// Shows the model and the signals' values.
+pub(crate) struct Inspector<M> {
+    describe: fn(&M) -> String,
+}
+
+impl<M: Debug> Inspector<M> {
+    pub(crate) fn new() -> Self {
+        Inspector {
+            describe: |model| format!("{model:#?}"),
+        }
+    }
+}
+
+impl<M> Inspector<M> {
+    /// The panel showing `model`, if it's open. Built after the view, so
+    /// the signals show the values the view left them with.
+    pub(crate) fn panel(&self, model: &M) -> Option<ElementBuilder> {
+        if !OPEN.get() {
+            return None;
+        }
+        let model = (self.describe)(model);
+        let lines: Vec<&str> = model.lines().collect();
+        let mut rows: Vec<ElementBuilder> = lines
+            .iter()
+            .take(MAX_MODEL_LINES)
+            .map(|line| value_row(line.to_string()))
+            .collect();
+        if lines.len() > MAX_MODEL_LINES {
+            rows.push(value_row(format!(
+                "… {} more lines",
+                lines.len() - MAX_MODEL_LINES
+            )));
+        }
+
+        let signals = describe_signals();
+        let signal_rows = if signals.is_empty() {
+            vec![value_row("No signals".to_string())]
+        } else {
+            signals
+                .into_iter()
+                .map(|(name, value)| value_row(format!("{name}: {value}")))
+                .collect()
+        };
+
+        Some(
+            side_panel()
+                .left(px(0.0))
+                .child(text("Model (F12 to hide)").color(Color::WHITE))
+                .children(rows)
+                .child(text("Signals").color(Color::WHITE))
+                .children(signal_rows),
+        )
+    }
+}
+
+/// The frame shared by the panels, to be put against the left or right of
+/// the window.
+fn side_panel() -> ElementBuilder {
+    portal()
+        .layer(DEVTOOLS_LAYER)
+        .top(px(0.0))
+        .w(px(PANEL_WIDTH))
+        .h(pc(100.0))
+        .col()
+        .gap(px(4.0))
+        .p(px(8.0))
+        .bg(Color::from_hex("#1e1e1e"))
+        .on_event_any(|_, event| panel_event(event))
+}
+
+fn value_row(line: String) -> ElementBuilder {
+    text(line).font_size(12.0).color(Color::from_hex("#d0d0d0"))
+}
+
+/// Note that the panel got `event`, so it isn't recorded.
+fn panel_event(event: &Event) -> EventResult {
+    // Hover changes aren't recorded, so they mustn't leave the flag set
+    if !matches!(event, Event::MouseEnter | Event::MouseLeave) {
+        PANEL_EVENT.set(true);
+    }
+    EventResult::Continue
+}
+
+/// A button that jumps to the entry at `target`, greyed out if there is
+/// none.
+fn button(label: &'static str, target: Option<usize>) -> ElementBuilder {
+    let button = div()
+        .p(px(4.0))
+        .radius(4.0)
+        .bg(Color::from_hex("#3a3a3a"))
+        .child(text(label).font_size(12.0).color(Color::WHITE));
+    match target {
+        Some(index) => button
+            .cursor(CursorIcon::Pointer)
+            .on_event_any(move |_, event| jump_on_click(event, index)),
+        None => button.opacity(0.4),
+    }
+}
+
+/// One event in the list. `order` is where it falls relative to the model
+/// shown: events after it are greyed out, since they haven't happened yet.
+fn entry_row<M>(index: usize, entry: &Entry<M>, order: std::cmp::Ordering) -> ElementBuilder {
+    let mut label = format!("{:7.2}s  {}", entry.at.as_secs_f32(), entry.event);
+    if entry.count > 1 {
+        label.push_str(&format!(" ×{}", entry.count));
+    }
+    let color = match order {
+        std::cmp::Ordering::Greater => Color::from_hex("#707070"),
+        _ => Color::from_hex("#d0d0d0"),
+    };
+    let row = div()
+        .w(pc(100.0))
+        .p(px(2.0))
+        .cursor(CursorIcon::Pointer)
+        .child(text(label).font_size(12.0).color(color))
+        .on_event_any(move |_, event| jump_on_click(event, index));
+    if order.is_eq() {
+        row.bg(Color::from_hex("#264f78"))
+    } else {
+        row
+    }
+}
+
+fn jump_on_click(event: &Event, index: usize) -> EventResult {
+    panel_event(event);
+    if let Event::Click {
+        button: MouseButton::Left,
+        ..
+    } = event
+    {
+        JUMP.set(Some(index));
+    }
+    EventResult::Stop
+}
+
+/// A short description of `event` for the list.
+fn describe(event: &Event) -> String {
+    match event {
+        Event::Click { button, info } => {
+            format!("Click {button:?} at ({:.0}, {:.0})", info.x, info.y)
+        }
+        Event::MouseDown { button } => format!("MouseDown {button:?}"),
+        Event::MouseUp { button } => format!("MouseUp {button:?}"),
+        Event::MouseMove { .. } => "MouseMove".to_string(),
+        Event::MouseDelta { .. } => "MouseDelta".to_string(),
+        Event::KeyDown { key, .. } => format!("KeyDown {key:?}"),
+        Event::KeyUp { key } => format!("KeyUp {key:?}"),
+        other => format!("{other:?}"),
+    }
+}
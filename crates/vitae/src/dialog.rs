@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use rfd::FileDialog;
+
+/// Ask the user to pick a folder with the system's folder picker. Returns
+/// `None` if they cancel it.
+///
+/// The dialog blocks until it is closed, so call this from an event
+/// handler.
+///
+/// # Example
+/// ```
+/// button("Open Folder").on_left_click(|m: &mut MyModel| {
+///     if let Some(folder) = dialog::pick_folder() {
+///         m.open(folder);
+///     }
+/// })
+/// ```
+pub fn pick_folder() -> Option<PathBuf> {
+    FileDialog::new().pick_folder()
+}
+
+/// Ask the user to pick a file with the system's file picker, showing only
+/// files with one of `extensions` (written without the dot). An empty list
+/// shows every file. Returns `None` if they cancel it.
+pub fn pick_file(extensions: &[&str]) -> Option<PathBuf> {
+    let mut dialog = FileDialog::new();
+    if !extensions.is_empty() {
+        dialog = dialog.add_filter("Files", extensions);
+    }
+    dialog.pick_file()
+}
@@ -0,0 +1,624 @@
+//! Panels docked around a central view, as in an IDE: each side holds a
+//! group of panels shown one at a time under tabs, and a panel can be
+//! dragged by its tab to another side, or away from the sides to float
+//! above the rest.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use vitae_core::{
+    div, pc, portal, px, text, Color, CursorIcon, ElementBuilder, Event, EventResult, MouseButton,
+};
+use vitae_render::text::{measure, DEFAULT_FONT_SIZE};
+
+use crate::signal::{use_keyed_signal, Signal};
+use crate::size::{use_element_origin, use_element_size};
+use crate::split::{split, SPLIT_DIVIDER_COLOR};
+
+/// The portal layer of floating panels, above ordinary portals.
+pub const DOCK_FLOAT_LAYER: i32 = 100;
+
+/// The color of the area a dragged panel would be docked to if dropped.
+pub const DOCK_PREVIEW_COLOR: Color = Color::new(0.26, 0.52, 0.96, 0.3);
+
+/// The background of the tabs, and of a floating panel.
+const TAB_STRIP_COLOR: Color = Color::new(0.18, 0.18, 0.18, 1.0);
+const ACTIVE_TAB_COLOR: Color = Color::new(0.3, 0.3, 0.3, 1.0);
+const FLOAT_COLOR: Color = Color::new(0.14, 0.14, 0.14, 1.0);
+
+/// The key of a dock that isn't given one.
+const DEFAULT_KEY: &str = "vitae-dock";
+
+/// Room left around the title of a tab, in pixels.
+const TAB_PADDING: f32 = 12.0;
+
+/// How far a tab has to be dragged before it comes loose, in pixels.
+const DRAG_THRESHOLD: f32 = 6.0;
+
+/// How close to an edge of the dock, as a share of its width or height, a
+/// panel has to be dropped to dock it to that side.
+const DROP_ZONE: f32 = 0.2;
+
+/// How big a panel is when it is dragged out of a side to float.
+const FLOAT_SIZE: (f32, f32) = (480.0, 360.0);
+
+/// The handler given to `Dock::on_change`, taking the model as `Any`.
+type ChangeHandler = Rc<dyn Fn(&mut dyn Any, DockLayout)>;
+
+/// A side of a dock that panels can be docked to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DockSide {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// The panels docked to one side of a dock.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DockArea {
+    /// The ids of the panels, in the order of their tabs
+    pub panels: Vec<String>,
+    /// The index of the panel shown
+    pub active: usize,
+    /// The share of the space the side takes up, from 0 to 1. The left side
+    /// shares the whole dock, the right side what the left leaves, the top
+    /// what is between them, and the bottom what the top leaves.
+    pub size: f32,
+}
+
+impl DockArea {
+    fn new(size: f32) -> Self {
+        Self {
+            panels: Vec::new(),
+            active: 0,
+            size,
+        }
+    }
+}
+
+/// A panel floating above the dock, in window coordinates.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FloatingPanel {
+    pub panel: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Where the panels of a dock are. Keep it in the model, and save it with
+/// the model to bring the arrangement back on the next run.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DockLayout {
+    pub left: DockArea,
+    pub right: DockArea,
+    pub top: DockArea,
+    pub bottom: DockArea,
+    /// The floating panels, from the bottom of the stack to the top
+    pub floating: Vec<FloatingPanel>,
+}
+
+impl Default for DockLayout {
+    fn default() -> Self {
+        Self {
+            left: DockArea::new(0.2),
+            right: DockArea::new(0.25),
+            top: DockArea::new(0.25),
+            bottom: DockArea::new(0.3),
+            floating: Vec::new(),
+        }
+    }
+}
+
+impl DockLayout {
+    /// A layout with no panels in it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn area(&self, side: DockSide) -> &DockArea {
+        match side {
+            DockSide::Left => &self.left,
+            DockSide::Right => &self.right,
+            DockSide::Top => &self.top,
+            DockSide::Bottom => &self.bottom,
+        }
+    }
+
+    pub fn area_mut(&mut self, side: DockSide) -> &mut DockArea {
+        match side {
+            DockSide::Left => &mut self.left,
+            DockSide::Right => &mut self.right,
+            DockSide::Top => &mut self.top,
+            DockSide::Bottom => &mut self.bottom,
+        }
+    }
+
+    /// The side `panel` is docked to, or `None` if it floats or isn't in
+    /// the layout.
+    pub fn side_of(&self, panel: &str) -> Option<DockSide> {
+        [
+            DockSide::Left,
+            DockSide::Right,
+            DockSide::Top,
+            DockSide::Bottom,
+        ]
+        .into_iter()
+        .find(|&side| self.area(side).panels.iter().any(|p| p == panel))
+    }
+
+    /// Whether `panel` is docked or floating.
+    pub fn contains(&self, panel: &str) -> bool {
+        self.side_of(panel).is_some() || self.floating.iter().any(|f| f.panel == panel)
+    }
+
+    /// Dock `panel` to `side`, after the panels already there, and show it,
+    /// taking it from wherever it was.
+    pub fn dock(&mut self, panel: impl Into<String>, side: DockSide) {
+        let panel = panel.into();
+        self.close(&panel);
+        let area = self.area_mut(side);
+        area.panels.push(panel);
+        area.active = area.panels.len() - 1;
+    }
+
+    /// Float `panel` with its top left corner at (`x`, `y`) in the window,
+    /// on top of the other floating panels, taking it from wherever it was.
+    pub fn float(&mut self, panel: impl Into<String>, x: f32, y: f32, width: f32, height: f32) {
+        let panel = panel.into();
+        self.close(&panel);
+        self.floating.push(FloatingPanel {
+            panel,
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    /// Take `panel` out of the layout, so it isn't shown.
+    pub fn close(&mut self, panel: &str) {
+        for side in [
+            DockSide::Left,
+            DockSide::Right,
+            DockSide::Top,
+            DockSide::Bottom,
+        ] {
+            let area = self.area_mut(side);
+            if let Some(index) = area.panels.iter().position(|p| p == panel) {
+                area.panels.remove(index);
+                if area.active > index || area.active >= area.panels.len() {
+                    area.active = area.active.saturating_sub(1);
+                }
+            }
+        }
+        self.floating.retain(|f| f.panel != panel);
+    }
+
+    /// Show `panel` in front of the others on its side, or on top of the
+    /// other floating panels.
+    pub fn activate(&mut self, panel: &str) {
+        if let Some(side) = self.side_of(panel) {
+            let area = self.area_mut(side);
+            area.active = area.panels.iter().position(|p| p == panel).unwrap_or(0);
+        } else if let Some(index) = self.floating.iter().position(|f| f.panel == panel) {
+            let floating = self.floating.remove(index);
+            self.floating.push(floating);
+        }
+    }
+}
+
+/// Panels docked around a view, made by `dock`. Use it as a child like any
+/// other element.
+pub struct Dock {
+    layout: DockLayout,
+    center: ElementBuilder,
+    panels: Vec<(String, String, ElementBuilder)>,
+    key: String,
+    on_change: Option<ChangeHandler>,
+}
+
+/// The panel being dragged by its tab, kept between views under the dock's
+/// key.
+#[derive(Clone)]
+struct PanelDrag {
+    panel: String,
+    /// Where the cursor was when the tab was pressed
+    start: (f32, f32),
+    /// Where the cursor is on the panel's title, to keep it there while the
+    /// panel moves
+    grab: (f32, f32),
+    /// Whether the tab has been dragged far enough to come loose
+    loose: bool,
+}
+
+#[derive(Clone, Default)]
+struct DockState {
+    /// Where the cursor last was, in window coordinates
+    cursor: (f32, f32),
+    drag: Option<PanelDrag>,
+}
+
+/// Put `center` in the middle of the panels docked around it as `layout`
+/// says, and float the rest above it. The dock fills its parent.
+///
+/// Panels are given with `Dock::panel`, and shown where the layout puts
+/// them; panels the layout leaves out aren't shown. Dragging a panel's tab
+/// to an edge of the dock docks it to that side, dropping it anywhere else
+/// floats it, and dragging the divider beside a side resizes it. Each change
+/// gives `on_change` a new layout to keep in the model.
+///
+/// # Example
+/// ```
+/// dock(&model.dock, editor(model))
+///     .key("workbench")
+///     .panel("files", "Files", file_tree(model))
+///     .panel("outline", "Outline", outline(model))
+///     .panel("terminal", "Terminal", terminal(model))
+///     .on_change(|m: &mut Ide, layout| m.dock = layout)
+/// ```
+pub fn dock(layout: &DockLayout, center: impl Into<ElementBuilder>) -> Dock {
+    Dock {
+        layout: layout.clone(),
+        center: center.into(),
+        panels: Vec::new(),
+        key: DEFAULT_KEY.to_string(),
+        on_change: None,
+    }
+}
+
+impl Dock {
+    /// Tell this dock apart from others in the view. Each dock needs a key
+    /// of its own, which is also the key of its element.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+
+    /// Add a panel the layout can place by its `id`, with `title` on its
+    /// tab.
+    pub fn panel(
+        mut self,
+        id: impl Into<String>,
+        title: impl Into<String>,
+        content: impl Into<ElementBuilder>,
+    ) -> Self {
+        self.panels.push((id.into(), title.into(), content.into()));
+        self
+    }
+
+    /// Call `handler` with the new layout each time a panel is moved or a
+    /// side is resized. Without it, the layout can't be changed.
+    pub fn on_change<M, F>(mut self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, DockLayout) + 'static,
+    {
+        self.on_change = Some(Rc::new(move |model: &mut dyn Any, layout| {
+            if let Some(model) = model.downcast_mut::<M>() {
+                handler(model, layout);
+            }
+        }));
+        self
+    }
+}
+
+/// The side of the dock at `origin` of `size` that a panel dropped at
+/// `cursor` docks to, if it is near enough to an edge.
+fn drop_side(cursor: (f32, f32), origin: (f32, f32), size: (f32, f32)) -> Option<DockSide> {
+    if size.0 <= 0.0 || size.1 <= 0.0 {
+        return None;
+    }
+    let across = (cursor.0 - origin.0) / size.0;
+    let down = (cursor.1 - origin.1) / size.1;
+    if !(0.0..=1.0).contains(&across) || !(0.0..=1.0).contains(&down) {
+        return None;
+    }
+    [
+        (across, DockSide::Left),
+        (1.0 - across, DockSide::Right),
+        (down, DockSide::Top),
+        (1.0 - down, DockSide::Bottom),
+    ]
+    .into_iter()
+    .filter(|&(distance, _)| distance < DROP_ZONE)
+    .min_by(|a, b| a.0.total_cmp(&b.0))
+    .map(|(_, side)| side)
+}
+
+/// What the tab (or floating title) of `panel` does: dragging it moves the
+/// panel, and clicking it shows it.
+fn tab_handler(
+    state: Signal<DockState>,
+    panel: String,
+    layout: &DockLayout,
+    origin: Signal<(f32, f32)>,
+    size: Signal<(f32, f32)>,
+    tab_height: f32,
+    on_change: Option<ChangeHandler>,
+) -> impl Fn(&mut dyn Any, &Event) -> EventResult + 'static {
+    let layout = layout.clone();
+    move |model, event| {
+        let mut current = state.get();
+        match *event {
+            Event::MouseMove { x, y } => {
+                current.cursor = (x, y);
+                if let Some(drag) = &mut current.drag {
+                    let moved = (x - drag.start.0).hypot(y - drag.start.1);
+                    drag.loose |= moved > DRAG_THRESHOLD;
+                }
+            }
+            Event::MouseDown {
+                button: MouseButton::Left,
+            } => {
+                let (x, y) = current.cursor;
+                let grab = match layout.floating.iter().find(|f| f.panel == panel) {
+                    Some(floating) => (x - floating.x, y - floating.y),
+                    None => (TAB_PADDING, tab_height / 2.0),
+                };
+                current.drag = Some(PanelDrag {
+                    panel: panel.clone(),
+                    start: (x, y),
+                    grab,
+                    loose: false,
+                });
+            }
+            Event::MouseUp {
+                button: MouseButton::Left,
+            } => {
+                let Some(drag) = current.drag.take() else {
+                    return EventResult::Continue;
+                };
+                let mut changed = layout.clone();
+                if drag.loose {
+                    match drop_side(current.cursor, origin.get(), size.get()) {
+                        Some(side) => changed.dock(drag.panel, side),
+                        None => {
+                            let (width, height) = layout
+                                .floating
+                                .iter()
+                                .find(|f| f.panel == drag.panel)
+                                .map_or(FLOAT_SIZE, |f| (f.width, f.height));
+                            let (x, y) = current.cursor;
+                            changed.float(
+                                drag.panel,
+                                x - drag.grab.0,
+                                y - drag.grab.1,
+                                width,
+                                height,
+                            );
+                        }
+                    }
+                } else {
+                    changed.activate(&drag.panel);
+                }
+                match &on_change {
+                    Some(on_change) if changed != layout => on_change(model, changed),
+                    _ => {}
+                }
+            }
+            _ => return EventResult::Continue,
+        }
+        state.set(current);
+        EventResult::Continue
+    }
+}
+
+impl From<Dock> for ElementBuilder {
+    fn from(dock: Dock) -> Self {
+        let Dock {
+            layout,
+            center,
+            panels,
+            key,
+            on_change,
+        } = dock;
+
+        let state = use_keyed_signal(&key, DockState::default);
+        let current = state.get();
+        let (origin, size) = (use_element_origin(&key), use_element_size(&key));
+        let tab_height = measure("Ag", DEFAULT_FONT_SIZE, None).1 + TAB_PADDING;
+
+        let titles: HashMap<String, String> = panels
+            .iter()
+            .map(|(id, title, _)| (id.clone(), title.clone()))
+            .collect();
+        let mut contents: HashMap<String, ElementBuilder> = panels
+            .into_iter()
+            .map(|(id, _, content)| (id, content))
+            .collect();
+
+        let tab = |panel: &str, active: bool| {
+            let tab = div()
+                .p(px(TAB_PADDING / 2.0))
+                .cursor(CursorIcon::Pointer)
+                .child(text(titles[panel].clone()).color(Color::WHITE))
+                .on_event_any(tab_handler(
+                    state,
+                    panel.to_string(),
+                    &layout,
+                    origin,
+                    size,
+                    tab_height,
+                    on_change.clone(),
+                ));
+            if active {
+                tab.bg(ACTIVE_TAB_COLOR)
+            } else {
+                tab
+            }
+        };
+
+        // The panels docked to a side, under a strip of their tabs, unless
+        // none of them were given
+        let mut area_view = |side: DockSide| {
+            let area = layout.area(side);
+            let shown: Vec<&String> = area
+                .panels
+                .iter()
+                .filter(|panel| titles.contains_key(*panel))
+                .collect();
+            let active = area
+                .panels
+                .get(area.active)
+                .filter(|panel| titles.contains_key(*panel))
+                .or(shown.first().copied())?;
+
+            // The content gets what the tabs leave of the side's height
+            let area_key = format!("{key}-{side:?}");
+            let (_, height) = use_element_size(&area_key).get();
+            let content_height = if height > tab_height {
+                pc((height - tab_height) / height * 100.0)
+            } else {
+                pc(100.0)
+            };
+            let tabs = div()
+                .w(pc(100.0))
+                .h(px(tab_height))
+                .row()
+                .bg(TAB_STRIP_COLOR)
+                .children(shown.iter().map(|&panel| tab(panel, panel == active)));
+            Some(
+                div().key(area_key).size(pc(100.0)).col().child(tabs).child(
+                    div()
+                        .w(pc(100.0))
+                        .h(content_height)
+                        .children(contents.remove(active)),
+                ),
+            )
+        };
+
+        // Each side is split off what the ones outside it leave, each split
+        // keeping the side's size in the layout
+        let resize = |set: fn(&mut DockLayout, f32)| {
+            let layout = layout.clone();
+            let on_change = on_change.clone();
+            Rc::new(move |model: &mut dyn Any, ratio: f32| {
+                let mut changed = layout.clone();
+                set(&mut changed, ratio);
+                if let Some(on_change) = &on_change {
+                    on_change(model, changed);
+                }
+            }) as Rc<dyn Fn(&mut dyn Any, f32)>
+        };
+        let mut middle = div().size(pc(100.0)).child(center);
+        if let Some(bottom) = area_view(DockSide::Bottom) {
+            middle = split(false, middle, bottom)
+                .key(format!("{key}-bottom-split"))
+                .ratio(1.0 - layout.bottom.size)
+                .on_resize_any(resize(|layout, ratio| layout.bottom.size = 1.0 - ratio))
+                .into();
+        }
+        if let Some(top) = area_view(DockSide::Top) {
+            middle = split(false, top, middle)
+                .key(format!("{key}-top-split"))
+                .ratio(layout.top.size)
+                .on_resize_any(resize(|layout, ratio| layout.top.size = ratio))
+                .into();
+        }
+        if let Some(right) = area_view(DockSide::Right) {
+            middle = split(true, middle, right)
+                .key(format!("{key}-right-split"))
+                .ratio(1.0 - layout.right.size)
+                .on_resize_any(resize(|layout, ratio| layout.right.size = 1.0 - ratio))
+                .into();
+        }
+        if let Some(left) = area_view(DockSide::Left) {
+            middle = split(true, left, middle)
+                .key(format!("{key}-left-split"))
+                .ratio(layout.left.size)
+                .on_resize_any(resize(|layout, ratio| layout.left.size = ratio))
+                .into();
+        }
+
+        // A floating panel being dragged follows the cursor
+        let dragged = current.drag.as_ref().filter(|drag| drag.loose);
+        let floating = layout.floating.iter().filter_map(|floating| {
+            let content = contents.remove(&floating.panel)?;
+            let (x, y) = match dragged {
+                Some(drag) if drag.panel == floating.panel => (
+                    current.cursor.0 - drag.grab.0,
+                    current.cursor.1 - drag.grab.1,
+                ),
+                _ => (floating.x, floating.y),
+            };
+            let title = div()
+                .w(pc(100.0))
+                .h(px(tab_height))
+                .bg(TAB_STRIP_COLOR)
+                .child(tab(&floating.panel, true));
+            Some(
+                portal()
+                    .layer(DOCK_FLOAT_LAYER)
+                    .left(px(x))
+                    .top(px(y))
+                    .w(px(floating.width))
+                    .h(px(floating.height))
+                    .col()
+                    .bg(FLOAT_COLOR)
+                    .border(1.0, SPLIT_DIVIDER_COLOR)
+                    .child(title)
+                    .child(
+                        div()
+                            .w(pc(100.0))
+                            .h(px((floating.height - tab_height).max(0.0)))
+                            .child(content),
+                    ),
+            )
+        });
+        let floating: Vec<_> = floating.collect();
+
+        // While a tab is dragged, the side it would dock to is marked, and
+        // a docked panel's title follows the cursor
+        let mut dragging = Vec::new();
+        if let Some(drag) = dragged {
+            let (origin, size) = (origin.get(), size.get());
+            if let Some(side) = drop_side(current.cursor, origin, size) {
+                let zone = pc(DROP_ZONE * 100.0);
+                let preview = portal()
+                    .layer(DOCK_FLOAT_LAYER + 1)
+                    .left(px(origin.0))
+                    .top(px(origin.1))
+                    .w(px(size.0))
+                    .h(px(size.1))
+                    .pointer_events(false);
+                let marker = div().absolute().bg(DOCK_PREVIEW_COLOR);
+                let marker = match side {
+                    DockSide::Left => marker.left(px(0.0)).top(px(0.0)).w(zone).h(pc(100.0)),
+                    DockSide::Right => marker.right(px(0.0)).top(px(0.0)).w(zone).h(pc(100.0)),
+                    DockSide::Top => marker.left(px(0.0)).top(px(0.0)).w(pc(100.0)).h(zone),
+                    DockSide::Bottom => marker.left(px(0.0)).bottom(px(0.0)).w(pc(100.0)).h(zone),
+                };
+                dragging.push(preview.child(marker));
+            }
+            if layout.side_of(&drag.panel).is_some() {
+                if let Some(title) = titles.get(&drag.panel) {
+                    dragging.push(
+                        portal()
+                            .layer(DOCK_FLOAT_LAYER + 1)
+                            .left(px(current.cursor.0 - drag.grab.0))
+                            .top(px(current.cursor.1 - drag.grab.1))
+                            .p(px(TAB_PADDING / 2.0))
+                            .bg(ACTIVE_TAB_COLOR)
+                            .opacity(0.8)
+                            .pointer_events(false)
+                            .child(text(title.clone()).color(Color::WHITE)),
+                    );
+                }
+            }
+        }
+
+        div()
+            .key(key)
+            .size(pc(100.0))
+            .child(middle)
+            .children(floating)
+            .children(dragging)
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+
+use vitae_render::RenderError;
+use winit::error::{EventLoopError, OsError};
+
+/// Why an app couldn't start or had to stop.
+#[derive(Debug)]
+pub enum Error {
+    /// The event loop couldn't be created or run
+    EventLoop(EventLoopError),
+    /// The window couldn't be created
+    Window(OsError),
+    /// The renderer couldn't start or draw, such as when there is no usable
+    /// GPU
+    Render(RenderError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::EventLoop(err) => write!(f, "event loop error: {err}"),
+            Error::Window(err) => write!(f, "couldn't create the window: {err}"),
+            Error::Render(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::EventLoop(err) => Some(err),
+            Error::Window(err) => Some(err),
+            Error::Render(err) => Some(err),
+        }
+    }
+}
+
+impl From<EventLoopError> for Error {
+    fn from(err: EventLoopError) -> Self {
+        Error::EventLoop(err)
+    }
+}
+
+impl From<OsError> for Error {
+    fn from(err: OsError) -> Self {
+        Error::Window(err)
+    }
+}
+
+impl From<RenderError> for Error {
+    fn from(err: RenderError) -> Self {
+        Error::Render(err)
+    }
+}
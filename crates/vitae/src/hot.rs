@@ -0,0 +1,100 @@
+//! Reloading the view function from a shared library whenever it is
+//! rebuilt (see `App::hot_reload`).
+
+use std::fs;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use libloading::Library;
+use vitae_core::ElementBuilder;
+
+/// How often the library is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+type View<M> = fn(&M) -> ElementBuilder;
+
+pub(crate) struct HotView<M> {
+    path: PathBuf,
+    symbol: String,
+    /// When the library was last modified and how big it was, as of the
+    /// last attempt to load it
+    version: Option<(SystemTime, u64)>,
+    /// Every library loaded so far. None are unloaded, since the elements,
+    /// handlers and signal values they made may still be around.
+    libraries: Vec<Library>,
+    next_check: Instant,
+    view: PhantomData<View<M>>,
+}
+
+impl<M> HotView<M> {
+    pub(crate) fn new(path: PathBuf, symbol: String) -> Self {
+        HotView {
+            path,
+            symbol,
+            version: None,
+            libraries: Vec::new(),
+            next_check: Instant::now(),
+            view: PhantomData,
+        }
+    }
+
+    /// When the library is next due to be checked for changes.
+    pub(crate) fn next_check(&self) -> Instant {
+        self.next_check
+    }
+
+    /// The view function from the library, if it has changed since it was
+    /// last loaded and is due to be checked by `now`.
+    pub(crate) fn reload_if_changed(&mut self, now: Instant) -> Option<View<M>> {
+        if now < self.next_check {
+            return None;
+        }
+        self.next_check = now + POLL_INTERVAL;
+
+        let metadata = fs::metadata(&self.path).ok()?;
+        let version = (metadata.modified().ok()?, metadata.len());
+        if self.version == Some(version) {
+            return None;
+        }
+        // A library that is still being written fails to load, and is
+        // tried again once it has changed some more
+        self.version = Some(version);
+        match self.load() {
+            Ok(view) => Some(view),
+            Err(err) => {
+                tracing::warn!(path = ?self.path, "couldn't reload the view: {err}");
+                None
+            }
+        }
+    }
+
+    fn load(&mut self) -> Result<View<M>, Box<dyn std::error::Error>> {
+        // Load a copy, since the platform may hand back the library it
+        // already loaded from the same path, and may keep the build from
+        // overwriting a loaded library
+        let file_name = self.path.file_name().ok_or("not a file")?;
+        let copy = std::env::temp_dir().join(format!(
+            "vitae-{}-{}-{}",
+            std::process::id(),
+            self.libraries.len(),
+            file_name.to_string_lossy()
+        ));
+        fs::copy(&self.path, &copy)?;
+        // SAFETY: the library is trusted to be built from the app's own
+        // code, linking the same vitae and defining `symbol` as a view
+        // function for `M`
+        let result = unsafe {
+            Library::new(&copy).and_then(|library| {
+                let view = *library.get::<View<M>>(self.symbol.as_bytes())?;
+                Ok((library, view))
+            })
+        };
+        // The loaded library doesn't need the file any more, except on
+        // Windows, where removing it fails
+        let _ = fs::remove_file(&copy);
+        let (library, view) = result?;
+        self.libraries.push(library);
+        Ok(view)
+    }
+}
@@ -1,15 +1,57 @@
+pub mod animation;
+pub mod chart;
+pub mod clipboard;
+pub mod code_view;
+pub mod data_grid;
+mod devtools;
+pub mod dialog;
+pub mod dock;
+mod error;
+#[cfg(feature = "hot-reload")]
+mod hot;
+pub mod link;
+#[cfg(feature = "serde")]
+mod persist;
 pub mod prelude;
 pub mod signal;
+pub mod size;
+pub mod split;
+pub mod task;
+pub mod timer;
+pub mod toast;
+pub mod undo;
 mod window;
 
 pub use vitae_core as core;
 pub use vitae_render as render;
+pub use winit;
 
-use vitae_core::ElementBuilder;
+/// Text utilities backed by the renderer's font system.
+pub mod text {
+    pub use vitae_render::caret::{caret_rect, move_caret, selection_rects, CaretRect};
+    pub use vitae_render::text::{
+        measure, measure_monospace, register_font, FontConfig, DEFAULT_FONT_SIZE,
+    };
+}
+
+use vitae_core::{ElementBuilder, Event, EventResult};
 use window::VitaeApp;
 use winit::event_loop::EventLoop;
 
-pub use signal::{use_signal, Signal};
+pub use animation::{use_animated, use_keyframes, use_spring, Animated, KeyframeAnimation};
+pub use chart::{chart, Chart, ChartKind, Series};
+pub use code_view::{code_view, CodeTheme, CodeView};
+pub use data_grid::{data_grid, Column, DataGrid};
+pub use dock::{dock, Dock, DockArea, DockLayout, DockSide, FloatingPanel};
+pub use error::Error;
+pub use link::{link, link_with, open_url};
+pub use signal::{use_keyed_signal, use_signal, Signal};
+pub use size::{use_element_size, use_window_size, WindowSize};
+pub use split::{split, Split};
+pub use task::spawn_task;
+pub use timer::{clear_timer, set_interval, set_timeout, TimerId};
+pub use undo::{can_redo, can_undo, redo, undo};
+pub use window::{set_fullscreen, set_pointer_lock};
 
 pub struct App<M: Clone + 'static> {
     event_loop: EventLoop<()>,
@@ -23,6 +65,9 @@ impl<M: Clone + 'static> App<M> {
     /// * `initial_model` - The initial state of your application
     /// * `view` - A function that takes a reference to the model and returns the UI tree
     ///
+    /// Fails if the event loop can't be created, such as when there is no
+    /// display to connect to.
+    ///
     /// # Example
     /// ```
     /// #[derive(Clone)]
@@ -32,16 +77,270 @@ impl<M: Clone + 'static> App<M> {
     ///     div().child(text(format!("Count: {}", model.count)))
     /// }
     ///
-    /// App::new(Counter { count: 0 }, view).run();
+    /// App::new(Counter { count: 0 }, view)?.run()?;
     /// ```
-    pub fn new(initial_model: M, view: fn(&M) -> ElementBuilder) -> Self {
-        App {
-            event_loop: EventLoop::new().unwrap(),
+    pub fn new(initial_model: M, view: fn(&M) -> ElementBuilder) -> Result<Self, Error> {
+        let event_loop = EventLoop::new()?;
+        task::set_waker(event_loop.create_proxy());
+        Ok(App {
+            event_loop,
             vitae_app: VitaeApp::new(initial_model, view),
+        })
+    }
+
+    /// Set the fonts used for all text, including per-script and
+    /// per-language fallbacks.
+    ///
+    /// # Example
+    /// ```
+    /// App::new(model, view)?
+    ///     .fonts(
+    ///         FontConfig::new(["Inter", "system-ui", "sans-serif"])
+    ///             .language_fallback("Hani", "ja", ["Hiragino Sans", "Noto Sans CJK JP"])
+    ///             .locale("ja"),
+    ///     )
+    ///     .run()?;
+    /// ```
+    pub fn fonts(self, config: text::FontConfig) -> Self {
+        vitae_render::text::set_font_config(config);
+        self
+    }
+
+    /// Bundle a font with the application, so text looks the same on every
+    /// machine regardless of which fonts are installed.
+    ///
+    /// Embedded fonts are tried before the configured font stack. Call this
+    /// once per font file.
+    ///
+    /// # Example
+    /// ```
+    /// App::new(model, view)?
+    ///     .with_font_bytes(include_bytes!("../assets/Inter.ttf"))
+    ///     .run()?;
+    /// ```
+    pub fn with_font_bytes(self, data: &'static [u8]) -> Self {
+        if vitae_render::text::register_font(data).is_empty() {
+            tracing::warn!("font data passed to with_font_bytes contains no fonts");
+        }
+        self
+    }
+
+    /// Like `with_font_bytes`, for a font file read from `path` when the
+    /// app starts, such as one installed next to the executable. A file
+    /// that can't be read is reported and skipped. Elements can ask for
+    /// its families by name with `font_family`.
+    ///
+    /// # Example
+    /// ```
+    /// App::new(model, view)?
+    ///     .with_font("fonts/Inter-Regular.ttf")
+    ///     .with_font("fonts/Inter-Bold.ttf")
+    ///     .run()?;
+    /// ```
+    pub fn with_font(self, path: impl AsRef<std::path::Path>) -> Self {
+        let path = path.as_ref();
+        match std::fs::read(path) {
+            // Fonts stay registered for as long as the app runs
+            Ok(data) => {
+                let data: &'static [u8] = Box::leak(data.into_boxed_slice());
+                if vitae_render::text::register_font(data).is_empty() {
+                    tracing::warn!("{} contains no fonts", path.display());
+                }
+            }
+            Err(err) => tracing::warn!("failed to read font {}: {err}", path.display()),
+        }
+        self
+    }
+
+    /// Round the layout to whole pixels before painting, so edges and thin
+    /// borders stay crisp rather than blurring across two pixels. On by
+    /// default; turn it off for smooth subpixel motion in slow animations.
+    ///
+    /// # Example
+    /// ```
+    /// App::new(model, view)?.pixel_snapping(false).run()?;
+    /// ```
+    pub fn pixel_snapping(mut self, enabled: bool) -> Self {
+        self.vitae_app.pixel_snapping = enabled;
+        self
+    }
+
+    /// Save the model to `path` as JSON, and start from the model saved
+    /// there on the next run instead of the initial one. The model is saved
+    /// once it has been left unchanged for a second, and when the app
+    /// exits.
+    ///
+    /// If the saved model can't be read, say because the model's type has
+    /// changed since, the app starts from the initial model and overwrites
+    /// it.
+    ///
+    /// # Example
+    /// ```
+    /// App::new(Notes::default(), view)?
+    ///     .with_persistence(config_dir.join("notes.json"))
+    ///     .run()?;
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn with_persistence(mut self, path: impl Into<std::path::PathBuf>) -> Self
+    where
+        M: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let (persistence, saved) = persist::Persistence::open(path.into());
+        if let Some(model) = saved {
+            self.vitae_app.model = model;
         }
+        self.vitae_app.persistence = Some(persistence);
+        self
+    }
+
+    /// Keep a history of the model so changes can be undone with `undo()`
+    /// and `redo()`, or with Ctrl+Z and Ctrl+Shift+Z (or Ctrl+Y; Cmd on
+    /// macOS) when no key handler takes them.
+    ///
+    /// Each press of a key or mouse button that changes the model is one
+    /// step, including everything that happens until the button is
+    /// released, such as a drag. Changes made by timers and background
+    /// tasks are undone along with the step before them.
+    ///
+    /// # Example
+    /// ```
+    /// App::new(Drawing::default(), view)?.undoable().run()?;
+    /// ```
+    pub fn undoable(mut self) -> Self
+    where
+        M: PartialEq,
+    {
+        self.vitae_app.history = Some(undo::History::new());
+        self
+    }
+
+    /// Record every mouse and key event dispatched, with the model each one
+    /// left behind, for finding out how the model got the way it is. F12
+    /// shows a panel listing the events; clicking one puts the model back
+    /// the way it was right after it, and the panel's buttons step through
+    /// them. Carrying on using the app from an earlier model drops the
+    /// events that followed it.
+    ///
+    /// Meant for debugging: a copy of the model is kept for every event, up
+    /// to the last thousand.
+    ///
+    /// # Example
+    /// ```
+    /// let app = App::new(ChessGame::new(), view)?;
+    /// let app = if cfg!(debug_assertions) { app.time_travel() } else { app };
+    /// app.run()?;
+    /// ```
+    pub fn time_travel(mut self) -> Self {
+        self.vitae_app.recorder = Some(devtools::Recorder::new());
+        self
+    }
+
+    /// Add a panel showing the model, written out with `Debug`, and the
+    /// value of every signal, kept up to date as the app is used. F12 shows
+    /// and hides it, along with the time-travel panel if there is one.
+    ///
+    /// Signals holding numbers, strings, `bool`s and options of those are
+    /// shown in full, and others by their type's name. Signals from
+    /// `use_signal` are numbered in the order the view creates them, and
+    /// keyed ones are named by their key.
+    ///
+    /// # Example
+    /// ```
+    /// let app = App::new(ChessGame::new(), view)?;
+    /// let app = if cfg!(debug_assertions) { app.inspector() } else { app };
+    /// app.run()?;
+    /// ```
+    pub fn inspector(mut self) -> Self
+    where
+        M: std::fmt::Debug,
+    {
+        self.vitae_app.inspector = Some(devtools::Inspector::new());
+        self
+    }
+
+    /// Call `handler` with the window's lifecycle events: `WindowFocused`,
+    /// `WindowMoved`, `ScaleFactorChanged` and `Resized`. The view is
+    /// rebuilt after each one, so the model can pause background work while
+    /// the window is in the background, or adapt to a new scale factor.
+    ///
+    /// # Example
+    /// ```
+    /// App::new(Player::default(), view)?
+    ///     .on_window_event(|m: &mut Player, event: &Event| {
+    ///         if let Event::WindowFocused { focused } = event {
+    ///             m.paused = !focused;
+    ///         }
+    ///     })
+    ///     .run()?;
+    /// ```
+    pub fn on_window_event(mut self, handler: fn(&mut M, &Event)) -> Self {
+        self.vitae_app.window_handler = Some(handler);
+        self
+    }
+
+    /// Call `handler` with every event winit sends to the window, before
+    /// vitae handles it, for events vitae doesn't have its own for, such as
+    /// touch, pen and IME input. A handler that returns
+    /// `EventResult::Stop` takes the event: vitae ignores it and rebuilds
+    /// the view. Returning `EventResult::Continue` leaves it to vitae as
+    /// usual.
+    ///
+    /// # Example
+    /// ```
+    /// use vitae::winit::event::WindowEvent;
+    ///
+    /// App::new(Sketch::default(), view)?
+    ///     .on_raw_event(|m: &mut Sketch, event: &WindowEvent| match event {
+    ///         WindowEvent::Touch(touch) => {
+    ///             m.add_point(touch.location.x as f32, touch.location.y as f32);
+    ///             EventResult::Stop
+    ///         }
+    ///         _ => EventResult::Continue,
+    ///     })
+    ///     .run()?;
+    /// ```
+    pub fn on_raw_event(
+        mut self,
+        handler: fn(&mut M, &winit::event::WindowEvent) -> EventResult,
+    ) -> Self {
+        self.vitae_app.raw_handler = Some(handler);
+        self
+    }
+
+    /// Take the view function from the shared library at `library`, where
+    /// it is exported as `symbol`, and load it again whenever the library
+    /// is rebuilt. The model is kept, so changes to the view show up
+    /// without restarting the app. The view passed to `App::new` is used
+    /// until the library first loads, and whenever it fails to.
+    ///
+    /// The library has to be built by the same compiler as the app and link
+    /// the same vitae dynamically, so they share signals and the model's
+    /// type; see the state management guide for a setup. Meant for
+    /// development only: libraries are never unloaded, and one that doesn't
+    /// match the app is undefined behavior.
+    ///
+    /// # Example
+    /// ```
+    /// App::new(Notes::default(), view)?
+    ///     .hot_reload("target/debug/libnotes_view.so", "view")
+    ///     .run()?;
+    /// ```
+    #[cfg(feature = "hot-reload")]
+    pub fn hot_reload(mut self, library: impl Into<std::path::PathBuf>, symbol: &str) -> Self {
+        self.vitae_app.hot_view = Some(hot::HotView::new(library.into(), symbol.to_string()));
+        self
     }
 
-    pub fn run(mut self) {
-        let _ = self.event_loop.run_app(&mut self.vitae_app);
+    /// Open the window and run the app until it is closed.
+    ///
+    /// Returns an error if the window or renderer couldn't be created, or
+    /// the renderer failed in a way it couldn't recover from, such as
+    /// running out of GPU memory.
+    pub fn run(mut self) -> Result<(), Error> {
+        self.event_loop.run_app(&mut self.vitae_app)?;
+        match self.vitae_app.error.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
     }
 }
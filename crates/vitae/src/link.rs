@@ -0,0 +1,67 @@
+use std::borrow::Cow;
+use std::io;
+use std::process::Command;
+
+use vitae_core::{
+    text, ClickInfo, Color, CursorIcon, ElementBuilder, Event, EventResult, MouseButton,
+};
+
+/// Default color for link text.
+pub const LINK_COLOR: Color = Color::new(0.1, 0.35, 0.85, 1.0);
+
+/// Create a link that opens `url` in the system browser when clicked.
+///
+/// # Example
+/// ```
+/// link("Source code", "https://github.com/korbindeman/vitae")
+/// ```
+pub fn link(label: impl Into<Cow<'static, str>>, url: impl Into<String>) -> ElementBuilder {
+    let url = url.into();
+    link_text(label).on_event_any(move |_, event| {
+        if let Event::Click {
+            button: MouseButton::Left,
+            ..
+        } = event
+        {
+            if let Err(err) = open_url(&url) {
+                tracing::warn!("failed to open {url}: {err}");
+            }
+        }
+        EventResult::Continue
+    })
+}
+
+/// Create a link that runs `on_click` instead of opening a URL.
+///
+/// # Example
+/// ```
+/// link_with("Show license", |m: &mut MyModel, _: &ClickInfo| m.show_license = true)
+/// ```
+pub fn link_with<M, F>(label: impl Into<Cow<'static, str>>, on_click: F) -> ElementBuilder
+where
+    M: 'static,
+    F: Fn(&mut M, &ClickInfo) + 'static,
+{
+    link_text(label).on_left_click_with(on_click)
+}
+
+fn link_text(label: impl Into<Cow<'static, str>>) -> ElementBuilder {
+    text(label)
+        .color(LINK_COLOR)
+        .underline()
+        .cursor(CursorIcon::Pointer)
+}
+
+/// Open a URL with the system's default handler (usually the web browser).
+pub fn open_url(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(url).spawn().map(|_| ())
+}
@@ -0,0 +1,98 @@
+//! Saving the model to disk and restoring it on the next run (see
+//! `App::with_persistence`).
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// How long the model has to stay unchanged before it is saved, so a burst
+/// of changes (such as typing) is written once.
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+pub(crate) struct Persistence<M> {
+    path: PathBuf,
+    to_json: fn(&M) -> serde_json::Result<String>,
+    /// What `path` holds, as far as we know, so unchanged models aren't
+    /// written again
+    saved: Option<String>,
+    /// When the model is due to be saved, if it may have changed since
+    due: Option<Instant>,
+}
+
+impl<M: Serialize + DeserializeOwned> Persistence<M> {
+    /// Persist the model to `path`, returning the model saved there by the
+    /// last run, if any.
+    pub(crate) fn open(path: PathBuf) -> (Self, Option<M>) {
+        let saved = match fs::read_to_string(&path) {
+            Ok(json) => Some(json),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                tracing::warn!("couldn't read the saved model from {path:?}: {err}");
+                None
+            }
+        };
+        let model = saved
+            .as_deref()
+            .and_then(|json| match serde_json::from_str(json) {
+                Ok(model) => Some(model),
+                Err(err) => {
+                    tracing::warn!("couldn't restore the saved model from {path:?}: {err}");
+                    None
+                }
+            });
+        let persistence = Persistence {
+            path,
+            to_json: serde_json::to_string_pretty::<M>,
+            saved,
+            due: None,
+        };
+        (persistence, model)
+    }
+}
+
+impl<M> Persistence<M> {
+    /// Note that the model may have changed, putting off saving it until it
+    /// has been left alone for a moment.
+    pub(crate) fn changed(&mut self, now: Instant) {
+        self.due = Some(now + SAVE_DELAY);
+    }
+
+    /// When the model is due to be saved, if it is.
+    pub(crate) fn due(&self) -> Option<Instant> {
+        self.due
+    }
+
+    /// Save `model` if it is due to be saved by `now`.
+    pub(crate) fn save_if_due(&mut self, model: &M, now: Instant) {
+        if self.due.is_some_and(|due| due <= now) {
+            self.save(model);
+        }
+    }
+
+    /// Save `model` now, unless it is what was last saved.
+    pub(crate) fn save(&mut self, model: &M) {
+        self.due = None;
+        let json = match (self.to_json)(model) {
+            Ok(json) => json,
+            Err(err) => {
+                tracing::warn!("couldn't serialize the model: {err}");
+                return;
+            }
+        };
+        if self.saved.as_ref() == Some(&json) {
+            return;
+        }
+        if let Some(dir) = self.path.parent() {
+            // A failure shows up as a failure to write the file below
+            let _ = fs::create_dir_all(dir);
+        }
+        match fs::write(&self.path, &json) {
+            Ok(()) => self.saved = Some(json),
+            Err(err) => tracing::warn!("couldn't save the model to {:?}: {err}", self.path),
+        }
+    }
+}
@@ -1,9 +1,17 @@
-pub use crate::{use_signal, App, Signal};
+pub use crate::{
+    can_redo, can_undo, chart, clear_timer, code_view, data_grid, dock, link, link_with, redo,
+    set_fullscreen, set_interval, set_pointer_lock, set_timeout, spawn_task, split, undo,
+    use_animated, use_element_size, use_keyed_signal, use_keyframes, use_signal, use_spring,
+    use_window_size, Animated, App, Chart, ChartKind, CodeTheme, CodeView, Column, DataGrid, Dock,
+    DockLayout, DockSide, KeyframeAnimation, Series, Signal, Split, TimerId, WindowSize,
+};
 pub use vitae_core::{
-    div, img, pc, portal, px, svg, text, Align, Color, Direction, Distribute, ElementBuilder,
-    Length, Svg, Texture,
+    div, img, input, keyframes, pc, popover, portal, px, svg, text, Align, ClickInfo, Color,
+    CursorIcon, Direction, Distribute, Easing, ElementBuilder, Event, EventResult, InputState,
+    Length, Modifiers, MouseButton, ObjectFit, Overflow, Placement, PlaybackDirection, Repeat,
+    Spring, Svg, TextAlign, Texture, Transition,
 };
-pub use vitae_render::{load_svg, load_texture};
+pub use vitae_render::{load_svg, load_texture, load_thumbnail};
 
 // SIZES
 pub const FULL: Length = Length::Percent(100.);
@@ -1,11 +1,21 @@
-use std::any::Any;
+use std::any::{Any, TypeId};
 use std::cell::{Cell, RefCell};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
 
 /// A unique identifier for a signal
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct SignalId(usize);
+pub struct SignalId(Slot);
+
+/// Where a signal's value is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Slot {
+    /// Created by the `use_signal` call at this position in the view
+    Call(usize),
+    /// Created by `use_keyed_signal`, numbered in the order keys were first
+    /// used
+    Keyed(usize),
+}
 
 /// A reactive signal that triggers re-renders when updated
 pub struct Signal<T> {
@@ -43,8 +53,7 @@ impl<T: Clone + 'static> Signal<T> {
             storage.borrow_mut().insert(self.id, Box::new(value));
         });
 
-        // Trigger redraw
-        REQUEST_REDRAW.with(|redraw| redraw.set(true));
+        request_redraw();
     }
 
     /// Update the signal value using a function
@@ -57,12 +66,15 @@ impl<T: Clone + 'static> Signal<T> {
 /// Storage for signal values (thread-local)
 pub struct SignalStorage {
     values: HashMap<SignalId, Box<dyn Any>>,
+    /// The name of each value's type, for the inspector
+    type_names: HashMap<SignalId, &'static str>,
 }
 
 impl SignalStorage {
     fn new() -> Self {
         SignalStorage {
             values: HashMap::new(),
+            type_names: HashMap::new(),
         }
     }
 
@@ -74,17 +86,26 @@ impl SignalStorage {
         self.values.insert(id, value);
     }
 
+    fn remove(&mut self, id: &SignalId) {
+        self.values.remove(id);
+        self.type_names.remove(id);
+    }
+
     fn get_or_insert<T: Clone + 'static>(
         &mut self,
         id: SignalId,
         init: impl FnOnce() -> T,
     ) -> &Box<dyn Any> {
+        self.type_names
+            .entry(id)
+            .or_insert_with(std::any::type_name::<T>);
         self.values.entry(id).or_insert_with(|| Box::new(init()))
     }
 
     /// Clear all signal values (used when resetting between frames)
     pub fn clear(&mut self) {
         self.values.clear();
+        self.type_names.clear();
     }
 }
 
@@ -92,6 +113,7 @@ impl SignalStorage {
 thread_local! {
     static SIGNAL_STORAGE: RefCell<SignalStorage> = RefCell::new(SignalStorage::new());
     static SIGNAL_COUNTER: Cell<usize> = Cell::new(0);
+    static KEYED_SIGNALS: RefCell<KeyedSignals> = RefCell::new(KeyedSignals::default());
     static REQUEST_REDRAW: Cell<bool> = Cell::new(false);
 }
 
@@ -104,7 +126,7 @@ pub fn use_signal<T: Clone + 'static>(init: impl FnOnce() -> T) -> Signal<T> {
         let id = SIGNAL_COUNTER.with(|c| {
             let id = c.get();
             c.set(id + 1);
-            SignalId(id)
+            SignalId(Slot::Call(id))
         });
 
         // Initialize if first time (or get existing value)
@@ -117,9 +139,93 @@ pub fn use_signal<T: Clone + 'static>(init: impl FnOnce() -> T) -> Signal<T> {
     })
 }
 
+/// The signals created by `use_keyed_signal`, by key and type.
+#[derive(Default)]
+struct KeyedSignals {
+    ids: HashMap<(String, TypeId), SignalId>,
+    /// Keys asked for while building the current view
+    used: HashSet<(String, TypeId)>,
+    next: usize,
+}
+
+/// Create a signal found by `key` rather than by when it is created.
+///
+/// `use_signal` tells signals apart by the order of the calls, so a signal
+/// created only under some condition, or in a list that gets reordered,
+/// can take over another's value. A keyed signal keeps its value however
+/// the view around it changes, as long as each view asks for it. Use the
+/// key of the element the state belongs to, such as the scroll offset of a
+/// list or the caret of a text field. Signals of different types can share
+/// a key. The value is dropped once a view is built without asking for it.
+///
+/// # Example
+/// ```
+/// fn photo_list(model: &Library) -> ElementBuilder {
+///     let scroll = use_keyed_signal("photo-list", || 0.0_f32);
+///
+///     div()
+///         .key("photo-list")
+///         .translate(0.0, -scroll.get())
+///         .on_scroll(move |_: &mut Library, _dx, dy| scroll.update(|y| (y + dy).max(0.0)))
+///         .children(model.photos.iter().map(photo_card))
+/// }
+/// ```
+pub fn use_keyed_signal<T: Clone + 'static>(key: &str, init: impl FnOnce() -> T) -> Signal<T> {
+    keyed_signal(key, TypeId::of::<T>(), init)
+}
+
+/// Create a keyed signal told apart from others with the same key by
+/// `kind` rather than by its type, so vitae can keep its own signals under
+/// the app's keys.
+pub(crate) fn keyed_signal<T: Clone + 'static>(
+    key: &str,
+    kind: TypeId,
+    init: impl FnOnce() -> T,
+) -> Signal<T> {
+    let id = KEYED_SIGNALS.with(|keyed| {
+        let keyed = &mut *keyed.borrow_mut();
+        let key = (key.to_string(), kind);
+        keyed.used.insert(key.clone());
+        *keyed.ids.entry(key).or_insert_with(|| {
+            keyed.next += 1;
+            SignalId(Slot::Keyed(keyed.next))
+        })
+    });
+    SIGNAL_STORAGE.with(|storage| {
+        storage.borrow_mut().get_or_insert(id, init);
+    });
+
+    Signal {
+        id,
+        _phantom: PhantomData,
+    }
+}
+
 /// Reset the signal counter (called before each render)
 pub(crate) fn reset_signal_counter() {
     SIGNAL_COUNTER.with(|c| c.set(0));
+    KEYED_SIGNALS.with(|keyed| keyed.borrow_mut().used.clear());
+}
+
+/// Drop the keyed signals the view just built didn't ask for (called after
+/// each render)
+pub(crate) fn drop_unused_keyed_signals() {
+    KEYED_SIGNALS.with(|keyed| {
+        let keyed = &mut *keyed.borrow_mut();
+        let used = &keyed.used;
+        keyed.ids.retain(|key, id| {
+            let keep = used.contains(key);
+            if !keep {
+                SIGNAL_STORAGE.with(|storage| storage.borrow_mut().remove(id));
+            }
+            keep
+        });
+    });
+}
+
+/// Request a redraw (and view rebuild) on the next event loop iteration
+pub(crate) fn request_redraw() {
+    REQUEST_REDRAW.with(|redraw| redraw.set(true));
 }
 
 /// Check if a redraw was requested by a signal update
@@ -131,6 +237,76 @@ pub(crate) fn take_redraw_request() -> bool {
     })
 }
 
+/// Every signal's name and value, for the inspector. Signals from
+/// `use_signal` are named by the order they're created in, and keyed ones
+/// by their key. Values of common types are shown in full, others by their
+/// type's name.
+pub(crate) fn describe_signals() -> Vec<(String, String)> {
+    let keys: HashMap<SignalId, String> = KEYED_SIGNALS.with(|keyed| {
+        keyed
+            .borrow()
+            .ids
+            .iter()
+            .map(|((key, _), id)| (*id, key.clone()))
+            .collect()
+    });
+    SIGNAL_STORAGE.with(|storage| {
+        let storage = storage.borrow();
+        let mut signals: Vec<_> = storage.values.iter().collect();
+        signals.sort_by_key(|(id, _)| id.0);
+        signals
+            .into_iter()
+            .map(|(id, value)| {
+                let name = match id.0 {
+                    Slot::Call(index) => format!("#{index}"),
+                    Slot::Keyed(_) => format!("{:?}", keys.get(id).map_or("", String::as_str)),
+                };
+                let value = describe_value(&**value).unwrap_or_else(|| {
+                    format!("<{}>", storage.type_names.get(id).unwrap_or(&"unknown"))
+                });
+                (name, value)
+            })
+            .collect()
+    })
+}
+
+/// `value` written out with `Debug`, if it is of a common type.
+fn describe_value(value: &dyn Any) -> Option<String> {
+    macro_rules! describe {
+        ($($ty:ty),*) => {
+            $(
+                if let Some(value) = value.downcast_ref::<$ty>() {
+                    return Some(format!("{value:?}"));
+                }
+                if let Some(value) = value.downcast_ref::<Option<$ty>>() {
+                    return Some(format!("{value:?}"));
+                }
+            )*
+        };
+    }
+    describe!(
+        bool,
+        char,
+        i8,
+        i16,
+        i32,
+        i64,
+        isize,
+        u8,
+        u16,
+        u32,
+        u64,
+        usize,
+        f32,
+        f64,
+        String,
+        &'static str,
+        (f32, f32),
+        crate::size::WindowSize
+    );
+    None
+}
+
 /// Clear all signal storage (useful for cleanup)
 pub(crate) fn clear_signals() {
     SIGNAL_STORAGE.with(|storage| storage.borrow_mut().clear());
@@ -0,0 +1,168 @@
+//! Sizes the view can depend on, kept in signals the window updates after
+//! laying the view out.
+
+use std::any::TypeId;
+use std::cell::{Cell, RefCell};
+
+use vitae_core::ElementTree;
+
+use crate::signal::{keyed_signal, Signal};
+
+/// Keeps the signals of `use_element_size` apart from the app's keyed
+/// signals of the same type.
+struct ElementSize;
+
+/// Keeps the signals of `use_element_origin` apart from the app's keyed
+/// signals and those of `use_element_size`.
+struct ElementOrigin;
+
+/// Keeps the signal of `use_window_size` apart from the app's keyed
+/// signals.
+struct WindowSizeSlot;
+
+/// The size of the window's contents.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WindowSize {
+    /// Width in physical pixels
+    pub width: f32,
+    /// Height in physical pixels
+    pub height: f32,
+    /// Physical pixels per logical pixel
+    pub scale_factor: f32,
+}
+
+impl WindowSize {
+    /// Width and height in logical pixels, which stay the same across
+    /// screens of different densities. Compare these against breakpoints.
+    pub fn logical(&self) -> (f32, f32) {
+        (
+            self.width / self.scale_factor,
+            self.height / self.scale_factor,
+        )
+    }
+}
+
+/// The key of an element and the signal of `use_element_size` or
+/// `use_element_origin` that watches it.
+type WatchedElement = (String, Signal<(f32, f32)>);
+
+thread_local! {
+    /// The elements whose sizes the view being built asked for
+    static WATCHED: RefCell<Vec<WatchedElement>> = const { RefCell::new(Vec::new()) };
+    /// The elements whose positions the view being built asked for
+    static WATCHED_ORIGINS: RefCell<Vec<WatchedElement>> = const { RefCell::new(Vec::new()) };
+    static WINDOW_SIZE: Cell<WindowSize> = Cell::new(WindowSize::default());
+    /// The window size signal, if the view being built asked for it
+    static WINDOW_WATCHED: Cell<Option<Signal<WindowSize>>> = const { Cell::new(None) };
+}
+
+/// Get the size and scale factor of the window.
+///
+/// The view is built again whenever the window is resized or moved to a
+/// screen with a different scale factor, so it can switch between
+/// arrangements for narrow and wide windows.
+///
+/// # Example
+/// ```
+/// fn view(model: &Library) -> ElementBuilder {
+///     let (width, _) = use_window_size().get().logical();
+///
+///     if width < 600.0 {
+///         // One column, with the albums behind a button
+///         div().col().child(photo_grid(model))
+///     } else {
+///         div().row().child(sidebar(model)).child(photo_grid(model))
+///     }
+/// }
+/// ```
+pub fn use_window_size() -> Signal<WindowSize> {
+    let size = keyed_signal("", TypeId::of::<WindowSizeSlot>(), || WINDOW_SIZE.get());
+    WINDOW_WATCHED.set(Some(size));
+    size
+}
+
+/// Record the window's new size. If the last view asked for it, this
+/// requests a rebuild.
+pub(crate) fn set_window_size(size: WindowSize) {
+    WINDOW_SIZE.set(size);
+    if let Some(signal) = WINDOW_WATCHED.get() {
+        if signal.get() != size {
+            signal.set(size);
+        }
+    }
+}
+
+/// Get the width and height, in logical pixels, of the element with `key`.
+///
+/// The size is only known once the view has been laid out, so it is
+/// `(0.0, 0.0)` the first time and the view is built again as soon as the
+/// element has a size, and again whenever it changes. Use it to arrange an
+/// element's contents by the space it actually got rather than by the
+/// window's size.
+///
+/// # Example
+/// ```
+/// fn sidebar(model: &Library) -> ElementBuilder {
+///     let (width, _) = use_element_size("sidebar").get();
+///
+///     div()
+///         .key("sidebar")
+///         .w(pc(25.0))
+///         .children(model.albums.iter().map(|album| {
+///             // Leave the covers out when the sidebar is narrow
+///             if width < 240.0 {
+///                 album_title(album)
+///             } else {
+///                 album_card(album)
+///             }
+///         }))
+/// }
+/// ```
+pub fn use_element_size(key: &str) -> Signal<(f32, f32)> {
+    let size = keyed_signal(key, TypeId::of::<ElementSize>(), || (0.0, 0.0));
+    WATCHED.with(|watched| watched.borrow_mut().push((key.to_string(), size)));
+    size
+}
+
+/// Get where the top left corner of the element with `key` is in the window,
+/// like `use_element_size` gets its size.
+pub(crate) fn use_element_origin(key: &str) -> Signal<(f32, f32)> {
+    let origin = keyed_signal(key, TypeId::of::<ElementOrigin>(), || (0.0, 0.0));
+    WATCHED_ORIGINS.with(|watched| watched.borrow_mut().push((key.to_string(), origin)));
+    origin
+}
+
+/// Forget the sizes asked for by the previous view (called before each
+/// render)
+pub(crate) fn reset_watched_sizes() {
+    WATCHED.with(|watched| watched.borrow_mut().clear());
+    WATCHED_ORIGINS.with(|watched| watched.borrow_mut().clear());
+    WINDOW_WATCHED.set(None);
+}
+
+/// Update the sizes and positions asked for by the view to where `tree`
+/// laid its elements out. Ones that changed request a rebuild.
+pub(crate) fn update_element_sizes(tree: &ElementTree) {
+    WATCHED.with(|watched| {
+        for (key, size) in watched.borrow().iter() {
+            let Some(id) = tree.find_key(key) else {
+                continue;
+            };
+            let layout = tree.get_node(id).layout;
+            if size.get() != (layout.width, layout.height) {
+                size.set((layout.width, layout.height));
+            }
+        }
+    });
+    WATCHED_ORIGINS.with(|watched| {
+        for (key, origin) in watched.borrow().iter() {
+            let Some(id) = tree.find_key(key) else {
+                continue;
+            };
+            let layout = tree.get_node(id).layout;
+            if origin.get() != (layout.x, layout.y) {
+                origin.set((layout.x, layout.y));
+            }
+        }
+    });
+}
@@ -0,0 +1,253 @@
+//! Two panes side by side or one above the other, with a divider between
+//! them that can be dragged to share the space differently.
+
+use std::any::Any;
+use std::rc::Rc;
+
+use vitae_core::{
+    div, pc, px, Color, CursorIcon, ElementBuilder, Event, EventResult, Length, MouseButton,
+};
+
+use crate::signal::use_keyed_signal;
+use crate::size::use_element_size;
+
+/// The width of the divider between the panes, in pixels.
+pub const SPLIT_DIVIDER_SIZE: f32 = 6.0;
+
+/// The color of the divider between the panes.
+pub const SPLIT_DIVIDER_COLOR: Color = Color::new(0.29, 0.29, 0.29, 1.0);
+
+/// The key of a split that isn't given one.
+const DEFAULT_KEY: &str = "vitae-split";
+
+/// The handler given to `Split::on_resize`, taking the model as `Any`.
+type ResizeHandler = Rc<dyn Fn(&mut dyn Any, f32)>;
+
+/// Two panes with a draggable divider, made by `split`. Use it as a child
+/// like any other element.
+pub struct Split {
+    horizontal: bool,
+    first: ElementBuilder,
+    second: ElementBuilder,
+    key: String,
+    ratio: f32,
+    min_first: f32,
+    min_second: f32,
+    on_resize: Option<ResizeHandler>,
+}
+
+/// Where a split's divider is, kept between views under the split's key.
+#[derive(Clone, Copy)]
+struct SplitState {
+    /// The share of the space the first pane gets
+    ratio: f32,
+    /// The ratio given with `Split::ratio` when `ratio` was last set to it
+    given: f32,
+    /// Where along the split the cursor last was, in window coordinates
+    cursor: f32,
+    /// Where the cursor was and how big the first pane was when the divider
+    /// was pressed, while it is dragged
+    drag: Option<(f32, f32)>,
+}
+
+/// Put `first` and `second` side by side if `horizontal`, or else `first`
+/// above `second`, with a divider between them that can be dragged to make
+/// one bigger and the other smaller. The split fills its parent, and each
+/// pane fills its part of it.
+///
+/// Where the divider is stays the same from one view to the next. To keep
+/// it between runs, save the ratio from `on_resize` and give it back with
+/// `ratio`.
+///
+/// # Example
+/// ```
+/// split(true, sidebar(model), editor(model))
+///     .key("editor-split")
+///     .ratio(model.sidebar_ratio)
+///     .min_first(160.0)
+///     .min_second(320.0)
+///     .on_resize(|m: &mut Editor, ratio| m.sidebar_ratio = ratio)
+/// ```
+pub fn split(horizontal: bool, first: ElementBuilder, second: ElementBuilder) -> Split {
+    Split {
+        horizontal,
+        first,
+        second,
+        key: DEFAULT_KEY.to_string(),
+        ratio: 0.5,
+        min_first: 0.0,
+        min_second: 0.0,
+        on_resize: None,
+    }
+}
+
+impl Split {
+    /// Tell this split apart from others in the view. Each split needs a
+    /// key of its own, which is also the key of the element holding the
+    /// panes.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+
+    /// Set the share of the space the first pane gets, from 0 to 1. Half
+    /// unless set. Dragging the divider moves it from there, until a view
+    /// gives a different ratio.
+    pub fn ratio(mut self, ratio: f32) -> Self {
+        self.ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Keep the first pane at least `size` pixels wide (or tall), as far as
+    /// the split has room for it.
+    pub fn min_first(mut self, size: f32) -> Self {
+        self.min_first = size;
+        self
+    }
+
+    /// Keep the second pane at least `size` pixels wide (or tall), as far
+    /// as the split has room for it.
+    pub fn min_second(mut self, size: f32) -> Self {
+        self.min_second = size;
+        self
+    }
+
+    /// Call `handler` with the first pane's share of the space each time
+    /// the divider is dropped after dragging it.
+    pub fn on_resize<M, F>(mut self, handler: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&mut M, f32) + 'static,
+    {
+        self.on_resize = Some(Rc::new(move |model: &mut dyn Any, ratio| {
+            if let Some(model) = model.downcast_mut::<M>() {
+                handler(model, ratio);
+            }
+        }));
+        self
+    }
+
+    /// Like `on_resize`, for a handler that takes the model as `Any`.
+    pub(crate) fn on_resize_any(mut self, handler: ResizeHandler) -> Self {
+        self.on_resize = Some(handler);
+        self
+    }
+}
+
+/// How big the first pane is when it asks for `first` pixels of
+/// `available`, keeping both panes to their minimum sizes where they fit.
+fn clamp_first(first: f32, available: f32, min_first: f32, min_second: f32) -> f32 {
+    let max = (available - min_second).max(min_first);
+    first.min(max).max(min_first).min(available)
+}
+
+impl From<Split> for ElementBuilder {
+    fn from(split: Split) -> Self {
+        let Split {
+            horizontal,
+            first,
+            second,
+            key,
+            ratio,
+            min_first,
+            min_second,
+            on_resize,
+        } = split;
+
+        let state = use_keyed_signal(&key, || SplitState {
+            ratio,
+            given: ratio,
+            cursor: 0.0,
+            drag: None,
+        });
+        let mut current = state.get();
+        if current.given != ratio {
+            current.ratio = ratio;
+            current.given = ratio;
+            current.drag = None;
+            state.set(current);
+        }
+
+        // The space there is to share, once the split has been laid out
+        let size = use_element_size(&key);
+        let available = move || {
+            let (width, height) = size.get();
+            let length = if horizontal { width } else { height };
+            (length, (length - SPLIT_DIVIDER_SIZE).max(0.0))
+        };
+
+        // Sizes are shares of the split rather than pixels, so the panes
+        // don't keep the window from being made smaller
+        let (length, space) = available();
+        let (first_size, second_size) = if space > 0.0 {
+            let first = clamp_first(current.ratio * space, space, min_first, min_second);
+            (
+                pc(first / length * 100.0),
+                pc((space - first) / length * 100.0),
+            )
+        } else {
+            (pc(current.ratio * 100.0), pc((1.0 - current.ratio) * 100.0))
+        };
+        let pane = |size: Length| {
+            if horizontal {
+                div().w(size).h(pc(100.0))
+            } else {
+                div().w(pc(100.0)).h(size)
+            }
+        };
+
+        let divider = pane(px(SPLIT_DIVIDER_SIZE))
+            .bg(SPLIT_DIVIDER_COLOR)
+            .cursor(if horizontal {
+                CursorIcon::ColResize
+            } else {
+                CursorIcon::RowResize
+            })
+            .on_event_any(move |model, event| {
+                let mut current = state.get();
+                let (_, space) = available();
+                match *event {
+                    Event::MouseMove { x, y } => {
+                        current.cursor = if horizontal { x } else { y };
+                        if let Some((start, first)) = current.drag {
+                            if space > 0.0 {
+                                let first = first + current.cursor - start;
+                                current.ratio =
+                                    clamp_first(first, space, min_first, min_second) / space;
+                            }
+                        }
+                    }
+                    Event::MouseDown {
+                        button: MouseButton::Left,
+                    } => {
+                        let first =
+                            clamp_first(current.ratio * space, space, min_first, min_second);
+                        current.drag = Some((current.cursor, first));
+                    }
+                    Event::MouseUp {
+                        button: MouseButton::Left,
+                    } => {
+                        if current.drag.take().is_some() {
+                            if let Some(on_resize) = &on_resize {
+                                on_resize(model, current.ratio);
+                            }
+                        }
+                    }
+                    _ => return EventResult::Continue,
+                }
+                state.set(current);
+                EventResult::Continue
+            });
+
+        let element = div().key(key).size(pc(100.0));
+        let element = if horizontal {
+            element.row()
+        } else {
+            element.col()
+        };
+        element
+            .child(pane(first_size).child(first))
+            .child(divider)
+            .child(pane(second_size).child(second))
+    }
+}
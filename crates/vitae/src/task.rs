@@ -0,0 +1,99 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+use winit::event_loop::EventLoopProxy;
+
+/// A task running on a background thread whose result hasn't been applied
+/// to the model yet. Returns `true` once it has finished.
+type PendingTask = Box<dyn FnMut(&mut dyn Any) -> bool>;
+
+thread_local! {
+    static PENDING_TASKS: RefCell<Vec<PendingTask>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Wakes the event loop when a background task finishes.
+static WAKER: OnceLock<Mutex<EventLoopProxy<()>>> = OnceLock::new();
+
+pub(crate) fn set_waker(proxy: EventLoopProxy<()>) {
+    let _ = WAKER.set(Mutex::new(proxy));
+}
+
+fn wake() {
+    if let Some(proxy) = WAKER.get() {
+        if let Ok(proxy) = proxy.lock() {
+            let _ = proxy.send_event(());
+        }
+    }
+}
+
+/// Run `work` on a background thread, then hand its result to `on_done`
+/// together with the model.
+///
+/// Use this for anything slow enough to freeze the window, such as file IO
+/// or heavy computation. `work` must not touch the model; `on_done` runs on
+/// the UI thread and the view is rebuilt afterwards. Call it from event
+/// handlers, not from the view.
+///
+/// # Example
+/// ```
+/// button("Load").on_left_click(|m: &mut MyModel| {
+///     let path = m.path.clone();
+///     m.loading = true;
+///     spawn_task(
+///         move || std::fs::read_to_string(path),
+///         |m: &mut MyModel, contents| {
+///             m.loading = false;
+///             m.contents = contents.ok();
+///         },
+///     );
+/// })
+/// ```
+pub fn spawn_task<M, T, W, D>(work: W, on_done: D)
+where
+    M: 'static,
+    T: Send + 'static,
+    W: FnOnce() -> T + Send + 'static,
+    D: FnOnce(&mut M, T) + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(work());
+        wake();
+    });
+
+    let mut on_done = Some(on_done);
+    PENDING_TASKS.with(|tasks| {
+        tasks
+            .borrow_mut()
+            .push(Box::new(move |model| match receiver.try_recv() {
+                Ok(result) => {
+                    if let (Some(on_done), Some(model)) = (on_done.take(), model.downcast_mut()) {
+                        on_done(model, result);
+                    }
+                    true
+                }
+                Err(TryRecvError::Empty) => false,
+                // The worker panicked, so there is nothing to deliver
+                Err(TryRecvError::Disconnected) => true,
+            }));
+    });
+}
+
+/// Apply the results of finished tasks to the model. Returns `true` if any
+/// task finished.
+pub(crate) fn poll_tasks(model: &mut dyn Any) -> bool {
+    // Take the list out so completions can spawn new tasks
+    let mut pending = PENDING_TASKS.with(|tasks| std::mem::take(&mut *tasks.borrow_mut()));
+    let before = pending.len();
+    pending.retain_mut(|task| !task(model));
+    let finished = pending.len() < before;
+    PENDING_TASKS.with(|tasks| {
+        let mut tasks = tasks.borrow_mut();
+        pending.append(&mut tasks);
+        *tasks = pending;
+    });
+    finished
+}
@@ -0,0 +1,135 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+/// Identifies a timer so it can be cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+/// Runs a timer's callback against the model.
+type TimerCallback = Box<dyn FnMut(&mut dyn Any)>;
+
+struct Timer {
+    id: TimerId,
+    due: Instant,
+    /// Interval timers fire again this long after they fire.
+    period: Option<Duration>,
+    callback: TimerCallback,
+}
+
+thread_local! {
+    static TIMERS: RefCell<Vec<Timer>> = const { RefCell::new(Vec::new()) };
+    static NEXT_TIMER_ID: Cell<u64> = const { Cell::new(0) };
+    /// The interval timer whose callback is running, and whether it has
+    /// been cleared by that callback.
+    static RUNNING: Cell<Option<(TimerId, bool)>> = const { Cell::new(None) };
+}
+
+pub(crate) fn add_timer(
+    due: Instant,
+    period: Option<Duration>,
+    callback: TimerCallback,
+) -> TimerId {
+    let id = TimerId(NEXT_TIMER_ID.with(|next| next.replace(next.get() + 1)));
+    TIMERS.with(|timers| {
+        timers.borrow_mut().push(Timer {
+            id,
+            due,
+            period,
+            callback,
+        })
+    });
+    id
+}
+
+/// Call `f` with the model once `delay` has passed. The view is rebuilt
+/// afterwards.
+///
+/// Timers run on the UI thread between events, so `f` can update the model
+/// like an event handler. Call this from event handlers, not from the view.
+///
+/// # Example
+/// ```
+/// .on_left_click(|m: &mut MyModel| {
+///     m.saved = true;
+///     set_timeout(Duration::from_secs(2), |m: &mut MyModel| m.saved = false);
+/// })
+/// ```
+pub fn set_timeout<M: 'static>(delay: Duration, f: impl FnOnce(&mut M) + 'static) -> TimerId {
+    let mut f = Some(f);
+    add_timer(
+        Instant::now() + delay,
+        None,
+        Box::new(move |model| {
+            if let (Some(f), Some(model)) = (f.take(), model.downcast_mut()) {
+                f(model);
+            }
+        }),
+    )
+}
+
+/// Call `f` with the model every `period` until the timer is cleared with
+/// `clear_timer`. The view is rebuilt after every call.
+///
+/// # Example
+/// ```
+/// m.ticker = Some(set_interval(Duration::from_secs(1), |m: &mut MyModel| {
+///     m.seconds += 1;
+/// }));
+/// ```
+pub fn set_interval<M: 'static>(period: Duration, mut f: impl FnMut(&mut M) + 'static) -> TimerId {
+    add_timer(
+        Instant::now() + period,
+        Some(period),
+        Box::new(move |model| {
+            if let Some(model) = model.downcast_mut() {
+                f(model);
+            }
+        }),
+    )
+}
+
+/// Stop a timer. Clearing a timer that already fired does nothing.
+pub fn clear_timer(id: TimerId) {
+    TIMERS.with(|timers| timers.borrow_mut().retain(|timer| timer.id != id));
+    RUNNING.with(|running| {
+        if let Some((running_id, _)) = running.get() {
+            if running_id == id {
+                running.set(Some((id, true)));
+            }
+        }
+    });
+}
+
+/// When the next timer is due, if any are set.
+pub(crate) fn next_timer_due() -> Option<Instant> {
+    TIMERS.with(|timers| timers.borrow().iter().map(|timer| timer.due).min())
+}
+
+/// Run the timers that are due. Returns `true` if any ran.
+pub(crate) fn run_due_timers(model: &mut dyn Any) -> bool {
+    let now = Instant::now();
+    // Take the due timers out so callbacks can set and clear timers
+    let due: Vec<Timer> = TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        let (due, pending) = timers.drain(..).partition(|timer| timer.due <= now);
+        *timers = pending;
+        due
+    });
+
+    let ran = !due.is_empty();
+    for mut timer in due {
+        RUNNING.with(|running| running.set(Some((timer.id, false))));
+        (timer.callback)(model);
+        let cleared = RUNNING
+            .with(|running| running.take())
+            .is_some_and(|(_, cleared)| cleared);
+
+        if let (Some(period), false) = (timer.period, cleared) {
+            // Skip missed ticks rather than firing them all at once
+            timer.due = (timer.due + period).max(now);
+            TIMERS.with(|timers| timers.borrow_mut().push(timer));
+        }
+    }
+    ran
+}
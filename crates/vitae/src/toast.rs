@@ -0,0 +1,248 @@
+//! Transient notifications, shown in a stack in the bottom right corner of
+//! the window until they time out or are clicked away.
+
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+use vitae_core::{
+    div, portal, px, text, Color, CursorIcon, ElementBuilder, Event, EventResult, MouseButton,
+    Transition,
+};
+
+use crate::signal::request_redraw;
+use crate::timer::{add_timer, clear_timer, TimerId};
+
+/// How long a toast stays up unless given another duration.
+pub const DEFAULT_TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// The portal layer of the toast stack, above ordinary portals.
+pub const TOAST_LAYER: i32 = 1000;
+
+/// What a toast is about, which sets its color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// A notification to show with `push`.
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub message: String,
+    pub kind: ToastKind,
+    /// How long it stays up, or `None` to keep it until it's clicked
+    pub duration: Option<Duration>,
+}
+
+impl Toast {
+    pub fn new(kind: ToastKind, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            kind,
+            duration: Some(DEFAULT_TOAST_DURATION),
+        }
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(ToastKind::Info, message)
+    }
+
+    pub fn success(message: impl Into<String>) -> Self {
+        Self::new(ToastKind::Success, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(ToastKind::Warning, message)
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(ToastKind::Error, message)
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Keep the toast up until it's clicked.
+    pub fn sticky(mut self) -> Self {
+        self.duration = None;
+        self
+    }
+}
+
+/// Identifies a toast so it can be dismissed early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ToastId(u64);
+
+struct Shown {
+    id: ToastId,
+    toast: Toast,
+    /// Time left before it's dismissed, counted down only while the timer
+    /// runs
+    remaining: Option<Duration>,
+    /// The running dismiss timer and when it was started
+    timer: Option<(TimerId, Instant)>,
+}
+
+impl Shown {
+    fn start_timer(&mut self) {
+        if let Some(remaining) = self.remaining {
+            let id = self.id;
+            let timer = add_timer(
+                Instant::now() + remaining,
+                None,
+                Box::new(move |_| dismiss(id)),
+            );
+            self.timer = Some((timer, Instant::now()));
+        }
+    }
+
+    fn stop_timer(&mut self) {
+        if let (Some((timer, started)), Some(remaining)) = (self.timer.take(), self.remaining) {
+            clear_timer(timer);
+            self.remaining = Some(remaining.saturating_sub(started.elapsed()));
+        }
+    }
+}
+
+thread_local! {
+    static TOASTS: RefCell<Vec<Shown>> = const { RefCell::new(Vec::new()) };
+    static NEXT_TOAST_ID: Cell<u64> = const { Cell::new(0) };
+    /// Timers are stopped while the cursor is over the stack, so toasts
+    /// don't vanish while being read.
+    static PAUSED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Show `toast` at the bottom of the stack. Call this on the UI thread,
+/// from event handlers, timers or `spawn_task` callbacks.
+///
+/// # Example
+/// ```
+/// .on_left_click(|m: &mut MyModel| {
+///     m.save();
+///     toast::push(Toast::success("Saved"));
+/// })
+/// ```
+pub fn push(toast: Toast) -> ToastId {
+    let id = ToastId(NEXT_TOAST_ID.with(|next| next.replace(next.get() + 1)));
+    let mut shown = Shown {
+        id,
+        remaining: toast.duration,
+        toast,
+        timer: None,
+    };
+    if !PAUSED.get() {
+        shown.start_timer();
+    }
+    TOASTS.with(|toasts| toasts.borrow_mut().push(shown));
+    request_redraw();
+    id
+}
+
+/// Take a toast down before its time is up. Does nothing if it's gone
+/// already.
+pub fn dismiss(id: ToastId) {
+    TOASTS.with(|toasts| {
+        let mut toasts = toasts.borrow_mut();
+        if let Some(index) = toasts.iter().position(|shown| shown.id == id) {
+            if let Some((timer, _)) = toasts.remove(index).timer {
+                clear_timer(timer);
+            }
+        }
+    });
+    request_redraw();
+}
+
+/// Take down every toast.
+pub fn clear() {
+    for shown in TOASTS.take() {
+        if let Some((timer, _)) = shown.timer {
+            clear_timer(timer);
+        }
+    }
+    request_redraw();
+}
+
+fn set_paused(paused: bool) {
+    if PAUSED.replace(paused) == paused {
+        return;
+    }
+    TOASTS.with(|toasts| {
+        for shown in toasts.borrow_mut().iter_mut() {
+            if paused {
+                shown.stop_timer();
+            } else {
+                shown.start_timer();
+            }
+        }
+    });
+}
+
+/// The stack of toasts. Put it in the view once, anywhere: it's a portal.
+///
+/// # Example
+/// ```
+/// div()
+///     .child(content(model))
+///     .child(toast::stack())
+/// ```
+pub fn stack() -> ElementBuilder {
+    let toasts: Vec<(ToastId, Toast)> = TOASTS.with(|toasts| {
+        toasts
+            .borrow()
+            .iter()
+            .map(|shown| (shown.id, shown.toast.clone()))
+            .collect()
+    });
+    if toasts.is_empty() {
+        // The stack can't be left once it disappears from under the cursor
+        PAUSED.set(false);
+    }
+
+    portal()
+        .layer(TOAST_LAYER)
+        .right(px(16.0))
+        .bottom(px(16.0))
+        .col()
+        .gap(px(8.0))
+        .on_event_any(|_, event| {
+            match event {
+                Event::MouseEnter => set_paused(true),
+                Event::MouseLeave => set_paused(false),
+                _ => {}
+            }
+            EventResult::Continue
+        })
+        .children(toasts.into_iter().map(|(id, toast)| toast_card(id, toast)))
+}
+
+fn toast_card(id: ToastId, toast: Toast) -> ElementBuilder {
+    let bg = match toast.kind {
+        ToastKind::Info => Color::from_hex("#333333"),
+        ToastKind::Success => Color::from_hex("#2e7d32"),
+        ToastKind::Warning => Color::from_hex("#b26a00"),
+        ToastKind::Error => Color::from_hex("#c62828"),
+    };
+    div()
+        .key(format!("vitae-toast-{}", id.0))
+        .enter(Transition::slide(0.0, 16.0))
+        .exit(Transition::fade())
+        .bg(bg)
+        .radius(6.0)
+        .p(px(12.0))
+        .cursor(CursorIcon::Pointer)
+        .child(text(toast.message).color(Color::WHITE))
+        .on_event_any(move |_, event| {
+            if let Event::Click {
+                button: MouseButton::Left,
+                ..
+            } = event
+            {
+                dismiss(id);
+            }
+            EventResult::Continue
+        })
+}
@@ -0,0 +1,194 @@
+//! Undo and redo for the whole model (see `App::undoable`).
+
+use std::cell::Cell;
+
+/// How many changes can be undone.
+const LIMIT: usize = 100;
+
+#[derive(Clone, Copy)]
+enum Step {
+    Undo,
+    Redo,
+}
+
+thread_local! {
+    static REQUEST: Cell<Option<Step>> = const { Cell::new(None) };
+    /// Whether there is something to undo and something to redo
+    static AVAILABLE: Cell<(bool, bool)> = const { Cell::new((false, false)) };
+}
+
+/// Put the model back the way it was before the last change. Takes effect
+/// once the current event has been handled. Does nothing unless the app was
+/// made with `App::undoable`.
+///
+/// # Example
+/// ```
+/// div()
+///     .child(text("Undo"))
+///     .disabled(!can_undo())
+///     .on_left_click(|_: &mut Drawing| undo())
+/// ```
+pub fn undo() {
+    REQUEST.set(Some(Step::Undo));
+}
+
+/// Make the last change undone by `undo` again. Takes effect once the
+/// current event has been handled.
+pub fn redo() {
+    REQUEST.set(Some(Step::Redo));
+}
+
+/// Whether there is a change to undo.
+pub fn can_undo() -> bool {
+    AVAILABLE.get().0
+}
+
+/// Whether there is an undone change to redo.
+pub fn can_redo() -> bool {
+    AVAILABLE.get().1
+}
+
+/// Snapshots of the model from before each change.
+pub(crate) struct History<M> {
+    past: Vec<M>,
+    future: Vec<M>,
+    /// The model from before the change in progress, which lasts from
+    /// pressing a key or mouse button until the event is handled, or until
+    /// the button is released
+    before: Option<M>,
+    changed: fn(&M, &M) -> bool,
+}
+
+impl<M: Clone + PartialEq> History<M> {
+    pub(crate) fn new() -> Self {
+        History {
+            past: Vec::new(),
+            future: Vec::new(),
+            before: None,
+            changed: M::ne,
+        }
+    }
+}
+
+impl<M: Clone> History<M> {
+    /// Start a change to `model`, unless one is in progress.
+    pub(crate) fn begin(&mut self, model: &M) {
+        if self.before.is_none() {
+            self.before = Some(model.clone());
+        }
+    }
+
+    /// Finish the change in progress, unless a mouse button is still
+    /// `held`, remembering the model from before it if it changed anything.
+    pub(crate) fn end(&mut self, model: &M, held: bool) {
+        if held {
+            return;
+        }
+        if let Some(before) = self.before.take() {
+            if (self.changed)(&before, model) {
+                if self.past.len() == LIMIT {
+                    self.past.remove(0);
+                }
+                self.past.push(before);
+                self.future.clear();
+            }
+        }
+        self.publish();
+    }
+
+    /// Undo or redo `model` if asked to. Returns whether it changed.
+    pub(crate) fn apply_request(&mut self, model: &mut M) -> bool {
+        let (from, to) = match REQUEST.take() {
+            Some(Step::Undo) => (&mut self.past, &mut self.future),
+            Some(Step::Redo) => (&mut self.future, &mut self.past),
+            None => return false,
+        };
+        let Some(state) = from.pop() else {
+            return false;
+        };
+        to.push(std::mem::replace(model, state));
+        self.publish();
+        true
+    }
+
+    fn publish(&self) {
+        AVAILABLE.set((!self.past.is_empty(), !self.future.is_empty()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Make one change to `model`, as handling an event does.
+    fn change(history: &mut History<i32>, model: &mut i32, to: i32, held: bool) {
+        history.begin(model);
+        *model = to;
+        history.end(model, held);
+    }
+
+    fn step(history: &mut History<i32>, model: &mut i32, step: fn()) -> bool {
+        step();
+        history.apply_request(model)
+    }
+
+    #[test]
+    fn a_drag_is_undone_at_once() {
+        let mut history = History::new();
+        let mut model = 0;
+        change(&mut history, &mut model, 1, false);
+
+        // Moving the mouse with the button held keeps the change going
+        change(&mut history, &mut model, 2, true);
+        change(&mut history, &mut model, 3, true);
+        assert!(can_undo() && !can_redo());
+        change(&mut history, &mut model, 4, false);
+
+        assert!(step(&mut history, &mut model, undo));
+        assert_eq!(model, 1);
+        assert!(step(&mut history, &mut model, undo));
+        assert_eq!(model, 0);
+        assert!(!can_undo() && can_redo());
+        assert!(!step(&mut history, &mut model, undo));
+
+        assert!(step(&mut history, &mut model, redo));
+        assert!(step(&mut history, &mut model, redo));
+        assert_eq!(model, 4);
+        assert!(!step(&mut history, &mut model, redo));
+    }
+
+    #[test]
+    fn a_new_change_clears_redo() {
+        let mut history = History::new();
+        let mut model = 0;
+        change(&mut history, &mut model, 1, false);
+        change(&mut history, &mut model, 2, false);
+        assert!(step(&mut history, &mut model, undo));
+        assert!(can_redo());
+
+        // Events that change nothing leave the undone change to redo
+        change(&mut history, &mut model, 1, false);
+        assert!(can_redo());
+
+        change(&mut history, &mut model, 5, false);
+        assert!(!can_redo());
+        assert!(!step(&mut history, &mut model, redo));
+        assert!(step(&mut history, &mut model, undo));
+        assert_eq!(model, 1);
+    }
+
+    #[test]
+    fn only_the_last_changes_are_kept() {
+        let mut history = History::new();
+        let mut model = 0;
+        for to in 1..=LIMIT as i32 + 50 {
+            change(&mut history, &mut model, to, false);
+        }
+        for _ in 0..LIMIT {
+            assert!(step(&mut history, &mut model, undo));
+        }
+        assert_eq!(model, 50);
+        assert!(!can_undo());
+        assert!(!step(&mut history, &mut model, undo));
+    }
+}
@@ -1,13 +1,109 @@
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
 use winit::application::ApplicationHandler;
-use winit::event::{ElementState, MouseButton, WindowEvent};
-use winit::event_loop::ActiveEventLoop;
+use winit::dpi::PhysicalSize;
+use winit::event::{
+    DeviceEvent, DeviceId, ElementState, MouseButton, MouseScrollDelta, StartCause, WindowEvent,
+};
+use winit::event_loop::{ActiveEventLoop, ControlFlow};
 use winit::keyboard::{Key as WinitKey, NamedKey as WinitNamedKey};
-use winit::window::{Window, WindowId};
+use winit::window::{CursorGrabMode, CursorIcon as WinitCursorIcon, Fullscreen, Window, WindowId};
 
-use vitae_core::{ElementBuilder, Event, Key, MouseButton as VitaeMouseButton, NamedKey};
+use vitae_core::{
+    min_size, ClickInfo, CursorIcon, ElementBuilder, ElementTree, Event, EventResult, Key, Layout,
+    Modifiers, MouseButton as VitaeMouseButton, NamedKey, NodeId, TransitionTracker,
+};
 use vitae_render::Renderer;
 
-use crate::signal::{reset_signal_counter, take_redraw_request};
+use crate::devtools::{self, Inspector, Recorder};
+use crate::error::Error;
+#[cfg(feature = "hot-reload")]
+use crate::hot::HotView;
+#[cfg(feature = "serde")]
+use crate::persist::Persistence;
+use crate::signal::{
+    drop_unused_keyed_signals, request_redraw, reset_signal_counter, take_redraw_request,
+};
+use crate::size::{reset_watched_sizes, set_window_size, update_element_sizes, WindowSize};
+use crate::task::poll_tasks;
+use crate::timer::{next_timer_due, run_due_timers};
+use crate::undo::{self, History};
+
+thread_local! {
+    static FULLSCREEN_REQUEST: Cell<Option<bool>> = const { Cell::new(None) };
+    static POINTER_LOCK_REQUEST: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+/// Put the window into fullscreen on its current monitor, or back into a
+/// normal window. Takes effect once the current event has been handled.
+///
+/// # Example
+/// ```
+/// div().on_key(|m: &mut MyModel, key: &Key| {
+///     if *key == Key::Named(NamedKey::F11) {
+///         m.fullscreen = !m.fullscreen;
+///         set_fullscreen(m.fullscreen);
+///     }
+/// })
+/// ```
+pub fn set_fullscreen(fullscreen: bool) {
+    FULLSCREEN_REQUEST.set(Some(fullscreen));
+}
+
+/// Lock the cursor in place and hide it, or release it again. While it is
+/// locked, `on_mouse_delta` handlers keep getting the mouse's motion, so a
+/// drag can go on without the cursor hitting the edge of the screen. Where
+/// the cursor can't be locked (X11 and Windows), it is kept inside the
+/// window instead. Takes effect once the current event has been handled.
+///
+/// # Example
+/// ```
+/// div()
+///     .on_mouse_down(|_: &mut Viewport, _| set_pointer_lock(true))
+///     .on_mouse_up(|_: &mut Viewport, _| set_pointer_lock(false))
+///     .on_mouse_delta(|m: &mut Viewport, dx: f32, dy: f32| m.orbit(dx, dy))
+/// ```
+pub fn set_pointer_lock(locked: bool) {
+    POINTER_LOCK_REQUEST.set(Some(locked));
+}
+
+/// Lock `window`'s cursor, or keep it inside the window where that isn't
+/// supported, hiding it; or release and show it.
+fn lock_pointer(window: &Window, locked: bool) {
+    let grabbed = if locked {
+        window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+    } else {
+        window.set_cursor_grab(CursorGrabMode::None)
+    };
+    if let Err(err) = grabbed {
+        tracing::warn!("failed to lock the pointer: {err}");
+    }
+    window.set_cursor_visible(!locked);
+}
+
+/// The event for the app's window handler that `event` is, if any (see
+/// `App::on_window_event`).
+fn lifecycle_event(event: &WindowEvent) -> Option<Event> {
+    match event {
+        WindowEvent::Focused(focused) => Some(Event::WindowFocused { focused: *focused }),
+        WindowEvent::Moved(position) => Some(Event::WindowMoved {
+            x: position.x,
+            y: position.y,
+        }),
+        WindowEvent::ScaleFactorChanged { scale_factor, .. } => Some(Event::ScaleFactorChanged {
+            scale_factor: *scale_factor,
+        }),
+        WindowEvent::Resized(size) => Some(Event::Resized {
+            width: size.width,
+            height: size.height,
+        }),
+        _ => None,
+    }
+}
 
 fn convert_key(winit_key: &WinitKey) -> Key {
     match winit_key {
@@ -52,13 +148,142 @@ fn convert_key(winit_key: &WinitKey) -> Key {
     }
 }
 
+fn window_size(window: &Window) -> WindowSize {
+    let size = window.inner_size();
+    WindowSize {
+        width: size.width as f32,
+        height: size.height as f32,
+        scale_factor: window.scale_factor() as f32,
+    }
+}
+
+fn convert_cursor(cursor: CursorIcon) -> WinitCursorIcon {
+    match cursor {
+        CursorIcon::Default => WinitCursorIcon::Default,
+        CursorIcon::Pointer => WinitCursorIcon::Pointer,
+        CursorIcon::ColResize => WinitCursorIcon::ColResize,
+        CursorIcon::RowResize => WinitCursorIcon::RowResize,
+        CursorIcon::Text => WinitCursorIcon::Text,
+        CursorIcon::Grab => WinitCursorIcon::Grab,
+        CursorIcon::Grabbing => WinitCursorIcon::Grabbing,
+        CursorIcon::Move => WinitCursorIcon::Move,
+        CursorIcon::NwseResize => WinitCursorIcon::NwseResize,
+        CursorIcon::NeswResize => WinitCursorIcon::NeswResize,
+        CursorIcon::NotAllowed => WinitCursorIcon::NotAllowed,
+    }
+}
+
+/// The cursor requested by a node or its closest ancestor that sets one.
+fn cursor_at(tree: &ElementTree, id: NodeId) -> CursorIcon {
+    let mut cur = Some(id);
+    while let Some(node_id) = cur {
+        let node = tree.get_node(node_id);
+        if let Some(cursor) = node
+            .style()
+            .map(|s| s.cursor)
+            .filter(|c| *c != CursorIcon::Default)
+        {
+            return cursor;
+        }
+        cur = node.parent;
+    }
+    CursorIcon::Default
+}
+
+/// Maximum delay between clicks that still counts as a multi-click.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+/// Maximum cursor travel (in pixels) between clicks of a multi-click.
+const MULTI_CLICK_DISTANCE: f32 = 4.0;
+/// How far (in pixels) the cursor has to move with a button held before
+/// it counts as a drag rather than a click.
+const DRAG_THRESHOLD: f32 = 4.0;
+/// Pixels scrolled per line by a mouse wheel that scrolls by lines.
+const SCROLL_LINE_HEIGHT: f32 = 40.0;
+
+/// Tracks consecutive clicks to compute click counts (double-click, etc).
+#[derive(Default)]
+struct ClickTracker {
+    last: Option<(Instant, (f32, f32), VitaeMouseButton, u32)>,
+}
+
+impl ClickTracker {
+    /// Register a click and return its count in the current sequence.
+    fn register(&mut self, button: VitaeMouseButton, x: f32, y: f32) -> u32 {
+        let now = Instant::now();
+        let count = match self.last {
+            Some((time, (last_x, last_y), last_button, count))
+                if last_button == button
+                    && now.duration_since(time) <= MULTI_CLICK_INTERVAL
+                    && (x - last_x).abs() <= MULTI_CLICK_DISTANCE
+                    && (y - last_y).abs() <= MULTI_CLICK_DISTANCE =>
+            {
+                count + 1
+            }
+            _ => 1,
+        };
+        self.last = Some((now, (x, y), button, count));
+        count
+    }
+}
+
 pub struct VitaeApp<'a, M: Clone> {
     renderer: Option<Renderer<'a>>,
-    model: M,
+    pub(crate) model: M,
     view_fn: fn(&M) -> ElementBuilder,
+    /// Where the cursor is, in logical pixels like the layout
     cursor_position: (f64, f64),
+    /// Whether the cursor is over the window, so `cursor_position` is
+    /// current
+    cursor_in_window: bool,
     model_dirty: bool,
     mouse_down_position: Option<(f32, f32)>,
+    modifiers: Modifiers,
+    clicks: ClickTracker,
+    /// Path from the root to the focused element (see `ElementTree::path_of`).
+    focus_path: Option<Vec<usize>>,
+    /// Path to the element a mouse button was pressed on. It keeps receiving
+    /// mouse moves until the button is released.
+    pointer_capture: Option<Vec<usize>>,
+    /// Where the cursor was at the last `DragMove`, while the captured
+    /// element is being dragged
+    drag_position: Option<(f32, f32)>,
+    /// Path to the element under the cursor. It and its ancestors count as
+    /// hovered.
+    hover_path: Option<Vec<usize>>,
+    transitions: TransitionTracker,
+    /// Where each element with layout handlers was last reported to be, by
+    /// path
+    reported_layouts: HashMap<Vec<usize>, Layout>,
+    /// Paths to the elements with visibility handlers that were last
+    /// reported to be in view
+    visible_paths: HashSet<Vec<usize>>,
+    cursor: CursorIcon,
+    /// Whether layout is rounded to whole pixels (see `App::pixel_snapping`)
+    pub(crate) pixel_snapping: bool,
+    /// The smallest size the window can be resized to, from the view's
+    /// fixed-size parts (see `min_size`)
+    min_size: PhysicalSize<u32>,
+    /// The error the event loop was stopped by, for `App::run` to return
+    pub(crate) error: Option<Error>,
+    /// Where the model is saved between runs (see `App::with_persistence`)
+    #[cfg(feature = "serde")]
+    pub(crate) persistence: Option<Persistence<M>>,
+    /// Past versions of the model, for undo (see `App::undoable`)
+    pub(crate) history: Option<History<M>>,
+    /// The events dispatched so far, for time travel (see
+    /// `App::time_travel`)
+    pub(crate) recorder: Option<Recorder<M>>,
+    /// Shows the model and signals (see `App::inspector`)
+    pub(crate) inspector: Option<Inspector<M>>,
+    /// Called with the window's focus, move, scale and resize events (see
+    /// `App::on_window_event`)
+    pub(crate) window_handler: Option<fn(&mut M, &Event)>,
+    /// Gets every window event before vitae does (see `App::on_raw_event`)
+    pub(crate) raw_handler: Option<fn(&mut M, &WindowEvent) -> EventResult>,
+    /// The library the view function is reloaded from (see
+    /// `App::hot_reload`)
+    #[cfg(feature = "hot-reload")]
+    pub(crate) hot_view: Option<HotView<M>>,
 }
 
 impl<'a, M: Clone + 'static> VitaeApp<'a, M> {
@@ -68,25 +293,397 @@ impl<'a, M: Clone + 'static> VitaeApp<'a, M> {
             model: initial_model,
             view_fn: view,
             cursor_position: (0.0, 0.0),
+            cursor_in_window: false,
             model_dirty: true,
             mouse_down_position: None,
+            modifiers: Modifiers::default(),
+            clicks: ClickTracker::default(),
+            focus_path: None,
+            pointer_capture: None,
+            drag_position: None,
+            hover_path: None,
+            transitions: TransitionTracker::new(),
+            reported_layouts: HashMap::new(),
+            visible_paths: HashSet::new(),
+            cursor: CursorIcon::Default,
+            min_size: PhysicalSize::new(0, 0),
+            pixel_snapping: true,
+            error: None,
+            #[cfg(feature = "serde")]
+            persistence: None,
+            history: None,
+            recorder: None,
+            inspector: None,
+            window_handler: None,
+            raw_handler: None,
+            #[cfg(feature = "hot-reload")]
+            hot_view: None,
         }
     }
 
-    fn build_tree(&self) -> ElementBuilder {
+    /// Finish the change to the model in progress and carry out any undo
+    /// or redo asked for. Returns whether the model was undone or redone.
+    fn update_history(&mut self) -> bool {
+        let Some(history) = &mut self.history else {
+            return false;
+        };
+        history.end(&self.model, self.mouse_down_position.is_some());
+        history.apply_request(&mut self.model)
+    }
+
+    /// Note that the model may have changed, so it gets saved if it is
+    /// persisted.
+    fn model_changed(&mut self) {
+        #[cfg(feature = "serde")]
+        if let Some(persistence) = &mut self.persistence {
+            persistence.changed(Instant::now());
+        }
+    }
+
+    /// Save the model if it is persisted and due to be saved.
+    fn save_model_if_due(&mut self) {
+        #[cfg(feature = "serde")]
+        if let Some(persistence) = &mut self.persistence {
+            persistence.save_if_due(&self.model, Instant::now());
+        }
+    }
+
+    /// When the model is next due to be saved, if it is persisted.
+    fn save_due(&self) -> Option<Instant> {
+        #[cfg(feature = "serde")]
+        if let Some(persistence) = &self.persistence {
+            return persistence.due();
+        }
+        None
+    }
+
+    /// Switch to the view function from the hot-reloaded library if it has
+    /// been rebuilt. Returns whether it was.
+    fn reload_view(&mut self) -> bool {
+        #[cfg(feature = "hot-reload")]
+        if let Some(view) = self
+            .hot_view
+            .as_mut()
+            .and_then(|hot_view| hot_view.reload_if_changed(Instant::now()))
+        {
+            self.view_fn = view;
+            return true;
+        }
+        false
+    }
+
+    /// When the hot-reloaded library is next due to be checked for
+    /// changes, if there is one.
+    fn reload_due(&self) -> Option<Instant> {
+        #[cfg(feature = "hot-reload")]
+        if let Some(hot_view) = &self.hot_view {
+            return Some(hot_view.next_check());
+        }
+        None
+    }
+
+    fn create_renderer(&mut self, event_loop: &ActiveEventLoop) -> Result<Renderer<'a>, Error> {
+        let window = event_loop.create_window(Window::default_attributes().with_title("vitae"))?;
+        set_window_size(window_size(&window));
+        if let Some(recorder) = &mut self.recorder {
+            recorder.start(&self.model);
+        }
+        let root = self.build_tree();
+        let mut renderer = Renderer::new(window, root)?;
+        renderer.set_pixel_snapping(self.pixel_snapping);
+        Ok(renderer)
+    }
+
+    /// Stop the event loop because of `error`, which `App::run` returns.
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: Error) {
+        self.error = Some(error);
+        event_loop.exit();
+    }
+
+    fn build_tree(&mut self) -> ElementBuilder {
+        let _span = tracing::debug_span!("view").entered();
         // Reset signal counter for consistent IDs across renders
         reset_signal_counter();
-        (self.view_fn)(&self.model)
+        reset_watched_sizes();
+        let mut root = (self.view_fn)(&self.model);
+        drop_unused_keyed_signals();
+        if let Some(panel) = self.recorder.as_ref().and_then(Recorder::panel) {
+            root = root.child(panel);
+        }
+        if let Some(panel) = self
+            .inspector
+            .as_ref()
+            .and_then(|inspector| inspector.panel(&self.model))
+        {
+            root = root.child(panel);
+        }
+        if self.transitions.apply(&mut root, Instant::now()) {
+            // Keep rebuilding every frame until transitions finish
+            request_redraw();
+        }
+        root
+    }
+
+    /// Call the layout handlers of elements laid out somewhere new since
+    /// they were last called, and the visibility handlers of elements that
+    /// came into or went out of view. Also update the element sizes the
+    /// view asked for.
+    fn report_layouts(&mut self) {
+        let Some(renderer) = self.renderer.as_mut() else {
+            return;
+        };
+        let (window_width, window_height) = window_size(renderer.window()).logical();
+        let tree = renderer.tree();
+        update_element_sizes(tree);
+        let mut reported = HashMap::new();
+        let mut visible_paths = HashSet::new();
+        let mut changed = false;
+        for (id, node) in tree.arena.iter() {
+            if node.layout_handlers.is_empty() && node.visibility_handlers.is_empty() {
+                continue;
+            }
+            let path = tree.path_of(id);
+            let layout = node.layout;
+
+            if !node.layout_handlers.is_empty() {
+                if self.reported_layouts.get(&path) != Some(&layout) {
+                    node.dispatch_layout(&mut self.model);
+                    changed = true;
+                }
+                reported.insert(path.clone(), layout);
+            }
+
+            if !node.visibility_handlers.is_empty() {
+                let visible = layout.x < window_width
+                    && layout.y < window_height
+                    && layout.x + layout.width > 0.0
+                    && layout.y + layout.height > 0.0;
+                if visible != self.visible_paths.contains(&path) {
+                    node.dispatch_visibility(&mut self.model, visible);
+                    changed = true;
+                }
+                if visible {
+                    visible_paths.insert(path);
+                }
+            }
+        }
+        self.reported_layouts = reported;
+        self.visible_paths = visible_paths;
+
+        if changed {
+            // Model was potentially modified
+            self.model_dirty = true;
+            renderer.window().request_redraw();
+        }
+    }
+
+    /// Hover the element under the cursor and show its cursor icon. Returns
+    /// the element mouse events at the cursor go to.
+    fn hover_cursor(&mut self) -> Option<NodeId> {
+        let renderer = self.renderer.as_mut()?;
+        let (x, y) = (self.cursor_position.0 as f32, self.cursor_position.1 as f32);
+        let hit = renderer.hit_test(x, y);
+        let hover_path = hit.map(|id| renderer.tree().path_of(id));
+        self.set_hover(hover_path);
+        let renderer = self.renderer.as_mut()?;
+
+        let cursor = hit
+            .map(|id| cursor_at(renderer.tree(), id))
+            .unwrap_or_default();
+        if cursor != self.cursor {
+            self.cursor = cursor;
+            renderer.window().set_cursor(convert_cursor(cursor));
+        }
+        hit
+    }
+
+    /// Send a mouse move to `hit`, the element under the cursor, or while a
+    /// button is held, to the element it was pressed on.
+    fn dispatch_pointer(&mut self, hit: Option<NodeId>, event: Event) {
+        let Some(renderer) = self.renderer.as_mut() else {
+            return;
+        };
+        let tree = renderer.tree();
+        let target = self
+            .pointer_capture
+            .as_deref()
+            .and_then(|path| tree.node_at_path(path))
+            .or(hit);
+        if let Some(node_id) = target {
+            tree.get_node(node_id).dispatch(&mut self.model, &event);
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(&event, &self.model);
+            }
+
+            // Model was potentially modified
+            self.model_dirty = true;
+            renderer.window().request_redraw();
+        }
+    }
+
+    /// Send drag events to the element the held button was pressed on, now
+    /// that the cursor is at `x` and `y`, once it has moved far enough from
+    /// where the button was pressed.
+    fn update_drag(&mut self, x: f32, y: f32) {
+        let Some(renderer) = self.renderer.as_mut() else {
+            return;
+        };
+        let tree = renderer.tree();
+        let Some(node_id) = self
+            .pointer_capture
+            .as_deref()
+            .and_then(|path| tree.node_at_path(path))
+        else {
+            return;
+        };
+        let node = tree.get_node(node_id);
+
+        let (last_x, last_y) = match self.drag_position {
+            Some(last) => last,
+            None => {
+                let Some((down_x, down_y)) = self.mouse_down_position else {
+                    return;
+                };
+                if (x - down_x).hypot(y - down_y) < DRAG_THRESHOLD {
+                    return;
+                }
+                node.dispatch(&mut self.model, &Event::DragStart);
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record(&Event::DragStart, &self.model);
+                }
+                (down_x, down_y)
+            }
+        };
+        self.drag_position = Some((x, y));
+        let event = Event::DragMove {
+            dx: x - last_x,
+            dy: y - last_y,
+        };
+        node.dispatch(&mut self.model, &event);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(&event, &self.model);
+        }
+
+        // Model was potentially modified
+        self.model_dirty = true;
+        renderer.window().request_redraw();
+    }
+
+    /// Move the hover to the element at `path` (or to nothing), sending
+    /// `MouseLeave` to the elements no longer hovered, innermost first, and
+    /// `MouseEnter` to the newly hovered ones, outermost first.
+    fn set_hover(&mut self, path: Option<Vec<usize>>) {
+        let Some(renderer) = self.renderer.as_mut() else {
+            return;
+        };
+        let old = std::mem::replace(&mut self.hover_path, path.clone());
+        if old == path {
+            return;
+        }
+        let tree = renderer.tree();
+
+        // The root is hovered whenever anything is, so it counts as one
+        // more level above the path
+        let common = match (&old, &path) {
+            (Some(old), Some(new)) => {
+                1 + old
+                    .iter()
+                    .zip(new)
+                    .take_while(|(old, new)| old == new)
+                    .count()
+            }
+            _ => 0,
+        };
+        if let Some(old) = &old {
+            for depth in (common..=old.len()).rev() {
+                if let Some(id) = tree.node_at_path(&old[..depth]) {
+                    tree.get_node(id)
+                        .dispatch(&mut self.model, &Event::MouseLeave);
+                }
+            }
+        }
+        if let Some(path) = &path {
+            for depth in common..=path.len() {
+                if let Some(id) = tree.node_at_path(&path[..depth]) {
+                    tree.get_node(id)
+                        .dispatch(&mut self.model, &Event::MouseEnter);
+                }
+            }
+        }
+
+        // Model was potentially modified
+        self.model_dirty = true;
+        renderer.window().request_redraw();
+    }
+
+    /// Move keyboard focus to the element at `path` (or to nothing),
+    /// sending `FocusLost` to the element that had it and `FocusGained` to
+    /// the one that gets it.
+    fn set_focus(&mut self, path: Option<Vec<usize>>) {
+        let Some(renderer) = self.renderer.as_mut() else {
+            return;
+        };
+        let old = std::mem::replace(&mut self.focus_path, path.clone());
+        if old == path {
+            return;
+        }
+        let tree = renderer.tree();
+        for (path, event) in [(old, Event::FocusLost), (path, Event::FocusGained)] {
+            if let Some(id) = path.and_then(|path| tree.node_at_path(&path)) {
+                tree.get_node(id).dispatch(&mut self.model, &event);
+            }
+        }
+
+        // Model was potentially modified
+        self.model_dirty = true;
+        renderer.window().request_redraw();
     }
 }
 
 impl<'a, M: Clone + 'static> ApplicationHandler for VitaeApp<'a, M> {
+    fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            // A timed frame is due (e.g. a caret blink); repaint without
+            // rebuilding the view.
+            if let Some(renderer) = self.renderer.as_ref() {
+                renderer.window().request_redraw();
+            }
+        }
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {
+        // A background task finished; apply its result to the model
+        if poll_tasks(&mut self.model) {
+            self.model_dirty = true;
+            if let Some(renderer) = self.renderer.as_ref() {
+                renderer.window().request_redraw();
+            }
+        }
+    }
+
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window = event_loop
-            .create_window(Window::default_attributes().with_title("vitae"))
-            .unwrap();
-        let root = self.build_tree();
-        self.renderer = Some(Renderer::new(window, root));
+        // Coming back from a suspend keeps the window, and only needs a new
+        // surface to draw to
+        let resumed = match self.renderer.as_mut() {
+            Some(renderer) => renderer.resume().map_err(Error::from),
+            None => self.create_renderer(event_loop).map(|renderer| {
+                self.renderer = Some(renderer);
+            }),
+        };
+        if let Err(error) = resumed {
+            self.fail(event_loop, error);
+        }
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.suspend();
+        }
+        // A suspended app may be closed without being told, as on Android
+        #[cfg(feature = "serde")]
+        if let Some(persistence) = &mut self.persistence {
+            persistence.save(&self.model);
+        }
     }
 
     fn window_event(
@@ -95,6 +692,16 @@ impl<'a, M: Clone + 'static> ApplicationHandler for VitaeApp<'a, M> {
         window_id: WindowId,
         event: WindowEvent,
     ) {
+        // A press can change the model; undo goes back to before it
+        if let Some(history) = &mut self.history {
+            if matches!(
+                event,
+                WindowEvent::MouseInput { .. } | WindowEvent::KeyboardInput { .. }
+            ) {
+                history.begin(&self.model);
+            }
+        }
+
         let Some(renderer) = self.renderer.as_mut() else {
             return;
         };
@@ -103,29 +710,137 @@ impl<'a, M: Clone + 'static> ApplicationHandler for VitaeApp<'a, M> {
             return;
         }
 
+        if let Some(handler) = self.raw_handler {
+            if handler(&mut self.model, &event) == EventResult::Stop {
+                self.model_dirty = true;
+                renderer.window().request_redraw();
+                return;
+            }
+        }
+
+        if let (Some(handler), Some(window_event)) = (self.window_handler, lifecycle_event(&event))
+        {
+            handler(&mut self.model, &window_event);
+            self.model_dirty = true;
+            renderer.window().request_redraw();
+        }
+
         match event {
             WindowEvent::CloseRequested => {
                 event_loop.exit();
             }
             WindowEvent::Resized(physical_size) => {
                 renderer.resize(physical_size);
+                set_window_size(window_size(renderer.window()));
+            }
+            WindowEvent::Occluded(false) => {
+                // Not every system keeps what a hidden window showed, so the
+                // frame is drawn again even if nothing changed
+                renderer.invalidate();
+                renderer.window().request_redraw();
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                renderer.set_scale_factor(scale_factor);
+                set_window_size(window_size(renderer.window()));
+                renderer.window().request_redraw();
             }
             WindowEvent::RedrawRequested => {
+                let _span = tracing::debug_span!("frame").entered();
+                let started = Instant::now();
+                let rebuilt = self.model_dirty;
+
                 // Only rebuild tree if model changed
                 if self.model_dirty {
+                    self.model_changed();
                     let root = self.build_tree();
                     if let Some(renderer) = self.renderer.as_mut() {
                         renderer.set_root(root);
+
+                        // Don't let the window shrink until the view's
+                        // panels overlap
+                        let tree = renderer.tree();
+                        let (width, height) = min_size(tree, tree.root);
+                        let scale_factor = renderer.scale_factor() as f32;
+                        let min = PhysicalSize::new(
+                            (width * scale_factor).ceil() as u32,
+                            (height * scale_factor).ceil() as u32,
+                        );
+                        if min != self.min_size {
+                            self.min_size = min;
+                            renderer.window().set_min_inner_size(Some(min));
+                        }
                     }
                     self.model_dirty = false;
+
+                    // The new view may have put something else under the
+                    // cursor, such as a modal portal over what was hovered
+                    if self.cursor_in_window {
+                        self.hover_cursor();
+                    }
                 }
                 // Render (uses cached tree if clean)
                 if let Some(renderer) = self.renderer.as_mut() {
-                    renderer.render().unwrap();
+                    if let Err(error) = renderer.render() {
+                        self.fail(event_loop, error.into());
+                        return;
+                    }
+                }
+                self.report_layouts();
+
+                if let Some(renderer) = self.renderer.as_mut() {
+                    tracing::debug!(
+                        rebuilt,
+                        nodes = renderer.tree().arena.len(),
+                        elapsed_ms = started.elapsed().as_secs_f64() * 1000.0,
+                        "frame drawn"
+                    );
                 }
             }
             WindowEvent::CursorMoved { position, .. } => {
-                self.cursor_position = (position.x, position.y);
+                let scale_factor = renderer.scale_factor();
+                self.cursor_position = (position.x / scale_factor, position.y / scale_factor);
+                self.cursor_in_window = true;
+                let (x, y) = (self.cursor_position.0 as f32, self.cursor_position.1 as f32);
+                let hit = self.hover_cursor();
+                self.dispatch_pointer(hit, Event::MouseMove { x, y });
+                self.update_drag(x, y);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                // Positive deltas scroll towards the end of the content
+                let (dx, dy) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => {
+                        (-x * SCROLL_LINE_HEIGHT, -y * SCROLL_LINE_HEIGHT)
+                    }
+                    MouseScrollDelta::PixelDelta(position) => {
+                        let position = position.to_logical::<f32>(renderer.scale_factor());
+                        (-position.x, -position.y)
+                    }
+                };
+                let hit = self.hover_cursor();
+                if let (Some(node_id), Some(renderer)) = (hit, self.renderer.as_mut()) {
+                    // Goes up to the nearest element that scrolls, from
+                    // whatever inside it is under the cursor
+                    let event = Event::Scroll { dx, dy };
+                    renderer.tree().bubble(node_id, &mut self.model, &event);
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.record(&event, &self.model);
+                    }
+                    self.model_dirty = true;
+                    renderer.window().request_redraw();
+                }
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor_in_window = false;
+                self.set_hover(None);
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                let state = modifiers.state();
+                self.modifiers = Modifiers {
+                    shift: state.shift_key(),
+                    ctrl: state.control_key(),
+                    alt: state.alt_key(),
+                    meta: state.super_key(),
+                };
             }
             WindowEvent::MouseInput { state, button, .. } => {
                 let vitae_button = match button {
@@ -137,9 +852,35 @@ impl<'a, M: Clone + 'static> ApplicationHandler for VitaeApp<'a, M> {
 
                 let (x, y) = self.cursor_position;
                 let (x, y) = (x as f32, y as f32);
-                let handler = renderer.hit_test(x, y);
+                let hit = renderer.hit_test(x, y);
+
+                if hit.is_none() && state == ElementState::Pressed {
+                    // Clicking empty space clears focus
+                    self.set_focus(None);
+                    self.pointer_capture = None;
+                }
+                let Some(renderer) = self.renderer.as_mut() else {
+                    return;
+                };
+
+                // The element the button was pressed on, if the cursor has
+                // left it since, and if it was dragged
+                let (captured, dragged) = match state {
+                    ElementState::Pressed => {
+                        self.drag_position = None;
+                        (None, None)
+                    }
+                    ElementState::Released => {
+                        let pressed = self
+                            .pointer_capture
+                            .take()
+                            .and_then(|path| renderer.tree().node_at_path(&path));
+                        let dragged = pressed.filter(|_| self.drag_position.take().is_some());
+                        (pressed.filter(|&id| Some(id) != hit), dragged)
+                    }
+                };
 
-                if let Some(handler) = handler {
+                if let Some(node_id) = hit {
                     let event = match state {
                         ElementState::Pressed => Event::MouseDown {
                             button: vitae_button,
@@ -148,30 +889,44 @@ impl<'a, M: Clone + 'static> ApplicationHandler for VitaeApp<'a, M> {
                             button: vitae_button,
                         },
                     };
-                    handler(&mut self.model, &event);
+                    renderer
+                        .tree()
+                        .get_node(node_id)
+                        .dispatch(&mut self.model, &event);
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.record(&event, &self.model);
+                    }
 
                     match state {
                         ElementState::Pressed => {
                             self.mouse_down_position = Some((x, y));
+                            let path = renderer.tree().path_of(node_id);
+                            self.pointer_capture = Some(path.clone());
+                            self.set_focus(Some(path));
                         }
                         ElementState::Released => {
                             // Only fire Click if mouse-down occurred on the same element
                             if let Some((down_x, down_y)) = self.mouse_down_position {
-                                let down_handler = renderer.hit_test(down_x, down_y);
-                                if down_handler.is_some() {
-                                    // Check if both positions hit the same handler by comparing pointer addresses
-                                    let same_element = std::ptr::eq(
-                                        handler.as_ref() as *const _ as *const (),
-                                        down_handler.as_ref().unwrap().as_ref() as *const _
-                                            as *const (),
-                                    );
-                                    if same_element {
-                                        handler(
-                                            &mut self.model,
-                                            &Event::Click {
-                                                button: vitae_button,
-                                            },
-                                        );
+                                if renderer.hit_test(down_x, down_y) == Some(node_id) {
+                                    let text_offset = renderer.text_offset_at(node_id, x, y);
+                                    let node = renderer.tree().get_node(node_id);
+                                    let layout = node.layout;
+                                    let info = ClickInfo {
+                                        x,
+                                        y,
+                                        local_x: x - layout.x,
+                                        local_y: y - layout.y,
+                                        modifiers: self.modifiers,
+                                        count: self.clicks.register(vitae_button, x, y),
+                                        text_offset,
+                                    };
+                                    let event = Event::Click {
+                                        button: vitae_button,
+                                        info,
+                                    };
+                                    node.dispatch(&mut self.model, &event);
+                                    if let Some(recorder) = &mut self.recorder {
+                                        recorder.record(&event, &self.model);
                                     }
                                 }
                             }
@@ -185,6 +940,37 @@ impl<'a, M: Clone + 'static> ApplicationHandler for VitaeApp<'a, M> {
                         renderer.window().request_redraw();
                     }
                 }
+
+                if let Some(node_id) = captured {
+                    // Let the pressed element finish whatever it started,
+                    // such as a drag
+                    if let Some(renderer) = self.renderer.as_mut() {
+                        let event = Event::MouseUp {
+                            button: vitae_button,
+                        };
+                        renderer
+                            .tree()
+                            .get_node(node_id)
+                            .dispatch(&mut self.model, &event);
+                        if let Some(recorder) = &mut self.recorder {
+                            recorder.record(&event, &self.model);
+                        }
+                        self.model_dirty = true;
+                        renderer.window().request_redraw();
+                    }
+                }
+
+                if let (Some(node_id), Some(renderer)) = (dragged, self.renderer.as_mut()) {
+                    renderer
+                        .tree()
+                        .get_node(node_id)
+                        .dispatch(&mut self.model, &Event::DragEnd);
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.record(&Event::DragEnd, &self.model);
+                    }
+                    self.model_dirty = true;
+                    renderer.window().request_redraw();
+                }
             }
             WindowEvent::KeyboardInput { event, .. } => {
                 let key = convert_key(&event.logical_key);
@@ -192,33 +978,172 @@ impl<'a, M: Clone + 'static> ApplicationHandler for VitaeApp<'a, M> {
                     ElementState::Pressed => Event::KeyDown {
                         key,
                         repeat: event.repeat,
+                        modifiers: self.modifiers,
                     },
                     ElementState::Released => Event::KeyUp { key },
                 };
 
-                // For now, keyboard events go to the root element
-                // TODO: implement focus system for targeted keyboard events
-                let root_handler = renderer.get_root_handler();
-                if let Some(handler) = root_handler {
-                    handler(&mut self.model, &vitae_event);
-                    // Model was potentially modified
-                    self.model_dirty = true;
-                    if let Some(renderer) = self.renderer.as_ref() {
-                        renderer.window().request_redraw();
+                // Key events go to the focused element and bubble up to the
+                // root; without focus they go straight to the root. While a
+                // modal portal is open, they stay inside it.
+                let tree = renderer.tree();
+                let scope = tree.top_modal().unwrap_or(tree.root);
+                let target = self
+                    .focus_path
+                    .as_deref()
+                    .and_then(|path| tree.node_at_path(path))
+                    .filter(|&id| tree.is_within(id, scope))
+                    .unwrap_or(scope);
+                let result = tree.bubble_within(target, scope, &mut self.model, &vitae_event);
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record(&vitae_event, &self.model);
+                }
+
+                // Unless a handler took it, Tab moves focus to the next
+                // element, and Shift+Tab to the previous one
+                if let (
+                    Event::KeyDown {
+                        key: Key::Named(NamedKey::Tab),
+                        ..
+                    },
+                    EventResult::Continue,
+                ) = (&vitae_event, result)
+                {
+                    if let Some(next) = tree.next_focus(scope, Some(target), self.modifiers.shift) {
+                        let path = tree.path_of(next);
+                        self.set_focus(Some(path));
                     }
                 }
+
+                // F12 shows and hides the developer tools, if there are any
+                if let (
+                    Event::KeyDown {
+                        key: Key::Named(NamedKey::F12),
+                        ..
+                    },
+                    EventResult::Continue,
+                    true,
+                ) = (
+                    &vitae_event,
+                    result,
+                    self.recorder.is_some() || self.inspector.is_some(),
+                ) {
+                    devtools::toggle();
+                }
+
+                // Likewise Ctrl+Z undoes, and Ctrl+Shift+Z or Ctrl+Y redoes
+                if let (
+                    Event::KeyDown {
+                        key: Key::Character(c),
+                        ..
+                    },
+                    EventResult::Continue,
+                    Some(_),
+                ) = (&vitae_event, result, &self.history)
+                {
+                    let command = self.modifiers.ctrl || self.modifiers.meta;
+                    match c.to_lowercase().as_str() {
+                        "z" if command && self.modifiers.shift => undo::redo(),
+                        "z" if command => undo::undo(),
+                        "y" if command => undo::redo(),
+                        _ => {}
+                    }
+                }
+
+                // Model was potentially modified
+                self.model_dirty = true;
+                if let Some(renderer) = self.renderer.as_ref() {
+                    renderer.window().request_redraw();
+                }
             }
             _ => {}
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        let DeviceEvent::MouseMotion { delta: (dx, dy) } = event else {
+            return;
+        };
+        if !self.cursor_in_window && self.pointer_capture.is_none() {
+            return;
+        }
+        let Some(renderer) = self.renderer.as_mut() else {
+            return;
+        };
+        let (x, y) = self.cursor_position;
+        let hit = renderer.hit_test(x as f32, y as f32);
+        let event = Event::MouseDelta {
+            dx: dx as f32,
+            dy: dy as f32,
+        };
+        self.dispatch_pointer(hit, event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.save_model_if_due();
+        if self.reload_view() {
+            request_redraw();
+        }
+        if self.update_history() {
+            self.model_dirty = true;
+            request_redraw();
+        }
+        if let Some(recorder) = &mut self.recorder {
+            if recorder.apply_request(&mut self.model) {
+                self.model_dirty = true;
+                request_redraw();
+            }
+        }
+
         if let Some(renderer) = self.renderer.as_ref() {
+            if run_due_timers(&mut self.model) {
+                self.model_dirty = true;
+                renderer.window().request_redraw();
+            }
+
+            if let Some(fullscreen) = FULLSCREEN_REQUEST.take() {
+                renderer
+                    .window()
+                    .set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+            }
+            if let Some(locked) = POINTER_LOCK_REQUEST.take() {
+                lock_pointer(renderer.window(), locked);
+            }
+
             // Check if any signal requested a redraw
             if take_redraw_request() {
                 self.model_dirty = true;
                 renderer.window().request_redraw();
             }
+
+            // Sleep until the next timer, timed frame, save or check for a
+            // rebuilt view, if any
+            let wake_at = [
+                renderer.next_frame_at(),
+                next_timer_due(),
+                self.save_due(),
+                self.reload_due(),
+            ]
+            .into_iter()
+            .flatten()
+            .min();
+            event_loop.set_control_flow(match wake_at {
+                Some(at) => ControlFlow::WaitUntil(at),
+                None => ControlFlow::Wait,
+            });
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        // Save changes made within the last moment, too
+        #[cfg(feature = "serde")]
+        if let Some(persistence) = &mut self.persistence {
+            persistence.save(&self.model);
         }
     }
 }